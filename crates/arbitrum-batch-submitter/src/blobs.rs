@@ -0,0 +1,55 @@
+//! EIP-4844 blobs of batch transactions
+//!
+//! Batches are packed into blobs as the inbox tracker unpacks them, see
+//! [`arbitrum_inbox_tracker::blobs`]. Commitments and proofs use the Ethereum
+//! KZG trusted setup.
+
+use alloy_eips::eip4844::{Blob, BlobTransactionSidecar, Bytes48};
+pub use arbitrum_inbox_tracker::blobs::{
+    DATA_BYTES_PER_BLOB, blob_count, decode_blobs, encode_blobs,
+};
+use eyre::Result;
+
+/// Blobs a single transaction may carry, as of Dencun
+pub const MAX_BLOBS_PER_TRANSACTION: usize = 6;
+
+/// Build the sidecar of a blob transaction, computing commitments and proofs
+pub fn blob_sidecar(blobs: Vec<Blob>) -> Result<BlobTransactionSidecar> {
+    let settings = c_kzg::ethereum_kzg_settings(0);
+    let mut commitments = Vec::with_capacity(blobs.len());
+    let mut proofs = Vec::with_capacity(blobs.len());
+    for blob in &blobs {
+        let blob = c_kzg::Blob::from_bytes(blob.as_slice())?;
+        let commitment = settings.blob_to_kzg_commitment(&blob)?.to_bytes();
+        let proof = settings.compute_blob_kzg_proof(&blob, &commitment)?;
+        commitments.push(Bytes48::from(commitment.into_inner()));
+        proofs.push(Bytes48::from(proof.to_bytes().into_inner()));
+    }
+    Ok(BlobTransactionSidecar::new(blobs, commitments, proofs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sidecar_proofs_verify() {
+        let blobs = encode_blobs(b"batch data");
+        let sidecar = blob_sidecar(blobs).unwrap();
+        assert_eq!(sidecar.commitments.len(), 1);
+
+        let settings = c_kzg::ethereum_kzg_settings(0);
+        let blob = c_kzg::Blob::from_bytes(sidecar.blobs[0].as_slice()).unwrap();
+        let commitment = c_kzg::Bytes48::from(sidecar.commitments[0].0);
+        let proof = c_kzg::Bytes48::from(sidecar.proofs[0].0);
+        assert!(
+            settings
+                .verify_blob_kzg_proof(&blob, &commitment, &proof)
+                .unwrap()
+        );
+        assert_eq!(
+            sidecar.versioned_hashes().next().unwrap()[0],
+            alloy_eips::eip4844::VERSIONED_HASH_VERSION_KZG
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! Encoding of L2 blocks into sequencer batch data
+//!
+//! The inverse of the inbox tracker's batch decoder: each block becomes the
+//! segments advancing the timestamp and L1 block number, one segment per
+//! delayed inbox message it consumes and a single L2 message carrying its
+//! sequenced transactions. The RLP list of segments is brotli-compressed
+//! behind the brotli header byte, which is the data the SequencerInbox takes.
+
+use alloy_rlp::Encodable;
+use arbitrum_consensus::messages::{MAX_L2_MESSAGE_SIZE, l2_message_kind};
+use arbitrum_inbox_tracker::batch::{
+    BROTLI_MESSAGE_HEADER_BYTE, MAX_DECOMPRESSED_LEN, MAX_SEGMENTS_PER_SEQUENCER_MESSAGE,
+    segment_kind,
+};
+use eyre::{Result, eyre};
+
+/// Brotli window used for batches, as in Nitro
+const BROTLI_WINDOW_BITS: i32 = 22;
+
+/// Headroom for the header byte and brotli framing of incompressible data
+const COMPRESSION_OVERHEAD: usize = 64;
+
+/// The parts of an L2 block that are posted in a batch
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockContents {
+    pub timestamp: u64,
+    pub l1_block_number: u64,
+    /// Delayed inbox messages consumed by the block
+    pub delayed_messages: u64,
+    /// EIP-2718 encodings of the sequenced transactions
+    pub transactions: Vec<Vec<u8>>,
+}
+
+/// Compressed batch data ready to be posted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedBatch {
+    pub data: Vec<u8>,
+    /// Number of blocks included, a prefix of those offered to the encoder
+    pub block_count: usize,
+    /// Number of messages the data decodes into
+    pub message_count: u64,
+    pub after_delayed_messages_read: u64,
+}
+
+/// Accumulates blocks into a single batch up to a compressed size limit
+pub struct BatchEncoder {
+    compression_level: u32,
+    max_size: usize,
+    payload: Vec<u8>,
+    segment_count: usize,
+    block_count: usize,
+    message_count: u64,
+    timestamp: u64,
+    l1_block_number: u64,
+    delayed_messages_read: u64,
+}
+
+impl BatchEncoder {
+    /// Create an encoder for a batch following `delayed_messages_read`
+    /// delayed inbox messages
+    pub fn new(compression_level: u32, max_size: usize, delayed_messages_read: u64) -> Self {
+        Self {
+            compression_level,
+            max_size,
+            payload: Vec::new(),
+            segment_count: 0,
+            block_count: 0,
+            message_count: 0,
+            // The decoder starts both from zero for every batch
+            timestamp: 0,
+            l1_block_number: 0,
+            delayed_messages_read,
+        }
+    }
+
+    /// Delayed inbox messages consumed once the batch is applied
+    pub fn delayed_messages_read(&self) -> u64 {
+        self.delayed_messages_read
+    }
+
+    /// Number of blocks added so far
+    pub fn block_count(&self) -> usize {
+        self.block_count
+    }
+
+    /// Add a block to the batch
+    ///
+    /// Returns `false`, leaving the batch unchanged, if the block would push
+    /// the batch over its size limit. A block that does not fit into an empty
+    /// batch can never be posted and is an error.
+    pub fn add_block(&mut self, block: &BlockContents) -> Result<bool> {
+        let segments = self.block_segments(block)?;
+
+        let payload_len = self.payload.len();
+        for segment in &segments {
+            segment.as_slice().encode(&mut self.payload);
+        }
+        let fits = self.segment_count + segments.len() <= MAX_SEGMENTS_PER_SEQUENCER_MESSAGE
+            && self.fits()?;
+        if !fits {
+            self.payload.truncate(payload_len);
+            if self.block_count == 0 {
+                return Err(eyre!(
+                    "Block does not fit into a batch of {} bytes",
+                    self.max_size
+                ));
+            }
+            return Ok(false);
+        }
+
+        self.segment_count += segments.len();
+        self.block_count += 1;
+        self.message_count += segments
+            .iter()
+            .filter(|segment| {
+                matches!(
+                    segment[0],
+                    segment_kind::DELAYED_MESSAGES | segment_kind::L2_MESSAGE
+                )
+            })
+            .count() as u64;
+        if !segments.is_empty() {
+            self.timestamp = self.timestamp.max(block.timestamp);
+            self.l1_block_number = self.l1_block_number.max(block.l1_block_number);
+        }
+        self.delayed_messages_read += block.delayed_messages;
+        Ok(true)
+    }
+
+    /// Compress the segments into the data posted to the SequencerInbox
+    pub fn finish(self) -> Result<EncodedBatch> {
+        let mut data = vec![BROTLI_MESSAGE_HEADER_BYTE];
+        data.extend(self.compress()?);
+        Ok(EncodedBatch {
+            data,
+            block_count: self.block_count,
+            message_count: self.message_count,
+            after_delayed_messages_read: self.delayed_messages_read,
+        })
+    }
+
+    /// Segments of a block, relative to the blocks already in the batch
+    fn block_segments(&self, block: &BlockContents) -> Result<Vec<Vec<u8>>> {
+        let mut segments = Vec::new();
+        if block.delayed_messages == 0 && block.transactions.is_empty() {
+            return Ok(segments);
+        }
+
+        if block.timestamp > self.timestamp {
+            segments.push(advance_segment(
+                segment_kind::ADVANCE_TIMESTAMP,
+                block.timestamp - self.timestamp,
+            ));
+        }
+        if block.l1_block_number > self.l1_block_number {
+            segments.push(advance_segment(
+                segment_kind::ADVANCE_L1_BLOCK_NUMBER,
+                block.l1_block_number - self.l1_block_number,
+            ));
+        }
+        for _ in 0..block.delayed_messages {
+            segments.push(vec![segment_kind::DELAYED_MESSAGES]);
+        }
+        if !block.transactions.is_empty() {
+            let mut segment = vec![segment_kind::L2_MESSAGE];
+            segment.extend(l2_message(&block.transactions)?);
+            segments.push(segment);
+        }
+        Ok(segments)
+    }
+
+    fn fits(&self) -> Result<bool> {
+        // Brotli barely expands incompressible data, so small batches fit as is
+        if self.payload.len() + COMPRESSION_OVERHEAD <= self.max_size {
+            return Ok(true);
+        }
+        if self.payload.len() > MAX_DECOMPRESSED_LEN {
+            return Ok(false);
+        }
+        // One byte goes to the header byte
+        Ok(self.compress()?.len() < self.max_size)
+    }
+
+    fn compress(&self) -> Result<Vec<u8>> {
+        let params = brotli::enc::BrotliEncoderParams {
+            quality: self.compression_level as i32,
+            lgwin: BROTLI_WINDOW_BITS,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        brotli::BrotliCompress(&mut &self.payload[..], &mut out, &params)?;
+        Ok(out)
+    }
+}
+
+fn advance_segment(kind: u8, advance: u64) -> Vec<u8> {
+    let mut segment = vec![kind];
+    advance.encode(&mut segment);
+    segment
+}
+
+/// The L2 message of a block: its signed transaction, or a batch of them
+fn l2_message(transactions: &[Vec<u8>]) -> Result<Vec<u8>> {
+    let message = match transactions {
+        [tx] => [&[l2_message_kind::SIGNED_TX], tx.as_slice()].concat(),
+        txs => {
+            let mut message = vec![l2_message_kind::BATCH];
+            for tx in txs {
+                message.extend_from_slice(&(tx.len() as u64 + 1).to_be_bytes());
+                message.push(l2_message_kind::SIGNED_TX);
+                message.extend_from_slice(tx);
+            }
+            message
+        }
+    };
+    if message.len() > MAX_L2_MESSAGE_SIZE {
+        return Err(eyre!(
+            "L2 message of {} bytes exceeds the limit of {MAX_L2_MESSAGE_SIZE}",
+            message.len()
+        ));
+    }
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, B256, U256, hex, keccak256};
+    use arbitrum_consensus::messages::parse_l1_message;
+    use arbitrum_inbox_tracker::{
+        BatchMessageContent, SequencerMessageHeader, batch::decode_batch,
+    };
+    use arbitrum_storage::{L1Message, L1MessageKind};
+
+    use super::*;
+
+    // EIP-155 example transaction
+    fn signed_tx() -> Vec<u8> {
+        hex!(
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        )
+        .to_vec()
+    }
+
+    /// Prepend the header the SequencerInbox would build for the batch
+    fn sequencer_message(batch: &EncodedBatch) -> Vec<u8> {
+        let header = SequencerMessageHeader {
+            min_timestamp: 0,
+            max_timestamp: u64::MAX,
+            min_l1_block: 0,
+            max_l1_block: u64::MAX,
+            after_delayed_messages: batch.after_delayed_messages_read,
+        };
+        [header.to_bytes().as_slice(), &batch.data].concat()
+    }
+
+    fn parse_l2_message(data: Vec<u8>) -> Vec<B256> {
+        let message = L1Message {
+            message_number: 0,
+            kind: L1MessageKind::L2Message,
+            sender: Address::ZERO,
+            data,
+            timestamp: 0,
+            block_number: 0,
+            request_id: B256::ZERO,
+            l1_base_fee: U256::ZERO,
+            batch_gas_cost: None,
+        };
+        parse_l1_message(&message, 42_161)
+            .unwrap()
+            .iter()
+            .map(|tx| tx.hash)
+            .collect()
+    }
+
+    #[test]
+    fn test_encoded_batch_round_trips_through_decoder() {
+        let mut encoder = BatchEncoder::new(11, 100_000, 5);
+        let blocks = [
+            BlockContents {
+                timestamp: 1_700_000_000,
+                l1_block_number: 18_000_000,
+                delayed_messages: 0,
+                transactions: vec![signed_tx()],
+            },
+            // Empty blocks are not posted
+            BlockContents {
+                timestamp: 1_700_000_001,
+                l1_block_number: 18_000_000,
+                ..Default::default()
+            },
+            BlockContents {
+                timestamp: 1_700_000_002,
+                l1_block_number: 18_000_001,
+                delayed_messages: 2,
+                transactions: vec![signed_tx(), signed_tx()],
+            },
+        ];
+        for block in &blocks {
+            assert!(encoder.add_block(block).unwrap());
+        }
+        let batch = encoder.finish().unwrap();
+        assert_eq!(batch.block_count, 3);
+        assert_eq!(batch.message_count, 4);
+        assert_eq!(batch.after_delayed_messages_read, 7);
+        assert_eq!(batch.data[0], BROTLI_MESSAGE_HEADER_BYTE);
+
+        // A message per delayed read and per block with transactions
+        let messages = decode_batch(3, &sequencer_message(&batch), 5).unwrap();
+        assert_eq!(messages.len() as u64, batch.message_count);
+        assert_eq!(messages[0].timestamp, 1_700_000_000);
+        assert_eq!(messages[0].l1_block_number, 18_000_000);
+        assert_eq!(
+            messages[1].content,
+            BatchMessageContent::Delayed { message_index: 5 }
+        );
+        assert_eq!(
+            messages[2].content,
+            BatchMessageContent::Delayed { message_index: 6 }
+        );
+        assert!(messages[1..].iter().all(|m| m.timestamp == 1_700_000_002));
+        assert!(
+            messages[1..]
+                .iter()
+                .all(|m| m.l1_block_number == 18_000_001)
+        );
+        assert_eq!(messages[3].delayed_messages_read, 7);
+
+        let BatchMessageContent::L2Message(first) = &messages[0].content else {
+            panic!("expected an L2 message");
+        };
+        let BatchMessageContent::L2Message(last) = &messages[3].content else {
+            panic!("expected an L2 message");
+        };
+        let hashes = parse_l2_message(first.clone());
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(parse_l2_message(last.clone()), vec![hashes[0], hashes[0]]);
+    }
+
+    #[test]
+    fn test_encoder_splits_at_max_size() {
+        let block = |i: u64| BlockContents {
+            timestamp: 1_000 + i,
+            l1_block_number: 1,
+            delayed_messages: 0,
+            // Incompressible data, so every block adds to the compressed size
+            transactions: vec![
+                (0..1_000u64)
+                    .map(|j| keccak256((i * 1_000 + j).to_be_bytes())[0])
+                    .collect(),
+            ],
+        };
+
+        let mut encoder = BatchEncoder::new(11, 4_000, 0);
+        let mut added = 0;
+        while encoder.add_block(&block(added)).unwrap() {
+            added += 1;
+        }
+        assert!(added >= 2);
+        assert_eq!(encoder.block_count(), added as usize);
+        let batch = encoder.finish().unwrap();
+        assert!(batch.data.len() <= 4_000);
+        assert_eq!(
+            decode_batch(0, &sequencer_message(&batch), 0)
+                .unwrap()
+                .len(),
+            added as usize
+        );
+
+        // A block larger than an empty batch can never be posted
+        let mut encoder = BatchEncoder::new(11, 500, 0);
+        assert!(encoder.add_block(&block(0)).is_err());
+    }
+}
@@ -1,33 +1,91 @@
+pub mod blobs;
+pub mod encoder;
+pub mod policy;
+pub mod poster;
+
 use std::{sync::Arc, time::Duration};
 
 use alloy_primitives::B256;
 use arbitrum_config::ArbitrumRethConfig;
-use arbitrum_storage::{ArbitrumBatch, ArbitrumBlock, ArbitrumStorage};
-use eyre::Result;
+use arbitrum_l1_client::{JsonRpcL1Client, L1Client};
+use arbitrum_storage::{ArbitrumBatch, ArbitrumBlock, ArbitrumStorage, ArbitrumTxKind};
+use eyre::{Result, eyre};
 use tokio::{sync::RwLock, time::interval};
 use tracing::{debug, error, info, warn};
 
+pub use crate::{
+    encoder::{BatchEncoder, BlockContents, EncodedBatch},
+    policy::{
+        Clock, DefaultPostingPolicy, MockClock, PendingBatch, PostingPolicy, PostingReason,
+        SystemClock,
+    },
+    poster::{BatchPoster, DataPostingMode, PostedBatch},
+};
+
+/// Upper bound on the interval at which pending blocks are checked
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Batch submitter responsible for submitting L2 batches to L1
 pub struct BatchSubmitter {
     config: ArbitrumRethConfig,
     storage: Arc<ArbitrumStorage>,
     is_running: Arc<RwLock<bool>>,
     last_submitted_block: Arc<RwLock<u64>>,
-    // TODO: Add L1 client for submitting batches
-    // l1_client: Arc<dyn L1Client>,
+    last_posting_mode: Arc<RwLock<Option<DataPostingMode>>>,
+    poster: Arc<BatchPoster>,
+    policy: Arc<dyn PostingPolicy>,
 }
 
 #[allow(dead_code)]
 impl BatchSubmitter {
-    /// Create a new batch submitter
+    /// Create a new batch submitter posting through the configured L1 endpoint
     pub async fn new(config: &ArbitrumRethConfig, storage: Arc<ArbitrumStorage>) -> Result<Self> {
+        let l1_client = Arc::new(JsonRpcL1Client::new(&config.l1)?);
+        Self::with_l1_client(config, storage, l1_client).await
+    }
+
+    /// Create a new batch submitter posting through the given client
+    pub async fn with_l1_client(
+        config: &ArbitrumRethConfig,
+        storage: Arc<ArbitrumStorage>,
+        l1_client: Arc<dyn L1Client>,
+    ) -> Result<Self> {
+        let policy = Arc::new(DefaultPostingPolicy::new(&config.sequencer));
+        Self::with_posting_policy(config, storage, l1_client, policy).await
+    }
+
+    /// Create a new batch submitter deciding when to post with the given policy
+    pub async fn with_posting_policy(
+        config: &ArbitrumRethConfig,
+        storage: Arc<ArbitrumStorage>,
+        l1_client: Arc<dyn L1Client>,
+        policy: Arc<dyn PostingPolicy>,
+    ) -> Result<Self> {
         info!("Initializing batch submitter");
 
+        let poster = BatchPoster::new(config, l1_client, Arc::clone(&storage))?;
+        info!("Posting batches from L1 account {}", poster.address());
+
+        // Pick up after the latest batch posted by a previous run
+        let last_submitted_block = match storage.get_latest_batch().await? {
+            Some(batch) => {
+                info!(
+                    "Resuming after batch {} ending at block {}",
+                    batch.batch_number, batch.block_range.1
+                );
+                batch.block_range.1
+            }
+            None => 0,
+        };
+
         Ok(Self {
             config: config.clone(),
             storage,
             is_running: Arc::new(RwLock::new(false)),
-            last_submitted_block: Arc::new(RwLock::new(0)),
+            last_submitted_block: Arc::new(RwLock::new(last_submitted_block)),
+            last_posting_mode: Arc::new(RwLock::new(None)),
+            poster: Arc::new(poster),
+            policy,
         })
     }
 
@@ -69,7 +127,10 @@ impl BatchSubmitter {
 
     /// Main batch submission loop
     async fn batch_submission_loop(&self) {
-        let mut interval = interval(Duration::from_secs(self.config.sequencer.batch_timeout));
+        // The timeout is in milliseconds and the policy measures it, so poll
+        // at least as often
+        let timeout = Duration::from_millis(self.config.sequencer.batch_timeout.max(1));
+        let mut interval = interval(timeout.min(MAX_POLL_INTERVAL));
 
         loop {
             interval.tick().await;
@@ -80,34 +141,44 @@ impl BatchSubmitter {
             }
 
             // Try to submit a batch
-            if let Err(e) = self.try_submit_batch().await {
+            if let Err(e) = self.try_submit_batch(false).await {
                 error!("Failed to submit batch: {}", e);
             }
         }
     }
 
     /// Try to submit a batch of transactions
-    async fn try_submit_batch(&self) -> Result<()> {
+    ///
+    /// Pending blocks are posted once the posting policy agrees, or right
+    /// away if `force` is set.
+    async fn try_submit_batch(&self, force: bool) -> Result<()> {
         debug!("Checking for batch submission");
 
+        // A batch left in flight by a previous run goes first
+        if let Some(in_flight) = self.storage.get_in_flight_batch().await? {
+            info!(
+                "Resuming in-flight batch {} at L1 nonce {}",
+                in_flight.batch.batch_number, in_flight.nonce
+            );
+            let batch = in_flight.batch.clone();
+            let data_len = in_flight.data.len();
+            let posted = self.poster.resume(in_flight).await?;
+            let result = self.submission_result(&batch, posted);
+            self.record_batch(batch, &result, data_len).await?;
+        }
+
         // Get the latest block number
         let latest_block = self.storage.get_current_block_number().await?;
         let last_submitted = *self.last_submitted_block.read().await;
 
-        // Check if we have enough blocks to submit
-        let blocks_to_submit = latest_block.saturating_sub(last_submitted);
-
-        if blocks_to_submit < self.config.sequencer.batch_size as u64 {
-            debug!(
-                "Not enough blocks for submission: {} < {}",
-                blocks_to_submit, self.config.sequencer.batch_size
-            );
+        if latest_block <= last_submitted {
+            debug!("No blocks to submit");
             return Ok(());
         }
 
         // Collect blocks for the batch
         let start_block = last_submitted + 1;
-        let end_block = start_block + self.config.sequencer.batch_size as u64 - 1;
+        let end_block = latest_block.min(last_submitted + self.config.sequencer.batch_size as u64);
 
         let blocks = self
             .collect_blocks_for_batch(start_block, end_block)
@@ -118,29 +189,62 @@ impl BatchSubmitter {
             return Ok(());
         }
 
-        // Create the batch
-        let batch = self.create_batch(blocks).await?;
+        // Blocks that do not fit into the size limit go into following batches
+        let mut remaining = &blocks[..];
+        while !remaining.is_empty() {
+            // Create the batch
+            let (batch, data) = self.create_batch(remaining).await?;
+            let block_count = (batch.block_range.1 - batch.block_range.0 + 1) as usize;
+
+            if !force {
+                let pending = PendingBatch {
+                    pending_blocks: latest_block - remaining[0].number + 1,
+                    size: data.len(),
+                    full: block_count < remaining.len(),
+                    oldest_block_timestamp: remaining[0].timestamp,
+                    l1_base_fee: self.poster.l1_base_fee().await?,
+                };
+                let Some(reason) = self.policy.should_post(&pending) else {
+                    debug!(
+                        "Holding back {} pending blocks ({} bytes)",
+                        pending.pending_blocks, pending.size
+                    );
+                    break;
+                };
+                info!("Posting batch {} on {}", batch.batch_number, reason);
+            }
+            remaining = &remaining[block_count..];
 
-        // Submit to L1
-        let l1_tx_hash = self.submit_batch_to_l1(&batch).await?;
+            // Submit to L1
+            let result = self.submit_batch_to_l1(&batch, &data).await?;
+            self.record_batch(batch, &result, data.len()).await?;
+        }
 
-        // Store batch information
-        let mut batch_with_l1_hash = batch;
-        batch_with_l1_hash.l1_tx_hash = Some(l1_tx_hash);
+        Ok(())
+    }
 
-        self.storage.store_batch(&batch_with_l1_hash).await?;
+    /// Store a batch confirmed on L1 and advance past its blocks
+    async fn record_batch(
+        &self,
+        mut batch: ArbitrumBatch,
+        result: &BatchSubmissionResult,
+        data_len: usize,
+    ) -> Result<()> {
+        batch.l1_tx_hash = Some(result.l1_tx_hash);
+        batch.l1_block_number = result.l1_block_number;
+        self.storage.store_batch(&batch).await?;
 
         // Update last submitted block
         {
             let mut last_submitted = self.last_submitted_block.write().await;
-            *last_submitted = end_block;
+            *last_submitted = (*last_submitted).max(result.end_block);
         }
+        *self.last_posting_mode.write().await = Some(result.data_posting_mode);
 
         info!(
-            "Batch submitted successfully: blocks {}-{}, L1 tx: {:?}",
-            start_block, end_block, l1_tx_hash
+            "Batch submitted successfully: blocks {}-{}, {} bytes, L1 tx: {:?}",
+            result.start_block, result.end_block, data_len, result.l1_tx_hash
         );
-
         Ok(())
     }
 
@@ -167,34 +271,105 @@ impl BatchSubmitter {
         Ok(blocks)
     }
 
-    /// Create a batch from the given blocks
-    async fn create_batch(&self, blocks: Vec<ArbitrumBlock>) -> Result<ArbitrumBatch> {
+    /// Create a batch from the leading blocks that fit into the size limit
+    ///
+    /// Returns the batch together with the compressed data to post.
+    async fn create_batch(&self, blocks: &[ArbitrumBlock]) -> Result<(ArbitrumBatch, Vec<u8>)> {
         debug!("Creating batch from {} blocks", blocks.len());
 
         if blocks.is_empty() {
-            return Err(eyre::eyre!("Cannot create batch from empty blocks"));
+            return Err(eyre!("Cannot create batch from empty blocks"));
         }
 
-        let start_block = blocks
-            .first()
-            .expect("Blocks vector should not be empty")
-            .number;
-        let end_block = blocks
-            .last()
-            .expect("Blocks vector should not be empty")
-            .number;
+        let latest = self.storage.get_latest_batch().await?;
+        let delayed_messages_read = latest
+            .as_ref()
+            .map_or(0, |batch| batch.after_delayed_messages_read);
+        let message_count = latest.as_ref().map_or(0, |batch| batch.message_counts.1);
+        let mut encoder = BatchEncoder::new(
+            self.config.sequencer.compression_level,
+            self.config.sequencer.max_batch_size,
+            delayed_messages_read,
+        );
+        for block in blocks {
+            let contents = self
+                .block_contents(block, encoder.delayed_messages_read())
+                .await?;
+            if !encoder.add_block(&contents)? {
+                debug!("Batch is full at block {}", block.number);
+                break;
+            }
+        }
+        let encoded = encoder.finish()?;
+        let included = &blocks[..encoded.block_count];
 
         // Get the next batch number
         let batch_number = self.get_next_batch_number().await?;
 
-        Ok(ArbitrumBatch {
+        let batch = ArbitrumBatch {
             batch_number,
-            block_range: (start_block, end_block),
-            l1_block_number: 0, // Will be set when submitted to L1
+            block_range: (included[0].number, included[included.len() - 1].number),
+            l1_block_number: 0, // Will be set once included on L1
             timestamp: chrono::Utc::now().timestamp() as u64,
-            transactions: blocks.iter().flat_map(|b| b.transactions.clone()).collect(),
+            transactions: included
+                .iter()
+                .flat_map(|b| b.transactions.clone())
+                .collect(),
             l1_tx_hash: None, // Will be filled after L1 submission
-        })
+            after_delayed_messages_read: encoded.after_delayed_messages_read,
+            message_counts: (message_count, message_count + encoded.message_count),
+            data_gas: 0, // Measured once read back from L1
+        };
+        Ok((batch, encoded.data))
+    }
+
+    /// Collect what a block contributes to a batch
+    ///
+    /// Transactions derived from the delayed inbox are posted as reads of
+    /// their message, which must follow on from `delayed_messages_read`.
+    async fn block_contents(
+        &self,
+        block: &ArbitrumBlock,
+        delayed_messages_read: u64,
+    ) -> Result<BlockContents> {
+        let mut contents = BlockContents {
+            timestamp: block.timestamp,
+            l1_block_number: block.l1_block_number,
+            ..Default::default()
+        };
+
+        for hash in &block.transactions {
+            let tx =
+                self.storage.get_transaction(hash).await?.ok_or_else(|| {
+                    eyre!("Transaction {} of block {} not found", hash, block.number)
+                })?;
+
+            if let Some(message_number) = tx.l1_sequence_number {
+                let next = delayed_messages_read + contents.delayed_messages;
+                if message_number + 1 == next {
+                    continue; // Another transaction of the same message
+                }
+                if message_number != next {
+                    return Err(eyre!(
+                        "Block {} reads delayed message {} but {} is next",
+                        block.number,
+                        message_number,
+                        next
+                    ));
+                }
+                contents.delayed_messages += 1;
+            } else if tx.kind == ArbitrumTxKind::Signed && !tx.raw.is_empty() {
+                contents.transactions.push(tx.raw);
+            } else {
+                return Err(eyre!(
+                    "Transaction {} of block {} has no signed encoding to post",
+                    hash,
+                    block.number
+                ));
+            }
+        }
+
+        Ok(contents)
     }
 
     /// Calculate the batch root hash
@@ -214,28 +389,75 @@ impl BatchSubmitter {
     }
 
     /// Submit the batch to L1
-    async fn submit_batch_to_l1(&self, batch: &ArbitrumBatch) -> Result<B256> {
-        info!("Submitting batch {} to L1", batch.batch_number);
-
-        // TODO: Implement actual L1 submission
-        // This would involve:
-        // 1. Encoding the batch data
-        // 2. Creating an L1 transaction
-        // 3. Signing and submitting the transaction
-        // 4. Waiting for confirmation
+    async fn submit_batch_to_l1(
+        &self,
+        batch: &ArbitrumBatch,
+        data: &[u8],
+    ) -> Result<BatchSubmissionResult> {
+        self.check_batch_is_new(batch).await?;
+        info!(
+            "Submitting batch {} to L1 ({} bytes)",
+            batch.batch_number,
+            data.len()
+        );
 
-        // For now, return a dummy transaction hash
-        let dummy_hash = B256::from_slice(&[0u8; 32]);
+        let posted = self.poster.post_batch(batch, data).await?;
+        Ok(self.submission_result(batch, posted))
+    }
 
-        info!("Batch submitted to L1 with tx hash: {:?}", dummy_hash);
-        Ok(dummy_hash)
+    /// Summarize a batch posted to L1
+    fn submission_result(
+        &self,
+        batch: &ArbitrumBatch,
+        posted: PostedBatch,
+    ) -> BatchSubmissionResult {
+        let PostedBatch { receipt, mode } = posted;
+        info!(
+            "Batch {} confirmed in L1 block {}",
+            batch.batch_number, receipt.block_number
+        );
+        BatchSubmissionResult {
+            batch_number: batch.batch_number,
+            l1_tx_hash: receipt.transaction_hash,
+            l1_block_number: receipt.block_number,
+            start_block: batch.block_range.0,
+            end_block: batch.block_range.1,
+            gas_used: receipt.gas_used,
+            data_posting_mode: mode,
+        }
     }
 
     /// Get the next batch number
+    ///
+    /// Batch 0 is posted when the rollup is created, so the sequencer starts
+    /// numbering from 1.
     async fn get_next_batch_number(&self) -> Result<u64> {
-        // TODO: Implement proper batch number tracking
-        // This should query the last batch number from storage and increment
-        Ok(1)
+        Ok(self
+            .storage
+            .get_latest_batch()
+            .await?
+            .map_or(1, |latest| latest.batch_number + 1))
+    }
+
+    /// Refuse to post a batch already stored or covering posted blocks
+    async fn check_batch_is_new(&self, batch: &ArbitrumBatch) -> Result<()> {
+        if self.storage.get_batch(batch.batch_number).await?.is_some() {
+            return Err(eyre!("Batch {} is already stored", batch.batch_number));
+        }
+        if let Some(latest) = self.storage.get_latest_batch().await?
+            && batch.block_range.0 <= latest.block_range.1
+        {
+            return Err(eyre!(
+                "Batch {} with blocks {}-{} overlaps batch {} with blocks {}-{}",
+                batch.batch_number,
+                batch.block_range.0,
+                batch.block_range.1,
+                latest.batch_number,
+                latest.block_range.0,
+                latest.block_range.1
+            ));
+        }
+        Ok(())
     }
 
     /// Get batch submission statistics
@@ -248,31 +470,14 @@ impl BatchSubmitter {
             latest_block,
             pending_blocks: latest_block.saturating_sub(last_submitted),
             total_batches_submitted: 0, // TODO: Track this
+            data_posting_mode: *self.last_posting_mode.read().await,
         }
     }
 
-    /// Check if the submitter should submit a batch based on time
-    #[allow(dead_code)]
-    async fn should_submit_by_time(&self) -> bool {
-        // TODO: Implement time-based batch submission
-        // This would check if enough time has passed since the last submission
-        false
-    }
-
-    /// Check if the submitter should submit a batch based on size
-    #[allow(dead_code)]
-    async fn should_submit_by_size(&self) -> bool {
-        let latest_block = self.storage.get_current_block_number().await.unwrap_or(0);
-        let last_submitted = *self.last_submitted_block.read().await;
-        let pending_blocks = latest_block.saturating_sub(last_submitted);
-
-        pending_blocks >= self.config.sequencer.batch_size as u64
-    }
-
     /// Force submit current pending blocks
     pub async fn force_submit(&self) -> Result<()> {
         info!("Force submitting current pending blocks");
-        self.try_submit_batch().await
+        self.try_submit_batch(true).await
     }
 
     /// Helper method to clone for async tasks
@@ -282,6 +487,9 @@ impl BatchSubmitter {
             storage: Arc::clone(&self.storage),
             is_running: Arc::clone(&self.is_running),
             last_submitted_block: Arc::clone(&self.last_submitted_block),
+            last_posting_mode: Arc::clone(&self.last_posting_mode),
+            poster: Arc::clone(&self.poster),
+            policy: Arc::clone(&self.policy),
         }
     }
 }
@@ -294,6 +502,8 @@ pub struct BatchSubmitterStats {
     pub latest_block: u64,
     pub pending_blocks: u64,
     pub total_batches_submitted: u64,
+    /// How the data of the latest batch was posted; `None` before the first
+    pub data_posting_mode: Option<DataPostingMode>,
 }
 
 /// Batch submission result
@@ -302,7 +512,294 @@ pub struct BatchSubmitterStats {
 pub struct BatchSubmissionResult {
     pub batch_number: u64,
     pub l1_tx_hash: B256,
+    pub l1_block_number: u64,
     pub start_block: u64,
     pub end_block: u64,
     pub gas_used: u64,
+    pub data_posting_mode: DataPostingMode,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::RangeInclusive;
+
+    use alloy_primitives::{Address, U256, hex, keccak256};
+    use alloy_sol_types::SolEvent;
+    use arbitrum_inbox_tracker::{
+        BatchMessageContent, InboxTracker, SequencerMessageHeader,
+        batch::decode_batch,
+        events::{
+            SequencerBatchDelivered, TimeBounds, decode_batch_from_origin, decode_message_counts,
+        },
+    };
+    use arbitrum_l1_client::{L1Log, MockL1Client};
+    use arbitrum_storage::ArbitrumTransaction;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::poster::tests::{poster_config, spawn_l1_rpc};
+
+    // EIP-155 example transaction
+    fn signed_tx() -> ArbitrumTransaction {
+        let raw = hex!(
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+        ArbitrumTransaction {
+            hash: keccak256(raw),
+            from: Address::repeat_byte(0x9d),
+            to: Some(Address::repeat_byte(0x35)),
+            value: U256::from(10u64.pow(18)),
+            gas: 21_000,
+            gas_price: U256::from(20_000_000_000u64),
+            nonce: 9,
+            data: vec![],
+            l1_sequence_number: None,
+            raw: raw.to_vec(),
+            kind: ArbitrumTxKind::Signed,
+        }
+    }
+
+    /// Store blocks each carrying the transaction
+    async fn store_blocks(
+        storage: &ArbitrumStorage,
+        tx: &ArbitrumTransaction,
+        numbers: RangeInclusive<u64>,
+    ) {
+        for number in numbers {
+            let block = ArbitrumBlock {
+                number,
+                hash: B256::with_last_byte(number as u8),
+                parent_hash: B256::with_last_byte(number as u8 - 1),
+                timestamp: 1_700_000_000 + number,
+                gas_used: 21_000,
+                gas_limit: 32_000_000,
+                transactions: vec![tx.hash],
+                l1_block_number: 5,
+                ..Default::default()
+            };
+            storage.store_block(&block).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submitted_batch_records_l1_inclusion() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(10).await;
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = poster_config(&spawn_l1_rpc(l1.clone()).await);
+        config.node.datadir = temp_dir.path().to_path_buf();
+        config.sequencer.batch_size = 2;
+        let storage = Arc::new(ArbitrumStorage::new(&config).await.unwrap());
+
+        let tx = signed_tx();
+        storage.store_transaction(&tx).await.unwrap();
+        store_blocks(&storage, &tx, 1..=2).await;
+
+        let submitter = BatchSubmitter::new(&config, Arc::clone(&storage))
+            .await
+            .unwrap();
+        submitter.force_submit().await.unwrap();
+
+        let batch = storage.get_batch(1).await.unwrap().unwrap();
+        assert_eq!(batch.block_range, (1, 2));
+        assert_eq!(batch.message_counts, (0, 2));
+        let l1_tx_hash = batch.l1_tx_hash.unwrap();
+        let receipt = l1.transaction_receipt(l1_tx_hash).await.unwrap().unwrap();
+        assert_eq!(batch.l1_block_number, receipt.block_number);
+        let stats = submitter.get_stats().await;
+        assert_eq!(stats.last_submitted_block, 2);
+        assert_eq!(stats.data_posting_mode, Some(DataPostingMode::Calldata));
+
+        // The posted data decodes into one message per block
+        let l1_tx = l1.transaction_by_hash(l1_tx_hash).await.unwrap().unwrap();
+        let data = decode_batch_from_origin(&l1_tx.input, 1).unwrap();
+        let header = SequencerMessageHeader {
+            min_timestamp: 0,
+            max_timestamp: u64::MAX,
+            min_l1_block: 0,
+            max_l1_block: u64::MAX,
+            after_delayed_messages: 0,
+        };
+        let messages = decode_batch(1, &[header.to_bytes().as_slice(), &data].concat(), 0).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(decode_message_counts(&l1_tx.input).unwrap(), (0, 2));
+        assert_eq!(messages[1].timestamp, 1_700_000_002);
+        assert_eq!(messages[1].l1_block_number, 5);
+    }
+
+    #[tokio::test]
+    async fn test_inbox_tracker_derives_batches_posted_in_blobs() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(10).await;
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = poster_config(&spawn_l1_rpc(l1.clone()).await);
+        config.node.datadir = temp_dir.path().to_path_buf();
+        config.sequencer.batch_size = 2;
+        config.sequencer.post_blobs = true;
+        let storage = Arc::new(ArbitrumStorage::new(&config).await.unwrap());
+
+        let tx = signed_tx();
+        storage.store_transaction(&tx).await.unwrap();
+        store_blocks(&storage, &tx, 1..=2).await;
+
+        let submitter = BatchSubmitter::new(&config, Arc::clone(&storage))
+            .await
+            .unwrap();
+        submitter.force_submit().await.unwrap();
+        assert_eq!(
+            submitter.get_stats().await.data_posting_mode,
+            Some(DataPostingMode::Blobs)
+        );
+        let batch = storage.get_batch(1).await.unwrap().unwrap();
+        let l1_tx_hash = batch.l1_tx_hash.unwrap();
+        let l1_tx = l1.transaction_by_hash(l1_tx_hash).await.unwrap().unwrap();
+        assert!(!l1_tx.blob_versioned_hashes.is_empty());
+
+        // The SequencerInbox announces the batch, its data left in the blobs
+        let log = SequencerBatchDelivered {
+            batchSequenceNumber: U256::from(1),
+            beforeAcc: B256::ZERO,
+            afterAcc: B256::repeat_byte(1),
+            delayedAcc: B256::ZERO,
+            afterDelayedMessagesRead: U256::ZERO,
+            timeBounds: TimeBounds {
+                minTimestamp: 0,
+                maxTimestamp: u64::MAX,
+                minBlockNumber: 0,
+                maxBlockNumber: u64::MAX,
+            },
+            dataLocation: 3,
+        }
+        .encode_log_data();
+        l1.add_log(L1Log {
+            address: config.l1.sequencer_inbox_address,
+            topics: log.topics().to_vec(),
+            data: log.data.to_vec(),
+            block_number: batch.l1_block_number,
+            block_hash: B256::ZERO,
+            transaction_hash: l1_tx_hash,
+            transaction_index: 0,
+            log_index: 0,
+            removed: false,
+        })
+        .await
+        .unwrap();
+
+        // Another node reads the batch, the poster does not execute it again
+        let follower_dir = TempDir::new().unwrap();
+        let mut follower_config = config.clone();
+        follower_config.node.datadir = follower_dir.path().to_path_buf();
+        let follower = Arc::new(ArbitrumStorage::new(&follower_config).await.unwrap());
+        let tracker =
+            InboxTracker::with_l1_client(&follower_config, Arc::clone(&follower), Arc::new(l1))
+                .await
+                .unwrap();
+        tracker.start().await.unwrap();
+        let mut messages = Vec::new();
+        while messages.len() < 2 {
+            match tracker.next_batch_message().await {
+                Some(message) => messages.push(message),
+                None => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        }
+        tracker.stop().await.unwrap();
+
+        // The tracker decodes the same messages as the posted data, one per block
+        assert!(messages.iter().all(|m| m.batch_number == 1));
+        assert_eq!(messages[1].timestamp, 1_700_000_002);
+        assert_eq!(messages[1].l1_block_number, 5);
+        let BatchMessageContent::L2Message(message) = &messages[0].content else {
+            panic!("expected an L2 message");
+        };
+        assert_eq!(message[1..], tx.raw[..]);
+        assert!(follower.get_batch(1).await.unwrap().unwrap().data_gas > 0);
+    }
+
+    #[tokio::test]
+    async fn test_restarted_submitter_continues_batch_numbering() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(10).await;
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = poster_config(&spawn_l1_rpc(l1.clone()).await);
+        config.node.datadir = temp_dir.path().to_path_buf();
+        config.sequencer.batch_size = 2;
+        let storage = Arc::new(ArbitrumStorage::new(&config).await.unwrap());
+        let tx = signed_tx();
+        storage.store_transaction(&tx).await.unwrap();
+        store_blocks(&storage, &tx, 1..=2).await;
+
+        let submitter = BatchSubmitter::new(&config, Arc::clone(&storage))
+            .await
+            .unwrap();
+        submitter.force_submit().await.unwrap();
+        let first = storage.get_batch(1).await.unwrap().unwrap();
+        drop(submitter);
+
+        store_blocks(&storage, &tx, 3..=4).await;
+        let restarted = BatchSubmitter::new(&config, Arc::clone(&storage))
+            .await
+            .unwrap();
+        assert_eq!(restarted.get_stats().await.last_submitted_block, 2);
+        restarted.force_submit().await.unwrap();
+
+        let unchanged = storage.get_batch(1).await.unwrap().unwrap();
+        assert_eq!(unchanged.l1_tx_hash, first.l1_tx_hash);
+        assert_eq!(unchanged.block_range, (1, 2));
+        let latest = storage.get_latest_batch().await.unwrap().unwrap();
+        assert_eq!(latest.batch_number, 2);
+        assert_eq!(latest.block_range, (3, 4));
+        assert_eq!(latest.message_counts, (2, 4));
+        assert_eq!(restarted.get_stats().await.last_submitted_block, 4);
+
+        // Blocks covered by a batch stored behind the submitter's back
+        store_blocks(&storage, &tx, 5..=6).await;
+        storage
+            .store_batch(&ArbitrumBatch {
+                batch_number: 3,
+                block_range: (5, 6),
+                ..latest
+            })
+            .await
+            .unwrap();
+        assert!(restarted.force_submit().await.is_err());
+        assert!(storage.get_batch(4).await.unwrap().is_none());
+        assert_eq!(restarted.get_stats().await.last_submitted_block, 4);
+    }
+
+    #[tokio::test]
+    async fn test_policy_holds_back_blocks_until_timeout() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(10).await;
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = poster_config(&spawn_l1_rpc(l1.clone()).await);
+        config.node.datadir = temp_dir.path().to_path_buf();
+        config.sequencer.batch_size = 10;
+        config.sequencer.batch_timeout = 5_000;
+        let storage = Arc::new(ArbitrumStorage::new(&config).await.unwrap());
+        let tx = signed_tx();
+        storage.store_transaction(&tx).await.unwrap();
+        store_blocks(&storage, &tx, 1..=2).await;
+
+        // Block 1 is two seconds old
+        let clock = Arc::new(MockClock::new(1_700_000_003_000));
+        let policy = Arc::new(DefaultPostingPolicy::with_clock(
+            &config.sequencer,
+            clock.clone(),
+        ));
+        let l1_client = Arc::new(JsonRpcL1Client::new(&config.l1).unwrap());
+        let submitter =
+            BatchSubmitter::with_posting_policy(&config, Arc::clone(&storage), l1_client, policy)
+                .await
+                .unwrap();
+
+        submitter.try_submit_batch(false).await.unwrap();
+        assert!(storage.get_latest_batch().await.unwrap().is_none());
+        assert_eq!(submitter.get_stats().await.pending_blocks, 2);
+
+        clock.advance(3_000);
+        submitter.try_submit_batch(false).await.unwrap();
+        let batch = storage.get_latest_batch().await.unwrap().unwrap();
+        assert_eq!(batch.block_range, (1, 2));
+        assert_eq!(submitter.get_stats().await.pending_blocks, 0);
+    }
 }
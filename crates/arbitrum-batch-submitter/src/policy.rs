@@ -0,0 +1,211 @@
+//! When to post the pending blocks as a batch
+//!
+//! Posting early keeps the latency of L1 finality low, posting late amortizes
+//! the fixed cost of an L1 transaction over more data. The submitter asks a
+//! [`PostingPolicy`] on every poll whether the batch it would post now is worth
+//! it. Policies read time from a [`Clock`], so they can be driven by a
+//! [`MockClock`] under tests.
+
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use arbitrum_config::SequencerConfig;
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    /// Milliseconds since the Unix epoch
+    fn now_millis(&self) -> u64;
+}
+
+/// The system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        chrono::Utc::now().timestamp_millis() as u64
+    }
+}
+
+/// A clock that only moves when told to
+#[derive(Debug, Default)]
+pub struct MockClock {
+    now: AtomicU64,
+}
+
+impl MockClock {
+    pub fn new(now_millis: u64) -> Self {
+        Self {
+            now: AtomicU64::new(now_millis),
+        }
+    }
+
+    pub fn set(&self, now_millis: u64) {
+        self.now.store(now_millis, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, millis: u64) {
+        self.now.fetch_add(millis, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_millis(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+/// The batch the submitter would post now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingBatch {
+    /// Blocks produced since the last posted batch
+    pub pending_blocks: u64,
+    /// Size of the compressed batch data in bytes
+    pub size: usize,
+    /// Whether the batch ran into its size limit before taking every block
+    pub full: bool,
+    /// Timestamp in seconds of the oldest block waiting to be posted
+    pub oldest_block_timestamp: u64,
+    /// Base fee of the L1 head in wei
+    pub l1_base_fee: u128,
+}
+
+/// Why a batch is posted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostingReason {
+    /// The batch is at or near its size limit
+    Size,
+    /// The oldest pending block has waited for the batch timeout
+    Timeout,
+    /// L1 gas is cheap enough to post early
+    CheapGas,
+}
+
+impl fmt::Display for PostingReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Size => write!(f, "size"),
+            Self::Timeout => write!(f, "timeout"),
+            Self::CheapGas => write!(f, "cheap L1 gas"),
+        }
+    }
+}
+
+/// Decides whether a pending batch is posted now
+pub trait PostingPolicy: Send + Sync {
+    /// The reason to post the batch now, or `None` to keep waiting
+    fn should_post(&self, pending: &PendingBatch) -> Option<PostingReason>;
+}
+
+/// Compressed size, in percent of the limit, at which a batch counts as full
+const SIZE_THRESHOLD_PERCENT: usize = 95;
+
+/// Posts on size, on timeout or when L1 gas is cheap, as configured
+pub struct DefaultPostingPolicy {
+    batch_size: u64,
+    max_batch_size: usize,
+    batch_timeout: u64,
+    cheap_l1_base_fee: u64,
+    clock: Arc<dyn Clock>,
+}
+
+impl DefaultPostingPolicy {
+    /// Create a policy on the system clock
+    pub fn new(config: &SequencerConfig) -> Self {
+        Self::with_clock(config, Arc::new(SystemClock))
+    }
+
+    /// Create a policy on the given clock
+    pub fn with_clock(config: &SequencerConfig, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            batch_size: config.batch_size as u64,
+            max_batch_size: config.max_batch_size,
+            batch_timeout: config.batch_timeout,
+            cheap_l1_base_fee: config.cheap_l1_base_fee,
+            clock,
+        }
+    }
+}
+
+impl PostingPolicy for DefaultPostingPolicy {
+    fn should_post(&self, pending: &PendingBatch) -> Option<PostingReason> {
+        if pending.full
+            || pending.pending_blocks >= self.batch_size
+            || pending.size * 100 >= self.max_batch_size * SIZE_THRESHOLD_PERCENT
+        {
+            return Some(PostingReason::Size);
+        }
+
+        let age = self
+            .clock
+            .now_millis()
+            .saturating_sub(pending.oldest_block_timestamp.saturating_mul(1_000));
+        if age >= self.batch_timeout {
+            return Some(PostingReason::Timeout);
+        }
+
+        if self.cheap_l1_base_fee > 0 && pending.l1_base_fee <= u128::from(self.cheap_l1_base_fee) {
+            return Some(PostingReason::CheapGas);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrum_config::ArbitrumRethConfig;
+
+    use super::*;
+
+    #[test]
+    fn test_default_policy_posts_on_size_timeout_and_cheap_gas() {
+        let mut config = ArbitrumRethConfig::default().sequencer;
+        config.batch_size = 10;
+        config.max_batch_size = 1_000;
+        config.batch_timeout = 5_000;
+        config.cheap_l1_base_fee = 1_000_000_000;
+        let clock = Arc::new(MockClock::new(1_700_000_002_000));
+        let policy = DefaultPostingPolicy::with_clock(&config, clock.clone());
+
+        let pending = PendingBatch {
+            pending_blocks: 2,
+            size: 300,
+            full: false,
+            oldest_block_timestamp: 1_700_000_000,
+            l1_base_fee: 2_000_000_000,
+        };
+        assert_eq!(policy.should_post(&pending), None);
+
+        let full = PendingBatch {
+            full: true,
+            ..pending.clone()
+        };
+        assert_eq!(policy.should_post(&full), Some(PostingReason::Size));
+        let near_limit = PendingBatch {
+            size: 950,
+            ..pending.clone()
+        };
+        assert_eq!(policy.should_post(&near_limit), Some(PostingReason::Size));
+        let many_blocks = PendingBatch {
+            pending_blocks: 10,
+            ..pending.clone()
+        };
+        assert_eq!(policy.should_post(&many_blocks), Some(PostingReason::Size));
+
+        let cheap = PendingBatch {
+            l1_base_fee: 1_000_000_000,
+            ..pending.clone()
+        };
+        assert_eq!(policy.should_post(&cheap), Some(PostingReason::CheapGas));
+
+        clock.advance(2_999);
+        assert_eq!(policy.should_post(&pending), None);
+        clock.advance(1);
+        assert_eq!(policy.should_post(&pending), Some(PostingReason::Timeout));
+    }
+}
@@ -0,0 +1,906 @@
+//! Posting of batches to the SequencerInbox on L1
+//!
+//! Wraps batch data in an `addSequencerL2BatchFromOrigin` call, signs it as
+//! an EIP-1559 transaction of the batch poster account and waits until the
+//! transaction is buried under the configured number of L1 confirmations.
+//! With blob posting enabled, batches whose data is cheaper to post in blobs
+//! go out as EIP-4844 transactions calling `addSequencerL2BatchFromBlobs`.
+//!
+//! The transaction awaiting confirmation is checkpointed in storage. If it is
+//! not included within the replacement timeout it is re-broadcast under the
+//! same nonce with bumped fees, and a restarted poster picks it up again.
+
+use std::{sync::Arc, time::Duration};
+
+use alloy_consensus::{
+    SignableTransaction, Signed, TxEip1559, TxEip4844, TxEip4844Variant, TxEip4844WithSidecar,
+    TxEnvelope,
+};
+use alloy_eips::{
+    eip2718::Encodable2718,
+    eip4844::{BlobTransactionSidecar, DATA_GAS_PER_BLOB},
+};
+use alloy_primitives::{Address, B256, Signature, TxKind, U256};
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use arbitrum_config::ArbitrumRethConfig;
+use arbitrum_inbox_tracker::events::{encode_batch_from_blobs, encode_batch_from_origin};
+use arbitrum_l1_client::{L1CallRequest, L1Client, L1Receipt};
+use arbitrum_storage::{ArbitrumBatch, ArbitrumStorage, InFlightBatch};
+use eyre::{Result, eyre};
+use tokio::{sync::Mutex, time::sleep};
+use tracing::{debug, info, warn};
+
+use crate::blobs::{MAX_BLOBS_PER_TRANSACTION, blob_count, blob_sidecar, encode_blobs};
+
+/// Margin added to the L1 gas estimate, in percent
+const GAS_LIMIT_MARGIN_PERCENT: u64 = 20;
+
+/// Fee bump of a replacement transaction in percent, above geth's minimum of 10
+const FEE_BUMP_PERCENT: u128 = 25;
+
+/// How the data of a batch is made available on L1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataPostingMode {
+    /// In the calldata of the SequencerInbox call
+    Calldata,
+    /// In EIP-4844 blobs attached to the SequencerInbox call
+    Blobs,
+}
+
+/// A batch-posting transaction confirmed on L1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostedBatch {
+    pub receipt: L1Receipt,
+    pub mode: DataPostingMode,
+}
+
+/// Where a batch-posting transaction stands on L1
+enum Inclusion {
+    Pending,
+    /// Included, but not yet under enough confirmations
+    Included,
+    Confirmed(L1Receipt),
+    Reverted(L1Receipt),
+}
+
+/// Signs and sends batch-posting transactions from the batch poster account
+pub struct BatchPoster {
+    l1_client: Arc<dyn L1Client>,
+    storage: Arc<ArbitrumStorage>,
+    signer: PrivateKeySigner,
+    chain_id: u64,
+    sequencer_inbox: Address,
+    confirmation_blocks: u64,
+    poll_interval: Duration,
+    replacement_timeout: u64,
+    max_fee_per_gas: u128,
+    post_blobs: bool,
+    /// Next nonce to use; fetched from L1 on first use and after a failed send
+    nonce: Mutex<Option<u64>>,
+}
+
+impl BatchPoster {
+    /// Create a poster for the configured batch poster account
+    pub fn new(
+        config: &ArbitrumRethConfig,
+        l1_client: Arc<dyn L1Client>,
+        storage: Arc<ArbitrumStorage>,
+    ) -> Result<Self> {
+        let key = config
+            .sequencer
+            .batch_poster_private_key
+            .as_deref()
+            .ok_or_else(|| eyre!("Batch poster private key is not configured"))?;
+        let signer: PrivateKeySigner = key
+            .parse()
+            .map_err(|e| eyre!("Invalid batch poster private key: {e}"))?;
+
+        Ok(Self {
+            l1_client,
+            storage,
+            signer,
+            chain_id: config.l1.chain_id,
+            sequencer_inbox: config.l1.sequencer_inbox_address,
+            confirmation_blocks: config.l1.confirmation_blocks,
+            poll_interval: Duration::from_millis(config.l1.poll_interval),
+            replacement_timeout: config.sequencer.replacement_timeout,
+            max_fee_per_gas: u128::from(config.sequencer.max_l1_fee_per_gas),
+            post_blobs: config.sequencer.post_blobs,
+            nonce: Mutex::new(None),
+        })
+    }
+
+    /// Address of the batch poster account
+    pub fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    /// Post the data of a batch and wait for the transaction to be confirmed
+    pub async fn post_batch(&self, batch: &ArbitrumBatch, data: &[u8]) -> Result<PostedBatch> {
+        let mode = self.posting_mode(data).await?;
+        let mut in_flight = InFlightBatch {
+            batch: batch.clone(),
+            data: data.to_vec(),
+            blobs: mode == DataPostingMode::Blobs,
+            nonce: 0,
+            gas_limit: 0,
+            max_fee_per_gas: 0,
+            max_priority_fee_per_gas: 0,
+            max_fee_per_blob_gas: 0,
+            tx_hashes: Vec::new(),
+            sent_at: 0,
+        };
+        self.send(&mut in_flight).await?;
+        info!(
+            "Batch {} sent in L1 transaction {:?} using {:?}",
+            batch.batch_number,
+            in_flight.tx_hashes.last(),
+            mode
+        );
+
+        self.track(in_flight).await
+    }
+
+    /// Wait for a batch transaction sent before a restart to be confirmed
+    pub async fn resume(&self, in_flight: InFlightBatch) -> Result<PostedBatch> {
+        info!(
+            "Resuming batch {} in L1 transaction {:?} with nonce {}",
+            in_flight.batch.batch_number,
+            in_flight.tx_hashes.last(),
+            in_flight.nonce
+        );
+        {
+            // L1 may have dropped the transaction from its mempool meanwhile
+            let mut next_nonce = self.nonce.lock().await;
+            if next_nonce.is_none_or(|nonce| nonce <= in_flight.nonce) {
+                *next_nonce = Some(in_flight.nonce + 1);
+            }
+        }
+
+        self.track(in_flight).await
+    }
+
+    /// Pick the cheaper way of posting `data` at the current L1 fees
+    async fn posting_mode(&self, data: &[u8]) -> Result<DataPostingMode> {
+        let blobs = blob_count(data.len());
+        if !self.post_blobs || blobs > MAX_BLOBS_PER_TRANSACTION {
+            return Ok(DataPostingMode::Calldata);
+        }
+
+        let calldata_cost = u128::from(calldata_gas(data)) * self.l1_base_fee().await?;
+        let blob_cost =
+            blobs as u128 * u128::from(DATA_GAS_PER_BLOB) * self.l1_client.blob_base_fee().await?;
+        debug!(
+            "Posting {} bytes costs {} wei in calldata and {} wei in {} blobs",
+            data.len(),
+            calldata_cost,
+            blob_cost,
+            blobs
+        );
+
+        Ok(if blob_cost < calldata_cost {
+            DataPostingMode::Blobs
+        } else {
+            DataPostingMode::Calldata
+        })
+    }
+
+    /// Sign and broadcast the first version of a batch transaction
+    async fn send(&self, in_flight: &mut InFlightBatch) -> Result<()> {
+        let input = call_input(in_flight);
+        let sidecar = sidecar(in_flight)?;
+        let estimate = self
+            .l1_client
+            .estimate_gas(&L1CallRequest {
+                from: self.address(),
+                to: self.sequencer_inbox,
+                input: input.clone(),
+                blob_versioned_hashes: sidecar
+                    .iter()
+                    .flat_map(|sidecar| sidecar.versioned_hashes())
+                    .collect(),
+            })
+            .await?;
+        in_flight.gas_limit = estimate + estimate * GAS_LIMIT_MARGIN_PERCENT / 100;
+        (
+            in_flight.max_fee_per_gas,
+            in_flight.max_priority_fee_per_gas,
+        ) = self.fee_caps().await?;
+        if in_flight.blobs {
+            // Room for the blob base fee to double, as for the base fee
+            in_flight.max_fee_per_blob_gas = 2 * self.l1_client.blob_base_fee().await?;
+        }
+
+        // Held until the transaction is broadcast so nonces are used in order
+        let mut next_nonce = self.nonce.lock().await;
+        in_flight.nonce = match *next_nonce {
+            Some(nonce) => nonce,
+            None => {
+                self.l1_client
+                    .transaction_count(self.address(), true)
+                    .await?
+            }
+        };
+
+        match self.broadcast(in_flight, input, sidecar).await {
+            Ok(()) => {
+                *next_nonce = Some(in_flight.nonce + 1);
+                Ok(())
+            }
+            Err(e) => {
+                // The nonce may be stale; ask L1 again next time
+                *next_nonce = None;
+                self.storage.delete_in_flight_batch().await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Wait for a batch transaction to be confirmed, replacing it while stuck
+    async fn track(&self, mut in_flight: InFlightBatch) -> Result<PostedBatch> {
+        let mode = if in_flight.blobs {
+            DataPostingMode::Blobs
+        } else {
+            DataPostingMode::Calldata
+        };
+
+        loop {
+            match self.inclusion(&in_flight.tx_hashes).await? {
+                Inclusion::Confirmed(receipt) => {
+                    self.storage.delete_in_flight_batch().await?;
+                    return Ok(PostedBatch { receipt, mode });
+                }
+                Inclusion::Reverted(receipt) => {
+                    self.storage.delete_in_flight_batch().await?;
+                    return Err(eyre!(
+                        "L1 transaction {:?} reverted",
+                        receipt.transaction_hash
+                    ));
+                }
+                Inclusion::Included => {}
+                Inclusion::Pending => {
+                    if now_millis() >= in_flight.sent_at + self.replacement_timeout {
+                        self.replace(&mut in_flight).await?;
+                    }
+                }
+            }
+
+            sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Check which, if any, version of a transaction made it into L1
+    async fn inclusion(&self, tx_hashes: &[B256]) -> Result<Inclusion> {
+        // Versions share a nonce, so at most one of them is included
+        for hash in tx_hashes.iter().rev() {
+            let Some(receipt) = self.l1_client.transaction_receipt(*hash).await? else {
+                continue;
+            };
+            if !receipt.success {
+                return Ok(Inclusion::Reverted(receipt));
+            }
+
+            let head = self.l1_client.block_number().await?;
+            if head >= receipt.block_number + self.confirmation_blocks {
+                // The including block may have been reorged out meanwhile
+                let canonical = self
+                    .l1_client
+                    .block_by_number(receipt.block_number)
+                    .await?
+                    .is_some_and(|header| header.hash == receipt.block_hash);
+                if canonical {
+                    return Ok(Inclusion::Confirmed(receipt));
+                }
+            }
+            return Ok(Inclusion::Included);
+        }
+
+        Ok(Inclusion::Pending)
+    }
+
+    /// Re-broadcast a stuck transaction under its nonce with bumped fees
+    async fn replace(&self, in_flight: &mut InFlightBatch) -> Result<()> {
+        let (market_fee, market_tip) = match self.fee_caps().await {
+            Ok(caps) => caps,
+            Err(e) => {
+                debug!("Bumping fees without a market estimate: {}", e);
+                (0, 0)
+            }
+        };
+        let max_fee_per_gas = bump(in_flight.max_fee_per_gas).max(market_fee);
+        if max_fee_per_gas > self.max_fee_per_gas {
+            warn!(
+                "Batch {} transaction is stuck at max fee {}, the cap is {}",
+                in_flight.batch.batch_number, in_flight.max_fee_per_gas, self.max_fee_per_gas
+            );
+            in_flight.sent_at = now_millis();
+            return self.storage.store_in_flight_batch(in_flight).await;
+        }
+        in_flight.max_fee_per_gas = max_fee_per_gas;
+        in_flight.max_priority_fee_per_gas = bump(in_flight.max_priority_fee_per_gas)
+            .max(market_tip)
+            .min(max_fee_per_gas);
+        if in_flight.blobs {
+            // Blob pools only accept replacements doubling the blob fee
+            let market_blob_fee = 2 * self.l1_client.blob_base_fee().await?;
+            in_flight.max_fee_per_blob_gas =
+                (2 * in_flight.max_fee_per_blob_gas).max(market_blob_fee);
+        }
+
+        let input = call_input(in_flight);
+        let sidecar = sidecar(in_flight)?;
+        match self.broadcast(in_flight, input, sidecar).await {
+            Ok(()) => {
+                info!(
+                    "Replaced batch {} transaction with {:?} at max fee {}",
+                    in_flight.batch.batch_number,
+                    in_flight.tx_hashes.last(),
+                    in_flight.max_fee_per_gas
+                );
+                Ok(())
+            }
+            Err(e) if e.to_string().contains("nonce too low") => {
+                if !matches!(
+                    self.inclusion(&in_flight.tx_hashes).await?,
+                    Inclusion::Pending
+                ) {
+                    return Ok(());
+                }
+                // No version of ours took the nonce, so the batch needs a new one
+                warn!(
+                    "Nonce {} of batch {} was used by another transaction, sending it again",
+                    in_flight.nonce, in_flight.batch.batch_number
+                );
+                *self.nonce.lock().await = None;
+                in_flight.tx_hashes.clear();
+                self.send(in_flight).await
+            }
+            Err(e) => {
+                // Underpriced replacements are retried with another bump
+                warn!(
+                    "Failed to replace batch {} transaction: {}",
+                    in_flight.batch.batch_number, e
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Sign the current version of a batch transaction, checkpoint and send it
+    ///
+    /// The checkpoint goes first so that a restart keeps tracking the
+    /// transaction; a version L1 refuses is dropped from it again.
+    async fn broadcast(
+        &self,
+        in_flight: &mut InFlightBatch,
+        input: Vec<u8>,
+        sidecar: Option<BlobTransactionSidecar>,
+    ) -> Result<()> {
+        let envelope = match sidecar {
+            None => self.sign(TxEip1559 {
+                chain_id: self.chain_id,
+                nonce: in_flight.nonce,
+                gas_limit: in_flight.gas_limit,
+                max_fee_per_gas: in_flight.max_fee_per_gas,
+                max_priority_fee_per_gas: in_flight.max_priority_fee_per_gas,
+                to: TxKind::Call(self.sequencer_inbox),
+                value: U256::ZERO,
+                access_list: Default::default(),
+                input: input.into(),
+            })?,
+            Some(sidecar) => {
+                let tx = TxEip4844 {
+                    chain_id: self.chain_id,
+                    nonce: in_flight.nonce,
+                    gas_limit: in_flight.gas_limit,
+                    max_fee_per_gas: in_flight.max_fee_per_gas,
+                    max_priority_fee_per_gas: in_flight.max_priority_fee_per_gas,
+                    to: self.sequencer_inbox,
+                    value: U256::ZERO,
+                    access_list: Default::default(),
+                    blob_versioned_hashes: sidecar.versioned_hashes().collect(),
+                    max_fee_per_blob_gas: in_flight.max_fee_per_blob_gas,
+                    input: input.into(),
+                };
+                self.sign(TxEip4844Variant::from(
+                    TxEip4844WithSidecar::from_tx_and_sidecar(tx, sidecar),
+                ))?
+            }
+        };
+        let hash = *envelope.tx_hash();
+        debug!(
+            "Sending L1 transaction {:?} with nonce {}, gas limit {}, max fee {}",
+            hash, in_flight.nonce, in_flight.gas_limit, in_flight.max_fee_per_gas
+        );
+
+        in_flight.tx_hashes.push(hash);
+        in_flight.sent_at = now_millis();
+        self.storage.store_in_flight_batch(in_flight).await?;
+
+        match self
+            .l1_client
+            .send_raw_transaction(&envelope.encoded_2718())
+            .await
+        {
+            Ok(returned) => {
+                if returned != hash {
+                    warn!(
+                        "L1 node reported hash {:?} for transaction {:?}",
+                        returned, hash
+                    );
+                }
+                Ok(())
+            }
+            Err(e) => {
+                in_flight.tx_hashes.pop();
+                self.storage.store_in_flight_batch(in_flight).await?;
+                Err(e)
+            }
+        }
+    }
+
+    fn sign<T>(&self, tx: T) -> Result<TxEnvelope>
+    where
+        T: SignableTransaction<Signature>,
+        TxEnvelope: From<Signed<T>>,
+    {
+        let signature = self.signer.sign_hash_sync(&tx.signature_hash())?;
+        Ok(TxEnvelope::from(tx.into_signed(signature)))
+    }
+
+    /// Base fee of the latest L1 block
+    pub async fn l1_base_fee(&self) -> Result<u128> {
+        let head = self.l1_client.block_number().await?;
+        let base_fee = self
+            .l1_client
+            .block_by_number(head)
+            .await?
+            .and_then(|header| header.base_fee_per_gas)
+            .ok_or_else(|| eyre!("L1 block {} has no base fee", head))?;
+        Ok(u128::from(base_fee))
+    }
+
+    /// EIP-1559 max fee and priority fee, within the configured cap
+    async fn fee_caps(&self) -> Result<(u128, u128)> {
+        let base_fee = self.l1_base_fee().await?;
+        if base_fee >= self.max_fee_per_gas {
+            return Err(eyre!(
+                "L1 base fee {} exceeds the cap of {}",
+                base_fee,
+                self.max_fee_per_gas
+            ));
+        }
+
+        let priority_fee = self.l1_client.max_priority_fee_per_gas().await?;
+        // Room for the base fee to double before the transaction is included
+        let max_fee = (2 * base_fee + priority_fee).min(self.max_fee_per_gas);
+        Ok((max_fee, priority_fee.min(max_fee - base_fee)))
+    }
+}
+
+/// SequencerInbox call posting the batch
+///
+/// The message counts are those of the L2 stream before and after the
+/// batch, which the batch data decodes into.
+fn call_input(in_flight: &InFlightBatch) -> Vec<u8> {
+    let batch = &in_flight.batch;
+    let (prev_message_count, new_message_count) = batch.message_counts;
+    if in_flight.blobs {
+        encode_batch_from_blobs(
+            batch.batch_number,
+            batch.after_delayed_messages_read,
+            prev_message_count,
+            new_message_count,
+        )
+    } else {
+        encode_batch_from_origin(
+            batch.batch_number,
+            &in_flight.data,
+            batch.after_delayed_messages_read,
+            prev_message_count,
+            new_message_count,
+        )
+    }
+}
+
+/// Blob sidecar carrying the batch data, if it is posted in blobs
+fn sidecar(in_flight: &InFlightBatch) -> Result<Option<BlobTransactionSidecar>> {
+    if !in_flight.blobs {
+        return Ok(None);
+    }
+    Ok(Some(blob_sidecar(encode_blobs(&in_flight.data))?))
+}
+
+fn bump(fee: u128) -> u128 {
+    fee + fee * FEE_BUMP_PERCENT / 100
+}
+
+fn now_millis() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+/// Calldata gas of `data`, as charged since EIP-2028
+fn calldata_gas(data: &[u8]) -> u64 {
+    let zeros = data.iter().filter(|b| **b == 0).count() as u64;
+    zeros * 4 + (data.len() as u64 - zeros) * 16
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use alloy_primitives::U64;
+    use arbitrum_inbox_tracker::events::decode_batch_from_origin;
+    use arbitrum_l1_client::{JsonRpcL1Client, MockL1Client, mock::MOCK_CHAIN_ID};
+    use axum::{Json, Router, extract::State, routing::post};
+    use serde_json::{Value, json};
+    use tempfile::TempDir;
+    use tokio::task::JoinHandle;
+
+    use super::*;
+
+    /// Hardhat / anvil development account 0
+    pub(crate) const POSTER_KEY: &str =
+        "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn param<T: serde::de::DeserializeOwned>(request: &Value, index: usize) -> T {
+        serde_json::from_value(request["params"][index].clone()).unwrap()
+    }
+
+    /// JSON-RPC front of a mock chain
+    async fn handler(State(l1): State<MockL1Client>, Json(request): Json<Value>) -> Json<Value> {
+        let id = request["id"].clone();
+        let result = match request["method"].as_str().unwrap_or_default() {
+            "eth_chainId" => Ok(json!(U64::from(l1.chain_id().await.unwrap()))),
+            "eth_blockNumber" => Ok(json!(U64::from(l1.block_number().await.unwrap()))),
+            "eth_getBlockByNumber" => {
+                let number: U64 = param(&request, 0);
+                let header = l1.block_by_number(number.to()).await.unwrap();
+                Ok(header.map_or(Value::Null, |header| {
+                    json!({
+                        "number": U64::from(header.number),
+                        "hash": header.hash,
+                        "parentHash": header.parent_hash,
+                        "timestamp": U64::from(header.timestamp),
+                        "baseFeePerGas": header.base_fee_per_gas.map(U64::from),
+                    })
+                }))
+            }
+            "eth_getTransactionCount" => {
+                let pending = request["params"][1] == "pending";
+                let count = l1.transaction_count(param(&request, 0), pending).await;
+                Ok(json!(U64::from(count.unwrap())))
+            }
+            "eth_estimateGas" => {
+                let call = L1CallRequest {
+                    from: serde_json::from_value(request["params"][0]["from"].clone()).unwrap(),
+                    to: serde_json::from_value(request["params"][0]["to"].clone()).unwrap(),
+                    input: serde_json::from_value::<alloy_primitives::Bytes>(
+                        request["params"][0]["input"].clone(),
+                    )
+                    .unwrap()
+                    .to_vec(),
+                    blob_versioned_hashes: serde_json::from_value(
+                        request["params"][0]["blobVersionedHashes"].clone(),
+                    )
+                    .unwrap_or_default(),
+                };
+                Ok(json!(U64::from(l1.estimate_gas(&call).await.unwrap())))
+            }
+            "eth_maxPriorityFeePerGas" => {
+                let fee = l1.max_priority_fee_per_gas().await.unwrap();
+                Ok(json!(alloy_primitives::U128::from(fee)))
+            }
+            "eth_blobBaseFee" => {
+                let fee = l1.blob_base_fee().await.unwrap();
+                Ok(json!(alloy_primitives::U128::from(fee)))
+            }
+            "eth_sendRawTransaction" => {
+                let raw: alloy_primitives::Bytes = param(&request, 0);
+                l1.send_raw_transaction(&raw).await.map(|hash| json!(hash))
+            }
+            "eth_getTransactionReceipt" => {
+                let receipt = l1.transaction_receipt(param(&request, 0)).await.unwrap();
+                Ok(receipt.map_or(Value::Null, |receipt| {
+                    json!({
+                        "transactionHash": receipt.transaction_hash,
+                        "blockNumber": U64::from(receipt.block_number),
+                        "blockHash": receipt.block_hash,
+                        "gasUsed": U64::from(receipt.gas_used),
+                        "effectiveGasPrice": alloy_primitives::U128::from(receipt.effective_gas_price),
+                        "status": U64::from(receipt.success),
+                    })
+                }))
+            }
+            method => Err(eyre!("method {method} not found")),
+        };
+        Json(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32000, "message": e.to_string() },
+            }),
+        })
+    }
+
+    /// Serve the mock chain over HTTP JSON-RPC and return its URL
+    ///
+    /// The chain mines a block every 10ms, including pending transactions.
+    pub(crate) async fn spawn_l1_rpc(l1: MockL1Client) -> String {
+        let miner = l1.clone();
+        tokio::spawn(async move {
+            let mut timestamp = 0;
+            loop {
+                sleep(Duration::from_millis(10)).await;
+                timestamp += 12;
+                miner.push_block(timestamp).await;
+            }
+        });
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/", post(handler)).with_state(l1);
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{addr}")
+    }
+
+    /// Configuration posting to `rpc_url` from the development account
+    pub(crate) fn poster_config(rpc_url: &str) -> ArbitrumRethConfig {
+        let mut config = ArbitrumRethConfig::default();
+        config.l1.rpc_url = rpc_url.to_string();
+        config.l1.confirmation_blocks = 2;
+        config.l1.poll_interval = 10;
+        config.sequencer.batch_poster_private_key = Some(POSTER_KEY.to_string());
+        config
+    }
+
+    /// Storage in a temporary data directory, set in `config`
+    async fn temp_storage(config: &mut ArbitrumRethConfig) -> (Arc<ArbitrumStorage>, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        config.node.datadir = temp_dir.path().to_path_buf();
+        let storage = Arc::new(ArbitrumStorage::new(config).await.unwrap());
+        (storage, temp_dir)
+    }
+
+    /// Mine a block every 10ms until the task completes
+    async fn mine_until_done<T>(l1: &MockL1Client, task: JoinHandle<T>) -> T {
+        let mut timestamp = 1_000;
+        while !task.is_finished() {
+            sleep(Duration::from_millis(10)).await;
+            timestamp += 12;
+            l1.push_block(timestamp).await;
+        }
+        task.await.unwrap()
+    }
+
+    /// Wait until a batch transaction has been broadcast
+    async fn wait_for_in_flight(storage: &ArbitrumStorage) -> InFlightBatch {
+        loop {
+            if let Some(in_flight) = storage.get_in_flight_batch().await.unwrap()
+                && !in_flight.tx_hashes.is_empty()
+            {
+                return in_flight;
+            }
+            sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    fn batch(batch_number: u64, block_range: (u64, u64)) -> ArbitrumBatch {
+        ArbitrumBatch {
+            batch_number,
+            block_range,
+            l1_block_number: 0,
+            timestamp: 0,
+            transactions: vec![],
+            l1_tx_hash: None,
+            after_delayed_messages_read: 3,
+            message_counts: (0, 0),
+            data_gas: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_posts_batches_over_json_rpc() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(10).await;
+        let mut config = poster_config(&spawn_l1_rpc(l1.clone()).await);
+        let (storage, _temp_dir) = temp_storage(&mut config).await;
+        let client = Arc::new(JsonRpcL1Client::new(&config.l1).unwrap());
+        let poster = BatchPoster::new(&config, client, Arc::clone(&storage)).unwrap();
+        assert_eq!(
+            poster.address(),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+                .parse::<Address>()
+                .unwrap()
+        );
+
+        for (nonce, data) in [vec![0u8, 1, 2], vec![0u8, 3]].into_iter().enumerate() {
+            let batch = batch(nonce as u64 + 1, (1, 4));
+            let posted = poster.post_batch(&batch, &data).await.unwrap();
+            assert_eq!(posted.mode, DataPostingMode::Calldata);
+            let receipt = posted.receipt;
+            assert!(l1.block_number().await.unwrap() >= receipt.block_number + 2);
+
+            let tx = l1
+                .transaction_by_hash(receipt.transaction_hash)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(tx.from, poster.address());
+            assert_eq!(tx.to, Some(config.l1.sequencer_inbox_address));
+            assert_eq!(tx.nonce, nonce as u64);
+            assert_eq!(tx.block_number, Some(receipt.block_number));
+            assert_eq!(
+                decode_batch_from_origin(&tx.input, batch.batch_number).unwrap(),
+                data
+            );
+        }
+        assert!(storage.get_in_flight_batch().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_posts_blobs_when_cheaper_than_calldata() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(10).await;
+        let mut config = poster_config(&spawn_l1_rpc(l1.clone()).await);
+        config.sequencer.post_blobs = true;
+        let (storage, _temp_dir) = temp_storage(&mut config).await;
+        let client = Arc::new(JsonRpcL1Client::new(&config.l1).unwrap());
+        let poster = BatchPoster::new(&config, client, storage).unwrap();
+        let data: Vec<u8> = (0..1_000u32).map(|i| (i % 255) as u8 + 1).collect();
+
+        // 16k gas of calldata at 1 gwei against one blob at the minimum blob fee
+        let first = ArbitrumBatch {
+            message_counts: (7, 11),
+            ..batch(1, (1, 4))
+        };
+        let posted = poster.post_batch(&first, &data).await.unwrap();
+        assert_eq!(posted.mode, DataPostingMode::Blobs);
+        let tx = l1
+            .transaction_by_hash(posted.receipt.transaction_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.input, encode_batch_from_blobs(1, 3, 7, 11));
+        assert_eq!(tx.blob_versioned_hashes.len(), 1);
+        let blob = l1.blob(tx.blob_versioned_hashes[0]).await.unwrap();
+        assert_eq!(
+            crate::blobs::decode_blobs(std::slice::from_ref(&*blob)).unwrap(),
+            data
+        );
+
+        // Blob space priced above calldata
+        l1.set_blob_base_fee(1_000_000_000).await;
+        let posted = poster.post_batch(&batch(2, (5, 6)), &data).await.unwrap();
+        assert_eq!(posted.mode, DataPostingMode::Calldata);
+        let tx = l1
+            .transaction_by_hash(posted.receipt.transaction_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.nonce, 1);
+        assert!(tx.blob_versioned_hashes.is_empty());
+        assert_eq!(decode_batch_from_origin(&tx.input, 2).unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_fees_above_cap() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(1).await;
+        let mut config = poster_config("http://unused");
+        config.sequencer.max_l1_fee_per_gas = 1_000_000_000; // The mock base fee
+        let (storage, _temp_dir) = temp_storage(&mut config).await;
+        let poster = BatchPoster::new(&config, Arc::new(l1.clone()), Arc::clone(&storage)).unwrap();
+
+        let err = poster
+            .post_batch(&batch(1, (1, 1)), &[0])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("exceeds the cap"));
+        assert_eq!(
+            l1.transaction_count(poster.address(), true).await.unwrap(),
+            0
+        );
+
+        config.sequencer.batch_poster_private_key = None;
+        assert!(BatchPoster::new(&config, Arc::new(l1), storage).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replaces_stuck_transaction() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(10).await;
+        let mut config = poster_config("http://unused");
+        config.sequencer.replacement_timeout = 50;
+        let (storage, _temp_dir) = temp_storage(&mut config).await;
+        let poster = Arc::new(
+            BatchPoster::new(&config, Arc::new(l1.clone()), Arc::clone(&storage)).unwrap(),
+        );
+
+        let task = tokio::spawn({
+            let poster = Arc::clone(&poster);
+            async move { poster.post_batch(&batch(1, (1, 4)), &[0, 1, 2]).await }
+        });
+        let first = wait_for_in_flight(&storage).await;
+        // Priced out of the blocks mined from now on
+        l1.set_base_fee(5_000_000_000).await;
+
+        let posted = mine_until_done(&l1, task).await.unwrap();
+        let hash = posted.receipt.transaction_hash;
+        assert_ne!(hash, first.tx_hashes[0]);
+        assert!(
+            l1.transaction_by_hash(first.tx_hashes[0])
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let tx = l1.transaction_by_hash(hash).await.unwrap().unwrap();
+        assert_eq!(tx.nonce, first.nonce);
+        assert!(posted.receipt.effective_gas_price >= 5_000_000_000);
+        assert!(storage.get_in_flight_batch().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resumes_in_flight_batch_after_restart() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(10).await;
+        let mut config = poster_config("http://unused");
+        config.sequencer.replacement_timeout = 50;
+        let (storage, _temp_dir) = temp_storage(&mut config).await;
+
+        // The poster goes down while its transaction is pending
+        let poster = Arc::new(
+            BatchPoster::new(&config, Arc::new(l1.clone()), Arc::clone(&storage)).unwrap(),
+        );
+        let task = tokio::spawn({
+            let poster = Arc::clone(&poster);
+            async move { poster.post_batch(&batch(1, (1, 4)), &[0, 1, 2]).await }
+        });
+        wait_for_in_flight(&storage).await;
+        task.abort();
+        let _ = task.await;
+        // A replacement may have gone out before the poster stopped
+        let in_flight = storage.get_in_flight_batch().await.unwrap().unwrap();
+
+        // Meanwhile another transaction of the account takes its nonce
+        let signer: PrivateKeySigner = POSTER_KEY.parse().unwrap();
+        let other = TxEip1559 {
+            chain_id: MOCK_CHAIN_ID,
+            nonce: in_flight.nonce,
+            gas_limit: 21_000,
+            max_fee_per_gas: 10_000_000_000,
+            max_priority_fee_per_gas: 5_000_000_000,
+            to: TxKind::Call(Address::repeat_byte(0x11)),
+            value: U256::ZERO,
+            access_list: Default::default(),
+            input: Default::default(),
+        };
+        let signature = signer.sign_hash_sync(&other.signature_hash()).unwrap();
+        let other = TxEnvelope::from(other.into_signed(signature));
+        l1.send_raw_transaction(&other.encoded_2718())
+            .await
+            .unwrap();
+
+        let restarted = Arc::new(
+            BatchPoster::new(&config, Arc::new(l1.clone()), Arc::clone(&storage)).unwrap(),
+        );
+        let checkpoint = storage.get_in_flight_batch().await.unwrap().unwrap();
+        assert_eq!(checkpoint.tx_hashes, in_flight.tx_hashes);
+        let task = tokio::spawn(async move { restarted.resume(checkpoint).await });
+        let posted = mine_until_done(&l1, task).await.unwrap();
+
+        let tx = l1
+            .transaction_by_hash(posted.receipt.transaction_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.nonce, in_flight.nonce + 1);
+        assert_eq!(
+            decode_batch_from_origin(&tx.input, 1).unwrap(),
+            vec![0, 1, 2]
+        );
+        assert!(storage.get_in_flight_batch().await.unwrap().is_none());
+    }
+}
@@ -1,5 +1,6 @@
 use std::path::{Path, PathBuf};
 
+use alloy_primitives::{Address, address};
 use eyre::Result;
 use serde::{Deserialize, Serialize};
 
@@ -31,10 +32,35 @@ pub struct NodeConfig {
 pub struct L1Config {
     pub rpc_url: String,
     pub ws_url: Option<String>,
+    /// Beacon node API serving the blobs of batches posted in them
+    #[serde(default)]
+    pub beacon_url: Option<String>,
     pub chain_id: u64,
     pub confirmation_blocks: u64,
     pub poll_interval: u64,
     pub start_block: u64,
+    /// Arbitrum Bridge contract emitting `MessageDelivered` events
+    #[serde(default = "default_bridge_address")]
+    pub bridge_address: Address,
+    /// Arbitrum Inbox contract emitting `InboxMessageDelivered` events
+    #[serde(default = "default_inbox_address")]
+    pub inbox_address: Address,
+    /// Arbitrum SequencerInbox contract emitting `SequencerBatchDelivered` events
+    #[serde(default = "default_sequencer_inbox_address")]
+    pub sequencer_inbox_address: Address,
+}
+
+// Arbitrum One deployment on Ethereum mainnet
+fn default_bridge_address() -> Address {
+    address!("0x8315177aB297bA92A06054cE80a67Ed4DBd7ed3a")
+}
+
+fn default_inbox_address() -> Address {
+    address!("0x4Dbd4fc535Ac27206064B68FfCf827b0A60BAB3f")
+}
+
+fn default_sequencer_inbox_address() -> Address {
+    address!("0x1c479675ad559DC151F6Ec7ed3FbF8ceE79582B6")
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,13 +78,79 @@ pub struct SequencerConfig {
     pub batch_timeout: u64,
     pub submit_interval: u64,
     pub max_batch_queue_size: usize,
+    /// Brotli quality (0-11) used to compress posted batches
+    #[serde(default = "default_compression_level")]
+    pub compression_level: u32,
+    /// Upper bound in bytes on the compressed data of a single batch
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: usize,
+    /// Hex-encoded private key of the L1 account posting batches
+    #[serde(default)]
+    pub batch_poster_private_key: Option<String>,
+    /// Cap in wei on the EIP-1559 max fee of batch-posting transactions
+    #[serde(default = "default_max_l1_fee_per_gas")]
+    pub max_l1_fee_per_gas: u64,
+    /// Post batches in EIP-4844 blobs when cheaper than calldata; nodes
+    /// following the chain read them through `l1.beacon_url`
+    #[serde(default)]
+    pub post_blobs: bool,
+    /// Milliseconds a batch transaction may stay unconfirmed before it is
+    /// replaced with higher fees
+    #[serde(default = "default_replacement_timeout")]
+    pub replacement_timeout: u64,
+    /// L1 base fee in wei at or below which pending blocks are posted without
+    /// waiting for the batch to fill up or time out, 0 to disable
+    #[serde(default)]
+    pub cheap_l1_base_fee: u64,
+}
+
+fn default_compression_level() -> u32 {
+    11
+}
+
+// Stays below the 128KiB transaction size limit of L1 nodes
+fn default_max_batch_size() -> usize {
+    100_000
+}
+
+fn default_max_l1_fee_per_gas() -> u64 {
+    200_000_000_000 // 200 gwei
+}
+
+fn default_replacement_timeout() -> u64 {
+    300_000 // 5 minutes
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GasConfig {
     pub l1_base_fee: u64,
+    /// Minimum L2 base fee in wei, charged while the gas backlog is tolerated
     pub l2_gas_price: u64,
     pub price_update_interval: u64,
+    /// L2 gas per second the chain sustains without the base fee rising
+    #[serde(default = "default_l2_speed_limit")]
+    pub l2_speed_limit: u64,
+    /// How slowly the base fee follows the gas backlog, the base fee growing
+    /// e-fold with every `l2_pricing_inertia` seconds of excess backlog
+    #[serde(default = "default_l2_pricing_inertia")]
+    pub l2_pricing_inertia: u64,
+    /// Seconds of gas at the speed limit the backlog may hold before the base
+    /// fee rises above its minimum
+    #[serde(default = "default_l2_backlog_tolerance")]
+    pub l2_backlog_tolerance: u64,
+}
+
+// Nitro's defaults
+fn default_l2_speed_limit() -> u64 {
+    7_000_000
+}
+
+fn default_l2_pricing_inertia() -> u64 {
+    102
+}
+
+fn default_l2_backlog_tolerance() -> u64 {
+    10
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,10 +208,14 @@ impl Default for ArbitrumRethConfig {
             l1: L1Config {
                 rpc_url: "https://ethereum.publicnode.com".to_string(),
                 ws_url: None,
+                beacon_url: None,
                 chain_id: 1,
                 confirmation_blocks: 6,
                 poll_interval: 2000,
                 start_block: 0,
+                bridge_address: default_bridge_address(),
+                inbox_address: default_inbox_address(),
+                sequencer_inbox_address: default_sequencer_inbox_address(),
             },
             l2: L2Config {
                 chain_id: 42161,
@@ -133,6 +229,13 @@ impl Default for ArbitrumRethConfig {
                 batch_timeout: 10_000,
                 submit_interval: 30_000,
                 max_batch_queue_size: 1000,
+                compression_level: default_compression_level(),
+                max_batch_size: default_max_batch_size(),
+                batch_poster_private_key: None,
+                max_l1_fee_per_gas: default_max_l1_fee_per_gas(),
+                post_blobs: false,
+                replacement_timeout: default_replacement_timeout(),
+                cheap_l1_base_fee: 0,
             },
             validator: ValidatorConfig {
                 enable: false,
@@ -161,6 +264,9 @@ impl Default for ArbitrumRethConfig {
                 l1_base_fee: 20_000_000_000, // 20 gwei
                 l2_gas_price: 100_000_000,   // 0.1 gwei
                 price_update_interval: 10,   // 10 seconds
+                l2_speed_limit: default_l2_speed_limit(),
+                l2_pricing_inertia: default_l2_pricing_inertia(),
+                l2_backlog_tolerance: default_l2_backlog_tolerance(),
             },
             rpc: RpcConfig {
                 port: 8548,
@@ -205,6 +311,17 @@ impl ArbitrumRethConfig {
         if self.sequencer.enabled && self.sequencer.batch_size == 0 {
             eyre::bail!("Sequencer batch size cannot be zero");
         }
+        if self.sequencer.enabled && self.sequencer.batch_poster_private_key.is_none() {
+            eyre::bail!("Sequencer requires a batch poster private key");
+        }
+        if self.sequencer.compression_level > 11 {
+            eyre::bail!("Batch compression level must be between 0 and 11");
+        }
+
+        // Validate gas configuration
+        if self.gas.l2_speed_limit == 0 || self.gas.l2_pricing_inertia == 0 {
+            eyre::bail!("L2 speed limit and pricing inertia cannot be zero");
+        }
 
         // Validate validator configuration
         if self.validator.enable && self.validator.stake_amount.is_empty() {
@@ -0,0 +1,201 @@
+//! ArbGasInfo precompile at 0x6c
+//!
+//! Reads of the L2 and L1 pricing for contracts and wallets, which show fee
+//! breakdowns from them. The values come from the ArbOS state the transaction
+//! runs on and the block's base fee, which calls see even where they pay
+//! none, as in `eth_call`.
+
+use alloy_primitives::U256;
+use alloy_sol_types::{SolCall, SolInterface, sol};
+use revm::context::{Block, ContextTr};
+
+use crate::{
+    l1_pricing::{self, TX_DATA_NON_ZERO_GAS},
+    precompiles::{
+        ArbitrumContext, PrecompileCall, PrecompileFailure, STORAGE_READ_COST, STORAGE_WRITE_COST,
+    },
+};
+
+sol! {
+    interface ArbGasInfo {
+        function getPricesInWeiWithAggregator(address aggregator) external view returns (uint256, uint256, uint256, uint256, uint256, uint256);
+        function getPricesInWei() external view returns (uint256, uint256, uint256, uint256, uint256, uint256);
+        function getPricesInArbGasWithAggregator(address aggregator) external view returns (uint256, uint256, uint256);
+        function getPricesInArbGas() external view returns (uint256, uint256, uint256);
+        function getGasAccountingParams() external view returns (uint256, uint256, uint256);
+        function getMinimumGasPrice() external view returns (uint256);
+        function getL1BaseFeeEstimate() external view returns (uint256);
+        function getL1BaseFeeEstimateInertia() external view returns (uint64);
+        function getL1RewardRate() external view returns (uint64);
+        function getL1RewardRecipient() external view returns (address);
+        function getL1GasPriceEstimate() external view returns (uint256);
+        function getCurrentTxL1GasFees() external view returns (uint256);
+        function getGasBacklog() external view returns (uint64);
+        function getPricingInertia() external view returns (uint64);
+        function getGasBacklogTolerance() external view returns (uint64);
+        function getL1PricingSurplus() external view returns (int256);
+        function getPerBatchGasCharge() external view returns (int64);
+        function getAmortizedCostCapBips() external view returns (uint64);
+        function getL1FeesAvailable() external view returns (uint256);
+        function getL1PricingEquilibrationUnits() external view returns (uint256);
+        function getLastL1PricingUpdateTime() external view returns (uint64);
+        function getL1PricingFundsDueForRewards() external view returns (uint256);
+        function getL1PricingUnitsSinceUpdate() external view returns (uint64);
+        function getLastL1PricingSurplus() external view returns (int256);
+    }
+}
+
+use ArbGasInfo::ArbGasInfoCalls;
+
+/// L1 calldata bytes ArbOS prices a transaction without data at
+const TX_FIXED_COST: u64 = 140;
+
+/// Execute a call into ArbGasInfo, returning its ABI-encoded result
+pub fn call<CTX>(context: &mut CTX, call: &mut PrecompileCall) -> Result<Vec<u8>, PrecompileFailure>
+where
+    CTX: ContextTr<Chain = ArbitrumContext>,
+{
+    let method = ArbGasInfoCalls::abi_decode(&call.input)
+        .map_err(|e| PrecompileFailure::Reject(format!("invalid ArbGasInfo call: {e}")))?;
+    call.not_payable()?;
+
+    let block_gas_limit = U256::from(context.block().gas_limit());
+    let chain = context.chain();
+    let model = &chain.arbos.l2_pricing_model;
+    let l2_pricing = &chain.arbos.l2_pricing;
+    let l1_pricing = &chain.arbos.l1_pricing;
+    let base_fee = U256::from(chain.base_fee);
+
+    let output = match method {
+        ArbGasInfoCalls::getPricesInWeiWithAggregator(_) | ArbGasInfoCalls::getPricesInWei(_) => {
+            call.burn(2 * STORAGE_READ_COST)?;
+            let per_l1_calldata_byte = l1_pricing.price_per_unit * U256::from(TX_DATA_NON_ZERO_GAS);
+            let per_l2_tx = per_l1_calldata_byte * U256::from(TX_FIXED_COST);
+            // Nitro has no compute component of the price rising on its own
+            let per_arb_gas_base = U256::from(model.min_base_fee).min(base_fee);
+            ArbGasInfo::getPricesInWeiCall::abi_encode_returns(&ArbGasInfo::getPricesInWeiReturn {
+                _0: per_l2_tx,
+                _1: per_l1_calldata_byte,
+                _2: base_fee * U256::from(STORAGE_WRITE_COST),
+                _3: per_arb_gas_base,
+                _4: base_fee - per_arb_gas_base,
+                _5: base_fee,
+            })
+        }
+        ArbGasInfoCalls::getPricesInArbGasWithAggregator(_)
+        | ArbGasInfoCalls::getPricesInArbGas(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            let per_l1_calldata_byte = l1_pricing.price_per_unit * U256::from(TX_DATA_NON_ZERO_GAS);
+            let per_l2_tx = per_l1_calldata_byte * U256::from(TX_FIXED_COST);
+            let in_gas = |wei: U256| wei.checked_div(base_fee).unwrap_or_default();
+            ArbGasInfo::getPricesInArbGasCall::abi_encode_returns(
+                &ArbGasInfo::getPricesInArbGasReturn {
+                    _0: in_gas(per_l2_tx),
+                    _1: in_gas(per_l1_calldata_byte),
+                    _2: U256::from(STORAGE_WRITE_COST),
+                },
+            )
+        }
+        ArbGasInfoCalls::getGasAccountingParams(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getGasAccountingParamsCall::abi_encode_returns(
+                &ArbGasInfo::getGasAccountingParamsReturn {
+                    _0: U256::from(model.speed_limit),
+                    _1: block_gas_limit,
+                    _2: block_gas_limit,
+                },
+            )
+        }
+        ArbGasInfoCalls::getMinimumGasPrice(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getMinimumGasPriceCall::abi_encode_returns(&U256::from(model.min_base_fee))
+        }
+        ArbGasInfoCalls::getL1BaseFeeEstimate(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getL1BaseFeeEstimateCall::abi_encode_returns(&l1_pricing.price_per_unit)
+        }
+        ArbGasInfoCalls::getL1BaseFeeEstimateInertia(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getL1BaseFeeEstimateInertiaCall::abi_encode_returns(&l1_pricing.inertia)
+        }
+        ArbGasInfoCalls::getL1RewardRate(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getL1RewardRateCall::abi_encode_returns(&l1_pricing.per_unit_reward)
+        }
+        ArbGasInfoCalls::getL1RewardRecipient(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getL1RewardRecipientCall::abi_encode_returns(&l1_pricing.pay_rewards_to)
+        }
+        ArbGasInfoCalls::getL1GasPriceEstimate(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getL1GasPriceEstimateCall::abi_encode_returns(&l1_pricing.price_per_unit)
+        }
+        ArbGasInfoCalls::getCurrentTxL1GasFees(_) => {
+            ArbGasInfo::getCurrentTxL1GasFeesCall::abi_encode_returns(&chain.poster_fee)
+        }
+        ArbGasInfoCalls::getGasBacklog(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getGasBacklogCall::abi_encode_returns(&l2_pricing.gas_backlog)
+        }
+        ArbGasInfoCalls::getPricingInertia(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getPricingInertiaCall::abi_encode_returns(&model.pricing_inertia)
+        }
+        ArbGasInfoCalls::getGasBacklogTolerance(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getGasBacklogToleranceCall::abi_encode_returns(&model.backlog_tolerance)
+        }
+        ArbGasInfoCalls::getL1PricingSurplus(_) => {
+            // The pool and what is due to each poster and as rewards
+            let reads = l1_pricing.batch_posters.len() as u64 + 2;
+            call.burn(reads * STORAGE_READ_COST)?;
+            ArbGasInfo::getL1PricingSurplusCall::abi_encode_returns(&l1_pricing::surplus(
+                l1_pricing,
+            ))
+        }
+        ArbGasInfoCalls::getPerBatchGasCharge(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            let charge = i64::try_from(l1_pricing.per_batch_gas_cost).unwrap_or(i64::MAX);
+            ArbGasInfo::getPerBatchGasChargeCall::abi_encode_returns(&charge)
+        }
+        ArbGasInfoCalls::getAmortizedCostCapBips(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getAmortizedCostCapBipsCall::abi_encode_returns(
+                &l1_pricing.amortized_cost_cap_bips,
+            )
+        }
+        ArbGasInfoCalls::getL1FeesAvailable(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getL1FeesAvailableCall::abi_encode_returns(&l1_pricing.l1_fees_available)
+        }
+        ArbGasInfoCalls::getL1PricingEquilibrationUnits(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getL1PricingEquilibrationUnitsCall::abi_encode_returns(&U256::from(
+                l1_pricing.equilibration_units,
+            ))
+        }
+        ArbGasInfoCalls::getLastL1PricingUpdateTime(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getLastL1PricingUpdateTimeCall::abi_encode_returns(
+                &l1_pricing.last_update_time,
+            )
+        }
+        ArbGasInfoCalls::getL1PricingFundsDueForRewards(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getL1PricingFundsDueForRewardsCall::abi_encode_returns(
+                &l1_pricing.funds_due_for_rewards,
+            )
+        }
+        ArbGasInfoCalls::getL1PricingUnitsSinceUpdate(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getL1PricingUnitsSinceUpdateCall::abi_encode_returns(
+                &l1_pricing.units_since_update,
+            )
+        }
+        ArbGasInfoCalls::getLastL1PricingSurplus(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            ArbGasInfo::getLastL1PricingSurplusCall::abi_encode_returns(&l1_pricing.last_surplus)
+        }
+    };
+    Ok(output)
+}
@@ -0,0 +1,309 @@
+//! ArbRetryableTx precompile at 0x6e
+//!
+//! Manages retryable tickets for contracts and wallets: a `redeem` schedules
+//! an attempt of the ticket's call with the gas left to the caller, which
+//! runs right after the calling transaction; `keepalive` extends a ticket by
+//! a lifetime and its beneficiary can `cancel` it.
+//!
+//! Tickets are read from storage through the EVM's database. Changes to them
+//! are made by the block once the transaction is done, from the events the
+//! precompile emits, so reverted calls leave no trace. Within a transaction,
+//! the events left in the journal are applied to what storage holds.
+
+use alloy_primitives::{Address, B256, U256};
+use alloy_sol_types::{SolCall, SolError, SolInterface, sol};
+use revm::{
+    context::{Block, Cfg, ContextTr, JournalTr},
+    inspector::JournalExt,
+};
+
+use crate::{
+    precompiles::{
+        ARBRETRYABLETX_ADDRESS, ArbitrumContext, COPY_GAS, PrecompileCall, PrecompileFailure,
+        STORAGE_READ_COST, STORAGE_WRITE_COST, log_cost,
+    },
+    retryables::{
+        self, RETRYABLE_LIFETIME_SECONDS, RetryableDatabase, TX_GAS, TicketUpdate,
+        retry_transaction, size_words,
+    },
+};
+
+sol! {
+    interface ArbRetryableTx {
+        function redeem(bytes32 ticketId) external returns (bytes32);
+        function getLifetime() external view returns (uint256);
+        function getTimeout(bytes32 ticketId) external view returns (uint256);
+        function keepalive(bytes32 ticketId) external returns (uint256);
+        function getBeneficiary(bytes32 ticketId) external view returns (address);
+        function cancel(bytes32 ticketId) external;
+        function getCurrentRedeemer() external view returns (address);
+        function submitRetryable(
+            bytes32 requestId,
+            uint256 l1BaseFee,
+            uint256 deposit,
+            uint256 callvalue,
+            uint256 gasFeeCap,
+            uint64 gasLimit,
+            uint256 maxSubmissionFee,
+            address feeRefundAddress,
+            address beneficiary,
+            address retryTo,
+            bytes retryData
+        ) external;
+
+        event TicketCreated(bytes32 indexed ticketId);
+        event LifetimeExtended(bytes32 indexed ticketId, uint256 newTimeout);
+        event RedeemScheduled(
+            bytes32 indexed ticketId,
+            bytes32 indexed retryTxHash,
+            uint64 indexed sequenceNum,
+            uint64 donatedGas,
+            address gasDonor,
+            uint256 maxRefund,
+            uint256 submissionFeeRefund
+        );
+        event Canceled(bytes32 indexed ticketId);
+
+        error NoTicketWithID();
+        error NotCallable();
+    }
+}
+
+use ArbRetryableTx::{ArbRetryableTxCalls, Canceled, LifetimeExtended, RedeemScheduled};
+
+/// Gas per word of a ticket to extend it, a hundredth of setting a slot
+const KEEPALIVE_WORD_GAS: u64 = STORAGE_WRITE_COST / 100;
+
+/// Gas paid ahead for reaping a ticket that was kept alive
+const RETRYABLE_REAP_PRICE: u64 = 58_000;
+
+/// Gas of clearing a slot of ArbOS state
+const STORAGE_CLEAR_COST: u64 = 5_000;
+
+/// Execute a call into ArbRetryableTx, returning its ABI-encoded result
+pub fn call<CTX>(context: &mut CTX, call: &mut PrecompileCall) -> Result<Vec<u8>, PrecompileFailure>
+where
+    CTX: ContextTr<Chain = ArbitrumContext, Db: RetryableDatabase, Journal: JournalExt>,
+{
+    let method = ArbRetryableTxCalls::abi_decode(&call.input)
+        .map_err(|e| PrecompileFailure::Reject(format!("invalid ArbRetryableTx call: {e}")))?;
+    call.not_payable()?;
+    if call.is_static
+        && matches!(
+            method,
+            ArbRetryableTxCalls::redeem(_)
+                | ArbRetryableTxCalls::keepalive(_)
+                | ArbRetryableTxCalls::cancel(_)
+        )
+    {
+        return Err(PrecompileFailure::Reject(
+            "ArbRetryableTx call modifies state in a static call".into(),
+        ));
+    }
+
+    let output = match method {
+        ArbRetryableTxCalls::redeem(args) => {
+            let retry_hash = redeem(context, call, args.ticketId)?;
+            ArbRetryableTx::redeemCall::abi_encode_returns(&retry_hash)
+        }
+        ArbRetryableTxCalls::getLifetime(_) => ArbRetryableTx::getLifetimeCall::abi_encode_returns(
+            &U256::from(RETRYABLE_LIFETIME_SECONDS),
+        ),
+        ArbRetryableTxCalls::getTimeout(args) => {
+            call.burn(STORAGE_READ_COST)?;
+            let ticket = open_ticket(context, args.ticketId)?;
+            ArbRetryableTx::getTimeoutCall::abi_encode_returns(&U256::from(ticket.timeout))
+        }
+        ArbRetryableTxCalls::keepalive(args) => {
+            let timeout = keepalive(context, call, args.ticketId)?;
+            ArbRetryableTx::keepaliveCall::abi_encode_returns(&U256::from(timeout))
+        }
+        ArbRetryableTxCalls::getBeneficiary(args) => {
+            call.burn(STORAGE_READ_COST)?;
+            let ticket = open_ticket(context, args.ticketId)?;
+            ArbRetryableTx::getBeneficiaryCall::abi_encode_returns(&ticket.beneficiary)
+        }
+        ArbRetryableTxCalls::cancel(args) => {
+            cancel(context, call, args.ticketId)?;
+            vec![]
+        }
+        ArbRetryableTxCalls::getCurrentRedeemer(_) => {
+            let redeemer = context
+                .chain()
+                .current_retry
+                .map_or(Address::ZERO, |(_, refund_to)| refund_to);
+            ArbRetryableTx::getCurrentRedeemerCall::abi_encode_returns(&redeemer)
+        }
+        // Submissions only come from L1
+        ArbRetryableTxCalls::submitRetryable(_) => {
+            return Err(PrecompileFailure::Revert(
+                ArbRetryableTx::NotCallable {}.abi_encode().into(),
+            ));
+        }
+    };
+    Ok(output)
+}
+
+/// Schedule a redeem attempt of a ticket with all the gas the call can
+/// spare, returning the hash of the attempt
+fn redeem<CTX>(
+    context: &mut CTX,
+    call: &mut PrecompileCall,
+    ticket_id: B256,
+) -> Result<B256, PrecompileFailure>
+where
+    CTX: ContextTr<Chain = ArbitrumContext, Db: RetryableDatabase, Journal: JournalExt>,
+{
+    not_current_retry(context, ticket_id)?;
+    let ticket = open_ticket(context, ticket_id)?;
+    call.burn(STORAGE_READ_COST * size_words(&ticket) + STORAGE_WRITE_COST)?;
+
+    // Keep what the event, the result and the update of the tries cost, and
+    // donate the rest
+    let sequence = ticket.num_tries;
+    let event_cost = log_cost(4, 4 * 32);
+    let future_costs = event_cost + COPY_GAS + STORAGE_READ_COST + STORAGE_WRITE_COST;
+    let gas_to_donate = call.gas_left().saturating_sub(future_costs);
+    if gas_to_donate < TX_GAS {
+        call.burn(future_costs)?;
+        return Err(PrecompileFailure::Reject(
+            "not enough gas to run redeem attempt".into(),
+        ));
+    }
+
+    let mut event = RedeemScheduled {
+        ticketId: ticket_id,
+        retryTxHash: B256::ZERO,
+        sequenceNum: sequence,
+        donatedGas: gas_to_donate,
+        gasDonor: call.caller,
+        maxRefund: U256::MAX,
+        submissionFeeRefund: U256::ZERO,
+    };
+    // The attempt runs in this block, at its base fee
+    let base_fee = context.block().basefee();
+    let retry = retry_transaction(&ticket, &event, base_fee, context.cfg().chain_id());
+    event.retryTxHash = retry.hash;
+    call.emit(context, ARBRETRYABLETX_ADDRESS, &event)?;
+    // The attempt gets the donated gas back when it runs
+    call.burn(gas_to_donate)?;
+    Ok(retry.hash)
+}
+
+/// Extend a ticket by a lifetime, returning its new timeout
+fn keepalive<CTX>(
+    context: &mut CTX,
+    call: &mut PrecompileCall,
+    ticket_id: B256,
+) -> Result<u64, PrecompileFailure>
+where
+    CTX: ContextTr<Chain = ArbitrumContext, Db: RetryableDatabase, Journal: JournalExt>,
+{
+    let ticket = open_ticket(context, ticket_id)?;
+    call.burn(KEEPALIVE_WORD_GAS * size_words(&ticket))?;
+
+    let now: u64 = context.block().timestamp().saturating_to();
+    if ticket.timeout > now + RETRYABLE_LIFETIME_SECONDS {
+        return Err(PrecompileFailure::Reject(
+            "timeout too far into the future".into(),
+        ));
+    }
+    let timeout = ticket.timeout + RETRYABLE_LIFETIME_SECONDS;
+    call.burn(RETRYABLE_REAP_PRICE)?;
+
+    let event = LifetimeExtended {
+        ticketId: ticket_id,
+        newTimeout: U256::from(timeout),
+    };
+    call.emit(context, ARBRETRYABLETX_ADDRESS, &event)?;
+    Ok(timeout)
+}
+
+/// Cancel a ticket on behalf of its beneficiary, returning its escrow
+fn cancel<CTX>(
+    context: &mut CTX,
+    call: &mut PrecompileCall,
+    ticket_id: B256,
+) -> Result<(), PrecompileFailure>
+where
+    CTX: ContextTr<Chain = ArbitrumContext, Db: RetryableDatabase, Journal: JournalExt>,
+{
+    not_current_retry(context, ticket_id)?;
+    let ticket = open_ticket(context, ticket_id)?;
+    call.burn(STORAGE_READ_COST)?;
+    if call.caller != ticket.beneficiary {
+        return Err(PrecompileFailure::Reject(
+            "only the beneficiary may cancel a retryable".into(),
+        ));
+    }
+    // No refunds are given for the storage a ticket frees
+    call.burn(STORAGE_CLEAR_COST * size_words(&ticket))?;
+
+    let escrow = retryables::escrow_address(ticket_id);
+    let journal = context.journal_mut();
+    let balance = journal
+        .load_account(escrow)
+        .map_err(|e| PrecompileFailure::Fatal(e.to_string()))?
+        .data
+        .info
+        .balance;
+    if let Some(error) = journal
+        .transfer(escrow, ticket.beneficiary, balance)
+        .map_err(|e| PrecompileFailure::Fatal(e.to_string()))?
+    {
+        return Err(PrecompileFailure::Fatal(format!(
+            "cannot return the escrow of ticket {ticket_id}: {error:?}"
+        )));
+    }
+
+    call.emit(
+        context,
+        ARBRETRYABLETX_ADDRESS,
+        &Canceled {
+            ticketId: ticket_id,
+        },
+    )
+}
+
+/// The ticket `ticket_id` as the transaction sees it, if it has not expired
+///
+/// Reverts with `NoTicketWithID` if there is none.
+fn open_ticket<CTX>(
+    context: &mut CTX,
+    ticket_id: B256,
+) -> Result<arbitrum_storage::Retryable, PrecompileFailure>
+where
+    CTX: ContextTr<Chain = ArbitrumContext, Db: RetryableDatabase, Journal: JournalExt>,
+{
+    let mut ticket = context
+        .db_mut()
+        .retryable(ticket_id)
+        .map_err(PrecompileFailure::Fatal)?;
+    for log in context.journal().logs() {
+        if let Some(update) = TicketUpdate::from_log(log.address, log.topics(), &log.data.data)
+            && update.ticket_id() == ticket_id
+        {
+            update.apply(&mut ticket);
+        }
+    }
+
+    let now: u64 = context.block().timestamp().saturating_to();
+    ticket
+        .filter(|ticket| ticket.timeout >= now)
+        .ok_or_else(|| {
+            PrecompileFailure::Revert(ArbRetryableTx::NoTicketWithID {}.abi_encode().into())
+        })
+}
+
+/// Reject changes to the ticket whose redeem attempt is running
+fn not_current_retry<CTX>(context: &CTX, ticket_id: B256) -> Result<(), PrecompileFailure>
+where
+    CTX: ContextTr<Chain = ArbitrumContext>,
+{
+    match context.chain().current_retry {
+        Some((current, _)) if current == ticket_id => Err(PrecompileFailure::Reject(
+            "retryable cannot modify itself".into(),
+        )),
+        _ => Ok(()),
+    }
+}
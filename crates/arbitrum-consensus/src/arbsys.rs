@@ -0,0 +1,313 @@
+//! ArbSys precompile at 0x64
+//!
+//! System information for contracts, and the L2-to-L1 sends: a send is hashed
+//! into the send accumulator and logged as an `L2ToL1Tx` event, which the
+//! outbox on L1 executes once a send root including it is confirmed. Value
+//! sent along is burnt on L2.
+//!
+//! The accumulator advances with the sends of a transaction that were not
+//! reverted, which are the `L2ToL1Tx` logs left in the journal.
+
+use alloy_primitives::{Address, B256, Log, U256, address, keccak256};
+use alloy_sol_types::{SolCall, SolError, SolEvent, SolInterface, sol};
+use arbitrum_storage::L2ToL1Send;
+use revm::{
+    context::{Block, Cfg, ContextTr, JournalTr, Transaction},
+    inspector::JournalExt,
+};
+
+use crate::{
+    outbox,
+    precompiles::{
+        ARBOS_VERSION, ARBSYS_ADDRESS, ArbitrumContext, PrecompileCall, PrecompileFailure,
+        STORAGE_READ_COST, STORAGE_WRITE_COST, words,
+    },
+};
+
+sol! {
+    interface ArbSys {
+        function arbBlockNumber() external view returns (uint256);
+        function arbBlockHash(uint256 arbBlockNum) external view returns (bytes32);
+        function arbChainID() external view returns (uint256);
+        function arbOSVersion() external view returns (uint256);
+        function isTopLevelCall() external view returns (bool);
+        function wasMyCallersAddressAliased() external view returns (bool);
+        function myCallersAddressWithoutAliasing() external view returns (address);
+        function sendTxToL1(address destination, bytes data) external payable returns (uint256);
+        function withdrawEth(address destination) external payable returns (uint256);
+
+        event L2ToL1Tx(
+            address caller,
+            address indexed destination,
+            uint256 indexed hash,
+            uint256 indexed position,
+            uint256 arbBlockNum,
+            uint256 ethBlockNum,
+            uint256 timestamp,
+            uint256 callvalue,
+            bytes data
+        );
+        event SendMerkleUpdate(
+            uint256 indexed reserved,
+            bytes32 indexed hash,
+            uint256 indexed position
+        );
+
+        error InvalidBlockNumber(uint256 requested, uint256 current);
+    }
+}
+
+use ArbSys::{ArbSysCalls, InvalidBlockNumber, L2ToL1Tx, SendMerkleUpdate};
+
+/// Offset ArbOS adds to the address of an L1 contract sending to L2
+pub const L1_ADDRESS_ALIAS_OFFSET: Address = address!("0x1111000000000000000000000000000000001111");
+
+/// Blocks back whose hashes `arbBlockHash` returns
+const BLOCK_HASH_WINDOW: u64 = 256;
+
+/// Gas of hashing, and per word hashed
+const KECCAK_GAS: u64 = 30;
+const KECCAK_WORD_GAS: u64 = 6;
+
+/// Execute a call into ArbSys, returning its ABI-encoded result
+pub fn call<CTX>(context: &mut CTX, call: &mut PrecompileCall) -> Result<Vec<u8>, PrecompileFailure>
+where
+    CTX: ContextTr<Chain = ArbitrumContext, Journal: JournalExt>,
+{
+    let method = ArbSysCalls::abi_decode(&call.input)
+        .map_err(|e| PrecompileFailure::Reject(format!("invalid ArbSys call: {e}")))?;
+    if !matches!(
+        method,
+        ArbSysCalls::sendTxToL1(_) | ArbSysCalls::withdrawEth(_)
+    ) {
+        call.not_payable()?;
+    }
+
+    match method {
+        ArbSysCalls::arbBlockNumber(_) => Ok(ArbSys::arbBlockNumberCall::abi_encode_returns(
+            &context.block().number(),
+        )),
+        ArbSysCalls::arbBlockHash(args) => {
+            let hash = block_hash(context, args.arbBlockNum)?;
+            Ok(ArbSys::arbBlockHashCall::abi_encode_returns(&hash))
+        }
+        ArbSysCalls::arbChainID(_) => {
+            call.burn(STORAGE_READ_COST)?;
+            Ok(ArbSys::arbChainIDCall::abi_encode_returns(&U256::from(
+                context.cfg().chain_id(),
+            )))
+        }
+        ArbSysCalls::arbOSVersion(_) => Ok(ArbSys::arbOSVersionCall::abi_encode_returns(
+            // Nitro counts ArbOS versions on from the 55 of classic Arbitrum
+            &U256::from(55 + ARBOS_VERSION),
+        )),
+        ArbSysCalls::isTopLevelCall(_) => Ok(ArbSys::isTopLevelCallCall::abi_encode_returns(
+            &(context.chain().depth() <= 2),
+        )),
+        ArbSysCalls::wasMyCallersAddressAliased(_) => {
+            Ok(ArbSys::wasMyCallersAddressAliasedCall::abi_encode_returns(
+                &callers_address_aliased(context),
+            ))
+        }
+        ArbSysCalls::myCallersAddressWithoutAliasing(_) => {
+            // The caller of the contract calling ArbSys, none from the top frame
+            let mut address = context.chain().caller(1).unwrap_or(Address::ZERO);
+            if callers_address_aliased(context) {
+                address = inverse_remap_l1_address(address);
+            }
+            Ok(ArbSys::myCallersAddressWithoutAliasingCall::abi_encode_returns(&address))
+        }
+        ArbSysCalls::sendTxToL1(args) => {
+            let position = send_tx_to_l1(context, call, args.destination, &args.data)?;
+            Ok(ArbSys::sendTxToL1Call::abi_encode_returns(&position))
+        }
+        ArbSysCalls::withdrawEth(args) => {
+            let position = send_tx_to_l1(context, call, args.destination, &[])?;
+            Ok(ArbSys::withdrawEthCall::abi_encode_returns(&position))
+        }
+    }
+}
+
+/// Hash of a send logged by ArbSys, none for other logs
+pub fn sent_hash(log: &Log) -> Option<B256> {
+    if log.address != ARBSYS_ADDRESS || log.topics().first() != Some(&L2ToL1Tx::SIGNATURE_HASH) {
+        return None;
+    }
+    log.topics().get(2).copied()
+}
+
+/// Send logged by ArbSys in transaction `tx_hash`, none for other logs
+pub fn sent(log: &Log, tx_hash: B256) -> Option<L2ToL1Send> {
+    if log.address != ARBSYS_ADDRESS {
+        return None;
+    }
+    let event = L2ToL1Tx::decode_log_data(&log.data).ok()?;
+    Some(L2ToL1Send {
+        position: event.position.saturating_to(),
+        hash: event.hash.into(),
+        caller: event.caller,
+        destination: event.destination,
+        arb_block_number: event.arbBlockNum.saturating_to(),
+        eth_block_number: event.ethBlockNum.saturating_to(),
+        timestamp: event.timestamp.saturating_to(),
+        callvalue: event.callvalue,
+        data: event.data.to_vec(),
+        transaction_hash: tx_hash,
+    })
+}
+
+/// Node of the send tree logged by ArbSys, none for other logs
+pub fn merkle_update(log: &Log) -> Option<outbox::MerkleUpdate> {
+    if log.address != ARBSYS_ADDRESS {
+        return None;
+    }
+    let event = SendMerkleUpdate::decode_log_data(&log.data).ok()?;
+    // The level is in the top 64 bits of the position, the leaf in the bottom
+    let [leaf, _, _, level] = *event.position.as_limbs();
+    Some(outbox::MerkleUpdate {
+        level,
+        leaf,
+        hash: event.hash,
+    })
+}
+
+/// Address an L1 contract sends to L2 from, its address plus the offset
+pub fn remap_l1_address(address: Address) -> Address {
+    let offset = U256::from_be_slice(L1_ADDRESS_ALIAS_OFFSET.as_slice());
+    let original = U256::from_be_slice(address.as_slice());
+    Address::from_word(original.wrapping_add(offset).into())
+}
+
+/// Address of an L1 contract before ArbOS aliased it
+pub fn inverse_remap_l1_address(address: Address) -> Address {
+    let offset = U256::from_be_slice(L1_ADDRESS_ALIAS_OFFSET.as_slice());
+    let aliased = U256::from_be_slice(address.as_slice());
+    Address::from_word(aliased.wrapping_sub(offset).into())
+}
+
+fn block_hash<CTX: ContextTr>(
+    context: &mut CTX,
+    requested: U256,
+) -> Result<B256, PrecompileFailure> {
+    let current = context.block().number();
+    if requested >= current || requested.saturating_add(U256::from(BLOCK_HASH_WINDOW)) < current {
+        let error = InvalidBlockNumber { requested, current };
+        return Err(PrecompileFailure::Revert(error.abi_encode().into()));
+    }
+    context
+        .block_hash(requested.to())
+        .ok_or_else(|| PrecompileFailure::Fatal(format!("No hash of block {requested}")))
+}
+
+/// Whether the caller of the contract calling ArbSys is the transaction's
+/// aliased L1 sender
+fn callers_address_aliased<CTX: ContextTr<Chain = ArbitrumContext>>(context: &CTX) -> bool {
+    let chain = context.chain();
+    let top_level = chain.depth() < 2 || chain.caller(1) == Some(context.tx().caller());
+    top_level && chain.aliases_sender
+}
+
+/// Record a send to `destination` on L1, returning its leaf in the send tree
+fn send_tx_to_l1<CTX>(
+    context: &mut CTX,
+    call: &mut PrecompileCall,
+    destination: Address,
+    data: &[u8],
+) -> Result<U256, PrecompileFailure>
+where
+    CTX: ContextTr<Chain = ArbitrumContext, Journal: JournalExt>,
+{
+    if call.is_static {
+        return Err(PrecompileFailure::Reject(
+            "sendTxToL1 modifies state in a static call".into(),
+        ));
+    }
+
+    let block_number = context.block().number();
+    let timestamp = context.block().timestamp();
+    let l1_block_number = U256::from(context.chain().l1_block_number);
+    let preimage = [
+        call.caller.as_slice(),
+        destination.as_slice(),
+        &block_number.to_be_bytes::<32>(),
+        &l1_block_number.to_be_bytes::<32>(),
+        &timestamp.to_be_bytes::<32>(),
+        &call.value.to_be_bytes::<32>(),
+        data,
+    ]
+    .concat();
+    call.burn(KECCAK_GAS + KECCAK_WORD_GAS * words(preimage.len()))?;
+    let send_hash = keccak256(&preimage);
+
+    let mut merkle = context.chain().arbos.send_merkle.clone();
+    let earlier: Vec<B256> = context
+        .journal()
+        .logs()
+        .iter()
+        .filter_map(sent_hash)
+        .collect();
+    for hash in earlier {
+        outbox::append(&mut merkle, hash);
+    }
+    let updates = outbox::append(&mut merkle, send_hash);
+    // The size and a partial per level touched are read and written
+    let slots = updates.len() as u64 + 2;
+    call.burn((STORAGE_READ_COST + STORAGE_WRITE_COST) * slots)?;
+
+    // The value came to ArbSys with the call
+    if !call.value.is_zero() {
+        let journal = context.journal_mut();
+        let account = journal
+            .load_account(ARBSYS_ADDRESS)
+            .map_err(|e| PrecompileFailure::Fatal(e.to_string()))?;
+        let balance = account.data.info.balance;
+        account.data.info.balance = balance.saturating_sub(call.value);
+        journal.caller_accounting_journal_entry(ARBSYS_ADDRESS, balance, false);
+    }
+
+    for update in updates {
+        let event = SendMerkleUpdate {
+            reserved: U256::ZERO,
+            hash: update.hash,
+            position: update.position(),
+        };
+        call.emit(context, ARBSYS_ADDRESS, &event)?;
+    }
+    let position = U256::from(merkle.size - 1);
+    let event = L2ToL1Tx {
+        caller: call.caller,
+        destination,
+        hash: send_hash.into(),
+        position,
+        arbBlockNum: block_number,
+        ethBlockNum: l1_block_number,
+        timestamp,
+        callvalue: call.value,
+        data: data.to_vec().into(),
+    };
+    call.emit(context, ARBSYS_ADDRESS, &event)?;
+    Ok(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remap_l1_address() {
+        let l1_contract = address!("0x00000000000000000000000000000000000000aa");
+        assert_eq!(
+            remap_l1_address(l1_contract),
+            address!("0x11110000000000000000000000000000000011bb")
+        );
+        assert_eq!(
+            inverse_remap_l1_address(address!("0x11110000000000000000000000000000000011bb")),
+            l1_contract
+        );
+        // Aliases wrap around the address space
+        assert_eq!(
+            inverse_remap_l1_address(address!("0x0111000000000000000000000000000000001110")),
+            address!("0xefffffffffffffffffffffffffffffffffffffff")
+        );
+    }
+}
@@ -0,0 +1,444 @@
+//! EVM execution of L2 transactions over [`ArbitrumStorage`]
+//!
+//! Transactions run through revm against the accounts, contract storage and
+//! code kept in storage. revm reads state synchronously, so a transaction is
+//! executed on a blocking thread that waits on the storage futures. Its state
+//! changes are written back before the next transaction of the block runs.
+
+use std::{fmt, sync::Arc};
+
+use alloy_primitives::{Address, B256, Bytes, TxKind, U256};
+use arbitrum_config::GasConfig;
+use arbitrum_storage::{
+    ArbitrumAccount, ArbitrumBlock, ArbitrumStorage, ArbitrumTransaction, ArbitrumTxKind,
+    L1PricingState, L2PricingState, Retryable, SendMerkleState,
+};
+use eyre::{Result, eyre};
+use revm::{
+    Context, Database, InspectEvm, MainBuilder, MainContext,
+    bytecode::Bytecode,
+    context::{
+        BlockEnv, CfgEnv, TxEnv,
+        result::{EVMError, ExecutionResult, InvalidTransaction, ResultAndState},
+    },
+    database_interface::DBErrorMarker,
+    primitives::{KECCAK_EMPTY, StorageKey, StorageValue, hardfork::SpecId},
+    state::{AccountInfo, EvmState},
+};
+use tokio::runtime::Handle;
+
+use crate::{
+    l1_pricing,
+    l2_pricing::L2PricingModel,
+    messages,
+    precompiles::{ArbitrumContext, ArbitrumPrecompiles, CallStack},
+    retryables::RetryableDatabase,
+};
+
+/// Hardfork of the EVM rules L2 transactions run under
+const SPEC: SpecId = SpecId::CANCUN;
+
+/// Outcome of executing a valid transaction, before its state is committed
+pub struct Execution {
+    pub result: ExecutionResult,
+    pub state: EvmState,
+}
+
+/// ArbOS state outside the accounts, which the precompiles read
+///
+/// Kept under its metadata keys between blocks, and updated in memory while
+/// a block executes.
+#[derive(Debug, Clone)]
+pub struct ArbOsState {
+    pub l2_pricing_model: L2PricingModel,
+    pub l2_pricing: L2PricingState,
+    pub l1_pricing: L1PricingState,
+    pub send_merkle: SendMerkleState, // Send accumulator before the transaction
+}
+
+impl ArbOsState {
+    /// Load the state as of the latest block, that of a new chain without
+    /// blocks
+    pub async fn load(storage: &ArbitrumStorage, config: &GasConfig) -> Result<Self> {
+        let l2_pricing_model = L2PricingModel::new(config);
+        let l2_pricing = storage
+            .get_l2_pricing()
+            .await?
+            .unwrap_or_else(|| l2_pricing_model.initial_state());
+        let l1_pricing = storage.get_l1_pricing().await?.unwrap_or_else(|| {
+            l1_pricing::initial_state(U256::from(messages::DEFAULT_INITIAL_L1_BASE_FEE))
+        });
+        let send_merkle = storage.get_send_merkle().await?.unwrap_or_default();
+        Ok(Self {
+            l2_pricing_model,
+            l2_pricing,
+            l1_pricing,
+            send_merkle,
+        })
+    }
+
+    /// Store the state after a block
+    pub async fn store(&self, storage: &ArbitrumStorage) -> Result<()> {
+        storage.store_l2_pricing(&self.l2_pricing).await?;
+        storage.store_l1_pricing(&self.l1_pricing).await?;
+        storage.store_send_merkle(&self.send_merkle).await
+    }
+}
+
+/// Execute a transaction of `block` on top of the state in storage
+///
+/// `poster_gas` of the transaction's gas limit pays for its L1 data and is not
+/// available to the EVM. As in Nitro, the sender buys the whole gas limit: the
+/// fee for the poster gas is taken out of the balance the EVM sees, which
+/// must then cover the rest of the gas and the value. Gas is charged at the
+/// block's base fee, which the transaction's gas price only caps, as Arbitrum
+/// pays no tips. `arbos` is the ArbOS state after the transactions before it.
+/// Returns the reason a transaction cannot be included, such as a wrong nonce
+/// or insufficient funds, as an inner error. Nothing is written to storage.
+pub async fn execute_transaction(
+    storage: Arc<ArbitrumStorage>,
+    chain_id: u64,
+    block: &ArbitrumBlock,
+    tx: &ArbitrumTransaction,
+    poster_gas: u64,
+    arbos: &ArbOsState,
+) -> Result<Result<Execution, InvalidTransaction>> {
+    let Some(gas_limit) = tx.gas.checked_sub(poster_gas) else {
+        return Ok(Err(InvalidTransaction::CallGasCostMoreThanGasLimit {
+            initial_gas: poster_gas,
+            gas_limit: tx.gas,
+        }));
+    };
+
+    if tx.gas_price < U256::from(block.base_fee) {
+        return Ok(Err(InvalidTransaction::GasPriceLessThanBasefee));
+    }
+
+    let mut cfg = CfgEnv::new_with_spec(SPEC).with_chain_id(chain_id);
+    // Only signed and unsigned transactions carry the sender's nonce
+    cfg.disable_nonce_check = !matches!(tx.kind, ArbitrumTxKind::Signed | ArbitrumTxKind::Unsigned);
+
+    let tx_env = TxEnv {
+        gas_limit,
+        gas_price: u128::from(block.base_fee),
+        ..tx_env(tx)
+    };
+    let poster_fee = U256::from(poster_gas) * U256::from(block.base_fee);
+    let balance = storage
+        .get_account(&tx.from)
+        .await?
+        .map_or(U256::ZERO, |account| account.balance);
+    if balance < poster_fee {
+        return Ok(Err(InvalidTransaction::LackOfFundForMaxFee {
+            fee: Box::new(U256::from(tx.gas) * U256::from(block.base_fee) + tx.value),
+            balance: Box::new(balance),
+        }));
+    }
+    let chain = arbitrum_context(block, tx, arbos.clone()).with_poster_fee(poster_fee);
+    let funds = Funds {
+        deposit: U256::ZERO,
+        poster_fee,
+    };
+    match transact(storage, cfg, block_env(block), tx_env, chain, funds).await? {
+        Ok(outcome) => Ok(Ok(Execution {
+            result: outcome.result,
+            state: outcome.state,
+        })),
+        Err(invalid) => Ok(Err(invalid)),
+    }
+}
+
+/// Run a call on top of `block` as `eth_call` does, without committing it
+///
+/// The call runs without gas price and nonce checks, with the block's gas
+/// limit if it sets none, so it is not held to the base fee either. The
+/// precompiles still see the block's base fee. `deposit` is credited to the
+/// sender first, as a retryable ticket's deposit is before its retry runs.
+pub async fn call(
+    storage: Arc<ArbitrumStorage>,
+    chain_id: u64,
+    block: &ArbitrumBlock,
+    tx: &ArbitrumTransaction,
+    arbos: &ArbOsState,
+    deposit: U256,
+) -> Result<ExecutionResult> {
+    let mut cfg = CfgEnv::new_with_spec(SPEC).with_chain_id(chain_id);
+    cfg.disable_nonce_check = true;
+
+    let tx_env = TxEnv {
+        gas_limit: if tx.gas == 0 { block.gas_limit } else { tx.gas },
+        gas_price: 0,
+        ..tx_env(tx)
+    };
+    let block_env = BlockEnv {
+        basefee: 0,
+        ..block_env(block)
+    };
+    let chain = arbitrum_context(block, tx, arbos.clone());
+    let funds = Funds {
+        deposit,
+        poster_fee: U256::ZERO,
+    };
+    let outcome = transact(storage, cfg, block_env, tx_env, chain, funds)
+        .await?
+        .map_err(|invalid| eyre!("Invalid transaction: {invalid}"))?;
+    Ok(outcome.result)
+}
+
+/// Estimate the L2 gas a transaction needs to execute on top of `block`
+///
+/// The L1 data fee is not included. The transaction runs as a [`call`] with
+/// `deposit` credited to its sender.
+pub async fn estimate_gas(
+    storage: Arc<ArbitrumStorage>,
+    chain_id: u64,
+    block: &ArbitrumBlock,
+    tx: &ArbitrumTransaction,
+    arbos: &ArbOsState,
+    deposit: U256,
+) -> Result<u64> {
+    match call(storage, chain_id, block, tx, arbos, deposit).await? {
+        // Refunds are only paid out at the end, so the gas they return is
+        // needed up front
+        ExecutionResult::Success {
+            gas_used,
+            gas_refunded,
+            ..
+        } => Ok(gas_used + gas_refunded),
+        ExecutionResult::Revert { .. } => Err(eyre!("Execution reverted")),
+        ExecutionResult::Halt { reason, .. } => Err(eyre!("Execution halted: {reason:?}")),
+    }
+}
+
+fn block_env(block: &ArbitrumBlock) -> BlockEnv {
+    BlockEnv {
+        number: U256::from(block.number),
+        timestamp: U256::from(block.timestamp),
+        gas_limit: block.gas_limit,
+        basefee: block.base_fee,
+        // Arbitrum blocks have a difficulty of 1, which PREVRANDAO returns
+        difficulty: U256::from(1),
+        prevrandao: Some(B256::with_last_byte(1)),
+        ..Default::default()
+    }
+}
+
+fn tx_env(tx: &ArbitrumTransaction) -> TxEnv {
+    TxEnv {
+        caller: tx.from,
+        gas_limit: tx.gas,
+        gas_price: tx.gas_price.saturating_to(),
+        kind: tx.to.map_or(TxKind::Create, TxKind::Call),
+        value: tx.value,
+        data: Bytes::from(tx.data.clone()),
+        nonce: tx.nonce,
+        ..Default::default()
+    }
+}
+
+/// What the precompiles see of `tx`
+fn arbitrum_context(
+    block: &ArbitrumBlock,
+    tx: &ArbitrumTransaction,
+    arbos: ArbOsState,
+) -> ArbitrumContext {
+    // Transactions made from L1 messages come from aliased L1 senders
+    let aliases_sender = matches!(
+        tx.kind,
+        ArbitrumTxKind::Unsigned | ArbitrumTxKind::Contract { .. } | ArbitrumTxKind::Retry { .. }
+    );
+    let chain = ArbitrumContext::new(block.l1_block_number, block.base_fee, arbos)
+        .with_aliased_sender(aliases_sender);
+    match tx.kind {
+        ArbitrumTxKind::Retry {
+            ticket_id,
+            refund_to,
+            ..
+        } => chain.with_retry(ticket_id, refund_to),
+        _ => chain,
+    }
+}
+
+/// Changes to the caller's balance made before the EVM runs
+struct Funds {
+    deposit: U256,    // Credited, as a retryable's deposit is before its retry
+    poster_fee: U256, // Paid for the L1 data, which the caller must hold
+}
+
+async fn transact(
+    storage: Arc<ArbitrumStorage>,
+    cfg: CfgEnv,
+    block_env: BlockEnv,
+    tx_env: TxEnv,
+    chain: ArbitrumContext,
+    funds: Funds,
+) -> Result<Result<ResultAndState, InvalidTransaction>> {
+    let db = StateDatabase {
+        storage,
+        handle: Handle::current(),
+        caller: tx_env.caller,
+        funds,
+    };
+    let outcome = tokio::task::spawn_blocking(move || {
+        let mut evm = Context::mainnet()
+            .with_db(db)
+            .with_block(block_env)
+            .with_cfg(cfg)
+            .with_chain(chain)
+            .build_mainnet_with_inspector(CallStack)
+            .with_precompiles(ArbitrumPrecompiles::default());
+        evm.inspect_tx(tx_env)
+    })
+    .await?;
+
+    match outcome {
+        Ok(outcome) => Ok(Ok(outcome)),
+        Err(EVMError::Transaction(invalid)) => Ok(Err(invalid)),
+        Err(e) => Err(eyre!("EVM execution failed: {e}")),
+    }
+}
+
+/// Write the state changes of an execution to storage
+///
+/// Returns the addresses of the accounts changed.
+pub async fn commit_state(storage: &ArbitrumStorage, state: EvmState) -> Result<Vec<Address>> {
+    let mut changed = Vec::new();
+    for (address, account) in state {
+        if !account.is_touched() {
+            continue;
+        }
+        changed.push(address);
+
+        // Self-destructed accounts go, as do accounts left empty (EIP-161)
+        if account.is_selfdestructed() || account.is_empty() {
+            storage.delete_account(&address).await?;
+            continue;
+        }
+
+        if account.is_created()
+            && let Some(code) = &account.info.code
+            && account.info.code_hash != KECCAK_EMPTY
+        {
+            storage
+                .store_code(account.info.code_hash, code.original_byte_slice())
+                .await?;
+        }
+        for (slot, value) in account.changed_storage_slots() {
+            storage
+                .store_storage(address, B256::from(*slot), value.present_value)
+                .await?;
+        }
+
+        let storage_root = storage
+            .get_account(&address)
+            .await?
+            .map_or(B256::ZERO, |existing| existing.storage_root);
+        let updated = ArbitrumAccount {
+            address,
+            balance: account.info.balance,
+            nonce: account.info.nonce,
+            code_hash: account.info.code_hash,
+            storage_root,
+        };
+        storage.store_account(address, &updated).await?;
+    }
+    Ok(changed)
+}
+
+/// Storage-backed state for revm, read from a blocking thread
+struct StateDatabase {
+    storage: Arc<ArbitrumStorage>,
+    handle: Handle,
+    caller: Address,
+    funds: Funds, // Applied to the caller's balance as storage holds it
+}
+
+impl Database for StateDatabase {
+    type Error = StateDatabaseError;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let account = self.handle.block_on(self.storage.get_account(&address))?;
+        let mut info = account.map(|account| AccountInfo {
+            balance: account.balance,
+            nonce: account.nonce,
+            // Accounts without code may carry a zero hash
+            code_hash: if account.code_hash == B256::ZERO {
+                KECCAK_EMPTY
+            } else {
+                account.code_hash
+            },
+            code: None,
+        });
+        if address == self.caller {
+            let Funds {
+                deposit,
+                poster_fee,
+            } = self.funds;
+            if !deposit.is_zero() {
+                let info = info.get_or_insert_with(AccountInfo::default);
+                info.balance += deposit;
+            }
+            if let Some(info) = &mut info {
+                info.balance = info.balance.saturating_sub(poster_fee);
+            }
+        }
+        Ok(info)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        if code_hash == KECCAK_EMPTY {
+            return Ok(Bytecode::default());
+        }
+        let code = self
+            .handle
+            .block_on(self.storage.get_code(&code_hash))?
+            .ok_or_else(|| StateDatabaseError(format!("Code {code_hash} not found")))?;
+        Ok(Bytecode::new_raw(code.into()))
+    }
+
+    fn storage(
+        &mut self,
+        address: Address,
+        index: StorageKey,
+    ) -> Result<StorageValue, Self::Error> {
+        let slot = B256::from(index);
+        Ok(self
+            .handle
+            .block_on(self.storage.get_storage(&address, &slot))?)
+    }
+
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        let block = self
+            .handle
+            .block_on(self.storage.get_block_by_number(number))?;
+        Ok(block.map_or(B256::ZERO, |block| block.hash))
+    }
+}
+
+impl RetryableDatabase for StateDatabase {
+    fn retryable(&mut self, id: B256) -> Result<Option<Retryable>, String> {
+        self.handle
+            .block_on(self.storage.get_retryable(&id))
+            .map_err(|e| format!("{e:#}"))
+    }
+}
+
+/// Storage failure surfaced through revm
+#[derive(Debug)]
+struct StateDatabaseError(String);
+
+impl fmt::Display for StateDatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for StateDatabaseError {}
+
+impl DBErrorMarker for StateDatabaseError {}
+
+impl From<eyre::Report> for StateDatabaseError {
+    fn from(e: eyre::Report) -> Self {
+        Self(format!("{e:#}"))
+    }
+}
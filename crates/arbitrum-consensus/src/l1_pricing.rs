@@ -0,0 +1,422 @@
+//! L1 data fees of L2 transactions
+//!
+//! Transactions the sequencer posts to L1 pay for the calldata they take up
+//! in their batch on top of their L2 execution. The data is estimated as the
+//! brotli-compressed size of the transaction, at 16 units per byte as for
+//! nonzero L1 calldata, and priced at the L1 price per unit. The cost is
+//! charged as extra L2 gas at the block's base fee, which the receipt reports
+//! as `gasUsedForL1`.
+//!
+//! The fees collect in the L1 pricer's pool. Batch posting reports tell the
+//! pricer what posting each batch cost, which it refunds to the poster along
+//! with a reward per unit, and the price per unit is adjusted as ArbOS does
+//! to bring the pool's surplus back to zero.
+
+use std::collections::btree_map::Entry;
+
+use alloy_consensus::{SignableTransaction, TxEnvelope, TxLegacy};
+use alloy_eips::eip2718::Encodable2718;
+use alloy_primitives::{Address, I256, Signature, TxKind, U256, address};
+use arbitrum_storage::{ArbitrumTransaction, ArbitrumTxKind, BatchPosterState, L1PricingState};
+use eyre::{Result, eyre};
+
+/// Account collecting the L1 data fees
+pub const L1_PRICER_FUNDS_POOL: Address = address!("0xa4b05fffffffffffffffffffffffffffffffffff");
+
+/// Units of a compressed byte, the L1 gas of a nonzero calldata byte
+pub const TX_DATA_NON_ZERO_GAS: u64 = 16;
+
+/// Brotli quality the data of a transaction is estimated at
+const BROTLI_LEVEL: i32 = 1;
+
+const BROTLI_WINDOW_BITS: i32 = 22;
+
+/// Batch poster of a new chain, which also receives the rewards
+pub const BATCH_POSTER_ADDRESS: Address = address!("0xa4b000000000000000000073657175656e636572");
+
+/// ArbOS's initial parameters of the L1 pricer
+pub const INITIAL_INERTIA: u64 = 10;
+pub const INITIAL_PER_UNIT_REWARD: u64 = 10;
+pub const INITIAL_EQUILIBRATION_UNITS: u64 = TX_DATA_NON_ZERO_GAS * 10_000_000;
+pub const INITIAL_PER_BATCH_GAS_COST: u64 = 210_000;
+
+/// Margin on the L1 fee of gas estimates, in basis points, so that a
+/// transaction still fits if the L1 price rises before it is included
+pub const ESTIMATION_PADDING_BIPS: u64 = 11_000;
+
+/// L1 calldata units of a transaction's encoding
+pub fn calldata_units(tx_bytes: &[u8]) -> u64 {
+    let params = brotli::enc::BrotliEncoderParams {
+        quality: BROTLI_LEVEL,
+        lgwin: BROTLI_WINDOW_BITS,
+        ..Default::default()
+    };
+    let mut compressed = Vec::new();
+    // Compressing into memory cannot fail
+    let size = match brotli::BrotliCompress(&mut &tx_bytes[..], &mut compressed, &params) {
+        Ok(_) => compressed.len(),
+        Err(_) => tx_bytes.len(),
+    };
+    size as u64 * TX_DATA_NON_ZERO_GAS
+}
+
+/// L1 calldata units `tx` is charged for
+///
+/// Only transactions in sequencer batches pay for their data. Those from the
+/// delayed inbox were paid for on L1.
+pub fn poster_units(tx: &ArbitrumTransaction) -> u64 {
+    if tx.kind != ArbitrumTxKind::Signed {
+        return 0;
+    }
+    calldata_units(&tx_bytes(tx))
+}
+
+/// Cost in wei of posting `tx` to L1 at `price_per_unit`
+pub fn poster_cost(tx: &ArbitrumTransaction, price_per_unit: U256) -> U256 {
+    price_per_unit * U256::from(poster_units(tx))
+}
+
+/// L2 gas paying for `poster_cost` at `base_fee`, none while L2 gas is free
+pub fn poster_gas(poster_cost: U256, base_fee: u64) -> u64 {
+    if base_fee == 0 {
+        return 0;
+    }
+    (poster_cost / U256::from(base_fee)).saturating_to()
+}
+
+/// L2 gas for the L1 fee of a gas estimate, with its padding
+pub fn estimated_poster_gas(tx: &ArbitrumTransaction, price_per_unit: U256, base_fee: u64) -> u64 {
+    let cost =
+        poster_cost(tx, price_per_unit) * U256::from(ESTIMATION_PADDING_BIPS) / U256::from(10_000);
+    poster_gas(cost, base_fee)
+}
+
+/// L1 pricer of a new chain, charging `price_per_unit`
+pub fn initial_state(price_per_unit: U256) -> L1PricingState {
+    L1PricingState {
+        price_per_unit,
+        units_since_update: 0,
+        last_update_time: 0,
+        last_surplus: I256::ZERO,
+        funds_due_for_rewards: U256::ZERO,
+        l1_fees_available: U256::ZERO,
+        batch_posters: [(
+            BATCH_POSTER_ADDRESS,
+            BatchPosterState {
+                funds_due: U256::ZERO,
+                pay_to: BATCH_POSTER_ADDRESS,
+            },
+        )]
+        .into(),
+        pay_rewards_to: BATCH_POSTER_ADDRESS,
+        per_unit_reward: INITIAL_PER_UNIT_REWARD,
+        equilibration_units: INITIAL_EQUILIBRATION_UNITS,
+        inertia: INITIAL_INERTIA,
+        per_batch_gas_cost: INITIAL_PER_BATCH_GAS_COST,
+        amortized_cost_cap_bips: 0,
+    }
+}
+
+/// Record the L1 fee a transaction paid into the pool for `units` of data
+pub fn record_poster_fee(state: &mut L1PricingState, units: u64, fee: U256) {
+    state.units_since_update = state.units_since_update.saturating_add(units);
+    state.l1_fees_available += fee;
+}
+
+/// Wei a batch poster reports spending on a batch whose data took
+/// `batch_data_gas` L1 gas, at `l1_base_fee`
+pub fn batch_posting_cost(state: &L1PricingState, batch_data_gas: u64, l1_base_fee: U256) -> U256 {
+    l1_base_fee * U256::from(state.per_batch_gas_cost.saturating_add(batch_data_gas))
+}
+
+/// Account for a batch poster spending `wei_spent` on a batch posted at
+/// `update_time`, reported in a block at `current_time`
+///
+/// The units sold since the last report are allocated to this one in
+/// proportion to the time the batch covers. The poster is owed what it spent
+/// and the reward recipient the reward for the allocated units, both paid
+/// out of the pool as far as its funds allow. The price per unit then moves
+/// to bring the surplus of the pool towards zero, by how far it is off and
+/// how fast it is changing.
+///
+/// Returns the payments to make out of the pool.
+pub fn update_for_batch_poster_spending(
+    state: &mut L1PricingState,
+    update_time: u64,
+    current_time: u64,
+    batch_poster: Address,
+    mut wei_spent: U256,
+    l1_base_fee: U256,
+) -> Result<Vec<(Address, U256)>> {
+    let mut last_update_time = state.last_update_time;
+    if last_update_time == 0 && update_time > 0 {
+        // There is no previous update on the first one
+        last_update_time = update_time - 1;
+    }
+    if update_time > current_time || update_time < last_update_time {
+        return Err(eyre!(
+            "Invalid batch time {update_time}, last update at {last_update_time} and now {current_time}"
+        ));
+    }
+
+    // Allocate the units sold in proportion to the time covered
+    let (numerator, denominator) = match current_time - last_update_time {
+        0 => (1, 1),
+        delta => (update_time - last_update_time, delta),
+    };
+    let units_allocated = (u128::from(state.units_since_update) * u128::from(numerator)
+        / u128::from(denominator)) as u64;
+    state.units_since_update -= units_allocated;
+
+    if state.amortized_cost_cap_bips != 0 {
+        let cap =
+            l1_base_fee * U256::from(units_allocated) * U256::from(state.amortized_cost_cap_bips)
+                / U256::from(10_000);
+        wei_spent = wei_spent.min(cap);
+    }
+
+    let poster = match state.batch_posters.entry(batch_poster) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(BatchPosterState {
+            funds_due: U256::ZERO,
+            pay_to: batch_poster,
+        }),
+    };
+    poster.funds_due += wei_spent;
+    let reward = U256::from(state.per_unit_reward) * U256::from(units_allocated);
+    state.funds_due_for_rewards += reward;
+
+    // Pay the rewards, then the poster, as far as the funds go
+    let mut payments = vec![];
+    let mut available = state.l1_fees_available;
+    let reward_payment = reward.min(available);
+    state.funds_due_for_rewards -= reward_payment;
+    available -= reward_payment;
+    if !reward_payment.is_zero() {
+        payments.push((state.pay_rewards_to, reward_payment));
+    }
+    let poster_payment = poster.funds_due.min(available);
+    if !poster_payment.is_zero() {
+        poster.funds_due -= poster_payment;
+        available -= poster_payment;
+        payments.push((poster.pay_to, poster_payment));
+    }
+    state.l1_fees_available = available;
+    state.last_update_time = update_time;
+
+    if units_allocated > 0 {
+        let surplus = surplus(state);
+
+        let equilibration_units = signed(U256::from(state.equilibration_units));
+        let inertia_units = state.equilibration_units / state.inertia.max(1);
+        let units = signed(U256::from(units_allocated));
+        let desired_derivative = (-surplus).div_euclid(equilibration_units);
+        let actual_derivative = (surplus - state.last_surplus).div_euclid(units);
+        let price_change = ((desired_derivative - actual_derivative) * units)
+            .div_euclid(signed(U256::from(inertia_units + units_allocated)));
+
+        state.last_surplus = surplus;
+        let price = signed(state.price_per_unit) + price_change;
+        state.price_per_unit = if price.is_negative() {
+            U256::ZERO
+        } else {
+            price.into_raw()
+        };
+    }
+
+    Ok(payments)
+}
+
+/// Funds in the pool less what is due to the batch posters and as rewards
+pub fn surplus(state: &L1PricingState) -> I256 {
+    let total_funds_due = state
+        .batch_posters
+        .values()
+        .fold(state.funds_due_for_rewards, |total, poster| {
+            total + poster.funds_due
+        });
+    signed(state.l1_fees_available) - signed(total_funds_due)
+}
+
+fn signed(value: U256) -> I256 {
+    I256::from_raw(value)
+}
+
+/// Encoding of a transaction as posted to L1
+///
+/// Transactions not yet signed, as in gas estimates, are encoded as legacy
+/// transactions with a placeholder signature of the same size.
+fn tx_bytes(tx: &ArbitrumTransaction) -> Vec<u8> {
+    if !tx.raw.is_empty() {
+        return tx.raw.clone();
+    }
+    let unsigned = TxLegacy {
+        chain_id: None,
+        nonce: tx.nonce,
+        gas_price: tx.gas_price.saturating_to(),
+        gas_limit: tx.gas,
+        to: tx.to.map_or(TxKind::Create, TxKind::Call),
+        value: tx.value,
+        input: tx.data.clone().into(),
+    };
+    let signature = Signature::new(U256::MAX, U256::MAX, false);
+    TxEnvelope::Legacy(unsigned.into_signed(signature)).encoded_2718()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{B256, keccak256};
+
+    use super::*;
+
+    fn signed_tx(raw: Vec<u8>) -> ArbitrumTransaction {
+        ArbitrumTransaction {
+            hash: B256::ZERO,
+            from: Address::ZERO,
+            to: Some(Address::ZERO),
+            value: U256::ZERO,
+            gas: 100_000,
+            gas_price: U256::from(1_000_000_000u64),
+            nonce: 0,
+            data: vec![],
+            l1_sequence_number: None,
+            raw,
+            kind: ArbitrumTxKind::Signed,
+        }
+    }
+
+    #[test]
+    fn test_poster_fee() {
+        // Repetitive data compresses well, random-looking data does not
+        let repetitive = signed_tx(vec![0xab; 1_000]);
+        let dense = signed_tx(
+            (0..32u32)
+                .flat_map(|i| keccak256(i.to_be_bytes()).0)
+                .collect(),
+        );
+        let repetitive_units = calldata_units(&repetitive.raw);
+        let dense_units = calldata_units(&dense.raw);
+        assert_eq!(repetitive_units % TX_DATA_NON_ZERO_GAS, 0);
+        assert!(repetitive_units < 100 * TX_DATA_NON_ZERO_GAS);
+        assert!(dense_units > 900 * TX_DATA_NON_ZERO_GAS);
+
+        let price = U256::from(30);
+        let cost = poster_cost(&dense, price);
+        assert_eq!(cost, U256::from(dense_units * 30));
+        assert_eq!(poster_gas(cost, 10), dense_units * 3);
+        assert_eq!(poster_gas(cost, 0), 0);
+        assert_eq!(
+            estimated_poster_gas(&dense, price, 10),
+            dense_units * 3 * 11 / 10
+        );
+
+        // Delayed inbox transactions were paid for on L1
+        let unsigned = ArbitrumTransaction {
+            kind: ArbitrumTxKind::Unsigned,
+            ..dense
+        };
+        assert_eq!(poster_cost(&unsigned, price), U256::ZERO);
+
+        // Unsigned requests are priced as a signed legacy transaction
+        let request = signed_tx(vec![]);
+        assert!(poster_cost(&request, price) > U256::ZERO);
+    }
+
+    #[test]
+    fn test_batch_poster_spending_updates_price() {
+        let initial_price = U256::from(50_000_000_000u64);
+        let mut state = initial_state(initial_price);
+        let other = address!("0x00000000000000000000000000000000000000bb");
+
+        // 100 bytes of data sold at the initial price
+        record_poster_fee(&mut state, 1_600, initial_price * U256::from(1_600));
+
+        // The first report covers 1s of the 11s since just before its batch,
+        // so it takes 145 of the units. The rewards on them are paid first,
+        // and the poster gets the rest of the pool
+        let payments = update_for_batch_poster_spending(
+            &mut state,
+            1_000,
+            1_010,
+            BATCH_POSTER_ADDRESS,
+            U256::from(10u64.pow(14)),
+            U256::from(10u64.pow(9)),
+        )
+        .unwrap();
+        assert_eq!(
+            payments,
+            vec![
+                (BATCH_POSTER_ADDRESS, U256::from(1_450)),
+                (BATCH_POSTER_ADDRESS, U256::from(79_999_999_998_550u64)),
+            ]
+        );
+        assert_eq!(state.units_since_update, 1_455);
+        assert_eq!(state.l1_fees_available, U256::ZERO);
+        assert_eq!(
+            state.batch_posters[&BATCH_POSTER_ADDRESS].funds_due,
+            U256::from(20_000_000_001_450u64)
+        );
+        // The pool falls short, so the price goes up
+        assert_eq!(
+            state.last_surplus,
+            I256::try_from(-20_000_000_001_450i64).unwrap()
+        );
+        assert_eq!(state.price_per_unit, U256::from(50_001_249_989u64));
+
+        // More data is sold, and a new poster reports a cheaper batch
+        let fee = state.price_per_unit * U256::from(3_200);
+        record_poster_fee(&mut state, 3_200, fee);
+        let payments = update_for_batch_poster_spending(
+            &mut state,
+            1_005,
+            1_020,
+            other,
+            U256::from(3 * 10u64.pow(13)),
+            U256::from(10u64.pow(9)),
+        )
+        .unwrap();
+        assert_eq!(
+            payments,
+            vec![
+                (BATCH_POSTER_ADDRESS, U256::from(11_630)),
+                (other, U256::from(30_000_000_000_000u64)),
+            ]
+        );
+        assert_eq!(state.units_since_update, 3_492);
+        assert_eq!(state.batch_posters[&other].pay_to, other);
+        assert_eq!(
+            state.last_surplus,
+            I256::try_from(110_003_999_951_720i64).unwrap()
+        );
+        // The surplus brings the price back down
+        assert_eq!(state.price_per_unit, U256::from(49_993_125_279u64));
+
+        // Batches cannot go back in time or come from the future
+        assert!(
+            update_for_batch_poster_spending(
+                &mut state,
+                1_004,
+                1_030,
+                other,
+                U256::ZERO,
+                U256::ZERO
+            )
+            .is_err()
+        );
+        assert!(
+            update_for_batch_poster_spending(
+                &mut state,
+                1_031,
+                1_030,
+                other,
+                U256::ZERO,
+                U256::ZERO
+            )
+            .is_err()
+        );
+        assert_eq!(
+            batch_posting_cost(&state, 40_030, U256::from(2)),
+            U256::from(2 * (210_000 + 40_030))
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! L2 base fee from the gas backlog
+//!
+//! Nitro prices L2 gas by how far the chain is behind its speed limit. Gas
+//! used by blocks adds to a backlog that drains at the speed limit per second
+//! of block time. While the backlog stays within the tolerance the base fee
+//! is the configured minimum, beyond it the fee grows exponentially with the
+//! excess, slowed by the pricing inertia.
+//!
+//! A block pays the base fee left by its parent: executing it first drains
+//! the backlog by the time since its parent and prices the next block, then
+//! adds its own gas.
+
+use arbitrum_config::GasConfig;
+use arbitrum_storage::L2PricingState;
+
+/// Basis points in one
+pub const ONE_IN_BIPS: u64 = 10_000;
+
+/// Terms of the Taylor expansion approximating the exponential
+const EXP_ACCURACY: u64 = 4;
+
+/// Parameters of the L2 pricing
+#[derive(Debug, Clone, Copy)]
+pub struct L2PricingModel {
+    pub speed_limit: u64,
+    pub pricing_inertia: u64,
+    pub backlog_tolerance: u64,
+    pub min_base_fee: u64,
+}
+
+impl L2PricingModel {
+    /// Create the pricing model of a gas configuration
+    pub fn new(config: &GasConfig) -> Self {
+        Self {
+            speed_limit: config.l2_speed_limit,
+            pricing_inertia: config.l2_pricing_inertia,
+            backlog_tolerance: config.l2_backlog_tolerance,
+            min_base_fee: config.l2_gas_price,
+        }
+    }
+
+    /// Pricing state of a chain without blocks
+    pub fn initial_state(&self) -> L2PricingState {
+        L2PricingState {
+            gas_backlog: 0,
+            base_fee: self.min_base_fee,
+        }
+    }
+
+    /// Drain `time_passed` seconds of gas from the backlog and reprice it
+    pub fn update(&self, state: &mut L2PricingState, time_passed: u64) {
+        let drained = time_passed.saturating_mul(self.speed_limit);
+        state.gas_backlog = state.gas_backlog.saturating_sub(drained);
+        state.base_fee = self.base_fee(state.gas_backlog);
+    }
+
+    /// Add the gas used by a transaction to the backlog
+    ///
+    /// Only gas spent on L2 execution counts, not the gas paying for L1 data.
+    pub fn grow_backlog(&self, state: &mut L2PricingState, gas: u64) {
+        state.gas_backlog = state.gas_backlog.saturating_add(gas);
+    }
+
+    /// Base fee for a gas backlog
+    pub fn base_fee(&self, gas_backlog: u64) -> u64 {
+        let tolerance = self.backlog_tolerance.saturating_mul(self.speed_limit);
+        if gas_backlog <= tolerance {
+            return self.min_base_fee;
+        }
+        let excess = u128::from(gas_backlog - tolerance);
+        let divisor = u128::from(self.pricing_inertia) * u128::from(self.speed_limit);
+        let exponent = (excess * u128::from(ONE_IN_BIPS) / divisor.max(1))
+            .try_into()
+            .unwrap_or(u64::MAX);
+        let fee = u128::from(self.min_base_fee) * u128::from(approx_exp_bips(exponent))
+            / u128::from(ONE_IN_BIPS);
+        fee.try_into().unwrap_or(u64::MAX)
+    }
+}
+
+/// e to the power of `exponent`, both in basis points
+///
+/// Evaluates the first terms of the Taylor series by Horner's method, as
+/// Nitro's `ApproxExpBasisPoints` does, saturating on overflow.
+pub fn approx_exp_bips(exponent: u64) -> u64 {
+    let mut result = ONE_IN_BIPS.saturating_add(exponent / EXP_ACCURACY);
+    for i in 1..EXP_ACCURACY {
+        result = ONE_IN_BIPS
+            .saturating_add(result.saturating_mul(exponent) / ((EXP_ACCURACY - i) * ONE_IN_BIPS));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> L2PricingModel {
+        L2PricingModel {
+            speed_limit: 7_000_000,
+            pricing_inertia: 102,
+            backlog_tolerance: 10,
+            min_base_fee: 100_000_000,
+        }
+    }
+
+    #[test]
+    fn test_approx_exp() {
+        assert_eq!(approx_exp_bips(0), ONE_IN_BIPS);
+        // e ≈ 2.7083 by the first four terms
+        assert_eq!(approx_exp_bips(ONE_IN_BIPS), 27_083);
+        assert!(approx_exp_bips(5_000) > 16_000 && approx_exp_bips(5_000) < 16_500);
+    }
+
+    #[test]
+    fn test_base_fee_follows_backlog() {
+        let model = model();
+        let mut state = model.initial_state();
+        assert_eq!(state.base_fee, model.min_base_fee);
+
+        // A backlog within 10s of the speed limit is tolerated
+        model.grow_backlog(&mut state, 70_000_000);
+        model.update(&mut state, 0);
+        assert_eq!(state.base_fee, model.min_base_fee);
+
+        // One inertia's worth of excess raises the fee e-fold
+        model.grow_backlog(&mut state, 102 * 7_000_000);
+        model.update(&mut state, 0);
+        assert_eq!(state.base_fee, 270_830_000);
+
+        // Time drains the backlog at the speed limit
+        model.update(&mut state, 50);
+        assert_eq!(state.gas_backlog, 70_000_000 + 52 * 7_000_000);
+        assert!(state.base_fee > model.min_base_fee && state.base_fee < 270_830_000);
+        model.update(&mut state, 1_000);
+        assert_eq!(state.gas_backlog, 0);
+        assert_eq!(state.base_fee, model.min_base_fee);
+    }
+}
@@ -1,14 +1,34 @@
 #![allow(dead_code)]
 
-use std::{collections::HashMap, sync::Arc};
+pub mod arbgasinfo;
+pub mod arbretryabletx;
+pub mod arbsys;
+pub mod execution;
+pub mod l1_pricing;
+pub mod l2_pricing;
+pub mod messages;
+pub mod outbox;
+pub mod precompiles;
+pub mod retryables;
+pub mod roots;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
 
 use alloy_primitives::{Address, B256, U256};
 use arbitrum_config::ArbitrumRethConfig;
 use arbitrum_storage::{
-    ArbitrumAccount, ArbitrumBlock, ArbitrumStorage, ArbitrumTransaction, L1Message,
+    ArbitrumAccount, ArbitrumBlock, ArbitrumReceipt, ArbitrumStorage, ArbitrumTransaction,
+    ArbitrumTxKind, L1Message, L1MessageKind, L1PricingState, L2Reorg, Log, Retryable,
 };
+use arbretryabletx::ArbRetryableTx::{RedeemScheduled, TicketCreated};
+use execution::ArbOsState;
 use eyre::Result;
-use tokio::sync::RwLock;
+use retryables::{RETRYABLE_LIFETIME_SECONDS, TicketUpdate, take_funds};
+use revm::context::result::{ExecutionResult as EvmExecutionResult, Output};
+use tokio::sync::{Mutex, RwLock, broadcast};
 use tracing::{debug, info, warn};
 
 /// Arbitrum L2 consensus engine with real storage integration
@@ -18,6 +38,8 @@ pub struct ArbitrumConsensus {
     is_running: Arc<RwLock<bool>>,
     current_block: Arc<RwLock<u64>>,
     state_cache: Arc<RwLock<HashMap<Address, ArbitrumAccount>>>,
+    /// Unwinds of stored blocks not yet followed
+    reorgs: Mutex<broadcast::Receiver<L2Reorg>>,
 }
 
 impl ArbitrumConsensus {
@@ -27,6 +49,7 @@ impl ArbitrumConsensus {
 
         Ok(Self {
             config: config.clone(),
+            reorgs: Mutex::new(storage.subscribe_reorgs()),
             storage,
             is_running: Arc::new(RwLock::new(false)),
             current_block: Arc::new(RwLock::new(0)),
@@ -88,16 +111,58 @@ impl ArbitrumConsensus {
     ) -> Result<Vec<ArbitrumTransaction>> {
         debug!("Processing L1 message: {}", message.message_number);
 
+        // A batch posting report carries the gas of the batch it names, as in
+        // Nitro, so it waits until that batch was read from L1
+        let mut message = message.clone();
+        if message.kind == L1MessageKind::BatchPostingReport
+            && message.batch_gas_cost.is_none()
+            && let Ok((data_hash, batch_number)) = messages::reported_batch(&message.data)
+        {
+            message.batch_gas_cost = Some(self.batch_gas(data_hash, batch_number).await?);
+        }
+        let message = &message;
+
         // Store the L1 message first
         self.storage.store_l1_message(message).await?;
 
-        // TODO: Implement L1 message processing logic
-        // This would involve:
-        // 1. Parsing the L1 message data
-        // 2. Creating corresponding L2 transactions
-        // 3. Updating L2 state accordingly
+        // Malformed messages are already on L1 and must not halt the chain;
+        // they are skipped without producing transactions
+        match messages::parse_l1_message(message, self.config.l2.chain_id) {
+            Ok(transactions) => {
+                debug!(
+                    "L1 message {} produced {} transactions",
+                    message.message_number,
+                    transactions.len()
+                );
+                Ok(transactions)
+            }
+            Err(e) => {
+                warn!(
+                    "Skipping invalid L1 message {}: {}",
+                    message.message_number, e
+                );
+                Ok(vec![])
+            }
+        }
+    }
 
-        Ok(vec![])
+    /// Derive the L2 transactions of a message sequenced in a batch
+    ///
+    /// The transactions come from the batch poster of L1 pricing. Invalid
+    /// messages were already posted to L1, so they are skipped without
+    /// producing transactions, as invalid delayed messages are.
+    pub fn process_sequencer_message(&self, data: &[u8]) -> Vec<ArbitrumTransaction> {
+        match messages::parse_sequencer_message(
+            data,
+            l1_pricing::BATCH_POSTER_ADDRESS,
+            self.config.l2.chain_id,
+        ) {
+            Ok(transactions) => transactions,
+            Err(e) => {
+                warn!("Skipping invalid sequencer message: {}", e);
+                vec![]
+            }
+        }
     }
 
     /// Validate a block according to Arbitrum consensus rules
@@ -142,6 +207,11 @@ impl ArbitrumConsensus {
 
     /// Validate a single transaction
     async fn validate_transaction(&self, tx: &ArbitrumTransaction) -> Result<()> {
+        // Transactions derived from L1 messages were authorized on L1
+        if !matches!(tx.kind, ArbitrumTxKind::Signed) {
+            return Ok(());
+        }
+
         // Basic transaction validation
         if tx.gas == 0 {
             return Err(eyre::eyre!("Transaction gas cannot be zero"));
@@ -208,32 +278,99 @@ impl ArbitrumConsensus {
     }
 
     /// Execute a block and return the resulting state changes
+    ///
+    /// The block is stored with the roots, bloom and gas used of its
+    /// execution, the base fee of the L2 pricing and the send accumulator
+    /// after it, under the hash of its header. Transactions that cannot be
+    /// included are left out of it, and the redeem attempts of retryable
+    /// tickets scheduled by a transaction follow it.
     pub async fn execute_block(&self, block: &ArbitrumBlock) -> Result<ExecutionResult> {
         debug!("Executing block: {}", block.number);
 
         // Validate block first
+        self.follow_unwinds().await?;
         self.validate_block(block).await?;
+        self.storage.begin_block().await?;
+
+        // The block pays the base fee its parent left, then the time since
+        // the parent drains the backlog for the next one
+        let mut arbos = ArbOsState::load(&self.storage, &self.config.gas).await?;
+        let block = &ArbitrumBlock {
+            base_fee: arbos.l2_pricing.base_fee,
+            ..block.clone()
+        };
+        let time_passed = match self.storage.get_block(&block.parent_hash).await? {
+            Some(parent) => block.timestamp.saturating_sub(parent.timestamp),
+            None => 0,
+        };
+        arbos
+            .l2_pricing_model
+            .update(&mut arbos.l2_pricing, time_passed);
+        self.reap_expired_retryables(block.timestamp).await?;
 
         let mut execution_result = ExecutionResult {
             block_number: block.number,
+            block_hash: B256::ZERO,
             state_root: B256::ZERO,
             gas_used: 0,
             transaction_results: vec![],
         };
+        let mut included = vec![];
+        let mut receipts = vec![];
 
-        // Execute each transaction
+        // Execute each transaction, then the redeem attempts it schedules
         for tx_hash in &block.transactions {
-            if let Some(tx) = self.storage.get_transaction(tx_hash).await? {
-                let tx_result = self.execute_transaction(&tx).await?;
+            let Some(tx) = self.storage.get_transaction(tx_hash).await? else {
+                warn!("Transaction not found during execution: {:?}", tx_hash);
+                continue;
+            };
+            let mut pending = VecDeque::from([tx]);
+            while let Some(tx) = pending.pop_front() {
+                let index = receipts.len() as u64;
+                let (tx_result, receipt) = self
+                    .execute_transaction(&tx, block, index, execution_result.gas_used, &mut arbos)
+                    .await?;
                 execution_result.gas_used += tx_result.gas_used;
                 execution_result.transaction_results.push(tx_result);
-            } else {
-                warn!("Transaction not found during execution: {:?}", tx_hash);
+                if let Some(receipt) = receipt {
+                    arbos.l2_pricing_model.grow_backlog(
+                        &mut arbos.l2_pricing,
+                        receipt.gas_used - receipt.gas_used_for_l1,
+                    );
+                    pending.extend(self.update_retryables(&receipt.logs, block).await?);
+                    receipts.push((roots::tx_type(&tx), receipt));
+                    included.push(tx);
+                }
             }
         }
 
-        // Store the block
-        self.storage.store_block(block).await?;
+        // Fold the block's state changes into the state trie. This rewrites
+        // the storage roots of accounts, so cached copies are stale
+        execution_result.state_root = self.storage.commit_state_root().await?;
+        self.state_cache.write().await.clear();
+        arbos.store(&self.storage).await?;
+
+        let block = ArbitrumBlock {
+            gas_used: execution_result.gas_used,
+            send_count: arbos.send_merkle.size,
+            send_root: outbox::root(&arbos.send_merkle),
+            arbos_version: precompiles::ARBOS_VERSION,
+            transactions: included.iter().map(|tx| tx.hash).collect(),
+            state_root: execution_result.state_root,
+            transactions_root: roots::transactions_root(&included),
+            receipts_root: roots::receipts_root(&receipts),
+            logs_bloom: roots::logs_bloom(receipts.iter().flat_map(|(_, r)| &r.logs)),
+            ..block.clone()
+        }
+        .seal();
+        execution_result.block_hash = block.hash;
+
+        // Store the block, then its receipts under its hash
+        self.storage.store_block(&block).await?;
+        for (_, mut receipt) in receipts {
+            receipt.block_hash = block.hash;
+            self.storage.store_receipt(&receipt).await?;
+        }
 
         // Update current block number
         {
@@ -241,110 +378,600 @@ impl ArbitrumConsensus {
             *current = block.number;
         }
 
-        // Calculate state root (simplified)
-        execution_result.state_root = self.calculate_state_root().await?;
-
         info!("Block {} executed successfully", block.number);
         Ok(execution_result)
     }
 
-    /// Execute a single transaction
-    async fn execute_transaction(&self, tx: &ArbitrumTransaction) -> Result<TransactionResult> {
+    /// Execute a single transaction and build its receipt
+    ///
+    /// `index` is the position of the transaction in `block`, after
+    /// `cumulative_gas_used` gas spent by the transactions before it. L1 fees
+    /// and batch posting reports update the L1 pricing in `arbos`, sends to
+    /// L1 are appended to its accumulator and stored. Retryable submissions
+    /// create their ticket, and redeem attempts run with their ticket's
+    /// escrow. There is no receipt if the transaction cannot be included.
+    async fn execute_transaction(
+        &self,
+        tx: &ArbitrumTransaction,
+        block: &ArbitrumBlock,
+        index: u64,
+        cumulative_gas_used: u64,
+        arbos: &mut ArbOsState,
+    ) -> Result<(TransactionResult, Option<ArbitrumReceipt>)> {
         debug!("Executing transaction: {:?}", tx.hash);
 
         // Store the transaction
         self.storage.store_transaction(tx).await?;
 
-        // Load sender account
-        let mut sender_account =
-            self.storage
-                .get_account(&tx.from)
+        let mut receipt = ArbitrumReceipt {
+            transaction_hash: tx.hash,
+            transaction_index: index,
+            block_hash: B256::ZERO,
+            block_number: block.number,
+            cumulative_gas_used,
+            gas_used: 0,
+            gas_used_for_l1: 0,
+            contract_address: None,
+            logs: vec![],
+            status: 1,
+            effective_gas_price: tx.gas_price,
+        };
+        let mut return_data = vec![];
+
+        match tx.kind {
+            ArbitrumTxKind::Deposit { .. } => {
+                // Deposits mint their value outside the EVM
+                self.mint(tx.to.unwrap_or(tx.from), tx.value).await?;
+                receipt.effective_gas_price = U256::ZERO;
+            }
+            ArbitrumTxKind::SubmitRetryable { .. } => {
+                match self.submit_retryable(tx, block).await? {
+                    Ok(logs) => receipt.logs = logs,
+                    Err(reason) => {
+                        warn!("Retryable submission {:?} failed: {}", tx.hash, reason);
+                        receipt.status = 0;
+                    }
+                }
+                receipt.effective_gas_price = U256::ZERO;
+            }
+            ArbitrumTxKind::Retry { ticket_id, .. }
+                if !self.start_retry(tx, ticket_id, block).await? =>
+            {
+                warn!(
+                    "Ticket {} of redeem attempt {:?} is gone",
+                    ticket_id, tx.hash
+                );
+                receipt.status = 0;
+                receipt.effective_gas_price = U256::ZERO;
+            }
+            ArbitrumTxKind::Signed
+            | ArbitrumTxKind::Unsigned
+            | ArbitrumTxKind::Contract { .. }
+            | ArbitrumTxKind::Retry { .. } => {
+                let chain_id = self.config.l2.chain_id;
+                let units = l1_pricing::poster_units(tx);
+                let poster_cost = arbos.l1_pricing.price_per_unit * U256::from(units);
+                let poster_gas = l1_pricing::poster_gas(poster_cost, block.base_fee);
+                let execution = match execution::execute_transaction(
+                    Arc::clone(&self.storage),
+                    chain_id,
+                    block,
+                    tx,
+                    poster_gas,
+                    arbos,
+                )
                 .await?
-                .unwrap_or(ArbitrumAccount {
-                    address: tx.from,
-                    balance: U256::ZERO,
-                    nonce: 0,
-                    code_hash: B256::ZERO,
-                    storage_root: B256::ZERO,
-                });
-
-        // Update sender account
-        let tx_cost = U256::from(tx.gas) * tx.gas_price + tx.value;
-        if sender_account.balance < tx_cost {
-            return Ok(TransactionResult {
-                tx_hash: tx.hash,
-                success: false,
-                gas_used: 21000, // Basic gas cost for failed transaction
-                return_data: vec![],
-            });
+                {
+                    Ok(execution) => execution,
+                    Err(invalid) if matches!(tx.kind, ArbitrumTxKind::Retry { .. }) => {
+                        // The attempt was paid for, so it fails instead
+                        warn!("Redeem attempt {:?} is invalid: {}", tx.hash, invalid);
+                        receipt.status = 0;
+                        self.end_retry(tx, block, &receipt).await?;
+                        let result = TransactionResult {
+                            tx_hash: tx.hash,
+                            success: false,
+                            gas_used: 0,
+                            return_data: vec![],
+                        };
+                        return Ok((result, Some(receipt)));
+                    }
+                    Err(invalid) => {
+                        warn!("Transaction {:?} is invalid: {}", tx.hash, invalid);
+                        let result = TransactionResult {
+                            tx_hash: tx.hash,
+                            success: false,
+                            gas_used: 0,
+                            return_data: vec![],
+                        };
+                        return Ok((result, None));
+                    }
+                };
+
+                let changed = execution::commit_state(&self.storage, execution.state).await?;
+                {
+                    let mut cache = self.state_cache.write().await;
+                    for address in changed {
+                        cache.remove(&address);
+                    }
+                }
+
+                // The sender paid the L1 fee out of the balance the EVM saw
+                let gas_price = U256::from(block.base_fee);
+                if poster_gas > 0 {
+                    let fee = U256::from(poster_gas) * gas_price;
+                    self.mint(l1_pricing::L1_PRICER_FUNDS_POOL, fee).await?;
+                    l1_pricing::record_poster_fee(&mut arbos.l1_pricing, units, fee);
+                }
+
+                let result = execution.result;
+                for send in result
+                    .logs()
+                    .iter()
+                    .filter_map(|log| arbsys::sent(log, tx.hash))
+                {
+                    outbox::append(&mut arbos.send_merkle, send.hash);
+                    self.storage.store_send(&send).await?;
+                }
+                for update in result.logs().iter().filter_map(arbsys::merkle_update) {
+                    self.storage
+                        .store_send_merkle_node(update.level, update.leaf, update.hash)
+                        .await?;
+                }
+                receipt.effective_gas_price = gas_price;
+                receipt.gas_used = result.gas_used() + poster_gas;
+                receipt.gas_used_for_l1 = poster_gas;
+                receipt.status = u64::from(result.is_success());
+                receipt.logs = result
+                    .logs()
+                    .iter()
+                    .map(|log| Log {
+                        address: log.address,
+                        topics: log.topics().to_vec(),
+                        data: log.data.data.to_vec(),
+                        block_hash: None,
+                        block_number: None,
+                        transaction_hash: None,
+                        transaction_index: None,
+                        log_index: None,
+                        removed: false,
+                    })
+                    .collect();
+                if let EvmExecutionResult::Success {
+                    output: Output::Create(_, address),
+                    ..
+                } = &result
+                {
+                    receipt.contract_address = *address;
+                }
+                return_data = result
+                    .output()
+                    .map(|output| output.to_vec())
+                    .unwrap_or_default();
+                self.end_retry(tx, block, &receipt).await?;
+            }
+            ArbitrumTxKind::BatchPostingReport {
+                batch_timestamp,
+                batch_poster,
+                batch_number,
+                l1_base_fee_estimate,
+                batch_data_gas,
+                ..
+            } => {
+                self.settle_batch_posting_report(
+                    &mut arbos.l1_pricing,
+                    block,
+                    batch_timestamp.saturating_to(),
+                    batch_poster,
+                    batch_number,
+                    l1_base_fee_estimate,
+                    batch_data_gas,
+                )
+                .await?;
+                receipt.effective_gas_price = U256::ZERO;
+            }
+            ArbitrumTxKind::Initialize {
+                initial_l1_base_fee,
+                ..
+            } => {
+                arbos.l1_pricing.price_per_unit = initial_l1_base_fee;
+                receipt.effective_gas_price = U256::ZERO;
+            }
+        }
+
+        receipt.cumulative_gas_used += receipt.gas_used;
+
+        let result = TransactionResult {
+            tx_hash: tx.hash,
+            success: receipt.status == 1,
+            gas_used: receipt.gas_used,
+            return_data,
+        };
+        Ok((result, Some(receipt)))
+    }
+
+    /// Create the ticket of a retryable submission and schedule its first
+    /// redeem attempt, returning the events of both
+    ///
+    /// The deposit is minted to the sender, who pays the submission fee and
+    /// escrows the call value out of it. The submission fee is burnt, and the
+    /// gas of the attempt too, which then runs at the base fee. What else of
+    /// the deposit the sender offered for fees goes to the fee refund
+    /// address. Without the gas or the funds for an attempt, the ticket waits
+    /// for a manual redeem. A submission that cannot pay fails, keeping the
+    /// deposit, as in ArbOS.
+    async fn submit_retryable(
+        &self,
+        tx: &ArbitrumTransaction,
+        block: &ArbitrumBlock,
+    ) -> Result<Result<Vec<Log>, String>> {
+        let ArbitrumTxKind::SubmitRetryable {
+            l1_base_fee,
+            deposit_value,
+            max_submission_fee,
+            fee_refund_address,
+            beneficiary,
+            ..
+        } = tx.kind
+        else {
+            return Err(eyre::eyre!("{:?} is not a retryable submission", tx.hash));
+        };
+        self.mint(tx.from, deposit_value).await?;
+
+        let balance = self.balance(tx.from).await?;
+        if balance < max_submission_fee {
+            return Ok(Err(format!(
+                "insufficient funds for max submission fee: {balance} < {max_submission_fee}"
+            )));
+        }
+        let submission_fee = retryables::submission_fee(tx.data.len(), l1_base_fee);
+        if max_submission_fee < submission_fee {
+            return Ok(Err(format!(
+                "max submission fee {max_submission_fee} is less than the actual submission fee {submission_fee}"
+            )));
+        }
+        if balance - max_submission_fee < tx.value {
+            return Ok(Err(format!(
+                "insufficient funds for call value: {balance} < {}",
+                max_submission_fee + tx.value
+            )));
         }
 
-        sender_account.balance -= tx_cost;
-        sender_account.nonce += 1;
-
-        // Handle recipient account if it's a transfer
-        if let Some(to_address) = tx.to {
-            let mut recipient_account =
-                self.storage
-                    .get_account(&to_address)
-                    .await?
-                    .unwrap_or(ArbitrumAccount {
-                        address: to_address,
-                        balance: U256::ZERO,
-                        nonce: 0,
-                        code_hash: B256::ZERO,
-                        storage_root: B256::ZERO,
-                    });
-
-            recipient_account.balance += tx.value;
-            self.storage
-                .store_account(to_address, &recipient_account)
+        let ticket_id = tx.hash;
+        self.transfer(tx.from, retryables::escrow_address(ticket_id), tx.value)
+            .await?;
+        let ticket = Retryable {
+            id: ticket_id,
+            num_tries: 0,
+            from: tx.from,
+            to: tx.to,
+            call_value: tx.value,
+            beneficiary,
+            data: tx.data.clone(),
+            timeout: block.timestamp + RETRYABLE_LIFETIME_SECONDS,
+        };
+        self.storage.store_retryable(&ticket).await?;
+        let mut logs = vec![retryables::event_log(&TicketCreated {
+            ticketId: ticket_id,
+        })];
+
+        // The deposit beyond the call value is what the sender offered for
+        // fees, and what may be refunded
+        let mut available_refund = deposit_value.saturating_sub(tx.value);
+        self.burn(tx.from, submission_fee).await?;
+        let withheld_submission_fee = take_funds(&mut available_refund, submission_fee);
+        let submission_fee_refund =
+            take_funds(&mut available_refund, max_submission_fee - submission_fee);
+        self.transfer(tx.from, fee_refund_address, submission_fee_refund)
+            .await?;
+
+        let base_fee = U256::from(block.base_fee);
+        let max_gas_cost = tx.gas_price * U256::from(tx.gas);
+        let balance = self.balance(tx.from).await?;
+        if balance < max_gas_cost || tx.gas < retryables::TX_GAS || tx.gas_price < base_fee {
+            let gas_cost_refund = take_funds(&mut available_refund, max_gas_cost);
+            self.transfer(tx.from, fee_refund_address, gas_cost_refund.min(balance))
                 .await?;
+            return Ok(Ok(logs));
         }
 
-        // Store updated sender account
-        self.storage.store_account(tx.from, &sender_account).await?;
+        let gas_cost = base_fee * U256::from(tx.gas);
+        self.burn(tx.from, gas_cost).await?;
+        let withheld_gas_funds = take_funds(&mut available_refund, gas_cost);
+        let gas_price_refund = take_funds(
+            &mut available_refund,
+            (tx.gas_price - base_fee) * U256::from(tx.gas),
+        );
+        self.transfer(tx.from, fee_refund_address, gas_price_refund)
+            .await?;
+        available_refund += withheld_gas_funds + withheld_submission_fee;
+
+        let mut event = RedeemScheduled {
+            ticketId: ticket_id,
+            retryTxHash: B256::ZERO,
+            sequenceNum: 0,
+            donatedGas: tx.gas,
+            gasDonor: fee_refund_address,
+            maxRefund: available_refund,
+            submissionFeeRefund: submission_fee,
+        };
+        event.retryTxHash =
+            retryables::retry_transaction(&ticket, &event, block.base_fee, self.config.l2.chain_id)
+                .hash;
+        logs.push(retryables::event_log(&event));
+        Ok(Ok(logs))
+    }
 
-        // Update local cache
-        {
-            let mut cache = self.state_cache.write().await;
-            cache.insert(tx.from, sender_account);
-            if let Some(to_address) = tx.to
-                && let Ok(Some(recipient)) = self.storage.get_account(&to_address).await
-            {
-                cache.insert(to_address, recipient);
-            }
+    /// Give a redeem attempt its ticket's call value and the gas it was paid
+    /// for, returning false if the ticket is gone
+    async fn start_retry(
+        &self,
+        tx: &ArbitrumTransaction,
+        ticket_id: B256,
+        block: &ArbitrumBlock,
+    ) -> Result<bool> {
+        let ticket = self.storage.get_retryable(&ticket_id).await?;
+        if ticket.is_none_or(|ticket| ticket.timeout < block.timestamp) {
+            return Ok(false);
         }
+        let escrow = retryables::escrow_address(ticket_id);
+        if self.balance(escrow).await? < tx.value {
+            return Ok(false);
+        }
+        self.transfer(escrow, tx.from, tx.value).await?;
+        self.mint(tx.from, U256::from(block.base_fee) * U256::from(tx.gas))
+            .await?;
+        Ok(true)
+    }
 
-        let result = TransactionResult {
-            tx_hash: tx.hash,
-            success: true,
-            gas_used: tx.gas.min(21000), // Basic transaction cost
-            return_data: vec![],
+    /// Settle a redeem attempt that ran with `receipt`
+    ///
+    /// The gas the attempt did not use is refunded to the refund address up
+    /// to its maximum refund, after the submission fee if the attempt
+    /// succeeded, and the rest to the sender. A successful attempt closes the
+    /// ticket, a failed one returns the call value to the escrow.
+    async fn end_retry(
+        &self,
+        tx: &ArbitrumTransaction,
+        block: &ArbitrumBlock,
+        receipt: &ArbitrumReceipt,
+    ) -> Result<()> {
+        let ArbitrumTxKind::Retry {
+            ticket_id,
+            refund_to,
+            mut max_refund,
+            submission_fee_refund,
+        } = tx.kind
+        else {
+            return Ok(());
         };
+        let success = receipt.status == 1;
+
+        // The EVM returned the unused gas to the sender, but the refund
+        // address comes first
+        let gas_refund = U256::from(block.base_fee) * U256::from(tx.gas - receipt.gas_used);
+        self.burn(tx.from, gas_refund).await?;
+        if success {
+            self.refund(&mut max_refund, refund_to, tx.from, submission_fee_refund)
+                .await?;
+        } else {
+            take_funds(&mut max_refund, submission_fee_refund);
+        }
+        self.refund(&mut max_refund, refund_to, tx.from, gas_refund)
+            .await?;
+
+        if success {
+            if let Some(ticket) = self.storage.get_retryable(&ticket_id).await? {
+                self.close_retryable(&ticket).await?;
+            }
+        } else {
+            self.transfer(tx.from, retryables::escrow_address(ticket_id), tx.value)
+                .await?;
+        }
+        Ok(())
+    }
 
-        Ok(result)
+    /// Mint `amount` back to `refund_to` as far as `max_refund` allows, and
+    /// the rest to `from`
+    async fn refund(
+        &self,
+        max_refund: &mut U256,
+        refund_to: Address,
+        from: Address,
+        amount: U256,
+    ) -> Result<()> {
+        let refunded = take_funds(max_refund, amount);
+        self.mint(refund_to, refunded).await?;
+        self.mint(from, amount - refunded).await
     }
 
-    /// Calculate the current state root
-    async fn calculate_state_root(&self) -> Result<B256> {
-        // TODO: Implement proper state root calculation
-        // This would involve building a Merkle tree of all account states
+    /// Apply the changes to tickets recorded in `logs`, returning the redeem
+    /// attempts they schedule
+    async fn update_retryables(
+        &self,
+        logs: &[Log],
+        block: &ArbitrumBlock,
+    ) -> Result<Vec<ArbitrumTransaction>> {
+        let mut retries = vec![];
+        for update in logs.iter().filter_map(TicketUpdate::from_receipt_log) {
+            let id = update.ticket_id();
+            let mut ticket = self.storage.get_retryable(&id).await?;
+            if ticket.is_none() {
+                warn!("Update of unknown ticket {}", id);
+                continue;
+            }
+            update.apply(&mut ticket);
+            match ticket {
+                Some(ticket) => {
+                    if let TicketUpdate::Redeem(event) = &update {
+                        retries.push(retryables::retry_transaction(
+                            &ticket,
+                            event,
+                            block.base_fee,
+                            self.config.l2.chain_id,
+                        ));
+                    }
+                    self.storage.store_retryable(&ticket).await?;
+                }
+                // Cancelled tickets returned their escrow in the EVM
+                None => {
+                    self.storage.delete_retryable(&id).await?;
+                }
+            }
+        }
+        Ok(retries)
+    }
 
-        // For now, return a dummy hash based on current block
-        use sha3::{Digest, Keccak256};
-        let mut hasher = Keccak256::new();
-        hasher.update(b"arbitrum_state_root");
-        hasher.update(self.current_block_number().await.to_be_bytes());
-        let result = hasher.finalize();
-        Ok(B256::from_slice(&result))
+    /// Close the tickets that expired before `timestamp`, earliest first and
+    /// at most [`retryables::REAPS_PER_BLOCK`] of them
+    async fn reap_expired_retryables(&self, timestamp: u64) -> Result<()> {
+        for _ in 0..retryables::REAPS_PER_BLOCK {
+            match self.storage.get_next_retryable_timeout().await? {
+                Some(ticket) if ticket.timeout < timestamp => {
+                    debug!("Ticket {} expired", ticket.id);
+                    self.close_retryable(&ticket).await?;
+                }
+                _ => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete a ticket, returning what is left in its escrow to its
+    /// beneficiary
+    async fn close_retryable(&self, ticket: &Retryable) -> Result<()> {
+        self.storage.delete_retryable(&ticket.id).await?;
+        let escrow = retryables::escrow_address(ticket.id);
+        let balance = self.balance(escrow).await?;
+        self.transfer(escrow, ticket.beneficiary, balance).await
+    }
+
+    /// L1 gas of the reported batch with sequencer message hash `data_hash`
+    async fn batch_gas(&self, data_hash: B256, batch_number: u64) -> Result<u64> {
+        self.storage.get_batch_gas(data_hash).await?.ok_or_else(|| {
+            eyre::eyre!(
+                "Batch {} of a posting report, with data hash {}, has not been read from L1",
+                batch_number,
+                data_hash
+            )
+        })
+    }
+
+    /// Refund a batch poster what it reports spending on a batch, from the
+    /// L1 pricer's pool
+    ///
+    /// The batch's data gas was derived from the sequencer message the report
+    /// hashes when the report was read, so every node charges the same.
+    /// Reports the pricer rejects are skipped, as ArbOS does.
+    #[allow(clippy::too_many_arguments)]
+    async fn settle_batch_posting_report(
+        &self,
+        l1_pricing: &mut L1PricingState,
+        block: &ArbitrumBlock,
+        batch_timestamp: u64,
+        batch_poster: Address,
+        batch_number: u64,
+        l1_base_fee: U256,
+        batch_data_gas: u64,
+    ) -> Result<()> {
+        let wei_spent = l1_pricing::batch_posting_cost(l1_pricing, batch_data_gas, l1_base_fee);
+        let mut updated = l1_pricing.clone();
+        match l1_pricing::update_for_batch_poster_spending(
+            &mut updated,
+            batch_timestamp,
+            block.timestamp,
+            batch_poster,
+            wei_spent,
+            l1_base_fee,
+        ) {
+            Ok(payments) => {
+                for (to, amount) in payments {
+                    self.transfer(l1_pricing::L1_PRICER_FUNDS_POOL, to, amount)
+                        .await?;
+                }
+                *l1_pricing = updated;
+            }
+            Err(e) => warn!("Skipping report of batch {}: {}", batch_number, e),
+        }
+        Ok(())
+    }
+
+    /// Move `amount` wei from `from` to `to`, which must hold it
+    async fn transfer(&self, from: Address, to: Address, amount: U256) -> Result<()> {
+        self.burn(from, amount).await?;
+        self.mint(to, amount).await
+    }
+
+    /// Credit `amount` wei to `to`, out of nothing
+    async fn mint(&self, to: Address, amount: U256) -> Result<()> {
+        // Crediting nothing must not create an empty account
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let mut account = self
+            .storage
+            .get_account(&to)
+            .await?
+            .unwrap_or(ArbitrumAccount {
+                address: to,
+                balance: U256::ZERO,
+                nonce: 0,
+                code_hash: B256::ZERO,
+                storage_root: B256::ZERO,
+            });
+        account.balance += amount;
+        self.update_account(to, account).await
+    }
+
+    /// Debit `amount` wei from `from`, which must hold it
+    async fn burn(&self, from: Address, amount: U256) -> Result<()> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let mut account = self
+            .storage
+            .get_account(&from)
+            .await?
+            .ok_or_else(|| eyre::eyre!("{from} has no account to pay {amount} wei from"))?;
+        account.balance = account
+            .balance
+            .checked_sub(amount)
+            .ok_or_else(|| eyre::eyre!("{from} cannot pay {amount} wei"))?;
+        self.update_account(from, account).await
+    }
+
+    /// Balance of `address`, zero without an account
+    async fn balance(&self, address: Address) -> Result<U256> {
+        Ok(self
+            .storage
+            .get_account(&address)
+            .await?
+            .map_or(U256::ZERO, |account| account.balance))
     }
 
     /// Get the current block number
     pub async fn current_block_number(&self) -> u64 {
+        if let Err(e) = self.follow_unwinds().await {
+            warn!("Failed to follow unwound blocks: {}", e);
+        }
         *self.current_block.read().await
     }
 
+    /// Go back to the head left by blocks unwound in storage since last
+    /// checked, as after an L1 reorg, dropping cached accounts
+    async fn follow_unwinds(&self) -> Result<()> {
+        let mut reorgs = self.reorgs.lock().await;
+        let mut unwound = false;
+        while let Ok(_) | Err(broadcast::error::TryRecvError::Lagged(_)) = reorgs.try_recv() {
+            unwound = true;
+        }
+        if unwound {
+            *self.current_block.write().await = self.storage.get_current_block_number().await?;
+            self.state_cache.write().await.clear();
+        }
+        Ok(())
+    }
+
     /// Get account state with caching
     pub async fn get_account(&self, address: &Address) -> Option<ArbitrumAccount> {
         // Check cache first
@@ -402,6 +1029,7 @@ impl ArbitrumConsensus {
 #[derive(Debug, Clone)]
 pub struct ExecutionResult {
     pub block_number: u64,
+    pub block_hash: B256,
     pub state_root: B256,
     pub gas_used: u64,
     pub transaction_results: Vec<TransactionResult>,
@@ -434,8 +1062,13 @@ mod tests {
     use super::*;
 
     async fn create_test_consensus() -> (ArbitrumConsensus, TempDir) {
+        create_test_consensus_with(ArbitrumRethConfig::default()).await
+    }
+
+    async fn create_test_consensus_with(
+        mut config: ArbitrumRethConfig,
+    ) -> (ArbitrumConsensus, TempDir) {
         let temp_dir = TempDir::new().unwrap();
-        let mut config = ArbitrumRethConfig::default();
         config.node.datadir = temp_dir.path().to_path_buf();
 
         let storage = ArbitrumStorage::new(&config).await.unwrap();
@@ -472,6 +1105,7 @@ mod tests {
             gas_used: 0,
             transactions: vec![],
             l1_block_number: 0,
+            ..Default::default()
         };
 
         let is_valid = consensus.validate_block(&block).await.unwrap();
@@ -485,18 +1119,1601 @@ mod tests {
 
         let block = ArbitrumBlock {
             number: 0,
-            hash: b256!("0x1234567890123456789012345678901234567890123456789012345678901234"),
             parent_hash: B256::ZERO,
             timestamp: 1000,
             gas_limit: 10000000,
             gas_used: 0,
             transactions: vec![],
             l1_block_number: 0,
+            ..Default::default()
         };
 
         let result = consensus.execute_block(&block).await.unwrap();
         assert_eq!(result.block_number, 0);
         assert_eq!(result.gas_used, 0);
         assert_eq!(consensus.current_block_number().await, 0);
+
+        // The block is stored under the hash of its header
+        let stored = consensus
+            .storage
+            .get_block_by_number(0)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.hash, result.block_hash);
+        assert_eq!(stored.hash, stored.compute_hash());
+        assert_eq!(stored.state_root, result.state_root);
+        assert_eq!(stored.arbos_version, precompiles::ARBOS_VERSION);
+
+        // and the next block builds on it
+        let next = ArbitrumBlock {
+            number: 1,
+            parent_hash: result.block_hash,
+            timestamp: 1001,
+            gas_limit: 10000000,
+            delayed_messages_read: 1,
+            ..Default::default()
+        };
+        let result = consensus.execute_block(&next).await.unwrap();
+        let stored = consensus
+            .storage
+            .get_block(&result.block_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.number, 1);
+        assert_eq!(stored.header().nonce, alloy_primitives::B64::from(1u64));
+    }
+
+    #[tokio::test]
+    async fn test_unwinding_a_block_reverts_its_state() {
+        use alloy_primitives::{address, keccak256};
+
+        let (consensus, _temp_dir) = create_test_consensus().await;
+        consensus.start().await.unwrap();
+        let genesis = ArbitrumBlock {
+            number: 0,
+            timestamp: 1000,
+            gas_limit: 10_000_000,
+            ..Default::default()
+        };
+        let genesis = consensus.execute_block(&genesis).await.unwrap();
+
+        let sender = address!("0x5555555555555555555555555555555555555555");
+        let recipient = address!("0x6666666666666666666666666666666666666666");
+        let funded = ArbitrumAccount {
+            address: sender,
+            balance: U256::from(10u64.pow(18)),
+            nonce: 0,
+            code_hash: B256::ZERO,
+            storage_root: B256::ZERO,
+        };
+        consensus.update_account(sender, funded).await.unwrap();
+        let root = consensus.storage.commit_state_root().await.unwrap();
+        let l1_pricing = consensus.storage.get_l1_pricing().await.unwrap();
+        let l2_pricing = consensus.storage.get_l2_pricing().await.unwrap();
+
+        let raw = keccak256(b"transfer").to_vec();
+        let tx = ArbitrumTransaction {
+            hash: keccak256(&raw),
+            from: sender,
+            to: Some(recipient),
+            value: U256::from(1_000),
+            gas: 2_000_000,
+            gas_price: U256::from(consensus.config.gas.l2_gas_price),
+            nonce: 0,
+            data: vec![],
+            l1_sequence_number: None,
+            raw,
+            kind: ArbitrumTxKind::Signed,
+        };
+        consensus.storage.store_transaction(&tx).await.unwrap();
+        let block = ArbitrumBlock {
+            number: 1,
+            parent_hash: genesis.block_hash,
+            timestamp: 1001,
+            gas_limit: 10_000_000,
+            transactions: vec![tx.hash],
+            ..Default::default()
+        };
+        let result = consensus.execute_block(&block).await.unwrap();
+        assert!(result.transaction_results[0].success);
+        assert_eq!(
+            consensus.balance(recipient).await.unwrap(),
+            U256::from(1_000)
+        );
+        assert!(
+            consensus
+                .balance(l1_pricing::L1_PRICER_FUNDS_POOL)
+                .await
+                .unwrap()
+                > U256::ZERO
+        );
+        assert_ne!(result.state_root, root);
+
+        // Unwinding the block puts back everything it wrote
+        consensus.storage.unwind_blocks_above(0).await.unwrap();
+        assert_eq!(consensus.current_block_number().await, 0);
+        let account = consensus.get_account(&sender).await.unwrap();
+        assert_eq!(account.balance, U256::from(10u64.pow(18)));
+        assert_eq!(account.nonce, 0);
+        assert!(consensus.get_account(&recipient).await.is_none());
+        assert_eq!(
+            consensus
+                .balance(l1_pricing::L1_PRICER_FUNDS_POOL)
+                .await
+                .unwrap(),
+            U256::ZERO
+        );
+        assert_eq!(consensus.storage.get_state_root().await.unwrap(), root);
+        assert_eq!(
+            consensus.storage.get_l1_pricing().await.unwrap(),
+            l1_pricing
+        );
+        assert_eq!(
+            consensus.storage.get_l2_pricing().await.unwrap(),
+            l2_pricing
+        );
+
+        // and the block executes again to the same state
+        consensus.storage.store_transaction(&tx).await.unwrap();
+        let again = consensus.execute_block(&block).await.unwrap();
+        assert_eq!(again.state_root, result.state_root);
+        assert_eq!(again.block_hash, result.block_hash);
+        assert_eq!(consensus.current_block_number().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_process_l1_message() {
+        use alloy_primitives::address;
+        use arbitrum_storage::ArbitrumTxKind;
+
+        let (consensus, _temp_dir) = create_test_consensus().await;
+        consensus.start().await.unwrap();
+
+        let to = address!("0x1111111111111111111111111111111111111111");
+        let mut data = to.to_vec();
+        data.extend_from_slice(&B256::from(U256::from(5_000)).0);
+        let mut message = L1Message {
+            message_number: 7,
+            kind: L1MessageKind::EthDeposit,
+            sender: address!("0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"),
+            data,
+            timestamp: 1_700_000_000,
+            block_number: 100,
+            request_id: B256::from(U256::from(7)),
+            l1_base_fee: U256::ZERO,
+            batch_gas_cost: None,
+        };
+
+        let txs = consensus.process_l1_message(&message).await.unwrap();
+        assert_eq!(txs.len(), 1);
+        assert!(matches!(txs[0].kind, ArbitrumTxKind::Deposit { .. }));
+        assert_eq!(
+            consensus.storage.get_l1_messages(7, 7).await.unwrap().len(),
+            1
+        );
+
+        // Malformed messages are recorded but produce no transactions
+        message.message_number = 8;
+        message.data.truncate(10);
+        assert!(
+            consensus
+                .process_l1_message(&message)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_block_execution_runs_contracts() {
+        use alloy_primitives::{address, hex};
+
+        let (consensus, _temp_dir) = create_test_consensus().await;
+        consensus.start().await.unwrap();
+
+        let sender = address!("0x5555555555555555555555555555555555555555");
+        let funded = ArbitrumAccount {
+            address: sender,
+            balance: U256::from(10u64.pow(18)),
+            nonce: 0,
+            code_hash: B256::ZERO,
+            storage_root: B256::ZERO,
+        };
+        consensus.update_account(sender, funded).await.unwrap();
+
+        // Stores the calldata word in slot 0, logs it under topic 0xaa and
+        // returns it
+        let runtime = hex!("6000358060005560005260aa60206000a160206000f3");
+        let init = [hex!("601680600b6000396000f3").as_slice(), &runtime].concat();
+        let contract = sender.create(0);
+        let word = B256::with_last_byte(0x2a);
+        let tx = |nonce: u64, to: Option<Address>, data: Vec<u8>| ArbitrumTransaction {
+            hash: B256::with_last_byte(0xf0 + nonce as u8),
+            from: sender,
+            to,
+            value: U256::ZERO,
+            gas: 1_000_000,
+            gas_price: U256::from(1_000_000_000u64),
+            nonce,
+            data,
+            l1_sequence_number: None,
+            raw: vec![],
+            kind: ArbitrumTxKind::Signed,
+        };
+        let txs = [tx(0, None, init), tx(1, Some(contract), word.to_vec())];
+        for tx in &txs {
+            consensus.storage.store_transaction(tx).await.unwrap();
+        }
+
+        let block = ArbitrumBlock {
+            number: 0,
+            parent_hash: B256::ZERO,
+            timestamp: 1000,
+            gas_limit: 10_000_000,
+            gas_used: 0,
+            transactions: txs.iter().map(|tx| tx.hash).collect(),
+            l1_block_number: 0,
+            ..Default::default()
+        };
+        let result = consensus.execute_block(&block).await.unwrap();
+        let [deploy, call] = &result.transaction_results[..] else {
+            panic!("expected two transaction results");
+        };
+        assert!(deploy.success && call.success);
+        assert_eq!(call.return_data, word.to_vec());
+        assert!(call.gas_used > 21_000 + 20_000); // Includes the SSTORE
+        assert_eq!(result.gas_used, deploy.gas_used + call.gas_used);
+
+        let deploy_receipt = consensus
+            .storage
+            .get_receipt(&txs[0].hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(deploy_receipt.contract_address, Some(contract));
+        assert_eq!(deploy_receipt.block_hash, result.block_hash);
+        let receipt = consensus
+            .storage
+            .get_receipt(&txs[1].hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(receipt.status, 1);
+        assert_eq!(receipt.transaction_index, 1);
+        assert_eq!(receipt.cumulative_gas_used, result.gas_used);
+        assert_eq!(receipt.logs.len(), 1);
+        assert_eq!(receipt.logs[0].address, contract);
+        assert_eq!(receipt.logs[0].topics, vec![B256::with_last_byte(0xaa)]);
+        assert_eq!(receipt.logs[0].data, word.to_vec());
+
+        assert_eq!(
+            consensus
+                .storage
+                .get_storage(&contract, &B256::ZERO)
+                .await
+                .unwrap(),
+            U256::from(0x2a)
+        );
+        let code_hash = consensus.get_account(&contract).await.unwrap().code_hash;
+        assert_eq!(
+            consensus.storage.get_code(&code_hash).await.unwrap(),
+            Some(runtime.to_vec())
+        );
+        let stored = consensus
+            .storage
+            .get_block(&result.block_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.gas_used, result.gas_used);
+        assert_eq!(stored.transactions, block.transactions);
+        assert_eq!(
+            stored.receipts_root,
+            roots::receipts_root(&[(0, deploy_receipt), (0, receipt)])
+        );
+        assert!(
+            stored
+                .logs_bloom
+                .contains_input(alloy_primitives::BloomInput::Raw(contract.as_slice()))
+        );
+        let contract_account = consensus.get_account(&contract).await.unwrap();
+        assert_ne!(contract_account.storage_root, B256::ZERO);
+        assert_eq!(
+            result.state_root,
+            consensus.storage.get_state_root().await.unwrap()
+        );
+        // Gas is paid at the base fee rather than the gas price
+        let sender_account = consensus.get_account(&sender).await.unwrap();
+        assert_eq!(sender_account.nonce, 2);
+        assert_eq!(
+            sender_account.balance,
+            U256::from(10u64.pow(18) - result.gas_used * stored.base_fee)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_l1_data_fee_is_charged_as_gas() {
+        use alloy_primitives::{address, keccak256};
+
+        let (consensus, _temp_dir) = create_test_consensus().await;
+        consensus.start().await.unwrap();
+
+        let sender = address!("0x5555555555555555555555555555555555555555");
+        let funded = ArbitrumAccount {
+            address: sender,
+            balance: U256::from(10u64.pow(18)),
+            nonce: 0,
+            code_hash: B256::ZERO,
+            storage_root: B256::ZERO,
+        };
+        consensus.update_account(sender, funded).await.unwrap();
+
+        // A transfer whose encoding does not compress
+        let raw: Vec<u8> = (0..4u32)
+            .flat_map(|i| keccak256(i.to_be_bytes()).0)
+            .collect();
+        let gas_price = 1_000_000_000u64;
+        let tx = ArbitrumTransaction {
+            hash: keccak256(&raw),
+            from: sender,
+            to: Some(address!("0x6666666666666666666666666666666666666666")),
+            value: U256::ZERO,
+            gas: 2_000_000,
+            gas_price: U256::from(gas_price),
+            nonce: 0,
+            data: vec![],
+            l1_sequence_number: None,
+            raw,
+            kind: ArbitrumTxKind::Signed,
+        };
+        consensus.storage.store_transaction(&tx).await.unwrap();
+
+        // The chain starts at the minimum base fee
+        let base_fee = consensus.config.gas.l2_gas_price;
+        let block = ArbitrumBlock {
+            number: 0,
+            timestamp: 1000,
+            gas_limit: 10_000_000,
+            transactions: vec![tx.hash],
+            ..Default::default()
+        };
+        let result = consensus.execute_block(&block).await.unwrap();
+        assert!(result.transaction_results[0].success);
+
+        let price_per_unit = U256::from(messages::DEFAULT_INITIAL_L1_BASE_FEE);
+        let poster_gas =
+            l1_pricing::poster_gas(l1_pricing::poster_cost(&tx, price_per_unit), base_fee);
+        assert!(poster_gas > 0);
+        let receipt = consensus
+            .storage
+            .get_receipt(&tx.hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(receipt.gas_used_for_l1, poster_gas);
+        assert_eq!(receipt.gas_used, 21_000 + poster_gas);
+        assert_eq!(result.gas_used, receipt.gas_used);
+
+        // Gas is paid at the base fee, below the transaction's gas price
+        assert_eq!(receipt.effective_gas_price, U256::from(base_fee));
+        let fee = U256::from(poster_gas * base_fee);
+        let pool = consensus
+            .get_account(&l1_pricing::L1_PRICER_FUNDS_POOL)
+            .await
+            .unwrap();
+        assert_eq!(pool.balance, fee);
+        let sender_account = consensus.get_account(&sender).await.unwrap();
+        assert_eq!(
+            sender_account.balance,
+            U256::from(10u64.pow(18) - receipt.gas_used * base_fee)
+        );
+
+        // A gas limit below the L1 fee leaves the transaction out
+        let starved = ArbitrumTransaction {
+            hash: B256::with_last_byte(0x77),
+            gas: poster_gas,
+            nonce: 1,
+            ..tx.clone()
+        };
+        consensus.storage.store_transaction(&starved).await.unwrap();
+        let next = ArbitrumBlock {
+            number: 1,
+            parent_hash: result.block_hash,
+            timestamp: 1001,
+            gas_limit: 10_000_000,
+            transactions: vec![starved.hash],
+            ..Default::default()
+        };
+        let result = consensus.execute_block(&next).await.unwrap();
+        assert!(!result.transaction_results[0].success);
+        assert!(
+            consensus
+                .storage
+                .get_receipt(&starved.hash)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Two transactions each affordable before the block, of which the
+        // second can pay for the gas the EVM gets but not for all of its gas
+        // limit: it is left out before it runs, and the block goes on
+        let poor = address!("0x8888888888888888888888888888888888888888");
+        let gas = 21_000 + poster_gas;
+        let balance = U256::from(2 * gas * base_fee - 1);
+        consensus
+            .update_account(
+                poor,
+                ArbitrumAccount {
+                    address: poor,
+                    balance,
+                    nonce: 0,
+                    code_hash: B256::ZERO,
+                    storage_root: B256::ZERO,
+                },
+            )
+            .await
+            .unwrap();
+        let affordable_first = ArbitrumTransaction {
+            hash: B256::with_last_byte(0x78),
+            from: poor,
+            gas,
+            gas_price: U256::from(base_fee),
+            nonce: 0,
+            ..tx.clone()
+        };
+        let unaffordable = ArbitrumTransaction {
+            hash: B256::with_last_byte(0x7a),
+            nonce: 1,
+            ..affordable_first.clone()
+        };
+        let affordable = ArbitrumTransaction {
+            hash: B256::with_last_byte(0x79),
+            nonce: 1,
+            ..tx
+        };
+        for tx in [&affordable_first, &unaffordable, &affordable] {
+            consensus.storage.store_transaction(tx).await.unwrap();
+        }
+        let last = ArbitrumBlock {
+            number: 2,
+            parent_hash: result.block_hash,
+            timestamp: 1002,
+            gas_limit: 10_000_000,
+            transactions: vec![affordable_first.hash, unaffordable.hash, affordable.hash],
+            ..Default::default()
+        };
+        let result = consensus.execute_block(&last).await.unwrap();
+        let successes: Vec<bool> = result
+            .transaction_results
+            .iter()
+            .map(|result| result.success)
+            .collect();
+        assert_eq!(successes, vec![true, false, true]);
+        let poor_account = consensus.get_account(&poor).await.unwrap();
+        assert_eq!(poor_account.balance, U256::from(gas * base_fee - 1));
+        assert_eq!(poor_account.nonce, 1);
+    }
+
+    #[tokio::test]
+    async fn test_base_fee_follows_gas_backlog() {
+        use alloy_primitives::address;
+        use arbitrum_storage::L2PricingState;
+
+        // A chain sustaining 1k gas/s, whose fee rises e-fold with 20s of
+        // backlog
+        let mut config = ArbitrumRethConfig::default();
+        config.gas.l2_speed_limit = 1_000;
+        config.gas.l2_backlog_tolerance = 0;
+        config.gas.l2_pricing_inertia = 20;
+        let (consensus, _temp_dir) = create_test_consensus_with(config).await;
+        consensus.start().await.unwrap();
+        let min_base_fee = consensus.config.gas.l2_gas_price;
+
+        let sender = address!("0x5555555555555555555555555555555555555555");
+        let funded = ArbitrumAccount {
+            address: sender,
+            balance: U256::from(10u64.pow(18)),
+            nonce: 0,
+            code_hash: B256::ZERO,
+            storage_root: B256::ZERO,
+        };
+        consensus.update_account(sender, funded).await.unwrap();
+        let transfer = |nonce: u64, gas_price: u64| ArbitrumTransaction {
+            hash: B256::with_last_byte(0xe0 + nonce as u8),
+            from: sender,
+            to: Some(address!("0x6666666666666666666666666666666666666666")),
+            value: U256::ZERO,
+            gas: 1_000_000,
+            gas_price: U256::from(gas_price),
+            nonce,
+            data: vec![],
+            l1_sequence_number: None,
+            raw: vec![],
+            kind: ArbitrumTxKind::Signed,
+        };
+        let execute = |number: u64, parent_hash: B256, txs: Vec<ArbitrumTransaction>| {
+            let consensus = &consensus;
+            async move {
+                for tx in &txs {
+                    consensus.storage.store_transaction(tx).await.unwrap();
+                }
+                let block = ArbitrumBlock {
+                    number,
+                    parent_hash,
+                    timestamp: 1000 + number,
+                    gas_limit: 10_000_000,
+                    transactions: txs.iter().map(|tx| tx.hash).collect(),
+                    ..Default::default()
+                };
+                let result = consensus.execute_block(&block).await.unwrap();
+                consensus
+                    .storage
+                    .get_block(&result.block_hash)
+                    .await
+                    .unwrap()
+                    .unwrap()
+            }
+        };
+
+        // The first block is priced at the minimum and fills the backlog
+        let first = execute(0, B256::ZERO, vec![transfer(0, 1_000_000_000)]).await;
+        assert_eq!(first.base_fee, min_base_fee);
+        assert_eq!(
+            consensus.storage.get_l2_pricing().await.unwrap(),
+            Some(L2PricingState {
+                gas_backlog: 21_000,
+                base_fee: min_base_fee,
+            })
+        );
+
+        // A second later 20k gas are left, an inertia's worth, which prices
+        // the block after at e times the minimum
+        let second = execute(1, first.hash, vec![]).await;
+        assert_eq!(second.base_fee, min_base_fee);
+        let third = execute(2, second.hash, vec![transfer(1, min_base_fee)]).await;
+        assert_eq!(third.base_fee, min_base_fee * 27_083 / 10_000);
+
+        // which a transaction capped at the minimum no longer pays
+        assert!(third.transactions.is_empty());
+        assert!(
+            consensus
+                .storage
+                .get_receipt(&transfer(1, min_base_fee).hash)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_posting_report_refunds_poster() {
+        use alloy_primitives::{address, keccak256};
+
+        let (consensus, _temp_dir) = create_test_consensus().await;
+        consensus.start().await.unwrap();
+
+        let sender = address!("0x5555555555555555555555555555555555555555");
+        let funded = ArbitrumAccount {
+            address: sender,
+            balance: U256::from(10u64.pow(18)),
+            nonce: 0,
+            code_hash: B256::ZERO,
+            storage_root: B256::ZERO,
+        };
+        consensus.update_account(sender, funded).await.unwrap();
+        let internal = |byte: u8, kind: ArbitrumTxKind| ArbitrumTransaction {
+            hash: B256::with_last_byte(byte),
+            from: Address::ZERO,
+            to: None,
+            value: U256::ZERO,
+            gas: 0,
+            gas_price: U256::ZERO,
+            nonce: 0,
+            data: vec![],
+            l1_sequence_number: None,
+            raw: vec![],
+            kind,
+        };
+
+        // The chain is initialized with its L1 price, which the first
+        // sequenced transaction pays for its data
+        let price_per_unit = U256::from(40_000_000_000u64);
+        let initialize = internal(
+            0x01,
+            ArbitrumTxKind::Initialize {
+                chain_id: U256::from(42_161),
+                initial_l1_base_fee: price_per_unit,
+                chain_config: vec![],
+            },
+        );
+        let raw: Vec<u8> = (0..4u32)
+            .flat_map(|i| keccak256(i.to_be_bytes()).0)
+            .collect();
+        let transfer = ArbitrumTransaction {
+            hash: keccak256(&raw),
+            from: sender,
+            to: Some(address!("0x6666666666666666666666666666666666666666")),
+            value: U256::ZERO,
+            gas: 2_000_000,
+            gas_price: U256::from(1_000_000_000u64),
+            nonce: 0,
+            data: vec![],
+            l1_sequence_number: None,
+            raw,
+            kind: ArbitrumTxKind::Signed,
+        };
+        for tx in [&initialize, &transfer] {
+            consensus.storage.store_transaction(tx).await.unwrap();
+        }
+        let block = ArbitrumBlock {
+            number: 0,
+            timestamp: 1000,
+            gas_limit: 10_000_000,
+            transactions: vec![initialize.hash, transfer.hash],
+            ..Default::default()
+        };
+        let result = consensus.execute_block(&block).await.unwrap();
+
+        let units = l1_pricing::poster_units(&transfer);
+        let base_fee = consensus.config.gas.l2_gas_price;
+        let fee = U256::from(l1_pricing::poster_gas(
+            price_per_unit * U256::from(units),
+            base_fee,
+        )) * U256::from(base_fee);
+        let pricing = consensus.storage.get_l1_pricing().await.unwrap().unwrap();
+        assert_eq!(pricing.price_per_unit, price_per_unit);
+        assert_eq!(pricing.units_since_update, units);
+        assert_eq!(pricing.l1_fees_available, fee);
+
+        // The batch carrying the transaction is reported a block later
+        let poster = address!("0x7777777777777777777777777777777777777777");
+        let data_hash = B256::repeat_byte(0xda);
+        let mut data = B256::from(U256::from(1000)).to_vec();
+        data.extend_from_slice(poster.as_slice());
+        data.extend_from_slice(data_hash.as_slice());
+        data.extend_from_slice(B256::ZERO.as_slice());
+        data.extend_from_slice(&B256::from(U256::from(1_000_000_000u64)).0);
+        let message = L1Message {
+            message_number: 1,
+            kind: L1MessageKind::BatchPostingReport,
+            sender: poster,
+            data,
+            timestamp: 1000,
+            block_number: 10,
+            request_id: B256::from(U256::from(1)),
+            l1_base_fee: U256::ZERO,
+            batch_gas_cost: None,
+        };
+
+        // The report is not read before the batch it names
+        let err = consensus.process_l1_message(&message).await.unwrap_err();
+        assert!(err.to_string().contains("has not been read from L1"));
+
+        consensus
+            .storage
+            .store_batch_gas(data_hash, 50_000)
+            .await
+            .unwrap();
+        let report = consensus
+            .process_l1_message(&message)
+            .await
+            .unwrap()
+            .remove(0);
+        assert!(matches!(
+            report.kind,
+            ArbitrumTxKind::BatchPostingReport {
+                batch_data_gas: 50_000,
+                ..
+            }
+        ));
+        consensus.storage.store_transaction(&report).await.unwrap();
+        let next = ArbitrumBlock {
+            number: 1,
+            parent_hash: result.block_hash,
+            timestamp: 1001,
+            gas_limit: 10_000_000,
+            transactions: vec![report.hash],
+            ..Default::default()
+        };
+        consensus.execute_block(&next).await.unwrap();
+
+        // Half the units fall to the batch, whose rewards are paid first and
+        // the poster gets the rest of the pool, short of what it spent
+        let reward = U256::from(units / 2 * l1_pricing::INITIAL_PER_UNIT_REWARD);
+        let rewarded = consensus
+            .get_account(&l1_pricing::BATCH_POSTER_ADDRESS)
+            .await
+            .unwrap();
+        assert_eq!(rewarded.balance, reward);
+        let refunded = consensus.get_account(&poster).await.unwrap();
+        assert_eq!(refunded.balance, fee - reward);
+        let pool = consensus
+            .get_account(&l1_pricing::L1_PRICER_FUNDS_POOL)
+            .await
+            .unwrap();
+        assert_eq!(pool.balance, U256::ZERO);
+
+        let spent = U256::from((210_000 + 50_000) * 1_000_000_000u64);
+        let pricing = consensus.storage.get_l1_pricing().await.unwrap().unwrap();
+        assert_eq!(pricing.l1_fees_available, U256::ZERO);
+        assert_eq!(pricing.last_update_time, 1000);
+        assert_eq!(
+            pricing.batch_posters[&poster].funds_due,
+            spent - (fee - reward)
+        );
+        assert!(pricing.price_per_unit > price_per_unit);
+    }
+
+    #[tokio::test]
+    async fn test_arbsys_sends_to_l1() {
+        use alloy_primitives::{address, hex, keccak256};
+        use alloy_sol_types::{SolCall, SolError, SolEvent};
+        use arbitrum_storage::SendMerkleState;
+        use arbsys::ArbSys;
+        use precompiles::ARBSYS_ADDRESS;
+
+        let (consensus, _temp_dir) = create_test_consensus().await;
+        consensus.start().await.unwrap();
+
+        let sender = address!("0x5555555555555555555555555555555555555555");
+        let funded = ArbitrumAccount {
+            address: sender,
+            balance: U256::from(10u64.pow(18)),
+            nonce: 0,
+            code_hash: B256::ZERO,
+            storage_root: B256::ZERO,
+        };
+        consensus.update_account(sender, funded).await.unwrap();
+
+        // Forwards its calldata and value to ArbSys and returns what it returns
+        let runtime = hex!("366000600037600060003660003460645af1503d600060003e3d6000f3");
+        let init = [hex!("601d80600b6000396000f3").as_slice(), &runtime].concat();
+        let forwarder = sender.create(1);
+        let destination = address!("0x7777777777777777777777777777777777777777");
+        let value = U256::from(10u64.pow(15));
+        let tx =
+            |nonce: u64, to: Option<Address>, value: U256, data: Vec<u8>| ArbitrumTransaction {
+                hash: B256::with_last_byte(0xe0 + nonce as u8),
+                from: sender,
+                to,
+                value,
+                gas: 5_000_000,
+                gas_price: U256::from(1_000_000_000u64),
+                nonce,
+                data,
+                l1_sequence_number: None,
+                raw: vec![],
+                kind: ArbitrumTxKind::Signed,
+            };
+        let payload = hex!("deadbeef").to_vec();
+        let txs = [
+            tx(
+                0,
+                Some(ARBSYS_ADDRESS),
+                value,
+                ArbSys::withdrawEthCall { destination }.abi_encode(),
+            ),
+            tx(1, None, U256::ZERO, init),
+            tx(
+                2,
+                Some(forwarder),
+                U256::ZERO,
+                ArbSys::sendTxToL1Call {
+                    destination,
+                    data: payload.clone().into(),
+                }
+                .abi_encode(),
+            ),
+            tx(
+                3,
+                Some(forwarder),
+                U256::ZERO,
+                ArbSys::myCallersAddressWithoutAliasingCall {}.abi_encode(),
+            ),
+            tx(
+                4,
+                Some(ARBSYS_ADDRESS),
+                U256::ZERO,
+                ArbSys::arbBlockHashCall {
+                    arbBlockNum: U256::ZERO,
+                }
+                .abi_encode(),
+            ),
+        ];
+        for tx in &txs {
+            consensus.storage.store_transaction(tx).await.unwrap();
+        }
+        let block = ArbitrumBlock {
+            number: 0,
+            timestamp: 1000,
+            gas_limit: 30_000_000,
+            transactions: txs.iter().map(|tx| tx.hash).collect(),
+            l1_block_number: 200,
+            ..Default::default()
+        };
+        let result = consensus.execute_block(&block).await.unwrap();
+        let [withdraw, _, send, caller, block_hash] = &result.transaction_results[..] else {
+            panic!("expected five transaction results");
+        };
+
+        // Sends get consecutive leaves and their value is burnt
+        assert!(withdraw.success && send.success);
+        assert_eq!(withdraw.return_data, U256::ZERO.to_be_bytes::<32>());
+        assert_eq!(send.return_data, U256::from(1).to_be_bytes::<32>());
+        let arbsys = consensus.get_account(&ARBSYS_ADDRESS).await;
+        assert!(arbsys.is_none_or(|account| account.balance.is_zero()));
+
+        let send_hash = |from: Address, value: U256, data: &[u8]| {
+            keccak256(
+                [
+                    from.as_slice(),
+                    destination.as_slice(),
+                    &U256::ZERO.to_be_bytes::<32>(),
+                    &U256::from(200).to_be_bytes::<32>(),
+                    &U256::from(1000).to_be_bytes::<32>(),
+                    &value.to_be_bytes::<32>(),
+                    data,
+                ]
+                .concat(),
+            )
+        };
+        let hashes = [
+            send_hash(sender, value, &[]),
+            send_hash(forwarder, U256::ZERO, &payload),
+        ];
+        let receipt = consensus
+            .storage
+            .get_receipt(&txs[2].hash)
+            .await
+            .unwrap()
+            .unwrap();
+        // The second leaf completes a node above the first two
+        let [update, sent] = &receipt.logs[..] else {
+            panic!("expected two logs");
+        };
+        assert_eq!(update.address, ARBSYS_ADDRESS);
+        assert_eq!(update.topics[0], ArbSys::SendMerkleUpdate::SIGNATURE_HASH);
+        assert_eq!(sent.topics[0], ArbSys::L2ToL1Tx::SIGNATURE_HASH);
+        assert_eq!(sent.topics[1], destination.into_word());
+        assert_eq!(sent.topics[2], hashes[1]);
+        assert_eq!(sent.topics[3], B256::with_last_byte(1));
+
+        let mut merkle = SendMerkleState::default();
+        for hash in hashes {
+            outbox::append(&mut merkle, hash);
+        }
+        assert_eq!(
+            consensus.storage.get_send_merkle().await.unwrap(),
+            Some(merkle.clone())
+        );
+        let stored = consensus
+            .storage
+            .get_block(&result.block_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.send_count, 2);
+        assert_eq!(stored.send_root, outbox::root(&merkle));
+
+        // Both sends are stored under their leaves, as the outbox needs them
+        let sends = consensus.storage.get_sends(0, 2).await.unwrap();
+        assert_eq!(sends.len(), 2);
+        assert_eq!(sends[0].hash, hashes[0]);
+        assert_eq!(sends[0].callvalue, value);
+        assert_eq!(sends[1].position, 1);
+        assert_eq!(sends[1].hash, hashes[1]);
+        assert_eq!(sends[1].caller, forwarder);
+        assert_eq!(sends[1].destination, destination);
+        assert_eq!(sends[1].eth_block_number, 200);
+        assert_eq!(sends[1].data, payload);
+        assert_eq!(sends[1].transaction_hash, txs[2].hash);
+        // So is the node the second completed, which proofs are built from
+        assert_eq!(
+            consensus.storage.get_send_merkle_node(1, 1).await.unwrap(),
+            Some(merkle.partials[1])
+        );
+
+        // The forwarder's caller is the sender, not aliased
+        assert!(caller.success);
+        assert_eq!(caller.return_data, sender.into_word().to_vec());
+
+        // Only past blocks have hashes
+        assert!(!block_hash.success);
+        let error = ArbSys::InvalidBlockNumber {
+            requested: U256::ZERO,
+            current: U256::ZERO,
+        };
+        assert_eq!(block_hash.return_data, error.abi_encode());
+
+        // Unwinding a block that sent to L1 takes its sends out of the
+        // accumulator
+        let withdraw = tx(
+            5,
+            Some(ARBSYS_ADDRESS),
+            value,
+            ArbSys::withdrawEthCall { destination }.abi_encode(),
+        );
+        consensus
+            .storage
+            .store_transaction(&withdraw)
+            .await
+            .unwrap();
+        let next = ArbitrumBlock {
+            number: 1,
+            parent_hash: result.block_hash,
+            timestamp: 1001,
+            gas_limit: 30_000_000,
+            transactions: vec![withdraw.hash],
+            l1_block_number: 201,
+            ..Default::default()
+        };
+        let next = consensus.execute_block(&next).await.unwrap();
+        assert!(next.transaction_results[0].success);
+        assert_eq!(
+            consensus
+                .storage
+                .get_send_merkle()
+                .await
+                .unwrap()
+                .unwrap()
+                .size,
+            3
+        );
+        consensus.storage.unwind_blocks_above(0).await.unwrap();
+        assert_eq!(
+            consensus.storage.get_send_merkle().await.unwrap(),
+            Some(merkle)
+        );
+        assert!(consensus.storage.get_send(2).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_arbgasinfo_reads_live_pricing() {
+        use alloy_primitives::{I256, address, keccak256};
+        use alloy_sol_types::SolCall;
+        use arbgasinfo::ArbGasInfo;
+        use arbitrum_storage::{BatchPosterState, L2PricingState};
+        use precompiles::ARBGASINFO_ADDRESS;
+
+        let (consensus, _temp_dir) = create_test_consensus().await;
+        consensus.start().await.unwrap();
+
+        // Pricing away from its initial values, the pool 300 wei short
+        let price_per_unit = U256::from(7_000_000_000u64);
+        let mut l1 = l1_pricing::initial_state(price_per_unit);
+        l1.l1_fees_available = U256::from(1_000);
+        l1.funds_due_for_rewards = U256::from(500);
+        l1.batch_posters.insert(
+            address!("0x00000000000000000000000000000000000000bb"),
+            BatchPosterState {
+                funds_due: U256::from(800),
+                pay_to: Address::ZERO,
+            },
+        );
+        consensus.storage.store_l1_pricing(&l1).await.unwrap();
+        let base_fee = 250_000_000u64;
+        let l2 = L2PricingState {
+            gas_backlog: 90_000_000,
+            base_fee,
+        };
+        consensus.storage.store_l2_pricing(&l2).await.unwrap();
+
+        let arbos = ArbOsState::load(&consensus.storage, &consensus.config.gas)
+            .await
+            .unwrap();
+        let block = ArbitrumBlock {
+            gas_limit: 32_000_000,
+            base_fee,
+            ..Default::default()
+        };
+        let read = |data: Vec<u8>| {
+            let tx = ArbitrumTransaction {
+                hash: B256::ZERO,
+                from: Address::ZERO,
+                to: Some(ARBGASINFO_ADDRESS),
+                value: U256::ZERO,
+                gas: 0,
+                gas_price: U256::ZERO,
+                nonce: 0,
+                data,
+                l1_sequence_number: None,
+                raw: vec![],
+                kind: ArbitrumTxKind::Signed,
+            };
+            let storage = Arc::clone(&consensus.storage);
+            let chain_id = consensus.config.l2.chain_id;
+            let (block, arbos) = (block.clone(), arbos.clone());
+            async move {
+                let result = execution::call(storage, chain_id, &block, &tx, &arbos, U256::ZERO)
+                    .await
+                    .unwrap();
+                assert!(result.is_success());
+                result.output().unwrap().to_vec()
+            }
+        };
+
+        // Calls pay no base fee, yet the prices are at the block's
+        let min_base_fee = consensus.config.gas.l2_gas_price;
+        let output = read(ArbGasInfo::getPricesInWeiCall {}.abi_encode()).await;
+        let prices = ArbGasInfo::getPricesInWeiCall::abi_decode_returns(&output).unwrap();
+        let per_byte = price_per_unit * U256::from(16);
+        assert_eq!(prices._0, per_byte * U256::from(140));
+        assert_eq!(prices._1, per_byte);
+        assert_eq!(prices._2, U256::from(base_fee * 20_000));
+        assert_eq!(prices._3, U256::from(min_base_fee));
+        assert_eq!(prices._4, U256::from(base_fee - min_base_fee));
+        assert_eq!(prices._5, U256::from(base_fee));
+
+        let output = read(ArbGasInfo::getPricesInArbGasCall {}.abi_encode()).await;
+        let prices = ArbGasInfo::getPricesInArbGasCall::abi_decode_returns(&output).unwrap();
+        assert_eq!(prices._1, per_byte / U256::from(base_fee));
+        assert_eq!(prices._2, U256::from(20_000));
+
+        let output = read(ArbGasInfo::getGasAccountingParamsCall {}.abi_encode()).await;
+        let params = ArbGasInfo::getGasAccountingParamsCall::abi_decode_returns(&output).unwrap();
+        assert_eq!(params._0, U256::from(consensus.config.gas.l2_speed_limit));
+        assert_eq!(params._1, U256::from(32_000_000));
+
+        let output = read(ArbGasInfo::getL1BaseFeeEstimateCall {}.abi_encode()).await;
+        assert_eq!(
+            ArbGasInfo::getL1BaseFeeEstimateCall::abi_decode_returns(&output).unwrap(),
+            price_per_unit
+        );
+        let output = read(ArbGasInfo::getMinimumGasPriceCall {}.abi_encode()).await;
+        assert_eq!(
+            ArbGasInfo::getMinimumGasPriceCall::abi_decode_returns(&output).unwrap(),
+            U256::from(min_base_fee)
+        );
+        let output = read(ArbGasInfo::getGasBacklogCall {}.abi_encode()).await;
+        assert_eq!(
+            ArbGasInfo::getGasBacklogCall::abi_decode_returns(&output).unwrap(),
+            90_000_000
+        );
+        let output = read(ArbGasInfo::getL1PricingSurplusCall {}.abi_encode()).await;
+        assert_eq!(
+            ArbGasInfo::getL1PricingSurplusCall::abi_decode_returns(&output).unwrap(),
+            I256::try_from(-300).unwrap()
+        );
+
+        // A transaction sees the L1 fee it pays
+        let sender = address!("0x5555555555555555555555555555555555555555");
+        let funded = ArbitrumAccount {
+            address: sender,
+            balance: U256::from(10u64.pow(18)),
+            nonce: 0,
+            code_hash: B256::ZERO,
+            storage_root: B256::ZERO,
+        };
+        consensus.update_account(sender, funded).await.unwrap();
+        let raw: Vec<u8> = (0..4u32)
+            .flat_map(|i| keccak256(i.to_be_bytes()).0)
+            .collect();
+        let tx = ArbitrumTransaction {
+            hash: keccak256(&raw),
+            from: sender,
+            to: Some(ARBGASINFO_ADDRESS),
+            value: U256::ZERO,
+            gas: 2_000_000,
+            gas_price: U256::from(base_fee),
+            nonce: 0,
+            data: ArbGasInfo::getCurrentTxL1GasFeesCall {}.abi_encode(),
+            l1_sequence_number: None,
+            raw,
+            kind: ArbitrumTxKind::Signed,
+        };
+        consensus.storage.store_transaction(&tx).await.unwrap();
+        let block = ArbitrumBlock {
+            timestamp: 1000,
+            gas_limit: 32_000_000,
+            transactions: vec![tx.hash],
+            ..Default::default()
+        };
+        let result = consensus.execute_block(&block).await.unwrap();
+        assert!(result.transaction_results[0].success);
+        let receipt = consensus
+            .storage
+            .get_receipt(&tx.hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(receipt.gas_used_for_l1 > 0);
+        assert_eq!(
+            ArbGasInfo::getCurrentTxL1GasFeesCall::abi_decode_returns(
+                &result.transaction_results[0].return_data
+            )
+            .unwrap(),
+            U256::from(receipt.gas_used_for_l1 * base_fee)
+        );
+    }
+
+    /// Retryable submission of `value` to `to` from an L1 sender, with a
+    /// deposit of 0.01 ETH
+    fn submission(
+        n: u8,
+        to: Address,
+        value: U256,
+        gas: u64,
+        gas_price: U256,
+        beneficiary: Address,
+    ) -> ArbitrumTransaction {
+        use alloy_primitives::address;
+
+        ArbitrumTransaction {
+            hash: B256::with_last_byte(0xa0 + n),
+            from: address!("0x1111111111111111111111111111111111111111"),
+            to: Some(to),
+            value,
+            gas,
+            gas_price,
+            nonce: 0,
+            data: vec![],
+            l1_sequence_number: Some(u64::from(n)),
+            raw: vec![],
+            kind: ArbitrumTxKind::SubmitRetryable {
+                request_id: B256::with_last_byte(n),
+                l1_base_fee: U256::from(1_000_000_000u64),
+                deposit_value: U256::from(10u64.pow(16)),
+                max_submission_fee: U256::from(2_000_000_000_000u64),
+                fee_refund_address: address!("0x2222222222222222222222222222222222222222"),
+                beneficiary,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retryable_submission_redeems_automatically() {
+        use alloy_primitives::address;
+        use alloy_sol_types::SolEvent;
+        use arbretryabletx::ArbRetryableTx::{RedeemScheduled, TicketCreated};
+
+        let (consensus, _temp_dir) = create_test_consensus().await;
+        consensus.start().await.unwrap();
+
+        let recipient = address!("0x3333333333333333333333333333333333333333");
+        let beneficiary = address!("0x4444444444444444444444444444444444444444");
+        let value = U256::from(10u64.pow(15));
+        let gas = 100_000;
+        let gas_price = U256::from(200_000_000u64);
+        // The first pays for its redeem attempt, the second has no gas for one
+        let redeemed = submission(0, recipient, value, gas, gas_price, beneficiary);
+        let waiting = submission(1, recipient, value, 0, U256::ZERO, beneficiary);
+        for tx in [&redeemed, &waiting] {
+            consensus.storage.store_transaction(tx).await.unwrap();
+        }
+        let block = ArbitrumBlock {
+            number: 0,
+            timestamp: 1000,
+            gas_limit: 30_000_000,
+            transactions: vec![redeemed.hash, waiting.hash],
+            ..Default::default()
+        };
+        let result = consensus.execute_block(&block).await.unwrap();
+
+        // The attempt runs right after its submission, under the hash its
+        // scheduling recorded
+        let receipt = consensus
+            .storage
+            .get_receipt(&redeemed.hash)
+            .await
+            .unwrap()
+            .unwrap();
+        let topics: Vec<B256> = receipt.logs.iter().map(|log| log.topics[0]).collect();
+        assert_eq!(
+            topics,
+            vec![
+                TicketCreated::SIGNATURE_HASH,
+                RedeemScheduled::SIGNATURE_HASH
+            ]
+        );
+        let retry_hash =
+            RedeemScheduled::decode_raw_log(&receipt.logs[1].topics, &receipt.logs[1].data)
+                .unwrap()
+                .retryTxHash;
+        let retry_tx = consensus
+            .storage
+            .get_transaction(&retry_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            retry_tx.compute_hash(consensus.config.l2.chain_id),
+            retry_hash
+        );
+        let hashes: Vec<B256> = result
+            .transaction_results
+            .iter()
+            .map(|result| result.tx_hash)
+            .collect();
+        assert_eq!(hashes, vec![redeemed.hash, retry_hash, waiting.hash]);
+        assert!(
+            result
+                .transaction_results
+                .iter()
+                .all(|result| result.success)
+        );
+        let stored = consensus
+            .storage
+            .get_block(&result.block_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.transactions, hashes);
+
+        let retry = consensus
+            .storage
+            .get_receipt(&retry_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(retry.gas_used, 21_000);
+
+        // The redeemed ticket is closed with its call value delivered, the
+        // other holds its call value in escrow
+        assert!(
+            consensus
+                .storage
+                .get_retryable(&redeemed.hash)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let ticket = consensus
+            .storage
+            .get_retryable(&waiting.hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            ticket.timeout,
+            1000 + retryables::RETRYABLE_LIFETIME_SECONDS
+        );
+        assert_eq!(ticket.num_tries, 0);
+        let balance = |address: Address| {
+            let consensus = &consensus;
+            async move {
+                consensus
+                    .get_account(&address)
+                    .await
+                    .map_or(U256::ZERO, |account| account.balance)
+            }
+        };
+        assert_eq!(balance(recipient).await, value);
+        assert_eq!(
+            balance(retryables::escrow_address(redeemed.hash)).await,
+            U256::ZERO
+        );
+        assert_eq!(
+            balance(retryables::escrow_address(waiting.hash)).await,
+            value
+        );
+
+        // The fee refund address gets what the first submission offered
+        // beyond its fees, its submission fee back and the gas its attempt
+        // left, and what the second offered beyond its submission fee
+        let ArbitrumTxKind::SubmitRetryable {
+            deposit_value,
+            max_submission_fee,
+            fee_refund_address,
+            ..
+        } = redeemed.kind
+        else {
+            unreachable!()
+        };
+        let base_fee = U256::from(stored.base_fee);
+        let submission_fee_refund =
+            max_submission_fee - retryables::submission_fee(0, U256::from(1_000_000_000u64));
+        assert_eq!(
+            balance(fee_refund_address).await,
+            max_submission_fee + gas_price * U256::from(gas) - base_fee * U256::from(21_000)
+                + submission_fee_refund
+        );
+        // The sender keeps the rest of both deposits
+        assert_eq!(
+            balance(redeemed.from).await,
+            deposit_value - value - max_submission_fee - gas_price * U256::from(gas)
+                + deposit_value
+                - value
+                - max_submission_fee
+        );
+    }
+
+    #[tokio::test]
+    async fn test_arbretryabletx_manages_tickets() {
+        use alloy_primitives::address;
+        use alloy_sol_types::{SolCall, SolError};
+        use arbretryabletx::ArbRetryableTx;
+        use precompiles::ARBRETRYABLETX_ADDRESS;
+
+        let (consensus, _temp_dir) = create_test_consensus().await;
+        consensus.start().await.unwrap();
+
+        let user = address!("0x5555555555555555555555555555555555555555");
+        let beneficiary = address!("0x6666666666666666666666666666666666666666");
+        let recipient = address!("0x3333333333333333333333333333333333333333");
+        for address in [user, beneficiary] {
+            let funded = ArbitrumAccount {
+                address,
+                balance: U256::from(10u64.pow(18)),
+                nonce: 0,
+                code_hash: B256::ZERO,
+                storage_root: B256::ZERO,
+            };
+            consensus.update_account(address, funded).await.unwrap();
+        }
+
+        // Three tickets wait for a manual redeem
+        let value = U256::from(10u64.pow(15));
+        let tickets: Vec<ArbitrumTransaction> = (0..3)
+            .map(|n| submission(n, recipient, value, 0, U256::ZERO, beneficiary))
+            .collect();
+        for tx in &tickets {
+            consensus.storage.store_transaction(tx).await.unwrap();
+        }
+        let block = ArbitrumBlock {
+            number: 0,
+            timestamp: 1000,
+            gas_limit: 30_000_000,
+            transactions: tickets.iter().map(|tx| tx.hash).collect(),
+            ..Default::default()
+        };
+        let parent = consensus.execute_block(&block).await.unwrap();
+        let [redeemed, canceled, expiring] = [0, 1, 2].map(|n| tickets[n].hash);
+
+        let tx = |n: u8, from: Address, nonce: u64, data: Vec<u8>| ArbitrumTransaction {
+            hash: B256::with_last_byte(0xe0 + n),
+            from,
+            to: Some(ARBRETRYABLETX_ADDRESS),
+            value: U256::ZERO,
+            gas: 1_000_000,
+            gas_price: U256::from(1_000_000_000u64),
+            nonce,
+            data,
+            l1_sequence_number: None,
+            raw: vec![],
+            kind: ArbitrumTxKind::Signed,
+        };
+        let txs = [
+            tx(
+                0,
+                user,
+                0,
+                ArbRetryableTx::keepaliveCall { ticketId: redeemed }.abi_encode(),
+            ),
+            tx(
+                1,
+                user,
+                1,
+                ArbRetryableTx::redeemCall { ticketId: redeemed }.abi_encode(),
+            ),
+            tx(
+                2,
+                user,
+                2,
+                ArbRetryableTx::cancelCall { ticketId: canceled }.abi_encode(),
+            ),
+            tx(
+                3,
+                beneficiary,
+                0,
+                ArbRetryableTx::cancelCall { ticketId: canceled }.abi_encode(),
+            ),
+            tx(
+                4,
+                user,
+                3,
+                ArbRetryableTx::getTimeoutCall { ticketId: canceled }.abi_encode(),
+            ),
+        ];
+        for tx in &txs {
+            consensus.storage.store_transaction(tx).await.unwrap();
+        }
+        let block = ArbitrumBlock {
+            number: 1,
+            parent_hash: parent.block_hash,
+            timestamp: 2000,
+            gas_limit: 30_000_000,
+            transactions: txs.iter().map(|tx| tx.hash).collect(),
+            ..Default::default()
+        };
+        let result = consensus.execute_block(&block).await.unwrap();
+        let [keepalive, redeem, retry, denied, cancel, timeout] = &result.transaction_results[..]
+        else {
+            panic!("expected six transaction results");
+        };
+
+        // Keepalive extends by a lifetime from the old timeout
+        let lifetime = retryables::RETRYABLE_LIFETIME_SECONDS;
+        assert!(keepalive.success);
+        assert_eq!(
+            keepalive.return_data,
+            U256::from(1000 + 2 * lifetime).to_be_bytes::<32>()
+        );
+
+        // The redeem's attempt follows it and closes the ticket
+        assert!(redeem.success && retry.success);
+        assert_eq!(redeem.return_data, retry.tx_hash.to_vec());
+        let retry_tx = consensus
+            .storage
+            .get_transaction(&retry.tx_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(retry_tx.nonce, 0);
+        assert_eq!(
+            retry_tx.compute_hash(consensus.config.l2.chain_id),
+            retry.tx_hash
+        );
+        let balance = |address: Address| {
+            let consensus = &consensus;
+            async move {
+                consensus
+                    .get_account(&address)
+                    .await
+                    .map_or(U256::ZERO, |account| account.balance)
+            }
+        };
+        assert_eq!(balance(recipient).await, value);
+        assert!(
+            consensus
+                .storage
+                .get_retryable(&redeemed)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Only the beneficiary may cancel, getting the escrow back, after
+        // which the ticket is gone
+        assert!(!denied.success && cancel.success);
+        let receipt = consensus
+            .storage
+            .get_receipt(&cancel.tx_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            balance(beneficiary).await,
+            U256::from(10u64.pow(18)) + value
+                - U256::from(receipt.gas_used) * receipt.effective_gas_price
+        );
+        assert!(
+            consensus
+                .storage
+                .get_retryable(&canceled)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(!timeout.success);
+        assert_eq!(
+            timeout.return_data,
+            ArbRetryableTx::NoTicketWithID {}.abi_encode()
+        );
+
+        // The last ticket expires, returning its escrow to the beneficiary
+        let before = balance(beneficiary).await;
+        let block = ArbitrumBlock {
+            number: 2,
+            parent_hash: result.block_hash,
+            timestamp: 1001 + lifetime,
+            gas_limit: 30_000_000,
+            ..Default::default()
+        };
+        consensus.execute_block(&block).await.unwrap();
+        assert!(
+            consensus
+                .storage
+                .get_retryable(&expiring)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(balance(beneficiary).await, before + value);
+        assert_eq!(
+            balance(retryables::escrow_address(expiring)).await,
+            U256::ZERO
+        );
+    }
+
+    #[tokio::test]
+    async fn test_l1_contract_messages_keep_the_bridge_alias() {
+        use alloy_primitives::{address, hex, keccak256};
+        use alloy_sol_types::SolCall;
+        use arbsys::ArbSys;
+
+        let (consensus, _temp_dir) = create_test_consensus().await;
+        consensus.start().await.unwrap();
+
+        // Forwards its calldata to ArbSys and returns what it returns
+        let runtime = hex!("366000600037600060003660003460645af1503d600060003e3d6000f3");
+        let code_hash = keccak256(runtime);
+        consensus
+            .storage
+            .store_code(code_hash, &runtime)
+            .await
+            .unwrap();
+        let forwarder = address!("0x7777777777777777777777777777777777777777");
+        let account = |address: Address, balance: U256, code_hash: B256| ArbitrumAccount {
+            address,
+            balance,
+            nonce: 0,
+            code_hash,
+            storage_root: B256::ZERO,
+        };
+        consensus
+            .update_account(forwarder, account(forwarder, U256::ZERO, code_hash))
+            .await
+            .unwrap();
+        let l1_contract = address!("0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa");
+        let alias = arbsys::remap_l1_address(l1_contract);
+        consensus
+            .update_account(alias, account(alias, U256::from(10u64.pow(18)), B256::ZERO))
+            .await
+            .unwrap();
+
+        // Contract transactions from L1 through the forwarder, whose sender
+        // the Inbox aliased before the Bridge recorded it
+        let message = |number: u64, calldata: Vec<u8>| {
+            let mut data = vec![messages::l2_message_kind::CONTRACT_TX];
+            data.extend_from_slice(&B256::from(U256::from(1_000_000)).0);
+            data.extend_from_slice(&B256::from(U256::from(1_000_000_000u64)).0);
+            data.extend_from_slice(forwarder.into_word().as_slice());
+            data.extend_from_slice(&B256::ZERO.0);
+            data.extend_from_slice(&calldata);
+            L1Message {
+                message_number: number,
+                kind: L1MessageKind::L2Message,
+                sender: alias,
+                data,
+                timestamp: 1_700_000_000,
+                block_number: 100,
+                request_id: B256::from(U256::from(number)),
+                l1_base_fee: U256::ZERO,
+                batch_gas_cost: None,
+            }
+        };
+        let mut txs = vec![];
+        for (number, calldata) in [
+            ArbSys::wasMyCallersAddressAliasedCall {}.abi_encode(),
+            ArbSys::myCallersAddressWithoutAliasingCall {}.abi_encode(),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            txs.extend(
+                consensus
+                    .process_l1_message(&message(number as u64, calldata))
+                    .await
+                    .unwrap(),
+            );
+        }
+        // The sender is not aliased a second time
+        assert!(txs.iter().all(|tx| tx.from == alias));
+        assert!(
+            txs.iter()
+                .all(|tx| tx.from != arbsys::remap_l1_address(alias))
+        );
+        for tx in &txs {
+            consensus.storage.store_transaction(tx).await.unwrap();
+        }
+        let block = ArbitrumBlock {
+            number: 0,
+            timestamp: 1000,
+            gas_limit: 30_000_000,
+            transactions: txs.iter().map(|tx| tx.hash).collect(),
+            ..Default::default()
+        };
+        let result = consensus.execute_block(&block).await.unwrap();
+        let [aliased, unaliased] = &result.transaction_results[..] else {
+            panic!("expected two transaction results");
+        };
+
+        // The forwarder sees the alias, which ArbSys undoes
+        assert!(aliased.success && unaliased.success);
+        assert_eq!(aliased.return_data, U256::from(1).to_be_bytes::<32>());
+        assert_eq!(unaliased.return_data, l1_contract.into_word().to_vec());
     }
 }
@@ -0,0 +1,813 @@
+//! Derivation of L2 transactions from delayed inbox messages
+//!
+//! Follows the message layouts of Nitro's `arbos/parse_l2.go`: every
+//! numeric field is a 32-byte big-endian word and addresses are either raw
+//! 20-byte values or right-aligned in a 32-byte word, depending on the kind.
+//!
+//! Messages carry the sender the Bridge recorded, which the Inbox on L1
+//! already aliased if it is a contract, so an L1 contract cannot act as the
+//! L2 account at its own address. The transactions they make come from that
+//! sender as it is, as Nitro uses the poster of the message header. Signed
+//! transactions come from their signer.
+
+use alloy_consensus::{Transaction, TxEnvelope, transaction::SignerRecoverable};
+use alloy_eips::eip2718::Decodable2718;
+use alloy_primitives::{Address, B256, U256, address, keccak256};
+use alloy_sol_types::{SolCall, sol};
+use arbitrum_storage::{ArbitrumTransaction, ArbitrumTxKind, L1Message, L1MessageKind};
+use eyre::{Result, eyre};
+
+sol! {
+    /// Calls ArbOS makes to itself through internal transactions
+    interface ArbosActs {
+        function batchPostingReport(uint256 batchTimestamp, address batchPosterAddress, uint64 batchNumber, uint64 batchDataGas, uint256 l1BaseFeeWei) external;
+    }
+}
+
+/// Address internal transactions are sent from and to
+pub const ARBOS_ADDRESS: Address = address!("0x00000000000000000000000000000000000a4b05");
+
+/// Kind byte at the start of an L2 message
+pub mod l2_message_kind {
+    pub const UNSIGNED_USER_TX: u8 = 0;
+    pub const CONTRACT_TX: u8 = 1;
+    pub const NON_MUTATING_CALL: u8 = 2;
+    pub const BATCH: u8 = 3;
+    pub const SIGNED_TX: u8 = 4;
+    pub const HEARTBEAT: u8 = 6;
+    pub const SIGNED_COMPRESSED_TX: u8 = 7;
+}
+
+/// Maximum size of a single L2 message
+pub const MAX_L2_MESSAGE_SIZE: usize = 256 * 1024;
+
+/// Maximum nesting depth of L2 message batches
+const MAX_BATCH_DEPTH: usize = 16;
+
+/// Initial L1 base fee assumed by initialize messages that do not carry one
+pub const DEFAULT_INITIAL_L1_BASE_FEE: u64 = 50_000_000_000;
+
+/// Parse a delayed inbox message into the L2 transactions it produces on
+/// chain `chain_id`
+///
+/// End-of-block, rollup-event and invalid messages produce no transactions.
+/// Transactions other than signed ones are hashed as Nitro encodes them.
+pub fn parse_l1_message(message: &L1Message, chain_id: u64) -> Result<Vec<ArbitrumTransaction>> {
+    let transactions = match message.kind {
+        L1MessageKind::L2Message => {
+            parse_l2_message(&message.data, message.sender, Some(message.request_id), 0)?
+        }
+        L1MessageKind::L2FundedByL1 => parse_l2_funded_by_l1(message)?,
+        L1MessageKind::SubmitRetryable => vec![parse_submit_retryable(message)?],
+        L1MessageKind::EthDeposit => vec![parse_eth_deposit(message)?],
+        L1MessageKind::BatchPostingReport => vec![parse_batch_posting_report(message)?],
+        L1MessageKind::Initialize => vec![parse_initialize(message)?],
+        L1MessageKind::BatchForGasEstimation => {
+            return Err(eyre!(
+                "Batch for gas estimation messages are not executable"
+            ));
+        }
+        L1MessageKind::EndOfBlock | L1MessageKind::RollupEvent | L1MessageKind::Invalid => {
+            vec![]
+        }
+    };
+
+    Ok(seal(transactions, Some(message.message_number), chain_id))
+}
+
+/// Parse an L2 message posted in a sequencer batch into the transactions it
+/// produces on chain `chain_id`
+///
+/// As in Nitro, sequenced messages come from `poster` and have no request
+/// id, so they cannot carry contract transactions.
+pub fn parse_sequencer_message(
+    data: &[u8],
+    poster: Address,
+    chain_id: u64,
+) -> Result<Vec<ArbitrumTransaction>> {
+    let transactions = parse_l2_message(data, poster, None, 0)?;
+    Ok(seal(transactions, None, chain_id))
+}
+
+/// Hash the transactions derived from a message, other than signed ones
+fn seal(
+    transactions: Vec<ArbitrumTransaction>,
+    l1_sequence_number: Option<u64>,
+    chain_id: u64,
+) -> Vec<ArbitrumTransaction> {
+    transactions
+        .into_iter()
+        .map(|mut tx| {
+            tx.l1_sequence_number = l1_sequence_number;
+            if tx.kind == ArbitrumTxKind::Signed {
+                tx
+            } else {
+                tx.seal(chain_id)
+            }
+        })
+        .collect()
+}
+
+/// Parse an L2 message, recursing into batches
+fn parse_l2_message(
+    data: &[u8],
+    poster: Address,
+    request_id: Option<B256>,
+    depth: usize,
+) -> Result<Vec<ArbitrumTransaction>> {
+    let (&kind, body) = data
+        .split_first()
+        .ok_or_else(|| eyre!("Empty L2 message"))?;
+
+    match kind {
+        l2_message_kind::UNSIGNED_USER_TX | l2_message_kind::CONTRACT_TX => {
+            Ok(vec![parse_unsigned_tx(body, poster, request_id, kind)?])
+        }
+        l2_message_kind::BATCH => {
+            if depth >= MAX_BATCH_DEPTH {
+                return Err(eyre!(
+                    "L2 message batches have a max depth of {MAX_BATCH_DEPTH}"
+                ));
+            }
+            let mut reader = Reader::new(body);
+            let mut transactions = Vec::new();
+            let mut index = 0u64;
+            // A truncated segment ends the batch, as in Nitro
+            while let Some(segment) = reader.bytestring() {
+                let sub_request_id = request_id.map(|id| sub_request_id(id, index));
+                transactions.extend(parse_l2_message(
+                    segment,
+                    poster,
+                    sub_request_id,
+                    depth + 1,
+                )?);
+                index += 1;
+            }
+            Ok(transactions)
+        }
+        l2_message_kind::SIGNED_TX => Ok(vec![parse_signed_tx(body)?]),
+        l2_message_kind::HEARTBEAT => Ok(vec![]),
+        l2_message_kind::NON_MUTATING_CALL | l2_message_kind::SIGNED_COMPRESSED_TX => {
+            Err(eyre!("L2 message kind {kind} is not supported"))
+        }
+        _ => Err(eyre!("Unknown L2 message kind {kind}")),
+    }
+}
+
+/// Parse an unsigned user or contract transaction
+fn parse_unsigned_tx(
+    data: &[u8],
+    poster: Address,
+    request_id: Option<B256>,
+    kind: u8,
+) -> Result<ArbitrumTransaction> {
+    let mut reader = Reader::new(data);
+    let gas = reader.u64_word("gas limit")?;
+    let max_fee_per_gas = reader.u256()?;
+    let nonce = if kind == l2_message_kind::UNSIGNED_USER_TX {
+        reader.u64_word("nonce")?
+    } else {
+        0
+    };
+    let to = reader.address_word()?;
+    let value = reader.u256()?;
+    let data = reader.rest().to_vec();
+
+    let kind = match kind {
+        l2_message_kind::UNSIGNED_USER_TX => ArbitrumTxKind::Unsigned,
+        _ => ArbitrumTxKind::Contract {
+            request_id: request_id.ok_or_else(|| eyre!("Contract tx requires an L1 request id"))?,
+        },
+    };
+
+    Ok(ArbitrumTransaction {
+        hash: B256::ZERO,
+        from: poster,
+        to: (to != Address::ZERO).then_some(to),
+        value,
+        gas,
+        gas_price: max_fee_per_gas,
+        nonce,
+        data,
+        l1_sequence_number: None,
+        raw: vec![],
+        kind,
+    })
+}
+
+/// Parse an EIP-2718 encoded signed Ethereum transaction
+fn parse_signed_tx(data: &[u8]) -> Result<ArbitrumTransaction> {
+    let envelope = TxEnvelope::decode_2718(&mut &data[..])
+        .map_err(|e| eyre!("Invalid signed transaction: {e}"))?;
+    if envelope.is_eip4844() {
+        return Err(eyre!("Blob transactions are not supported on L2"));
+    }
+    let from = envelope
+        .recover_signer()
+        .map_err(|e| eyre!("Invalid transaction signature: {e}"))?;
+
+    Ok(ArbitrumTransaction {
+        hash: *envelope.tx_hash(),
+        from,
+        to: envelope.to(),
+        value: envelope.value(),
+        gas: envelope.gas_limit(),
+        gas_price: U256::from(envelope.max_fee_per_gas()),
+        nonce: envelope.nonce(),
+        data: envelope.input().to_vec(),
+        l1_sequence_number: None,
+        raw: data.to_vec(),
+        kind: ArbitrumTxKind::Signed,
+    })
+}
+
+/// Parse an unsigned transaction funded by an L1 deposit of its value
+///
+/// Produces the deposit followed by the transaction itself.
+fn parse_l2_funded_by_l1(message: &L1Message) -> Result<Vec<ArbitrumTransaction>> {
+    let (&kind, body) = message
+        .data
+        .split_first()
+        .ok_or_else(|| eyre!("L2 funded by L1 message has no data"))?;
+    let deposit_request_id = sub_request_id(message.request_id, 0);
+    let tx_request_id = sub_request_id(message.request_id, 1);
+
+    let tx = parse_unsigned_tx(body, message.sender, Some(tx_request_id), kind)?;
+    let deposit = ArbitrumTransaction {
+        hash: B256::ZERO,
+        from: Address::ZERO,
+        to: Some(message.sender),
+        value: tx.value,
+        gas: 0,
+        gas_price: U256::ZERO,
+        nonce: 0,
+        data: vec![],
+        l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Deposit {
+            l1_request_id: deposit_request_id,
+        },
+    };
+    Ok(vec![deposit, tx])
+}
+
+/// Parse a retryable ticket submission
+fn parse_submit_retryable(message: &L1Message) -> Result<ArbitrumTransaction> {
+    let mut reader = Reader::new(&message.data);
+    let retry_to = reader.address_word()?;
+    let retry_value = reader.u256()?;
+    let deposit_value = reader.u256()?;
+    let max_submission_fee = reader.u256()?;
+    let fee_refund_address = reader.address_word()?;
+    let beneficiary = reader.address_word()?;
+    let gas = reader.u64_word("gas limit")?;
+    let max_fee_per_gas = reader.u256()?;
+    let data_length = reader.u64_word("retry data length")? as usize;
+    if data_length > MAX_L2_MESSAGE_SIZE {
+        return Err(eyre!("Retry data of {data_length} bytes is too large"));
+    }
+    let retry_data = reader.take(data_length)?.to_vec();
+
+    Ok(ArbitrumTransaction {
+        hash: B256::ZERO,
+        from: message.sender,
+        to: (retry_to != Address::ZERO).then_some(retry_to),
+        value: retry_value,
+        gas,
+        gas_price: max_fee_per_gas,
+        nonce: 0,
+        data: retry_data,
+        l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::SubmitRetryable {
+            request_id: message.request_id,
+            l1_base_fee: message.l1_base_fee,
+            deposit_value,
+            max_submission_fee,
+            fee_refund_address,
+            beneficiary,
+        },
+    })
+}
+
+/// Parse an ETH deposit
+fn parse_eth_deposit(message: &L1Message) -> Result<ArbitrumTransaction> {
+    let mut reader = Reader::new(&message.data);
+    let to = reader.address()?;
+    let value = reader.u256()?;
+
+    Ok(ArbitrumTransaction {
+        hash: B256::ZERO,
+        from: message.sender,
+        to: Some(to),
+        value,
+        gas: 0,
+        gas_price: U256::ZERO,
+        nonce: 0,
+        data: vec![],
+        l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Deposit {
+            l1_request_id: message.request_id,
+        },
+    })
+}
+
+/// Sequencer message hash and number of the batch a batch posting report
+/// names
+pub fn reported_batch(data: &[u8]) -> Result<(B256, u64)> {
+    let mut reader = Reader::new(data);
+    reader.take(32 + 20)?; // Batch timestamp and poster
+    let data_hash = reader.b256()?;
+    let batch_number = reader.u64_word("batch number")?;
+    Ok((data_hash, batch_number))
+}
+
+/// Parse a batch posting report into an ArbOS internal transaction
+///
+/// As in Nitro, the transaction calls `batchPostingReport` with the gas of
+/// the batch, which the message must carry, plus the extra gas reported.
+fn parse_batch_posting_report(message: &L1Message) -> Result<ArbitrumTransaction> {
+    let mut reader = Reader::new(&message.data);
+    let batch_timestamp = reader.u256()?;
+    let batch_poster = reader.address()?;
+    let data_hash = reader.b256()?;
+    let batch_number = reader.u64_word("batch number")?;
+    let l1_base_fee_estimate = reader.u256()?;
+    // Older reports end before the extra gas field
+    let extra_gas = if reader.rest().is_empty() {
+        0
+    } else {
+        u64::from_be_bytes(reader.array::<8>()?)
+    };
+    let batch_gas = message
+        .batch_gas_cost
+        .ok_or_else(|| eyre!("Report of batch {batch_number} lacks the gas of the batch"))?;
+    let batch_data_gas = batch_gas.saturating_add(extra_gas);
+
+    let data = ArbosActs::batchPostingReportCall {
+        batchTimestamp: batch_timestamp,
+        batchPosterAddress: batch_poster,
+        batchNumber: batch_number,
+        batchDataGas: batch_data_gas,
+        l1BaseFeeWei: l1_base_fee_estimate,
+    }
+    .abi_encode();
+    Ok(internal_tx(
+        data,
+        ArbitrumTxKind::BatchPostingReport {
+            batch_timestamp,
+            batch_poster,
+            data_hash,
+            batch_number,
+            l1_base_fee_estimate,
+            batch_data_gas,
+        },
+    ))
+}
+
+/// Parse the chain initialization message
+fn parse_initialize(message: &L1Message) -> Result<ArbitrumTransaction> {
+    let mut reader = Reader::new(&message.data);
+    let chain_id = reader.u256()?;
+    let mut initial_l1_base_fee = U256::from(DEFAULT_INITIAL_L1_BASE_FEE);
+    let mut chain_config = Vec::new();
+    if !reader.rest().is_empty() {
+        match reader.array::<1>()?[0] {
+            0 => {}
+            1 => initial_l1_base_fee = reader.u256()?,
+            version => return Err(eyre!("Unknown initialize message version {version}")),
+        }
+        chain_config = reader.rest().to_vec();
+    }
+
+    // Nitro reads this message at genesis, ArbOS has no call for it
+    Ok(internal_tx(
+        message.data.clone(),
+        ArbitrumTxKind::Initialize {
+            chain_id,
+            initial_l1_base_fee,
+            chain_config,
+        },
+    ))
+}
+
+/// Build an internal transaction, sent by ArbOS to itself with `data`
+fn internal_tx(data: Vec<u8>, kind: ArbitrumTxKind) -> ArbitrumTransaction {
+    ArbitrumTransaction {
+        hash: B256::ZERO,
+        from: ARBOS_ADDRESS,
+        to: Some(ARBOS_ADDRESS),
+        value: U256::ZERO,
+        gas: 0,
+        gas_price: U256::ZERO,
+        nonce: 0,
+        data,
+        l1_sequence_number: None,
+        raw: vec![],
+        kind,
+    }
+}
+
+/// Request id of the `index`-th transaction derived from a message
+fn sub_request_id(request_id: B256, index: u64) -> B256 {
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(request_id.as_slice());
+    preimage[32..].copy_from_slice(&B256::from(U256::from(index)).0);
+    keccak256(preimage)
+}
+
+/// Cursor over a message body
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(eyre!(
+                "Message truncated: needed {len} bytes, {} left",
+                self.data.len()
+            ));
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn b256(&mut self) -> Result<B256> {
+        Ok(B256::from(self.array::<32>()?))
+    }
+
+    fn u256(&mut self) -> Result<U256> {
+        Ok(U256::from_be_bytes(self.array::<32>()?))
+    }
+
+    /// A 32-byte word that must fit in a u64
+    fn u64_word(&mut self, field: &str) -> Result<u64> {
+        u64::try_from(self.u256()?).map_err(|_| eyre!("Message {field} does not fit in 64 bits"))
+    }
+
+    /// A raw 20-byte address
+    fn address(&mut self) -> Result<Address> {
+        Ok(Address::from(self.array::<20>()?))
+    }
+
+    /// An address right-aligned in a 32-byte word
+    fn address_word(&mut self) -> Result<Address> {
+        Ok(Address::from_word(self.b256()?))
+    }
+
+    /// A length-prefixed byte string, or `None` if the rest is too short
+    fn bytestring(&mut self) -> Option<&'a [u8]> {
+        let len = u64::from_be_bytes(self.data.get(..8)?.try_into().ok()?) as usize;
+        if len > MAX_L2_MESSAGE_SIZE || self.data.len() - 8 < len {
+            return None;
+        }
+        self.data = &self.data[8..];
+        self.take(len).ok()
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, b256, hex};
+
+    use super::*;
+
+    const CHAIN_ID: u64 = 42_161;
+
+    fn word(value: u64) -> [u8; 32] {
+        B256::from(U256::from(value)).0
+    }
+
+    fn message(kind: L1MessageKind, data: Vec<u8>) -> L1Message {
+        L1Message {
+            message_number: 42,
+            kind,
+            sender: address!("0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"),
+            data,
+            timestamp: 1_700_000_000,
+            block_number: 100,
+            request_id: B256::from(U256::from(42)),
+            l1_base_fee: U256::from(30_000_000_000u64),
+            batch_gas_cost: None,
+        }
+    }
+
+    fn unsigned_body(nonce: Option<u64>, to: Address, value: u64, calldata: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&word(100_000));
+        body.extend_from_slice(&word(1_000_000_000));
+        if let Some(nonce) = nonce {
+            body.extend_from_slice(&word(nonce));
+        }
+        body.extend_from_slice(to.into_word().as_slice());
+        body.extend_from_slice(&word(value));
+        body.extend_from_slice(calldata);
+        body
+    }
+
+    #[test]
+    fn test_parse_eth_deposit() {
+        let to = address!("0x1111111111111111111111111111111111111111");
+        let mut data = to.to_vec();
+        data.extend_from_slice(&word(5_000));
+        let msg = message(L1MessageKind::EthDeposit, data);
+
+        let txs = parse_l1_message(&msg, CHAIN_ID).unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].from, msg.sender);
+        assert_eq!(txs[0].to, Some(to));
+        assert_eq!(txs[0].value, U256::from(5_000));
+        assert_eq!(txs[0].l1_sequence_number, Some(42));
+        assert_eq!(
+            txs[0].kind,
+            ArbitrumTxKind::Deposit {
+                l1_request_id: msg.request_id
+            }
+        );
+        // Hashed as Nitro encodes the deposit type
+        let encoded = txs[0].encoded_2718(CHAIN_ID);
+        assert_eq!(encoded[0], 0x64);
+        assert_eq!(txs[0].hash, keccak256(&encoded));
+
+        let truncated = message(L1MessageKind::EthDeposit, vec![0; 40]);
+        assert!(parse_l1_message(&truncated, CHAIN_ID).is_err());
+    }
+
+    #[test]
+    fn test_parse_unsigned_and_contract_txs() {
+        let to = address!("0x2222222222222222222222222222222222222222");
+        let mut data = vec![l2_message_kind::UNSIGNED_USER_TX];
+        data.extend(unsigned_body(Some(7), to, 1, &[0xde, 0xad]));
+        let msg = message(L1MessageKind::L2Message, data);
+        let txs = parse_l1_message(&msg, CHAIN_ID).unwrap();
+        assert_eq!(txs[0].kind, ArbitrumTxKind::Unsigned);
+        // The sender is not aliased again
+        assert_eq!(txs[0].from, msg.sender);
+        assert_eq!(txs[0].nonce, 7);
+        assert_eq!(txs[0].gas, 100_000);
+        assert_eq!(txs[0].gas_price, U256::from(1_000_000_000u64));
+        assert_eq!(txs[0].to, Some(to));
+        assert_eq!(txs[0].data, vec![0xde, 0xad]);
+
+        // A zero destination creates a contract
+        let mut data = vec![l2_message_kind::CONTRACT_TX];
+        data.extend(unsigned_body(None, Address::ZERO, 0, &[0x60, 0x00]));
+        let msg = message(L1MessageKind::L2Message, data);
+        let txs = parse_l1_message(&msg, CHAIN_ID).unwrap();
+        assert_eq!(txs[0].to, None);
+        assert_eq!(
+            txs[0].kind,
+            ArbitrumTxKind::Contract {
+                request_id: msg.request_id
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_l2_batch_assigns_sub_request_ids() {
+        let to = address!("0x2222222222222222222222222222222222222222");
+        let mut segment = vec![l2_message_kind::CONTRACT_TX];
+        segment.extend(unsigned_body(None, to, 0, &[]));
+
+        let mut data = vec![l2_message_kind::BATCH];
+        for _ in 0..2 {
+            data.extend_from_slice(&(segment.len() as u64).to_be_bytes());
+            data.extend_from_slice(&segment);
+        }
+        // Trailing garbage shorter than a length prefix is ignored
+        data.extend_from_slice(&[0, 0, 1]);
+        let msg = message(L1MessageKind::L2Message, data);
+
+        let txs = parse_l1_message(&msg, CHAIN_ID).unwrap();
+        assert_eq!(txs.len(), 2);
+        for (index, tx) in txs.iter().enumerate() {
+            assert_eq!(
+                tx.kind,
+                ArbitrumTxKind::Contract {
+                    request_id: sub_request_id(msg.request_id, index as u64)
+                }
+            );
+        }
+        assert_ne!(txs[0].hash, txs[1].hash);
+    }
+
+    #[test]
+    fn test_parse_sequencer_message() {
+        let poster = address!("0xa4b000000000000000000073657175656e636572");
+        let to = address!("0x2222222222222222222222222222222222222222");
+        let mut data = vec![l2_message_kind::UNSIGNED_USER_TX];
+        data.extend(unsigned_body(Some(7), to, 1, &[]));
+        let txs = parse_sequencer_message(&data, poster, CHAIN_ID).unwrap();
+        assert_eq!(txs[0].from, poster);
+        assert_eq!(txs[0].l1_sequence_number, None);
+        assert_eq!(txs[0].hash, txs[0].compute_hash(CHAIN_ID));
+
+        // Without a request id there are no contract transactions
+        let mut data = vec![l2_message_kind::CONTRACT_TX];
+        data.extend(unsigned_body(None, to, 0, &[]));
+        assert!(parse_sequencer_message(&data, poster, CHAIN_ID).is_err());
+    }
+
+    #[test]
+    fn test_parse_signed_tx() {
+        // EIP-155 example transaction
+        let raw = hex!(
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+        let mut data = vec![l2_message_kind::SIGNED_TX];
+        data.extend_from_slice(&raw);
+
+        let txs = parse_l1_message(&message(L1MessageKind::L2Message, data), CHAIN_ID).unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].kind, ArbitrumTxKind::Signed);
+        assert_eq!(txs[0].hash, keccak256(raw));
+        assert_eq!(txs[0].raw, raw);
+        assert_eq!(
+            txs[0].from,
+            address!("0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F")
+        );
+        assert_eq!(txs[0].nonce, 9);
+        assert_eq!(txs[0].value, U256::from(10u64.pow(18)));
+
+        let bad = message(
+            L1MessageKind::L2Message,
+            vec![l2_message_kind::SIGNED_TX, 0xf8],
+        );
+        assert!(parse_l1_message(&bad, CHAIN_ID).is_err());
+    }
+
+    #[test]
+    fn test_parse_l2_funded_by_l1() {
+        let to = address!("0x3333333333333333333333333333333333333333");
+        let mut data = vec![l2_message_kind::CONTRACT_TX];
+        data.extend(unsigned_body(None, to, 9, &[]));
+        let msg = message(L1MessageKind::L2FundedByL1, data);
+
+        let txs = parse_l1_message(&msg, CHAIN_ID).unwrap();
+        // The deposit funds the sender of the transaction
+        assert_eq!(txs.len(), 2);
+        assert_eq!(txs[0].to, Some(msg.sender));
+        assert_eq!(txs[1].from, msg.sender);
+        assert_eq!(txs[0].value, U256::from(9));
+        assert_eq!(
+            txs[0].kind,
+            ArbitrumTxKind::Deposit {
+                l1_request_id: sub_request_id(msg.request_id, 0)
+            }
+        );
+        assert_eq!(
+            txs[1].kind,
+            ArbitrumTxKind::Contract {
+                request_id: sub_request_id(msg.request_id, 1)
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_submit_retryable() {
+        let retry_to = address!("0x4444444444444444444444444444444444444444");
+        let refund = address!("0x5555555555555555555555555555555555555555");
+        let beneficiary = address!("0x6666666666666666666666666666666666666666");
+        let mut data = Vec::new();
+        data.extend_from_slice(retry_to.into_word().as_slice());
+        data.extend_from_slice(&word(10)); // call value
+        data.extend_from_slice(&word(1_000)); // deposit
+        data.extend_from_slice(&word(200)); // max submission fee
+        data.extend_from_slice(refund.into_word().as_slice());
+        data.extend_from_slice(beneficiary.into_word().as_slice());
+        data.extend_from_slice(&word(50_000)); // gas limit
+        data.extend_from_slice(&word(2)); // max fee per gas
+        data.extend_from_slice(&word(3)); // data length
+        data.extend_from_slice(&[1, 2, 3]);
+        let msg = message(L1MessageKind::SubmitRetryable, data.clone());
+
+        let txs = parse_l1_message(&msg, CHAIN_ID).unwrap();
+        assert_eq!(txs[0].from, msg.sender);
+        assert_eq!(txs[0].to, Some(retry_to));
+        assert_eq!(txs[0].value, U256::from(10));
+        assert_eq!(txs[0].gas, 50_000);
+        assert_eq!(txs[0].data, vec![1, 2, 3]);
+        assert_eq!(
+            txs[0].kind,
+            ArbitrumTxKind::SubmitRetryable {
+                request_id: msg.request_id,
+                l1_base_fee: msg.l1_base_fee,
+                deposit_value: U256::from(1_000),
+                max_submission_fee: U256::from(200),
+                fee_refund_address: refund,
+                beneficiary,
+            }
+        );
+
+        assert_eq!(txs[0].hash, txs[0].compute_hash(CHAIN_ID));
+        assert_ne!(txs[0].hash, txs[0].compute_hash(1));
+
+        // Declared retry data longer than the message
+        data.truncate(data.len() - 1);
+        assert!(
+            parse_l1_message(&message(L1MessageKind::SubmitRetryable, data), CHAIN_ID).is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_batch_posting_report_and_initialize() {
+        let poster = address!("0xc1b634853cb333d3ad8663715b08f41a3aec47cc");
+        let mut data = Vec::new();
+        data.extend_from_slice(&word(1_700_000_000));
+        data.extend_from_slice(poster.as_slice());
+        data.extend_from_slice(&[0xab; 32]);
+        data.extend_from_slice(&word(563_218));
+        data.extend_from_slice(&word(23_417_925_113));
+        data.extend_from_slice(&77u64.to_be_bytes());
+
+        // The report cannot be parsed before the gas of its batch is known
+        let mut report = message(L1MessageKind::BatchPostingReport, data.clone());
+        assert!(parse_l1_message(&report, CHAIN_ID).is_err());
+        assert_eq!(
+            reported_batch(&data).unwrap(),
+            (B256::repeat_byte(0xab), 563_218)
+        );
+
+        // Nitro's ArbitrumInternalTx calling batchPostingReport, sent by ArbOS
+        report.batch_gas_cost = Some(100_000);
+        let txs = parse_l1_message(&report, CHAIN_ID).unwrap();
+        assert_eq!(txs[0].from, ARBOS_ADDRESS);
+        assert_eq!(txs[0].to, Some(ARBOS_ADDRESS));
+        assert_eq!(
+            txs[0].data,
+            hex!(
+                "b6693771"
+                "000000000000000000000000000000000000000000000000000000006553f100"
+                "000000000000000000000000c1b634853cb333d3ad8663715b08f41a3aec47cc"
+                "0000000000000000000000000000000000000000000000000000000000089812"
+                "00000000000000000000000000000000000000000000000000000000000186ed"
+                "0000000000000000000000000000000000000000000000000000000573d12df9"
+            )
+        );
+        assert_eq!(txs[0].encoded_2718(CHAIN_ID)[0], 0x6a);
+        assert_eq!(
+            txs[0].hash,
+            b256!("0x8be6514fada5db19589f20f47ba8d36b71b6ff17b493b085623a114cf9c21985")
+        );
+        assert_eq!(
+            txs[0].kind,
+            ArbitrumTxKind::BatchPostingReport {
+                batch_timestamp: U256::from(1_700_000_000),
+                batch_poster: poster,
+                data_hash: B256::repeat_byte(0xab),
+                batch_number: 563_218,
+                l1_base_fee_estimate: U256::from(23_417_925_113u64),
+                batch_data_gas: 100_077,
+            }
+        );
+
+        let mut data = word(42_161).to_vec();
+        data.push(1);
+        data.extend_from_slice(&word(1_000));
+        data.extend_from_slice(b"{}");
+        let txs = parse_l1_message(&message(L1MessageKind::Initialize, data), CHAIN_ID).unwrap();
+        assert_eq!(
+            txs[0].kind,
+            ArbitrumTxKind::Initialize {
+                chain_id: U256::from(42_161),
+                initial_l1_base_fee: U256::from(1_000),
+                chain_config: b"{}".to_vec(),
+            }
+        );
+
+        let txs = parse_l1_message(
+            &message(L1MessageKind::Initialize, word(42_161).to_vec()),
+            CHAIN_ID,
+        )
+        .unwrap();
+        assert!(matches!(
+            &txs[0].kind,
+            ArbitrumTxKind::Initialize { initial_l1_base_fee, chain_config, .. }
+                if *initial_l1_base_fee == U256::from(DEFAULT_INITIAL_L1_BASE_FEE)
+                    && chain_config.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_end_of_block_produces_no_transactions() {
+        let msg = message(L1MessageKind::EndOfBlock, vec![]);
+        assert!(parse_l1_message(&msg, CHAIN_ID).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+//! Accumulator of the L2-to-L1 sends
+//!
+//! Every send recorded by ArbSys is a leaf of a Merkle tree whose root, the
+//! send root, goes into the block header for the outbox on L1. As in ArbOS,
+//! the tree is kept as the roots of its complete subtrees: appending a leaf
+//! merges subtrees of equal size the way a binary counter carries.
+
+use alloy_primitives::{B256, U256, keccak256};
+use arbitrum_storage::SendMerkleState;
+
+/// Node of the send tree completed by an append
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MerkleUpdate {
+    pub level: u64,
+    pub leaf: u64, // Index of the leaf whose append completed the node
+    pub hash: B256,
+}
+
+impl MerkleUpdate {
+    /// Position of the node as ArbSys logs it, its level above its leaf
+    pub fn position(&self) -> U256 {
+        (U256::from(self.level) << 192) + U256::from(self.leaf)
+    }
+}
+
+/// Append the hash of a send, returning the nodes it completes
+pub fn append(state: &mut SendMerkleState, item: B256) -> Vec<MerkleUpdate> {
+    let leaf = state.size;
+    state.size += 1;
+
+    let mut updates = vec![];
+    let mut so_far = keccak256(item);
+    let mut level = 0;
+    loop {
+        let partial = partial(state, level);
+        if level == num_partials(leaf) || partial == B256::ZERO {
+            set_partial(state, level, so_far);
+            return updates;
+        }
+        so_far = keccak256([partial.as_slice(), so_far.as_slice()].concat());
+        set_partial(state, level, B256::ZERO);
+        level += 1;
+        updates.push(MerkleUpdate {
+            level,
+            leaf,
+            hash: so_far,
+        });
+    }
+}
+
+/// Root of the send tree, zero without sends
+///
+/// Complete subtrees smaller than the one to their left are padded with
+/// zero leaves to its size before they are combined.
+pub fn root(state: &SendMerkleState) -> B256 {
+    let mut root: Option<(B256, u64)> = None;
+    let mut capacity = 1u64;
+    for level in 0..num_partials(state.size) {
+        let partial = partial(state, level);
+        if partial != B256::ZERO {
+            root = Some(match root {
+                None => (partial, capacity),
+                Some((mut hash, mut hash_capacity)) => {
+                    while hash_capacity < capacity {
+                        hash = keccak256([hash.as_slice(), B256::ZERO.as_slice()].concat());
+                        hash_capacity *= 2;
+                    }
+                    (
+                        keccak256([partial.as_slice(), hash.as_slice()].concat()),
+                        2 * capacity,
+                    )
+                }
+            });
+        }
+        capacity = capacity.saturating_mul(2);
+    }
+    root.map_or(B256::ZERO, |(hash, _)| hash)
+}
+
+/// Proof of the send at `leaf` against the root of the tree of `sends`
+///
+/// `sends` are the hashes of the sends in order. The proof holds the sibling
+/// of each node on the path from the leaf to the root, bottom up, as the
+/// outbox on L1 folds it. Siblings beyond the last send are zero.
+pub fn proof(sends: &[B256], leaf: u64) -> Vec<B256> {
+    let leaves: Vec<B256> = sends.iter().map(keccak256).collect();
+    (0..height(leaves.len() as u64))
+        .map(|level| node(&leaves, level, (leaf >> level) ^ 1))
+        .collect()
+}
+
+/// Levels of siblings in the proofs of a tree of `size` leaves
+pub fn height(size: u64) -> u64 {
+    num_partials(size.saturating_sub(1))
+}
+
+/// Root the outbox folds the proof of the send `item` at `leaf` into
+pub fn fold(item: B256, leaf: u64, proof: &[B256]) -> B256 {
+    let mut hash = keccak256(item);
+    for (level, sibling) in proof.iter().enumerate() {
+        hash = if (leaf >> level) & 1 == 0 {
+            keccak256([hash.as_slice(), sibling.as_slice()].concat())
+        } else {
+            keccak256([sibling.as_slice(), hash.as_slice()].concat())
+        };
+    }
+    hash
+}
+
+/// Node `index` of `level` of the tree of `leaves`, as [`root`] pads it
+fn node(leaves: &[B256], level: u64, index: u64) -> B256 {
+    let first = index << level;
+    if first >= leaves.len() as u64 {
+        return B256::ZERO;
+    }
+    if level == 0 {
+        return leaves[first as usize];
+    }
+    let left = node(leaves, level - 1, 2 * index);
+    let right = node(leaves, level - 1, 2 * index + 1);
+    keccak256([left.as_slice(), right.as_slice()].concat())
+}
+
+/// Levels of partials a tree of `size` leaves can use
+fn num_partials(size: u64) -> u64 {
+    u64::from(u64::BITS - size.leading_zeros())
+}
+
+fn partial(state: &SendMerkleState, level: u64) -> B256 {
+    state
+        .partials
+        .get(level as usize)
+        .copied()
+        .unwrap_or(B256::ZERO)
+}
+
+fn set_partial(state: &mut SendMerkleState, level: u64, hash: B256) {
+    let level = level as usize;
+    if state.partials.len() <= level {
+        state.partials.resize(level + 1, B256::ZERO);
+    }
+    state.partials[level] = hash;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_pair(left: B256, right: B256) -> B256 {
+        keccak256([left.as_slice(), right.as_slice()].concat())
+    }
+
+    #[test]
+    fn test_append_and_root() {
+        let items: Vec<B256> = (1..=4).map(B256::with_last_byte).collect();
+        let leaves: Vec<B256> = items.iter().map(keccak256).collect();
+        let mut state = SendMerkleState::default();
+        assert_eq!(root(&state), B256::ZERO);
+
+        assert!(append(&mut state, items[0]).is_empty());
+        assert_eq!(root(&state), leaves[0]);
+
+        let pair = hash_pair(leaves[0], leaves[1]);
+        let updates = append(&mut state, items[1]);
+        assert_eq!(
+            updates,
+            vec![MerkleUpdate {
+                level: 1,
+                leaf: 1,
+                hash: pair,
+            }]
+        );
+        assert_eq!(root(&state), pair);
+
+        // The lone third leaf is padded to a pair
+        assert!(append(&mut state, items[2]).is_empty());
+        assert_eq!(
+            root(&state),
+            hash_pair(pair, hash_pair(leaves[2], B256::ZERO))
+        );
+
+        let full = hash_pair(pair, hash_pair(leaves[2], leaves[3]));
+        let updates = append(&mut state, items[3]);
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[1].hash, full);
+        assert_eq!(
+            updates[1].position(),
+            (U256::from(2) << 192) + U256::from(3)
+        );
+        assert_eq!(state.size, 4);
+        assert_eq!(root(&state), full);
+    }
+
+    #[test]
+    fn test_proofs_fold_to_root() {
+        let sends: Vec<B256> = (1..=5).map(B256::with_last_byte).collect();
+        for size in 1..=sends.len() {
+            let mut state = SendMerkleState::default();
+            for send in &sends[..size] {
+                append(&mut state, *send);
+            }
+            for leaf in 0..size as u64 {
+                // Fold the proof as the outbox does
+                let proof = proof(&sends[..size], leaf);
+                let mut hash = keccak256(sends[leaf as usize]);
+                for (level, sibling) in proof.iter().enumerate() {
+                    hash = if (leaf >> level) & 1 == 0 {
+                        hash_pair(hash, *sibling)
+                    } else {
+                        hash_pair(*sibling, hash)
+                    };
+                }
+                assert_eq!(hash, root(&state), "leaf {leaf} of {size}");
+                assert_eq!(fold(sends[leaf as usize], leaf, &proof), hash);
+            }
+        }
+        assert!(proof(&sends[..1], 0).is_empty());
+        assert_eq!(proof(&sends[..3], 2)[0], B256::ZERO);
+    }
+}
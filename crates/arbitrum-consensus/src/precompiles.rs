@@ -0,0 +1,302 @@
+//! Arbitrum precompiles in the EVM
+//!
+//! ArbOS exposes its state and system calls to contracts as precompiles at
+//! low addresses, next to the Ethereum ones. They see the transaction through
+//! the [`ArbitrumContext`] execution sets up, and the call frames through the
+//! [`CallStack`] inspector.
+
+use alloy_primitives::{Address, B256, Bytes, Log, U256, address};
+use alloy_sol_types::SolEvent;
+use revm::{
+    context::{Cfg, ContextTr, JournalTr, LocalContextTr},
+    handler::{EthPrecompiles, PrecompileProvider},
+    inspector::{Inspector, JournalExt},
+    interpreter::{
+        CallInput, CallInputs, CallOutcome, CreateInputs, CreateOutcome, Gas, InputsImpl,
+        InstructionResult, InterpreterResult,
+    },
+};
+use tracing::debug;
+
+use crate::{
+    arbgasinfo, arbretryabletx, arbsys, execution::ArbOsState, retryables::RetryableDatabase,
+};
+
+/// ArbSys, for system information and L2-to-L1 sends
+pub const ARBSYS_ADDRESS: Address = address!("0x0000000000000000000000000000000000000064");
+
+/// ArbGasInfo, for the gas and L1 pricing
+pub const ARBGASINFO_ADDRESS: Address = address!("0x000000000000000000000000000000000000006c");
+
+/// ArbRetryableTx, for managing retryable tickets
+pub const ARBRETRYABLETX_ADDRESS: Address = address!("0x000000000000000000000000000000000000006e");
+
+/// ArbOS version whose behaviour the precompiles follow
+pub const ARBOS_VERSION: u64 = 20;
+
+/// Addresses of the Arbitrum precompiles
+const ARBITRUM_PRECOMPILES: [Address; 3] =
+    [ARBSYS_ADDRESS, ARBGASINFO_ADDRESS, ARBRETRYABLETX_ADDRESS];
+
+/// Gas to copy a word of arguments or results
+pub const COPY_GAS: u64 = 3;
+
+/// Gas of a log, per topic and per byte of data
+const LOG_GAS: u64 = 375;
+const LOG_TOPIC_GAS: u64 = 375;
+const LOG_DATA_GAS: u64 = 8;
+
+/// Gas of reading a slot of ArbOS state
+pub const STORAGE_READ_COST: u64 = 800;
+
+/// Gas of writing a slot of ArbOS state
+pub const STORAGE_WRITE_COST: u64 = 20_000;
+
+/// What the precompiles know of the transaction being executed
+#[derive(Debug, Clone)]
+pub struct ArbitrumContext {
+    pub l1_block_number: u64,
+    pub base_fee: u64,        // Base fee of the block, even for calls that pay none
+    pub aliases_sender: bool, // Whether the transaction type carries an aliased L1 sender
+    pub poster_fee: U256,     // L1 data fee the transaction pays
+    pub arbos: ArbOsState,    // ArbOS state before the transaction
+    pub current_retry: Option<(B256, Address)>, // Ticket and refund address of a redeem attempt
+    callers: Vec<Address>,    // Callers of the open call frames, innermost last
+}
+
+impl ArbitrumContext {
+    /// Context of a transaction of a block in L1 block `l1_block_number`,
+    /// charging `base_fee`
+    pub fn new(l1_block_number: u64, base_fee: u64, arbos: ArbOsState) -> Self {
+        Self {
+            l1_block_number,
+            base_fee,
+            aliases_sender: false,
+            poster_fee: U256::ZERO,
+            arbos,
+            current_retry: None,
+            callers: vec![],
+        }
+    }
+
+    /// Set whether the transaction comes from an aliased L1 sender
+    pub fn with_aliased_sender(mut self, aliases_sender: bool) -> Self {
+        self.aliases_sender = aliases_sender;
+        self
+    }
+
+    /// Set the L1 data fee the transaction pays
+    pub fn with_poster_fee(mut self, poster_fee: U256) -> Self {
+        self.poster_fee = poster_fee;
+        self
+    }
+
+    /// Set the ticket the transaction is a redeem attempt of, refunding
+    /// `refund_to`
+    pub fn with_retry(mut self, ticket_id: B256, refund_to: Address) -> Self {
+        self.current_retry = Some((ticket_id, refund_to));
+        self
+    }
+
+    /// Number of open call frames, the precompile's own included
+    pub fn depth(&self) -> usize {
+        self.callers.len()
+    }
+
+    /// Caller of the frame `up` frames above the precompile's
+    pub fn caller(&self, up: usize) -> Option<Address> {
+        let index = self.callers.len().checked_sub(up + 1)?;
+        self.callers.get(index).copied()
+    }
+}
+
+/// Inspector keeping the callers of the open call frames in the
+/// [`ArbitrumContext`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CallStack;
+
+impl<CTX: ContextTr<Chain = ArbitrumContext>> Inspector<CTX> for CallStack {
+    fn call(&mut self, context: &mut CTX, inputs: &mut CallInputs) -> Option<CallOutcome> {
+        context.chain_mut().callers.push(inputs.caller);
+        None
+    }
+
+    fn call_end(&mut self, context: &mut CTX, _inputs: &CallInputs, _outcome: &mut CallOutcome) {
+        context.chain_mut().callers.pop();
+    }
+
+    fn create(&mut self, context: &mut CTX, inputs: &mut CreateInputs) -> Option<CreateOutcome> {
+        context.chain_mut().callers.push(inputs.caller);
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        context: &mut CTX,
+        _inputs: &CreateInputs,
+        _outcome: &mut CreateOutcome,
+    ) {
+        context.chain_mut().callers.pop();
+    }
+}
+
+/// The Ethereum precompiles together with the Arbitrum ones
+#[derive(Debug, Clone, Default)]
+pub struct ArbitrumPrecompiles {
+    eth: EthPrecompiles,
+}
+
+impl<CTX> PrecompileProvider<CTX> for ArbitrumPrecompiles
+where
+    CTX: ContextTr<Chain = ArbitrumContext, Db: RetryableDatabase, Journal: JournalExt>,
+{
+    type Output = InterpreterResult;
+
+    fn set_spec(&mut self, spec: <CTX::Cfg as Cfg>::Spec) -> bool {
+        <EthPrecompiles as PrecompileProvider<CTX>>::set_spec(&mut self.eth, spec)
+    }
+
+    fn run(
+        &mut self,
+        context: &mut CTX,
+        address: &Address,
+        inputs: &InputsImpl,
+        is_static: bool,
+        gas_limit: u64,
+    ) -> Result<Option<InterpreterResult>, String> {
+        if !ARBITRUM_PRECOMPILES.contains(address) {
+            return self.eth.run(context, address, inputs, is_static, gas_limit);
+        }
+
+        let input = match &inputs.input {
+            CallInput::SharedBuffer(range) => context
+                .local()
+                .shared_memory_buffer_slice(range.clone())
+                .map(|slice| Bytes::copy_from_slice(&slice))
+                .unwrap_or_default(),
+            CallInput::Bytes(bytes) => bytes.clone(),
+        };
+        let mut call = PrecompileCall {
+            input,
+            caller: inputs.caller_address,
+            value: inputs.call_value,
+            is_static,
+            gas: Gas::new(gas_limit),
+        };
+        let outcome = call
+            .burn(COPY_GAS * words(call.input.len().saturating_sub(4)))
+            .and_then(|()| match *address {
+                ARBSYS_ADDRESS => arbsys::call(context, &mut call),
+                ARBGASINFO_ADDRESS => arbgasinfo::call(context, &mut call),
+                _ => arbretryabletx::call(context, &mut call),
+            });
+        call.finish(outcome)
+    }
+
+    fn warm_addresses(&self) -> Box<impl Iterator<Item = Address>> {
+        Box::new(self.eth.warm_addresses().chain(ARBITRUM_PRECOMPILES))
+    }
+
+    fn contains(&self, address: &Address) -> bool {
+        ARBITRUM_PRECOMPILES.contains(address) || self.eth.contains(address)
+    }
+}
+
+/// A call into one of the Arbitrum precompiles
+pub struct PrecompileCall {
+    pub input: Bytes,
+    pub caller: Address,
+    pub value: U256,
+    pub is_static: bool,
+    gas: Gas,
+}
+
+/// Why a precompile call did not return
+#[derive(Debug)]
+pub enum PrecompileFailure {
+    /// The call ran out of gas
+    OutOfGas,
+    /// The call reverted with a Solidity error, keeping the gas left
+    Revert(Bytes),
+    /// The call is invalid and reverts with all its gas, as ArbOS does
+    Reject(String),
+    /// State could not be read, which aborts the transaction
+    Fatal(String),
+}
+
+impl PrecompileCall {
+    /// Charge `cost` gas
+    pub fn burn(&mut self, cost: u64) -> Result<(), PrecompileFailure> {
+        if self.gas.record_cost(cost) {
+            Ok(())
+        } else {
+            Err(PrecompileFailure::OutOfGas)
+        }
+    }
+
+    /// Gas the call has left
+    pub fn gas_left(&self) -> u64 {
+        self.gas.remaining()
+    }
+
+    /// Reject calls that send value to a method that is not payable
+    pub fn not_payable(&self) -> Result<(), PrecompileFailure> {
+        if self.value.is_zero() {
+            Ok(())
+        } else {
+            Err(PrecompileFailure::Reject("method is not payable".into()))
+        }
+    }
+
+    /// Emit an event of the precompile at `address`, paying for its log
+    pub fn emit<CTX: ContextTr>(
+        &mut self,
+        context: &mut CTX,
+        address: Address,
+        event: &impl SolEvent,
+    ) -> Result<(), PrecompileFailure> {
+        let data = event.encode_log_data();
+        self.burn(log_cost(data.topics().len(), data.data.len()))?;
+        context.journal_mut().log(Log { address, data });
+        Ok(())
+    }
+
+    fn finish(
+        mut self,
+        outcome: Result<Vec<u8>, PrecompileFailure>,
+    ) -> Result<Option<InterpreterResult>, String> {
+        let outcome = outcome.and_then(|output| {
+            self.burn(COPY_GAS * words(output.len()))?;
+            Ok(output)
+        });
+        let (result, output) = match outcome {
+            Ok(output) => (InstructionResult::Return, output.into()),
+            Err(PrecompileFailure::Revert(data)) => (InstructionResult::Revert, data),
+            Err(PrecompileFailure::Reject(reason)) => {
+                debug!("Precompile call rejected: {}", reason);
+                self.gas.spend_all();
+                (InstructionResult::Revert, Bytes::new())
+            }
+            Err(PrecompileFailure::OutOfGas) => {
+                self.gas.spend_all();
+                (InstructionResult::PrecompileOOG, Bytes::new())
+            }
+            Err(PrecompileFailure::Fatal(e)) => return Err(e),
+        };
+        Ok(Some(InterpreterResult {
+            result,
+            gas: self.gas,
+            output,
+        }))
+    }
+}
+
+/// Gas of a log with `topics` topics and `len` bytes of data
+pub fn log_cost(topics: usize, len: usize) -> u64 {
+    LOG_GAS + LOG_TOPIC_GAS * topics as u64 + LOG_DATA_GAS * len as u64
+}
+
+/// Words of 32 bytes needed for `len` bytes
+pub fn words(len: usize) -> u64 {
+    (len as u64).div_ceil(32)
+}
@@ -0,0 +1,239 @@
+//! Retryable tickets
+//!
+//! A retryable submitted from L1 becomes a ticket holding a call to make on
+//! L2. The submission pays the ticket's fee, escrows its call value and
+//! schedules a first redeem attempt with the gas it brought. Attempts that
+//! fail leave the ticket for anyone to redeem through ArbRetryableTx until it
+//! times out, which `keepalive` postpones by a lifetime at a time. A ticket
+//! that expires or is cancelled returns its escrow to its beneficiary.
+//!
+//! L2 gas is burnt, as the base fee of any transaction is, so the gas a
+//! redeem attempt was paid ahead for and does not use is minted back.
+
+use alloy_primitives::{Address, B256, U256, keccak256};
+use alloy_sol_types::SolEvent;
+use arbitrum_storage::{ArbitrumTransaction, ArbitrumTxKind, Log, Retryable};
+
+use crate::{
+    arbretryabletx::ArbRetryableTx::{Canceled, LifetimeExtended, RedeemScheduled},
+    precompiles::ARBRETRYABLETX_ADDRESS,
+};
+
+/// Seconds a ticket lives, and that `keepalive` extends it by
+pub const RETRYABLE_LIFETIME_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// L1 gas ArbOS charges a retryable's submission for, and per byte of its
+/// retry data
+pub const RETRYABLE_SUBMISSION_GAS: u64 = 1_400;
+pub const RETRYABLE_SUBMISSION_BYTE_GAS: u64 = 6;
+
+/// Expired tickets reaped at the start of a block, as ArbOS does
+pub const REAPS_PER_BLOCK: usize = 2;
+
+/// Gas below which a redeem attempt cannot run
+pub const TX_GAS: u64 = 21_000;
+
+/// Tickets in storage, as the EVM's database reads them for the precompiles
+pub trait RetryableDatabase {
+    /// The ticket `id`, expired or not
+    fn retryable(&mut self, id: B256) -> Result<Option<Retryable>, String>;
+}
+
+/// Fee of submitting a ticket with `data_len` bytes of retry data at
+/// `l1_base_fee`
+pub fn submission_fee(data_len: usize, l1_base_fee: U256) -> U256 {
+    let gas = RETRYABLE_SUBMISSION_GAS + RETRYABLE_SUBMISSION_BYTE_GAS * data_len as u64;
+    l1_base_fee * U256::from(gas)
+}
+
+/// Account holding the call value of a ticket until it is redeemed
+pub fn escrow_address(ticket_id: B256) -> Address {
+    let hash = keccak256([b"retryable escrow".as_slice(), ticket_id.as_slice()].concat());
+    Address::from_slice(&hash[12..])
+}
+
+/// Take up to `amount` out of `pool`, returning what was taken
+pub fn take_funds(pool: &mut U256, amount: U256) -> U256 {
+    let taken = (*pool).min(amount);
+    *pool -= taken;
+    taken
+}
+
+/// Receipt log of an event ArbOS emits as ArbRetryableTx
+pub fn event_log(event: &impl SolEvent) -> Log {
+    let data = event.encode_log_data();
+    Log {
+        address: ARBRETRYABLETX_ADDRESS,
+        topics: data.topics().to_vec(),
+        data: data.data.to_vec(),
+        block_hash: None,
+        block_number: None,
+        transaction_hash: None,
+        transaction_index: None,
+        log_index: None,
+        removed: false,
+    }
+}
+
+/// Words of storage a ticket takes, its fields and its retry data
+pub fn size_words(ticket: &Retryable) -> u64 {
+    7 + (ticket.data.len() as u64).div_ceil(32)
+}
+
+/// Redeem attempt `event` schedules of `ticket`, run at `base_fee` on chain
+/// `chain_id`
+///
+/// The gas donor is refunded what the attempt does not use, as ArbOS makes
+/// it the attempt's refund address. The attempt is hashed from its fields,
+/// so the hash the event records is the one it is built with.
+pub fn retry_transaction(
+    ticket: &Retryable,
+    event: &RedeemScheduled,
+    base_fee: u64,
+    chain_id: u64,
+) -> ArbitrumTransaction {
+    ArbitrumTransaction {
+        hash: B256::ZERO,
+        from: ticket.from,
+        to: ticket.to,
+        value: ticket.call_value,
+        gas: event.donatedGas,
+        gas_price: U256::from(base_fee),
+        nonce: event.sequenceNum,
+        data: ticket.data.clone(),
+        l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Retry {
+            ticket_id: ticket.id,
+            refund_to: event.gasDonor,
+            max_refund: event.maxRefund,
+            submission_fee_refund: event.submissionFeeRefund,
+        },
+    }
+    .seal(chain_id)
+}
+
+/// Change to a ticket recorded by an ArbRetryableTx event
+pub enum TicketUpdate {
+    /// A redeem attempt was scheduled, taking a try
+    Redeem(RedeemScheduled),
+    /// The ticket lives until a new timeout
+    Extended { ticket_id: B256, timeout: u64 },
+    /// The ticket was cancelled, its escrow already returned
+    Canceled { ticket_id: B256 },
+}
+
+impl TicketUpdate {
+    /// Update of the log with `topics` and `data` at `address`, none for logs
+    /// of other contracts and for ticket creations
+    pub fn from_log(address: Address, topics: &[B256], data: &[u8]) -> Option<Self> {
+        if address != ARBRETRYABLETX_ADDRESS {
+            return None;
+        }
+        match *topics.first()? {
+            RedeemScheduled::SIGNATURE_HASH => RedeemScheduled::decode_raw_log(topics, data)
+                .ok()
+                .map(Self::Redeem),
+            LifetimeExtended::SIGNATURE_HASH => LifetimeExtended::decode_raw_log(topics, data)
+                .ok()
+                .map(|event| Self::Extended {
+                    ticket_id: event.ticketId,
+                    timeout: event.newTimeout.saturating_to(),
+                }),
+            Canceled::SIGNATURE_HASH => {
+                Canceled::decode_raw_log(topics, data)
+                    .ok()
+                    .map(|event| Self::Canceled {
+                        ticket_id: event.ticketId,
+                    })
+            }
+            _ => None,
+        }
+    }
+
+    /// Update of a log in a receipt
+    pub fn from_receipt_log(log: &Log) -> Option<Self> {
+        Self::from_log(log.address, &log.topics, &log.data)
+    }
+
+    /// Ticket the update is for
+    pub fn ticket_id(&self) -> B256 {
+        match self {
+            Self::Redeem(event) => event.ticketId,
+            Self::Extended { ticket_id, .. } | Self::Canceled { ticket_id } => *ticket_id,
+        }
+    }
+
+    /// Apply the update to `ticket`, which is gone once cancelled
+    pub fn apply(&self, ticket: &mut Option<Retryable>) {
+        match self {
+            Self::Redeem(_) => {
+                if let Some(ticket) = ticket {
+                    ticket.num_tries += 1;
+                }
+            }
+            Self::Extended { timeout, .. } => {
+                if let Some(ticket) = ticket {
+                    ticket.timeout = *timeout;
+                }
+            }
+            Self::Canceled { .. } => *ticket = None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::address;
+
+    use super::*;
+
+    #[test]
+    fn test_escrow_and_fees() {
+        // The escrow of a ticket is fixed by its id, and differs per ticket
+        let escrow = escrow_address(B256::with_last_byte(1));
+        assert_eq!(escrow, escrow_address(B256::with_last_byte(1)));
+        assert_ne!(escrow, escrow_address(B256::with_last_byte(2)));
+        assert_ne!(escrow, Address::ZERO);
+
+        assert_eq!(
+            submission_fee(10, U256::from(1_000)),
+            U256::from(1_000 * (1_400 + 60))
+        );
+
+        let mut pool = U256::from(10);
+        assert_eq!(take_funds(&mut pool, U256::from(4)), U256::from(4));
+        assert_eq!(take_funds(&mut pool, U256::from(9)), U256::from(6));
+        assert_eq!(pool, U256::ZERO);
+
+        let ticket = Retryable {
+            id: B256::with_last_byte(1),
+            num_tries: 0,
+            from: address!("0x1111111111111111111111111111111111111111"),
+            to: None,
+            call_value: U256::ZERO,
+            beneficiary: Address::ZERO,
+            data: vec![0; 33],
+            timeout: 0,
+        };
+        assert_eq!(size_words(&ticket), 9);
+
+        // Attempts are hashed from their fields, so each try has its own
+        let event = |sequence: u64| RedeemScheduled {
+            ticketId: ticket.id,
+            retryTxHash: B256::ZERO,
+            sequenceNum: sequence,
+            donatedGas: 100_000,
+            gasDonor: ticket.from,
+            maxRefund: U256::MAX,
+            submissionFeeRefund: U256::ZERO,
+        };
+        let retry = retry_transaction(&ticket, &event(0), 1_000, 42_161);
+        assert_eq!(retry.hash, retry.compute_hash(42_161));
+        assert_eq!(retry.tx_type(), 0x68);
+        assert_ne!(
+            retry.hash,
+            retry_transaction(&ticket, &event(1), 1_000, 42_161).hash
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Header commitments to the transactions, receipts and logs of a block
+
+use alloy_consensus::{
+    Eip658Value, Receipt, ReceiptWithBloom, proofs::ordered_trie_root_with_encoder,
+};
+use alloy_primitives::{B256, Bloom, BloomInput};
+use alloy_rlp::{BufMut, Encodable};
+use arbitrum_storage::{ArbitrumReceipt, ArbitrumTransaction, Log};
+
+/// EIP-2718 type of a transaction, 0 for legacy ones
+pub fn tx_type(tx: &ArbitrumTransaction) -> u8 {
+    match tx.raw.first() {
+        Some(&ty) if ty < 0x7f => ty,
+        _ => 0,
+    }
+}
+
+/// Root of the trie of the encoded transactions by index
+pub fn transactions_root(txs: &[ArbitrumTransaction]) -> B256 {
+    ordered_trie_root_with_encoder(txs, |tx, buf| {
+        // Transactions derived from L1 carry no envelope, so the block
+        // commits to their hash instead
+        if tx.raw.is_empty() {
+            buf.put_slice(tx.hash.as_slice());
+        } else {
+            buf.put_slice(&tx.raw);
+        }
+    })
+}
+
+/// Root of the trie of the encoded receipts by index
+///
+/// Each receipt comes with the type of its transaction.
+pub fn receipts_root(receipts: &[(u8, ArbitrumReceipt)]) -> B256 {
+    ordered_trie_root_with_encoder(receipts, |(ty, receipt), buf| {
+        let logs = receipt
+            .logs
+            .iter()
+            .map(|log| {
+                alloy_primitives::Log::new_unchecked(
+                    log.address,
+                    log.topics.clone(),
+                    log.data.clone().into(),
+                )
+            })
+            .collect();
+        let encoded = ReceiptWithBloom::new(
+            Receipt {
+                status: Eip658Value::Eip658(receipt.status == 1),
+                cumulative_gas_used: receipt.cumulative_gas_used,
+                logs,
+            },
+            logs_bloom(&receipt.logs),
+        );
+        if *ty != 0 {
+            buf.put_u8(*ty);
+        }
+        encoded.encode(buf);
+    })
+}
+
+/// Bloom filter over the addresses and topics of `logs`
+pub fn logs_bloom<'a>(logs: impl IntoIterator<Item = &'a Log>) -> Bloom {
+    let mut bloom = Bloom::ZERO;
+    for log in logs {
+        bloom.accrue(BloomInput::Raw(log.address.as_slice()));
+        for topic in &log.topics {
+            bloom.accrue(BloomInput::Raw(topic.as_slice()));
+        }
+    }
+    bloom
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_consensus::{EMPTY_ROOT_HASH, ReceiptEnvelope, proofs::calculate_receipt_root};
+    use alloy_primitives::{Address, U256, address, b256};
+
+    use super::*;
+
+    fn receipt(status: u64, cumulative_gas_used: u64, logs: Vec<Log>) -> ArbitrumReceipt {
+        ArbitrumReceipt {
+            transaction_hash: B256::ZERO,
+            transaction_index: 0,
+            block_hash: B256::ZERO,
+            block_number: 0,
+            cumulative_gas_used,
+            gas_used: 0,
+            gas_used_for_l1: 0,
+            contract_address: None,
+            logs,
+            status,
+            effective_gas_price: U256::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_roots_match_ethereum_encoding() {
+        assert_eq!(transactions_root(&[]), EMPTY_ROOT_HASH);
+        assert_eq!(receipts_root(&[]), EMPTY_ROOT_HASH);
+
+        let log = Log {
+            address: address!("0x5555555555555555555555555555555555555555"),
+            topics: vec![b256!(
+                "0x00000000000000000000000000000000000000000000000000000000000000aa"
+            )],
+            data: vec![0x2a],
+            block_hash: None,
+            block_number: None,
+            transaction_hash: None,
+            transaction_index: None,
+            log_index: None,
+            removed: false,
+        };
+        let receipts = [
+            (0, receipt(1, 21_000, vec![])),
+            (2, receipt(0, 50_000, vec![log.clone()])),
+        ];
+
+        // The same receipts as Ethereum legacy and EIP-1559 receipts
+        let envelope = |receipt: &ArbitrumReceipt| ReceiptWithBloom {
+            receipt: Receipt {
+                status: Eip658Value::Eip658(receipt.status == 1),
+                cumulative_gas_used: receipt.cumulative_gas_used,
+                logs: receipt
+                    .logs
+                    .iter()
+                    .map(|log| {
+                        alloy_primitives::Log::new_unchecked(
+                            log.address,
+                            log.topics.clone(),
+                            log.data.clone().into(),
+                        )
+                    })
+                    .collect(),
+            },
+            logs_bloom: logs_bloom(&receipt.logs),
+        };
+        let expected = calculate_receipt_root(&[
+            ReceiptEnvelope::Legacy(envelope(&receipts[0].1)),
+            ReceiptEnvelope::Eip1559(envelope(&receipts[1].1)),
+        ]);
+        assert_eq!(receipts_root(&receipts), expected);
+
+        let bloom = logs_bloom([&log]);
+        assert!(bloom.contains_input(BloomInput::Raw(log.address.as_slice())));
+        assert!(!bloom.contains_input(BloomInput::Raw(Address::ZERO.as_slice())));
+    }
+}
@@ -0,0 +1,469 @@
+//! Decoding of sequencer batch data into the ordered L2 message stream
+//!
+//! Mirrors Nitro's inbox multiplexer: the SequencerInbox header is followed
+//! by a header byte and the (brotli-compressed) RLP list of batch segments.
+//! Segments either carry L2 messages, read the next delayed inbox message,
+//! or advance the timestamp / L1 block number applied to later messages.
+
+use std::io::Read;
+
+use alloy_rlp::{Decodable, Header};
+use eyre::{Result, eyre};
+use tracing::{debug, warn};
+
+/// Length of the header the SequencerInbox prepends to the posted data
+pub const SEQUENCER_HEADER_LEN: usize = 40;
+
+/// Header byte of brotli-compressed batch payloads
+pub const BROTLI_MESSAGE_HEADER_BYTE: u8 = 0x00;
+/// Header flag of payloads stored with a data availability committee
+pub const DAS_MESSAGE_HEADER_FLAG: u8 = 0x80;
+/// Header flag of payloads authenticated by L1 (such as blobs)
+pub const L1_AUTHENTICATED_MESSAGE_HEADER_FLAG: u8 = 0x40;
+/// Header flag of payloads posted as the versioned hashes of their blobs
+pub const BLOB_HASHES_HEADER_FLAG: u8 = L1_AUTHENTICATED_MESSAGE_HEADER_FLAG | 0x10;
+
+/// Maximum size of a decompressed batch payload
+pub const MAX_DECOMPRESSED_LEN: usize = 16 * 1024 * 1024;
+/// Maximum size of a single L2 message
+pub const MAX_L2_MESSAGE_SIZE: usize = 256 * 1024;
+/// Maximum number of segments read from a single batch
+pub const MAX_SEGMENTS_PER_SEQUENCER_MESSAGE: usize = 100 * 1024;
+
+/// Kind byte at the start of a batch segment
+pub mod segment_kind {
+    pub const L2_MESSAGE: u8 = 0;
+    pub const L2_MESSAGE_BROTLI: u8 = 1;
+    pub const DELAYED_MESSAGES: u8 = 2;
+    pub const ADVANCE_TIMESTAMP: u8 = 3;
+    pub const ADVANCE_L1_BLOCK_NUMBER: u8 = 4;
+}
+
+/// Bounds the SequencerInbox enforced on a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequencerMessageHeader {
+    pub min_timestamp: u64,
+    pub max_timestamp: u64,
+    pub min_l1_block: u64,
+    pub max_l1_block: u64,
+    pub after_delayed_messages: u64,
+}
+
+impl SequencerMessageHeader {
+    /// Serialize the header as the SequencerInbox does when hashing a batch
+    pub fn to_bytes(&self) -> [u8; SEQUENCER_HEADER_LEN] {
+        let mut out = [0u8; SEQUENCER_HEADER_LEN];
+        for (i, value) in [
+            self.min_timestamp,
+            self.max_timestamp,
+            self.min_l1_block,
+            self.max_l1_block,
+            self.after_delayed_messages,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            out[i * 8..(i + 1) * 8].copy_from_slice(&value.to_be_bytes());
+        }
+        out
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let word = |i: usize| {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&bytes[i * 8..(i + 1) * 8]);
+            u64::from_be_bytes(buf)
+        };
+        Self {
+            min_timestamp: word(0),
+            max_timestamp: word(1),
+            min_l1_block: word(2),
+            max_l1_block: word(3),
+            after_delayed_messages: word(4),
+        }
+    }
+}
+
+/// A sequencer batch split into its segments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequencerMessage {
+    pub header: SequencerMessageHeader,
+    pub segments: Vec<Vec<u8>>,
+}
+
+impl SequencerMessage {
+    /// Parse the SequencerInbox header followed by the posted batch data
+    ///
+    /// Payloads that fail to decompress or decode yield no segments, as in
+    /// Nitro; only payloads stored outside of L1 calldata are an error.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < SEQUENCER_HEADER_LEN {
+            return Err(eyre!(
+                "Sequencer message of {} bytes is shorter than its header",
+                data.len()
+            ));
+        }
+        let (header, payload) = data.split_at(SEQUENCER_HEADER_LEN);
+        let header = SequencerMessageHeader::from_bytes(header);
+
+        let segments = match payload.split_first() {
+            None => Vec::new(),
+            Some((&BROTLI_MESSAGE_HEADER_BYTE, compressed)) => {
+                match decompress(compressed, MAX_DECOMPRESSED_LEN) {
+                    Ok(decompressed) => parse_segments(&decompressed),
+                    Err(e) => {
+                        warn!("Sequencer message decompression failed: {}", e);
+                        Vec::new()
+                    }
+                }
+            }
+            Some((&flag, _))
+                if flag & (DAS_MESSAGE_HEADER_FLAG | L1_AUTHENTICATED_MESSAGE_HEADER_FLAG) != 0 =>
+            {
+                return Err(eyre!(
+                    "Batch data with header byte {flag:#04x} is not stored in L1 calldata"
+                ));
+            }
+            Some((&flag, _)) => {
+                warn!("Unknown sequencer message header byte {:#04x}", flag);
+                Vec::new()
+            }
+        };
+
+        Ok(Self { header, segments })
+    }
+}
+
+/// Content of a message in the batch stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchMessageContent {
+    /// L2 message posted by the sequencer
+    L2Message(Vec<u8>),
+    /// The next delayed inbox message, read from storage by index
+    Delayed { message_index: u64 },
+}
+
+/// A message of the L2 stream together with the batch it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchMessage {
+    /// `ArbitrumBatch::batch_number` of the batch carrying this message
+    pub batch_number: u64,
+    /// Position of the message within its batch
+    pub index: u64,
+    pub timestamp: u64,
+    pub l1_block_number: u64,
+    /// Delayed messages consumed once this message is applied
+    pub delayed_messages_read: u64,
+    pub content: BatchMessageContent,
+}
+
+/// Decode a batch into its ordered message stream
+///
+/// `data` is the SequencerInbox header followed by the posted data and
+/// `delayed_messages_read` the delayed message count before this batch.
+/// Delayed messages that the batch accounts for but does not read through a
+/// segment are appended at the end.
+pub fn decode_batch(
+    batch_number: u64,
+    data: &[u8],
+    delayed_messages_read: u64,
+) -> Result<Vec<BatchMessage>> {
+    let message = SequencerMessage::parse(data)?;
+    let header = message.header;
+    if delayed_messages_read > header.after_delayed_messages {
+        return Err(eyre!(
+            "Batch {} reads delayed messages up to {} but {} were already read",
+            batch_number,
+            header.after_delayed_messages,
+            delayed_messages_read
+        ));
+    }
+
+    let mut messages = Vec::new();
+    let mut delayed_read = delayed_messages_read;
+    // Both start at zero and are clamped to the header bounds when applied
+    let mut timestamp = 0u64;
+    let mut l1_block_number = 0u64;
+
+    let mut push = |content, timestamp: u64, l1_block_number: u64, delayed_read| {
+        messages.push(BatchMessage {
+            batch_number,
+            index: messages.len() as u64,
+            timestamp: timestamp.clamp(header.min_timestamp, header.max_timestamp),
+            l1_block_number: l1_block_number.clamp(header.min_l1_block, header.max_l1_block),
+            delayed_messages_read: delayed_read,
+            content,
+        });
+    };
+
+    for segment in &message.segments {
+        let Some((&kind, body)) = segment.split_first() else {
+            continue;
+        };
+        match kind {
+            segment_kind::ADVANCE_TIMESTAMP | segment_kind::ADVANCE_L1_BLOCK_NUMBER => {
+                let Ok(advance) = u64::decode(&mut &body[..]) else {
+                    warn!("Invalid advance segment in batch {}", batch_number);
+                    continue;
+                };
+                if kind == segment_kind::ADVANCE_TIMESTAMP {
+                    timestamp = timestamp.saturating_add(advance);
+                } else {
+                    l1_block_number = l1_block_number.saturating_add(advance);
+                }
+            }
+            segment_kind::L2_MESSAGE => {
+                push(
+                    BatchMessageContent::L2Message(body.to_vec()),
+                    timestamp,
+                    l1_block_number,
+                    delayed_read,
+                );
+            }
+            segment_kind::L2_MESSAGE_BROTLI => match decompress(body, MAX_L2_MESSAGE_SIZE) {
+                Ok(decompressed) => push(
+                    BatchMessageContent::L2Message(decompressed),
+                    timestamp,
+                    l1_block_number,
+                    delayed_read,
+                ),
+                Err(e) => debug!(
+                    "Dropping compressed message in batch {}: {}",
+                    batch_number, e
+                ),
+            },
+            segment_kind::DELAYED_MESSAGES => {
+                if delayed_read >= header.after_delayed_messages {
+                    warn!(
+                        "Batch {} reads past its delayed message count {}",
+                        batch_number, header.after_delayed_messages
+                    );
+                    continue;
+                }
+                delayed_read += 1;
+                push(
+                    BatchMessageContent::Delayed {
+                        message_index: delayed_read - 1,
+                    },
+                    timestamp,
+                    l1_block_number,
+                    delayed_read,
+                );
+            }
+            _ => warn!("Unknown segment kind {} in batch {}", kind, batch_number),
+        }
+    }
+
+    while delayed_read < header.after_delayed_messages {
+        delayed_read += 1;
+        push(
+            BatchMessageContent::Delayed {
+                message_index: delayed_read - 1,
+            },
+            timestamp,
+            l1_block_number,
+            delayed_read,
+        );
+    }
+
+    Ok(messages)
+}
+
+/// Count the segments of a batch that read a delayed message
+pub fn count_delayed_segments(data: &[u8]) -> Result<u64> {
+    let message = SequencerMessage::parse(data)?;
+    Ok(message
+        .segments
+        .iter()
+        .filter(|segment| segment.first() == Some(&segment_kind::DELAYED_MESSAGES))
+        .count() as u64)
+}
+
+/// L1 gas a batch poster spends on a sequencer message, as ArbOS charges it
+///
+/// Covers the calldata of the message, hashing it and the two storage
+/// writes of adding it and its posting report to the inbox.
+pub fn batch_gas_cost(sequencer_message: &[u8]) -> u64 {
+    const TX_DATA_ZERO_GAS: u64 = 4;
+    const TX_DATA_NON_ZERO_GAS: u64 = 16;
+    const KECCAK256_GAS: u64 = 30;
+    const KECCAK256_WORD_GAS: u64 = 6;
+    const SSTORE_SET_GAS: u64 = 20_000;
+
+    let data_gas: u64 = sequencer_message
+        .iter()
+        .map(|&byte| {
+            if byte == 0 {
+                TX_DATA_ZERO_GAS
+            } else {
+                TX_DATA_NON_ZERO_GAS
+            }
+        })
+        .sum();
+    let words = (sequencer_message.len() as u64).div_ceil(32);
+    data_gas + KECCAK256_GAS + words * KECCAK256_WORD_GAS + 2 * SSTORE_SET_GAS
+}
+
+/// Split a decompressed payload into its RLP-encoded segments
+fn parse_segments(mut payload: &[u8]) -> Vec<Vec<u8>> {
+    let mut segments = Vec::new();
+    while !payload.is_empty() {
+        if segments.len() >= MAX_SEGMENTS_PER_SEQUENCER_MESSAGE {
+            warn!("Too many segments in sequencer message");
+            break;
+        }
+        match Header::decode_bytes(&mut payload, false) {
+            Ok(segment) => segments.push(segment.to_vec()),
+            Err(e) => {
+                warn!("Error parsing sequencer message segment: {}", e);
+                break;
+            }
+        }
+    }
+    segments
+}
+
+/// Decompress brotli data, refusing outputs larger than `max_len`
+fn decompress(data: &[u8], max_len: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    brotli::Decompressor::new(data, 4096)
+        .take(max_len as u64 + 1)
+        .read_to_end(&mut out)?;
+    if out.len() > max_len {
+        return Err(eyre!("Decompressed data exceeds {max_len} bytes"));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use alloy_rlp::Encodable;
+
+    use super::*;
+
+    pub(crate) fn compress(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        brotli::BrotliCompress(
+            &mut &data[..],
+            &mut out,
+            &brotli::enc::BrotliEncoderParams::default(),
+        )
+        .unwrap();
+        out
+    }
+
+    fn advance(kind: u8, value: u64) -> Vec<u8> {
+        let mut segment = vec![kind];
+        value.encode(&mut segment);
+        segment
+    }
+
+    /// Build a SequencerInbox header followed by brotli-compressed segments
+    pub(crate) fn build_batch(header: SequencerMessageHeader, segments: &[Vec<u8>]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for segment in segments {
+            segment.as_slice().encode(&mut payload);
+        }
+        let mut data = header.to_bytes().to_vec();
+        data.push(BROTLI_MESSAGE_HEADER_BYTE);
+        data.extend(compress(&payload));
+        data
+    }
+
+    fn header() -> SequencerMessageHeader {
+        SequencerMessageHeader {
+            min_timestamp: 1_000,
+            max_timestamp: 2_000,
+            min_l1_block: 50,
+            max_l1_block: 60,
+            after_delayed_messages: 12,
+        }
+    }
+
+    #[test]
+    fn test_batch_gas_cost() {
+        // An empty message still pays for the hash and the inbox writes
+        assert_eq!(batch_gas_cost(&[]), 40_030);
+        // 40 zero header bytes in two words and 24 nonzero data bytes
+        let message = [[0u8; SEQUENCER_HEADER_LEN].as_slice(), &[0xff; 24]].concat();
+        assert_eq!(batch_gas_cost(&message), 40 * 4 + 24 * 16 + 40_030 + 2 * 6);
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let header = header();
+        assert_eq!(
+            SequencerMessageHeader::from_bytes(&header.to_bytes()),
+            header
+        );
+    }
+
+    #[test]
+    fn test_decode_batch_segments() {
+        let segments = vec![
+            advance(segment_kind::ADVANCE_TIMESTAMP, 1_500),
+            advance(segment_kind::ADVANCE_L1_BLOCK_NUMBER, 55),
+            vec![segment_kind::L2_MESSAGE, 4, 0xaa],
+            vec![segment_kind::DELAYED_MESSAGES],
+            advance(segment_kind::ADVANCE_TIMESTAMP, 1_000),
+            [
+                vec![segment_kind::L2_MESSAGE_BROTLI],
+                compress(&[4, 0xbb, 0xcc]),
+            ]
+            .concat(),
+            vec![],
+            vec![0x7f, 1, 2, 3],
+        ];
+        let data = build_batch(header(), &segments);
+        assert_eq!(SequencerMessage::parse(&data).unwrap().segments, segments);
+
+        let messages = decode_batch(7, &data, 10).unwrap();
+        let contents: Vec<_> = messages.iter().map(|m| m.content.clone()).collect();
+        assert_eq!(
+            contents,
+            vec![
+                BatchMessageContent::L2Message(vec![4, 0xaa]),
+                BatchMessageContent::Delayed { message_index: 10 },
+                BatchMessageContent::L2Message(vec![4, 0xbb, 0xcc]),
+                // Read implicitly up to the header's delayed message count
+                BatchMessageContent::Delayed { message_index: 11 },
+            ]
+        );
+        assert!(messages.iter().all(|m| m.batch_number == 7));
+        assert_eq!(
+            messages.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+        assert_eq!(messages[0].timestamp, 1_500);
+        assert_eq!(messages[0].l1_block_number, 55);
+        assert_eq!(messages[0].delayed_messages_read, 10);
+        assert_eq!(messages[1].delayed_messages_read, 11);
+        // Advances beyond the header bounds are clamped
+        assert_eq!(messages[2].timestamp, 2_000);
+        assert_eq!(messages[3].delayed_messages_read, 12);
+
+        assert_eq!(count_delayed_segments(&data).unwrap(), 1);
+        assert!(decode_batch(7, &data, 13).is_err());
+    }
+
+    #[test]
+    fn test_decode_batch_tolerates_bad_payloads() {
+        let mut data = header().to_bytes().to_vec();
+        assert!(decode_batch(1, &data[..39], 12).is_err());
+
+        // Header only: all accounted delayed messages are read
+        let messages = decode_batch(1, &data, 11).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].timestamp, 1_000);
+        assert_eq!(messages[0].l1_block_number, 50);
+
+        // Corrupt brotli data yields an empty batch
+        data.extend_from_slice(&[BROTLI_MESSAGE_HEADER_BYTE, 0xff, 0xff, 0xff]);
+        assert!(SequencerMessage::parse(&data).unwrap().segments.is_empty());
+
+        // Data held outside of L1 calldata cannot be decoded here
+        let mut blob = header().to_bytes().to_vec();
+        blob.push(0x50);
+        assert!(SequencerMessage::parse(&blob).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Packing of batch data into EIP-4844 blobs
+//!
+//! The data is RLP-encoded as a byte string, so its length survives the zero
+//! padding of the last blob, and spread over the low 31 bytes of every 32-byte
+//! field element. The high byte stays zero, which keeps each element below
+//! the BLS modulus. The batch poster packs batches this way and the tracker
+//! unpacks the blobs it reads back from L1.
+
+use alloy_eips::eip4844::{BYTES_PER_BLOB, Blob, FIELD_ELEMENT_BYTES_USIZE};
+use alloy_rlp::{Encodable, Header};
+use eyre::{Result, eyre};
+
+/// Data bytes carried by a field element
+const DATA_BYTES_PER_FIELD_ELEMENT: usize = FIELD_ELEMENT_BYTES_USIZE - 1;
+
+/// Data bytes carried by a blob
+pub const DATA_BYTES_PER_BLOB: usize =
+    BYTES_PER_BLOB / FIELD_ELEMENT_BYTES_USIZE * DATA_BYTES_PER_FIELD_ELEMENT;
+
+/// Number of blobs needed for `len` bytes of data
+pub fn blob_count(len: usize) -> usize {
+    let encoded_len = Header {
+        list: false,
+        payload_length: len,
+    }
+    .length()
+        + len;
+    encoded_len.div_ceil(DATA_BYTES_PER_BLOB)
+}
+
+/// Pack data into blobs
+pub fn encode_blobs(data: &[u8]) -> Vec<Blob> {
+    let mut encoded = Vec::with_capacity(data.len() + 9);
+    data.encode(&mut encoded);
+
+    encoded
+        .chunks(DATA_BYTES_PER_BLOB)
+        .map(|chunk| {
+            let mut blob = Blob::ZERO;
+            for (element, bytes) in chunk.chunks(DATA_BYTES_PER_FIELD_ELEMENT).enumerate() {
+                let start = element * FIELD_ELEMENT_BYTES_USIZE + 1;
+                blob[start..start + bytes.len()].copy_from_slice(bytes);
+            }
+            blob
+        })
+        .collect()
+}
+
+/// Unpack the data of [`encode_blobs`]
+pub fn decode_blobs(blobs: &[Blob]) -> Result<Vec<u8>> {
+    let mut encoded = Vec::with_capacity(blobs.len() * DATA_BYTES_PER_BLOB);
+    for blob in blobs {
+        for element in blob.chunks(FIELD_ELEMENT_BYTES_USIZE) {
+            if element[0] != 0 {
+                return Err(eyre!("Blob field element exceeds 31 bytes of data"));
+            }
+            encoded.extend_from_slice(&element[1..]);
+        }
+    }
+
+    let mut buf = encoded.as_slice();
+    let data = Header::decode_bytes(&mut buf, false)
+        .map_err(|e| eyre!("Invalid blob data: {e}"))?
+        .to_vec();
+    if buf.iter().any(|b| *b != 0) {
+        return Err(eyre!("Blob data is followed by non-zero padding"));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::keccak256;
+
+    use super::*;
+
+    #[test]
+    fn test_blobs_round_trip() {
+        let data: Vec<u8> = (0..DATA_BYTES_PER_BLOB as u64 + 1_000)
+            .map(|i| keccak256(i.to_be_bytes())[0])
+            .collect();
+        let blobs = encode_blobs(&data);
+        assert_eq!(blobs.len(), 2);
+        assert_eq!(blob_count(data.len()), 2);
+        assert!(blobs.iter().all(|blob| blob.chunks(32).all(|e| e[0] == 0)));
+        assert_eq!(decode_blobs(&blobs).unwrap(), data);
+
+        assert_eq!(blob_count(0), 1);
+        assert_eq!(decode_blobs(&encode_blobs(&[])).unwrap(), Vec::<u8>::new());
+
+        let mut corrupt = blobs;
+        corrupt[1][0] = 1;
+        assert!(decode_blobs(&corrupt).is_err());
+    }
+}
@@ -0,0 +1,509 @@
+//! ABI decoding of the Bridge, Inbox and SequencerInbox event logs
+
+use alloy_primitives::{Address, B256, U256};
+use alloy_sol_types::{SolCall, SolEvent, sol};
+use arbitrum_l1_client::L1Log;
+use arbitrum_storage::L1MessageKind;
+use eyre::{Result, eyre};
+
+sol! {
+    /// Emitted by the Bridge for every message appended to the delayed inbox
+    event MessageDelivered(
+        uint256 indexed messageIndex,
+        bytes32 indexed beforeInboxAcc,
+        address inbox,
+        uint8 kind,
+        address sender,
+        bytes32 messageDataHash,
+        uint256 baseFeeL1,
+        uint64 timestamp
+    );
+
+    /// Emitted by an inbox with the payload of a delayed message
+    event InboxMessageDelivered(uint256 indexed messageNum, bytes data);
+
+    /// Emitted by the Inbox when the payload is the calldata of the L1 transaction
+    event InboxMessageDeliveredFromOrigin(uint256 indexed messageNum);
+
+    struct TimeBounds {
+        uint64 minTimestamp;
+        uint64 maxTimestamp;
+        uint64 minBlockNumber;
+        uint64 maxBlockNumber;
+    }
+
+    /// Emitted by the SequencerInbox for every posted batch
+    event SequencerBatchDelivered(
+        uint256 indexed batchSequenceNumber,
+        bytes32 indexed beforeAcc,
+        bytes32 indexed afterAcc,
+        bytes32 delayedAcc,
+        uint256 afterDelayedMessagesRead,
+        TimeBounds timeBounds,
+        uint8 dataLocation
+    );
+
+    /// Emitted by the SequencerInbox when the batch data is stored in a separate event
+    event SequencerBatchData(uint256 indexed batchSequenceNumber, bytes data);
+
+    function sendL2MessageFromOrigin(bytes messageData);
+
+    function addSequencerL2BatchFromOrigin(
+        uint256 sequenceNumber,
+        bytes data,
+        uint256 afterDelayedMessagesRead,
+        address gasRefunder,
+        uint256 prevMessageCount,
+        uint256 newMessageCount
+    );
+
+    function addSequencerL2BatchFromBlobs(
+        uint256 sequenceNumber,
+        uint256 afterDelayedMessagesRead,
+        address gasRefunder,
+        uint256 prevMessageCount,
+        uint256 newMessageCount
+    );
+}
+
+/// Header of a delayed message as announced by `MessageDelivered`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelayedMessageInfo {
+    pub message_index: u64,
+    pub before_inbox_acc: B256,
+    pub inbox: Address,
+    pub kind: L1MessageKind,
+    pub sender: Address,
+    pub message_data_hash: B256,
+    pub l1_base_fee: U256,
+    pub timestamp: u64,
+}
+
+impl DelayedMessageInfo {
+    /// Request id assigned to the message on L2, the message index as bytes32
+    pub fn request_id(&self) -> B256 {
+        B256::from(U256::from(self.message_index))
+    }
+}
+
+/// Where the SequencerInbox put the data of a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchDataLocation {
+    TxInput,
+    SeparateBatchEvent,
+    NoData,
+    Blob,
+}
+
+impl TryFrom<u8> for BatchDataLocation {
+    type Error = eyre::Report;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Self::TxInput),
+            1 => Ok(Self::SeparateBatchEvent),
+            2 => Ok(Self::NoData),
+            3 => Ok(Self::Blob),
+            other => Err(eyre!("Unknown batch data location: {other}")),
+        }
+    }
+}
+
+/// Batch metadata announced by `SequencerBatchDelivered`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequencerBatchInfo {
+    pub sequence_number: u64,
+    pub before_acc: B256,
+    pub after_acc: B256,
+    pub delayed_acc: B256,
+    pub after_delayed_messages_read: u64,
+    pub min_timestamp: u64,
+    pub max_timestamp: u64,
+    pub min_block_number: u64,
+    pub max_block_number: u64,
+    pub data_location: BatchDataLocation,
+}
+
+/// A decoded inbox contract log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InboxLog {
+    /// Bridge announcement of a delayed message
+    MessageDelivered(DelayedMessageInfo),
+    /// Payload of a delayed message
+    MessageData { message_index: u64, data: Vec<u8> },
+    /// Payload of a delayed message is the calldata of the emitting transaction
+    MessageDataFromOrigin { message_index: u64 },
+    /// SequencerInbox announcement of a batch
+    BatchDelivered(SequencerBatchInfo),
+    /// Data of a batch posted with [`BatchDataLocation::SeparateBatchEvent`]
+    BatchData { sequence_number: u64, data: Vec<u8> },
+}
+
+/// Topics of every event understood by [`decode_inbox_log`]
+pub const INBOX_EVENT_SIGNATURES: [B256; 5] = [
+    MessageDelivered::SIGNATURE_HASH,
+    InboxMessageDelivered::SIGNATURE_HASH,
+    InboxMessageDeliveredFromOrigin::SIGNATURE_HASH,
+    SequencerBatchDelivered::SIGNATURE_HASH,
+    SequencerBatchData::SIGNATURE_HASH,
+];
+
+fn to_u64(value: U256, field: &str) -> Result<u64> {
+    u64::try_from(value).map_err(|_| eyre!("{field} does not fit in u64: {value}"))
+}
+
+/// Decode an inbox contract log; `None` if the log is not an inbox event
+pub fn decode_inbox_log(log: &L1Log) -> Result<Option<InboxLog>> {
+    let Some(signature) = log.topics.first() else {
+        return Ok(None);
+    };
+    let topics = log.topics.iter().copied();
+
+    let decoded = match *signature {
+        MessageDelivered::SIGNATURE_HASH => {
+            let event = MessageDelivered::decode_raw_log(topics, &log.data)?;
+            InboxLog::MessageDelivered(DelayedMessageInfo {
+                message_index: to_u64(event.messageIndex, "messageIndex")?,
+                before_inbox_acc: event.beforeInboxAcc,
+                inbox: event.inbox,
+                kind: L1MessageKind::from(event.kind),
+                sender: event.sender,
+                message_data_hash: event.messageDataHash,
+                l1_base_fee: event.baseFeeL1,
+                timestamp: event.timestamp,
+            })
+        }
+        InboxMessageDelivered::SIGNATURE_HASH => {
+            let event = InboxMessageDelivered::decode_raw_log(topics, &log.data)?;
+            InboxLog::MessageData {
+                message_index: to_u64(event.messageNum, "messageNum")?,
+                data: event.data.to_vec(),
+            }
+        }
+        InboxMessageDeliveredFromOrigin::SIGNATURE_HASH => {
+            let event = InboxMessageDeliveredFromOrigin::decode_raw_log(topics, &log.data)?;
+            InboxLog::MessageDataFromOrigin {
+                message_index: to_u64(event.messageNum, "messageNum")?,
+            }
+        }
+        SequencerBatchDelivered::SIGNATURE_HASH => {
+            let event = SequencerBatchDelivered::decode_raw_log(topics, &log.data)?;
+            InboxLog::BatchDelivered(SequencerBatchInfo {
+                sequence_number: to_u64(event.batchSequenceNumber, "batchSequenceNumber")?,
+                before_acc: event.beforeAcc,
+                after_acc: event.afterAcc,
+                delayed_acc: event.delayedAcc,
+                after_delayed_messages_read: to_u64(
+                    event.afterDelayedMessagesRead,
+                    "afterDelayedMessagesRead",
+                )?,
+                min_timestamp: event.timeBounds.minTimestamp,
+                max_timestamp: event.timeBounds.maxTimestamp,
+                min_block_number: event.timeBounds.minBlockNumber,
+                max_block_number: event.timeBounds.maxBlockNumber,
+                data_location: BatchDataLocation::try_from(event.dataLocation)?,
+            })
+        }
+        SequencerBatchData::SIGNATURE_HASH => {
+            let event = SequencerBatchData::decode_raw_log(topics, &log.data)?;
+            InboxLog::BatchData {
+                sequence_number: to_u64(event.batchSequenceNumber, "batchSequenceNumber")?,
+                data: event.data.to_vec(),
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(decoded))
+}
+
+/// Extract the message payload from a `sendL2MessageFromOrigin` transaction input
+pub fn decode_message_from_origin(input: &[u8]) -> Result<Vec<u8>> {
+    let call = sendL2MessageFromOriginCall::abi_decode(input)?;
+    Ok(call.messageData.to_vec())
+}
+
+/// Build the input of an `addSequencerL2BatchFromOrigin` transaction
+///
+/// No gas refunder is used. The message counts let the Bridge check that the
+/// batch follows on from the previous one.
+pub fn encode_batch_from_origin(
+    sequence_number: u64,
+    data: &[u8],
+    after_delayed_messages_read: u64,
+    prev_message_count: u64,
+    new_message_count: u64,
+) -> Vec<u8> {
+    addSequencerL2BatchFromOriginCall {
+        sequenceNumber: U256::from(sequence_number),
+        data: data.to_vec().into(),
+        afterDelayedMessagesRead: U256::from(after_delayed_messages_read),
+        gasRefunder: Address::ZERO,
+        prevMessageCount: U256::from(prev_message_count),
+        newMessageCount: U256::from(new_message_count),
+    }
+    .abi_encode()
+}
+
+/// Encode an `addSequencerL2BatchFromBlobs` call, whose data is in the blobs of
+/// the transaction
+pub fn encode_batch_from_blobs(
+    sequence_number: u64,
+    after_delayed_messages_read: u64,
+    prev_message_count: u64,
+    new_message_count: u64,
+) -> Vec<u8> {
+    addSequencerL2BatchFromBlobsCall {
+        sequenceNumber: U256::from(sequence_number),
+        afterDelayedMessagesRead: U256::from(after_delayed_messages_read),
+        gasRefunder: Address::ZERO,
+        prevMessageCount: U256::from(prev_message_count),
+        newMessageCount: U256::from(new_message_count),
+    }
+    .abi_encode()
+}
+
+/// Message counts before and after the batch, as declared by the input of
+/// an `addSequencerL2BatchFromOrigin` or `addSequencerL2BatchFromBlobs` call
+pub fn decode_message_counts(input: &[u8]) -> Result<(u64, u64)> {
+    let (prev_message_count, new_message_count) =
+        match addSequencerL2BatchFromOriginCall::abi_decode(input) {
+            Ok(call) => (call.prevMessageCount, call.newMessageCount),
+            Err(_) => {
+                let call = addSequencerL2BatchFromBlobsCall::abi_decode(input)?;
+                (call.prevMessageCount, call.newMessageCount)
+            }
+        };
+    Ok((
+        prev_message_count.saturating_to(),
+        new_message_count.saturating_to(),
+    ))
+}
+
+/// Extract the batch data from an `addSequencerL2BatchFromOrigin` transaction input
+pub fn decode_batch_from_origin(input: &[u8], sequence_number: u64) -> Result<Vec<u8>> {
+    let call = addSequencerL2BatchFromOriginCall::abi_decode(input)?;
+    if call.sequenceNumber != U256::from(sequence_number) {
+        return Err(eyre!(
+            "Transaction posts batch {} instead of {}",
+            call.sequenceNumber,
+            sequence_number
+        ));
+    }
+    Ok(call.data.to_vec())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use alloy_primitives::{address, b256, hex, keccak256};
+    use arbitrum_l1_client::L1Transaction;
+    use serde_json::Value;
+
+    use super::*;
+
+    fn hex_u64(value: &Value) -> u64 {
+        u64::from_str_radix(value.as_str().unwrap().trim_start_matches("0x"), 16).unwrap()
+    }
+
+    fn hex_bytes(value: &Value) -> Vec<u8> {
+        hex::decode(value.as_str().unwrap()).unwrap()
+    }
+
+    /// Load a recorded fixture of `eth_getLogs` / `eth_getTransactionByHash` results
+    pub(crate) fn load_fixture(json: &str) -> (Vec<L1Log>, Vec<L1Transaction>) {
+        let fixture: Value = serde_json::from_str(json).unwrap();
+        let logs = fixture["logs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|log| L1Log {
+                address: log["address"].as_str().unwrap().parse().unwrap(),
+                topics: log["topics"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|t| t.as_str().unwrap().parse().unwrap())
+                    .collect(),
+                data: hex_bytes(&log["data"]),
+                block_number: hex_u64(&log["blockNumber"]),
+                block_hash: log["blockHash"].as_str().unwrap().parse().unwrap(),
+                transaction_hash: log["transactionHash"].as_str().unwrap().parse().unwrap(),
+                transaction_index: hex_u64(&log["transactionIndex"]),
+                log_index: hex_u64(&log["logIndex"]),
+                removed: log["removed"].as_bool().unwrap(),
+            })
+            .collect();
+        let transactions = fixture["transactions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tx| L1Transaction {
+                hash: tx["hash"].as_str().unwrap().parse().unwrap(),
+                from: tx["from"].as_str().unwrap().parse().unwrap(),
+                to: tx["to"].as_str().map(|to| to.parse().unwrap()),
+                nonce: hex_u64(&tx["nonce"]),
+                input: hex_bytes(&tx["input"]),
+                block_number: Some(hex_u64(&tx["blockNumber"])),
+                blob_versioned_hashes: vec![],
+            })
+            .collect();
+        (logs, transactions)
+    }
+
+    pub(crate) const DEPOSIT_FIXTURE: &str = include_str!("../testdata/deposit.json");
+    pub(crate) const ORIGIN_FIXTURE: &str = include_str!("../testdata/origin.json");
+    pub(crate) const BATCH_FIXTURE: &str = include_str!("../testdata/batch.json");
+
+    #[test]
+    fn test_event_signatures() {
+        assert_eq!(
+            MessageDelivered::SIGNATURE_HASH,
+            b256!("0x5e3c1311ea442664e8b1611bfabef659120ea7a0a2cfc0667700bebc69cbffe1")
+        );
+        assert_eq!(
+            InboxMessageDelivered::SIGNATURE_HASH,
+            b256!("0xff64905f73a67fb594e0f940a8075a860db489ad991e032f48c81123eb52d60b")
+        );
+        assert_eq!(
+            InboxMessageDeliveredFromOrigin::SIGNATURE_HASH,
+            b256!("0xab532385be8f1005a4b6ba8fa20a2245facb346134ac739fe9a5198dc1580b9c")
+        );
+        assert_eq!(
+            SequencerBatchDelivered::SIGNATURE_HASH,
+            b256!("0x7394f4a19a13c7b92b5bb71033245305946ef78452f7b4986ac1390b5df4ebd7")
+        );
+    }
+
+    #[test]
+    fn test_decode_eth_deposit_fixture() {
+        let (logs, _) = load_fixture(DEPOSIT_FIXTURE);
+
+        let Some(InboxLog::MessageDelivered(info)) = decode_inbox_log(&logs[0]).unwrap() else {
+            panic!("expected MessageDelivered");
+        };
+        assert_eq!(info.message_index, 1_412_305);
+        assert_eq!(info.kind, L1MessageKind::EthDeposit);
+        assert_eq!(
+            info.inbox,
+            address!("0x4Dbd4fc535Ac27206064B68FfCf827b0A60BAB3f")
+        );
+        assert_eq!(
+            info.sender,
+            address!("0x3fC91A3afd70395Cd496C647d5a6CC9D4B2b7FAD")
+        );
+        assert_eq!(info.l1_base_fee, U256::from(23_417_925_113u64));
+        assert_eq!(info.timestamp, 1_700_574_539);
+        assert_eq!(
+            info.request_id(),
+            b256!("0x0000000000000000000000000000000000000000000000000000000000158cd1")
+        );
+
+        let Some(InboxLog::MessageData {
+            message_index,
+            data,
+        }) = decode_inbox_log(&logs[1]).unwrap()
+        else {
+            panic!("expected InboxMessageDelivered");
+        };
+        assert_eq!(message_index, info.message_index);
+        assert_eq!(keccak256(&data), info.message_data_hash);
+        // Deposit payload: destination address followed by the amount
+        assert_eq!(&data[..20], info.sender.as_slice());
+        assert_eq!(
+            U256::from_be_slice(&data[20..]),
+            U256::from(250_000_000_000_000_000u64)
+        );
+    }
+
+    #[test]
+    fn test_decode_message_from_origin_fixture() {
+        let (logs, transactions) = load_fixture(ORIGIN_FIXTURE);
+
+        let Some(InboxLog::MessageDelivered(info)) = decode_inbox_log(&logs[0]).unwrap() else {
+            panic!("expected MessageDelivered");
+        };
+        assert_eq!(info.kind, L1MessageKind::L2Message);
+
+        assert_eq!(
+            decode_inbox_log(&logs[1]).unwrap(),
+            Some(InboxLog::MessageDataFromOrigin {
+                message_index: info.message_index
+            })
+        );
+
+        let data = decode_message_from_origin(&transactions[0].input).unwrap();
+        assert_eq!(keccak256(&data), info.message_data_hash);
+    }
+
+    #[test]
+    fn test_decode_sequencer_batch_fixture() {
+        let (logs, transactions) = load_fixture(BATCH_FIXTURE);
+
+        let Some(InboxLog::MessageDelivered(report)) = decode_inbox_log(&logs[0]).unwrap() else {
+            panic!("expected MessageDelivered");
+        };
+        assert_eq!(report.kind, L1MessageKind::BatchPostingReport);
+        assert_eq!(
+            report.inbox,
+            address!("0x1c479675ad559DC151F6Ec7ed3FbF8ceE79582B6")
+        );
+
+        let Some(InboxLog::BatchDelivered(batch)) = decode_inbox_log(&logs[2]).unwrap() else {
+            panic!("expected SequencerBatchDelivered");
+        };
+        assert_eq!(batch.sequence_number, 563_218);
+        assert_eq!(
+            batch.before_acc,
+            b256!("0x71fe8379384bfc5a5269290e718672ef3ffa539f0a663c61c2430b29b7a9c3c1")
+        );
+        assert_eq!(
+            batch.after_acc,
+            b256!("0xda5675ca7877a24fcce3ecb12698518eb63e2179282e5866fc04dce43993700d")
+        );
+        assert_eq!(
+            batch.delayed_acc,
+            b256!("0xddb61564dc93d3940cf024246cea49ece8549438ed70273ae05bbf821962b585")
+        );
+        assert_eq!(batch.after_delayed_messages_read, report.message_index + 1);
+        assert_eq!(batch.min_timestamp, 1_700_488_163);
+        assert_eq!(batch.max_timestamp, 1_700_578_163);
+        assert_eq!(batch.min_block_number, 18_643_912);
+        assert_eq!(batch.max_block_number, 18_650_414);
+        assert_eq!(batch.data_location, BatchDataLocation::TxInput);
+
+        let input = &transactions[0].input;
+        let data = decode_batch_from_origin(input, batch.sequence_number).unwrap();
+        assert_eq!(
+            &encode_batch_from_origin(
+                batch.sequence_number,
+                &data,
+                batch.after_delayed_messages_read,
+                150_000_000,
+                150_000_003
+            ),
+            input
+        );
+        assert!(decode_batch_from_origin(input, batch.sequence_number + 1).is_err());
+        assert_eq!(
+            decode_message_counts(input).unwrap(),
+            (150_000_000, 150_000_003)
+        );
+        assert_eq!(
+            decode_message_counts(&encode_batch_from_blobs(7, 3, 20, 25)).unwrap(),
+            (20, 25)
+        );
+        assert!(decode_message_counts(&[0; 4]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_and_malformed_logs() {
+        let (mut logs, _) = load_fixture(DEPOSIT_FIXTURE);
+
+        let mut unknown = logs[0].clone();
+        unknown.topics[0] = B256::ZERO;
+        assert_eq!(decode_inbox_log(&unknown).unwrap(), None);
+
+        logs[0].data.truncate(64);
+        assert!(decode_inbox_log(&logs[0]).is_err());
+    }
+}
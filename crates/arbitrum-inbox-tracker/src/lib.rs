@@ -1,36 +1,78 @@
 #![allow(dead_code)]
 
-use std::{collections::VecDeque, sync::Arc, time::Duration};
-
-use alloy_primitives::{Address, B256};
+pub mod batch;
+pub mod blobs;
+pub mod events;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use alloy_primitives::{Address, B256, keccak256};
+use alloy_sol_types::SolEvent;
 use arbitrum_config::ArbitrumRethConfig;
-use arbitrum_storage::{ArbitrumStorage, L1Message};
-use eyre::Result;
+use arbitrum_l1_client::{JsonRpcL1Client, L1BlockHeader, L1Client, L1Transaction, LogFilter};
+use arbitrum_storage::{ArbitrumBatch, ArbitrumStorage, L1Message, SyncStatus};
+use eyre::{Result, eyre};
 use tokio::{sync::RwLock, time::interval};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    batch::{BLOB_HASHES_HEADER_FLAG, count_delayed_segments, decode_batch},
+    blobs::decode_blobs,
+    events::{
+        INBOX_EVENT_SIGNATURES, InboxMessageDelivered, InboxMessageDeliveredFromOrigin,
+        decode_batch_from_origin, decode_inbox_log, decode_message_counts,
+        decode_message_from_origin,
+    },
+};
+pub use crate::{
+    batch::{BatchMessage, BatchMessageContent, SequencerMessageHeader, batch_gas_cost},
+    events::{BatchDataLocation, DelayedMessageInfo, InboxLog, SequencerBatchInfo},
+};
+
+/// Maximum number of L1 blocks covered by a single `eth_getLogs` query
+const MAX_LOG_RANGE: u64 = 1000;
+
+/// Number of processed L1 block hashes kept for reorg detection
+const MAX_TRACKED_L1_BLOCKS: usize = 256;
 
 /// Inbox tracker responsible for monitoring L1 for new messages and batches
 pub struct InboxTracker {
     config: ArbitrumRethConfig,
     storage: Arc<ArbitrumStorage>,
     is_running: Arc<RwLock<bool>>,
-    last_processed_l1_block: Arc<RwLock<u64>>,
+    sync_status: Arc<RwLock<SyncStatus>>,
     pending_messages: Arc<RwLock<VecDeque<L1Message>>>,
-    // TODO: Add L1 client for monitoring
-    // l1_client: Arc<dyn L1Client>,
+    batch_messages: Arc<RwLock<VecDeque<BatchMessage>>>,
+    l1_client: Arc<dyn L1Client>,
 }
 
 impl InboxTracker {
     /// Create a new inbox tracker
     pub async fn new(config: &ArbitrumRethConfig, storage: Arc<ArbitrumStorage>) -> Result<Self> {
+        let l1_client = Arc::new(JsonRpcL1Client::new(&config.l1)?);
+        Self::with_l1_client(config, storage, l1_client).await
+    }
+
+    /// Create a new inbox tracker reading L1 through the given client
+    pub async fn with_l1_client(
+        config: &ArbitrumRethConfig,
+        storage: Arc<ArbitrumStorage>,
+        l1_client: Arc<dyn L1Client>,
+    ) -> Result<Self> {
         info!("Initializing inbox tracker");
 
         Ok(Self {
             config: config.clone(),
             storage,
+            l1_client,
             is_running: Arc::new(RwLock::new(false)),
-            last_processed_l1_block: Arc::new(RwLock::new(0)),
+            sync_status: Arc::new(RwLock::new(SyncStatus::default())),
             pending_messages: Arc::new(RwLock::new(VecDeque::new())),
+            batch_messages: Arc::new(RwLock::new(VecDeque::new())),
         })
     }
 
@@ -80,12 +122,26 @@ impl InboxTracker {
     }
 
     /// Initialize the last processed L1 block number
+    ///
+    /// Resumes from the checkpoint in storage when present, otherwise starts
+    /// from the configured start block.
     async fn initialize_last_processed_block(&self) -> Result<()> {
-        // TODO: Load from storage or use config default
-        let start_block = self.config.l1.start_block;
+        let status = match self.storage.get_sync_status().await? {
+            Some(status) => {
+                info!(
+                    "Resuming inbox tracker from L1 block {} (delayed message {:?}, batch {:?})",
+                    status.l1_block_number, status.last_delayed_message, status.last_batch
+                );
+                status
+            }
+            None => SyncStatus {
+                l1_block_number: self.config.l1.start_block,
+                ..Default::default()
+            },
+        };
 
-        let mut last_processed = self.last_processed_l1_block.write().await;
-        *last_processed = start_block;
+        let start_block = status.l1_block_number;
+        *self.sync_status.write().await = status;
 
         info!("Initialized last processed L1 block: {}", start_block);
         Ok(())
@@ -93,7 +149,7 @@ impl InboxTracker {
 
     /// Main L1 monitoring loop
     async fn l1_monitoring_loop(&self) {
-        let mut interval = interval(Duration::from_secs(5)); // Check L1 every 5 seconds
+        let mut interval = interval(Duration::from_millis(self.config.l1.poll_interval.max(1)));
 
         loop {
             interval.tick().await;
@@ -130,51 +186,166 @@ impl InboxTracker {
     }
 
     /// Process new L1 blocks for inbox events
+    ///
+    /// Only blocks at least `confirmation_blocks` deep are processed, and at
+    /// most [`MAX_LOG_RANGE`] of them per call so that a tracker far behind
+    /// the L1 head catches up over several polls. If the next block does not
+    /// extend the chain processed so far, the orphaned L1 blocks are rolled
+    /// back instead.
     async fn process_new_l1_blocks(&self) -> Result<()> {
-        // TODO: Get latest L1 block number from L1 client
         let latest_l1_block = self.get_latest_l1_block().await?;
-        let last_processed = *self.last_processed_l1_block.read().await;
+        let confirmed_block = latest_l1_block.saturating_sub(self.config.l1.confirmation_blocks);
+        let last_processed = self.sync_status.read().await.l1_block_number;
 
-        if latest_l1_block <= last_processed {
+        if confirmed_block <= last_processed {
             return Ok(());
         }
 
-        debug!(
-            "Processing L1 blocks {}-{}",
-            last_processed + 1,
-            latest_l1_block
-        );
+        let from_block = last_processed + 1;
+        let to_block = confirmed_block.min(last_processed + MAX_LOG_RANGE);
 
-        // Process each new block
-        for block_number in (last_processed + 1)..=latest_l1_block {
-            self.process_l1_block(block_number).await?;
+        // Make sure the range builds on the last block we processed
+        let first = self.get_l1_header(from_block).await?;
+        if let Some(fork_block) = self.detect_reorg(&first).await? {
+            return self.rollback_to_l1_block(fork_block).await;
         }
+        let last = self.get_l1_header(to_block).await?;
+        debug!("Processing L1 blocks {}-{}", from_block, to_block);
 
-        // Update last processed block
-        {
-            let mut last_processed = self.last_processed_l1_block.write().await;
-            *last_processed = latest_l1_block;
+        let mut processed_hashes = Vec::new();
+        for block in self.get_l1_blocks(from_block, to_block).await? {
+            processed_hashes.push((block.number, block.hash));
+            self.process_l1_block(block).await?;
+        }
+        if processed_hashes.last().is_none_or(|(n, _)| *n != to_block) {
+            processed_hashes.push((to_block, last.hash));
         }
 
+        // Update last processed block and checkpoint the progress
+        let status = {
+            let mut status = self.sync_status.write().await;
+            status.l1_block_number = to_block;
+            status.l1_block_hashes.extend(processed_hashes);
+            let excess = status
+                .l1_block_hashes
+                .len()
+                .saturating_sub(MAX_TRACKED_L1_BLOCKS);
+            status.l1_block_hashes.drain(..excess);
+            status.clone()
+        };
+        self.storage.store_sync_status(&status).await?;
+
+        Ok(())
+    }
+
+    /// Check whether `header` extends the last processed L1 block
+    ///
+    /// Returns the newest tracked L1 block that is still canonical when it
+    /// does not.
+    async fn detect_reorg(&self, header: &L1BlockHeader) -> Result<Option<u64>> {
+        let hashes = self.sync_status.read().await.l1_block_hashes.clone();
+        match hashes.last() {
+            Some((number, hash)) if *number + 1 == header.number => {
+                if header.parent_hash == *hash {
+                    return Ok(None);
+                }
+            }
+            // Nothing recorded for the parent (fresh start or legacy checkpoint)
+            _ => return Ok(None),
+        }
+
+        warn!(
+            "L1 reorg detected: block {} does not extend processed block {}",
+            header.number,
+            header.number - 1
+        );
+        for (number, hash) in hashes.iter().rev() {
+            if let Some(canonical) = self.l1_client.block_by_number(*number).await?
+                && canonical.hash == *hash
+            {
+                return Ok(Some(*number));
+            }
+        }
+
+        Err(eyre!(
+            "L1 reorg is deeper than the {} tracked L1 blocks",
+            hashes.len()
+        ))
+    }
+
+    /// Roll back everything derived from L1 blocks above `fork_block`
+    ///
+    /// Delayed messages and batches recorded from orphaned blocks are
+    /// deleted, L2 blocks derived from them are unwound and the tracker
+    /// resumes from `fork_block`.
+    async fn rollback_to_l1_block(&self, fork_block: u64) -> Result<()> {
+        let last_delayed_message = self.storage.rollback_l1_messages_after(fork_block).await?;
+        let last_batch = self.storage.rollback_batches_after(fork_block).await?;
+        let removed_logs = self
+            .storage
+            .unwind_blocks_after_l1_block(fork_block)
+            .await?;
+
+        self.pending_messages
+            .write()
+            .await
+            .retain(|message| message.block_number <= fork_block);
+        self.batch_messages
+            .write()
+            .await
+            .retain(|message| last_batch.is_some_and(|last| message.batch_number <= last));
+
+        let status = {
+            let mut status = self.sync_status.write().await;
+            status.l1_block_number = fork_block;
+            status.last_delayed_message = last_delayed_message;
+            status.last_batch = last_batch;
+            status.l1_block_hashes.retain(|(n, _)| *n <= fork_block);
+            status.clone()
+        };
+        self.storage.store_sync_status(&status).await?;
+
+        info!(
+            "Rolled back inbox to L1 block {} (delayed message {:?}, batch {:?}, {} L2 logs removed)",
+            fork_block,
+            last_delayed_message,
+            last_batch,
+            removed_logs.len()
+        );
         Ok(())
     }
 
     /// Process a single L1 block for inbox events
-    async fn process_l1_block(&self, block_number: u64) -> Result<()> {
-        debug!("Processing L1 block: {}", block_number);
+    async fn process_l1_block(&self, block: L1Block) -> Result<()> {
+        debug!("Processing L1 block: {}", block.number);
 
-        // TODO: Get block data from L1 client
-        let block = self.get_l1_block(block_number).await?;
+        // Batches go first, so that the gas of a batch is known before the
+        // posting report delivered with it can be sequenced
+        let mut events = block.events;
+        events.sort_by_key(|event| !matches!(event.event_type, L1EventType::BatchSubmitted(_)));
 
         // Look for inbox-related events
-        for event in block.events {
+        for event in events {
             match event.event_type {
-                L1EventType::MessageSent => {
+                L1EventType::MessageSent(_) => {
                     let message = self.parse_message_sent_event(&event).await?;
+                    let last = self.sync_status.read().await.last_delayed_message;
+                    if last.is_some_and(|last| message.message_number <= last) {
+                        debug!("Skipping known delayed message {}", message.message_number);
+                        continue;
+                    }
+                    let message_number = message.message_number;
                     self.add_pending_message(message).await?;
+                    self.sync_status.write().await.last_delayed_message = Some(message_number);
                 }
-                L1EventType::BatchSubmitted => {
+                L1EventType::BatchSubmitted(_) => {
+                    let last = self.sync_status.read().await.last_batch;
+                    if last.is_some_and(|last| event.message_number <= last) {
+                        debug!("Skipping known batch {}", event.message_number);
+                        continue;
+                    }
                     self.handle_batch_submitted_event(&event).await?;
+                    self.sync_status.write().await.last_batch = Some(event.message_number);
                 }
                 L1EventType::StateUpdated => {
                     self.handle_state_updated_event(&event).await?;
@@ -190,31 +361,196 @@ impl InboxTracker {
 
     /// Parse a MessageSent event into an L1Message
     async fn parse_message_sent_event(&self, event: &L1Event) -> Result<L1Message> {
-        // TODO: Parse the actual event data
-        // This is a simplified implementation
+        let L1EventType::MessageSent(info) = &event.event_type else {
+            return Err(eyre!("Not a MessageSent event: {:?}", event.event_type));
+        };
 
         Ok(L1Message {
-            message_number: event.message_number,
-            sender: event.sender,
+            message_number: info.message_index,
+            kind: info.kind,
+            sender: info.sender,
             data: event.data.clone(),
-            timestamp: event.timestamp,
+            timestamp: info.timestamp,
             block_number: event.block_number,
+            request_id: info.request_id(),
+            l1_base_fee: info.l1_base_fee,
+            batch_gas_cost: None,
         })
     }
 
     /// Handle a BatchSubmitted event
+    ///
+    /// Records the batch and queues its decoded messages, unless we posted
+    /// it ourselves. Fails while the batch data cannot be read, so the tracker retries the batch rather
+    /// than moving past it.
     async fn handle_batch_submitted_event(&self, event: &L1Event) -> Result<()> {
-        debug!("Handling BatchSubmitted event: {:?}", event);
+        let L1EventType::BatchSubmitted(info) = &event.event_type else {
+            return Err(eyre!("Not a BatchSubmitted event: {:?}", event.event_type));
+        };
+        debug!("Handling batch {}", info.sequence_number);
+
+        let mut transaction = None; // The posting transaction, if the data is in it
+        let mut blob_hashes = Vec::new();
+        let data = match info.data_location {
+            BatchDataLocation::TxInput => {
+                let transaction = transaction.insert(self.batch_transaction(event).await?);
+                decode_batch_from_origin(&transaction.input, info.sequence_number)?
+            }
+            BatchDataLocation::SeparateBatchEvent => event.data.clone(),
+            BatchDataLocation::NoData => Vec::new(),
+            BatchDataLocation::Blob => {
+                let transaction = transaction.insert(self.batch_transaction(event).await?);
+                if transaction.blob_versioned_hashes.is_empty() {
+                    return Err(eyre!(
+                        "L1 transaction {} of blob batch {} carries no blobs",
+                        event.transaction_hash,
+                        info.sequence_number
+                    ));
+                }
+                let blobs = self
+                    .l1_client
+                    .blobs(event.timestamp, &transaction.blob_versioned_hashes)
+                    .await?;
+                blob_hashes.clone_from(&transaction.blob_versioned_hashes);
+                decode_blobs(&blobs)?
+            }
+        };
 
-        // TODO: Process batch submission
-        // This would involve:
-        // 1. Validating the batch
-        // 2. Updating local state
-        // 3. Triggering any necessary actions
+        let header = SequencerMessageHeader {
+            min_timestamp: info.min_timestamp,
+            max_timestamp: info.max_timestamp,
+            min_l1_block: info.min_block_number,
+            max_l1_block: info.max_block_number,
+            after_delayed_messages: info.after_delayed_messages_read,
+        };
+        let mut sequencer_message = header.to_bytes().to_vec();
+        sequencer_message.extend_from_slice(&data);
+
+        // The SequencerInbox hashes blob batches, and ArbOS charges for them,
+        // as the versioned hashes of their blobs
+        let data_gas = if info.data_location == BatchDataLocation::Blob {
+            let mut posted = header.to_bytes().to_vec();
+            posted.push(BLOB_HASHES_HEADER_FLAG);
+            for hash in &blob_hashes {
+                posted.extend_from_slice(hash.as_slice());
+            }
+            let data_gas = batch_gas_cost(&posted);
+            self.storage
+                .store_batch_gas(keccak256(&posted), data_gas)
+                .await?;
+            data_gas
+        } else {
+            let data_gas = batch_gas_cost(&sequencer_message);
+            self.storage
+                .store_batch_gas(keccak256(&sequencer_message), data_gas)
+                .await?;
+            data_gas
+        };
+
+        let delayed_messages_read = self
+            .delayed_messages_read_before(info, &sequencer_message)
+            .await?;
+        let messages = decode_batch(
+            info.sequence_number,
+            &sequencer_message,
+            delayed_messages_read,
+        )?;
+        debug!(
+            "Batch {} carries {} messages",
+            info.sequence_number,
+            messages.len()
+        );
+        let message_count = self
+            .message_count_before(info, transaction.as_ref())
+            .await?;
+        let message_counts = (message_count, message_count + messages.len() as u64);
+
+        // Batches we posted ourselves are already recorded with their L2
+        // blocks, whose messages are not executed again, but only L1 tells
+        // what their data cost
+        match self.storage.get_batch(info.sequence_number).await? {
+            Some(batch) => {
+                if batch.data_gas != data_gas {
+                    self.storage
+                        .store_batch(&ArbitrumBatch { data_gas, ..batch })
+                        .await?;
+                }
+                return Ok(());
+            }
+            None => {
+                self.storage
+                    .store_batch(&ArbitrumBatch {
+                        batch_number: info.sequence_number,
+                        block_range: (0, 0), // Widened as its messages are executed
+                        l1_block_number: event.block_number,
+                        timestamp: event.timestamp,
+                        transactions: vec![],
+                        l1_tx_hash: Some(event.transaction_hash),
+                        after_delayed_messages_read: info.after_delayed_messages_read,
+                        message_counts,
+                        data_gas,
+                    })
+                    .await?;
+            }
+        }
+        // Queued last, so the batch of every message is found in storage
+        self.batch_messages.write().await.extend(messages);
 
         Ok(())
     }
 
+    /// The L1 transaction that posted the batch of `event`
+    async fn batch_transaction(&self, event: &L1Event) -> Result<L1Transaction> {
+        self.l1_client
+            .transaction_by_hash(event.transaction_hash)
+            .await?
+            .ok_or_else(|| eyre!("L1 transaction {} not found", event.transaction_hash))
+    }
+
+    /// Messages of the L2 stream before the given batch
+    ///
+    /// Taken from the previous batch when it is known. Otherwise the count is
+    /// the one the poster declared to the SequencerInbox, if the batch was
+    /// posted by a call carrying it.
+    async fn message_count_before(
+        &self,
+        info: &SequencerBatchInfo,
+        transaction: Option<&L1Transaction>,
+    ) -> Result<u64> {
+        if info.sequence_number == 0 {
+            return Ok(0);
+        }
+        if let Some(previous) = self.storage.get_batch(info.sequence_number - 1).await? {
+            return Ok(previous.message_counts.1);
+        }
+
+        Ok(transaction
+            .and_then(|transaction| decode_message_counts(&transaction.input).ok())
+            .map_or(0, |(prev_message_count, _)| prev_message_count))
+    }
+
+    /// Delayed messages read before the given batch
+    ///
+    /// Taken from the previous batch when it is known. Otherwise the tracker
+    /// started mid-chain and the count is derived from the batch itself,
+    /// assuming it reads no delayed messages beyond its explicit segments.
+    async fn delayed_messages_read_before(
+        &self,
+        info: &SequencerBatchInfo,
+        sequencer_message: &[u8],
+    ) -> Result<u64> {
+        if info.sequence_number == 0 {
+            return Ok(0);
+        }
+        if let Some(previous) = self.storage.get_batch(info.sequence_number - 1).await? {
+            return Ok(previous.after_delayed_messages_read);
+        }
+
+        Ok(info
+            .after_delayed_messages_read
+            .saturating_sub(count_delayed_segments(sequencer_message)?))
+    }
+
     /// Handle a StateUpdated event
     async fn handle_state_updated_event(&self, event: &L1Event) -> Result<()> {
         debug!("Handling StateUpdated event: {:?}", event);
@@ -273,24 +609,174 @@ impl InboxTracker {
 
     /// Get the latest L1 block number
     async fn get_latest_l1_block(&self) -> Result<u64> {
-        // TODO: Get from actual L1 client
-        Ok(1000) // Dummy value
-    }
-
-    /// Get L1 block data
-    async fn get_l1_block(&self, block_number: u64) -> Result<L1Block> {
-        // TODO: Get from actual L1 client
-        Ok(L1Block {
-            number: block_number,
-            hash: B256::ZERO,
-            timestamp: chrono::Utc::now().timestamp() as u64,
-            events: vec![], // Dummy empty events
-        })
+        self.l1_client.block_number().await
+    }
+
+    /// Get an L1 block header that must exist
+    async fn get_l1_header(&self, number: u64) -> Result<L1BlockHeader> {
+        self.l1_client
+            .block_by_number(number)
+            .await?
+            .ok_or_else(|| eyre!("L1 block {} not found", number))
+    }
+
+    /// Addresses of the L1 contracts whose events feed the inbox
+    fn inbox_contracts(&self) -> [Address; 3] {
+        [
+            self.config.l1.bridge_address,
+            self.config.l1.inbox_address,
+            self.config.l1.sequencer_inbox_address,
+        ]
+    }
+
+    /// Get the L1 blocks in the inclusive range that contain inbox events
+    async fn get_l1_blocks(&self, from_block: u64, to_block: u64) -> Result<Vec<L1Block>> {
+        let filter = LogFilter::new(from_block, to_block)
+            .with_addresses(self.inbox_contracts())
+            .with_event_signatures(INBOX_EVENT_SIGNATURES);
+        let logs = self.l1_client.logs(&filter).await?;
+
+        // Payloads are emitted separately from the announcements, so decode
+        // everything first and join them afterwards
+        let mut message_data: HashMap<(Address, u64), Vec<u8>> = HashMap::new();
+        let mut batch_data: HashMap<u64, Vec<u8>> = HashMap::new();
+        let mut announcements = Vec::new();
+        for log in &logs {
+            match decode_inbox_log(log)? {
+                Some(InboxLog::MessageData {
+                    message_index,
+                    data,
+                }) => {
+                    message_data.insert((log.address, message_index), data);
+                }
+                Some(InboxLog::MessageDataFromOrigin { message_index }) => {
+                    let data = self.get_message_from_origin(log.transaction_hash).await?;
+                    message_data.insert((log.address, message_index), data);
+                }
+                Some(InboxLog::BatchData {
+                    sequence_number,
+                    data,
+                }) => {
+                    batch_data.insert(sequence_number, data);
+                }
+                Some(announcement) => announcements.push((log, announcement)),
+                None => {}
+            }
+        }
+
+        // Messages can be delivered by inboxes we do not track (e.g. the
+        // rollup event inbox); fetch their payloads from those contracts
+        let mut other_inboxes: Vec<Address> = Vec::new();
+        for (_, announcement) in &announcements {
+            if let InboxLog::MessageDelivered(info) = announcement
+                && !message_data.contains_key(&(info.inbox, info.message_index))
+                && !other_inboxes.contains(&info.inbox)
+            {
+                other_inboxes.push(info.inbox);
+            }
+        }
+        if !other_inboxes.is_empty() {
+            let filter = LogFilter::new(from_block, to_block)
+                .with_addresses(other_inboxes)
+                .with_event_signatures([
+                    InboxMessageDelivered::SIGNATURE_HASH,
+                    InboxMessageDeliveredFromOrigin::SIGNATURE_HASH,
+                ]);
+            for log in self.l1_client.logs(&filter).await? {
+                let (message_index, data) = match decode_inbox_log(&log)? {
+                    Some(InboxLog::MessageData {
+                        message_index,
+                        data,
+                    }) => (message_index, data),
+                    Some(InboxLog::MessageDataFromOrigin { message_index }) => (
+                        message_index,
+                        self.get_message_from_origin(log.transaction_hash).await?,
+                    ),
+                    _ => continue,
+                };
+                message_data.insert((log.address, message_index), data);
+            }
+        }
+
+        let mut blocks: Vec<L1Block> = Vec::new();
+        for (log, announcement) in announcements {
+            if blocks.last().is_none_or(|b| b.number != log.block_number) {
+                let header = self.get_l1_header(log.block_number).await?;
+                blocks.push(L1Block {
+                    number: header.number,
+                    hash: header.hash,
+                    timestamp: header.timestamp,
+                    events: Vec::new(),
+                });
+            }
+            let Some(block) = blocks.last_mut() else {
+                continue;
+            };
+            if log.block_hash != block.hash {
+                return Err(eyre!(
+                    "L1 block {} changed while fetching logs",
+                    log.block_number
+                ));
+            }
+
+            let event = match announcement {
+                InboxLog::MessageDelivered(info) => {
+                    let data = message_data
+                        .remove(&(info.inbox, info.message_index))
+                        .ok_or_else(|| {
+                            eyre!("Missing data for delayed message {}", info.message_index)
+                        })?;
+                    if keccak256(&data) != info.message_data_hash {
+                        return Err(eyre!(
+                            "Data hash mismatch for delayed message {}",
+                            info.message_index
+                        ));
+                    }
+                    L1Event {
+                        message_number: info.message_index,
+                        sender: info.sender,
+                        data,
+                        timestamp: info.timestamp,
+                        block_number: log.block_number,
+                        transaction_hash: log.transaction_hash,
+                        event_type: L1EventType::MessageSent(info),
+                    }
+                }
+                InboxLog::BatchDelivered(info) => {
+                    // Only batches posted as a separate event carry their data
+                    // in the logs; other locations are resolved by the decoder
+                    let data = batch_data.remove(&info.sequence_number).unwrap_or_default();
+                    L1Event {
+                        message_number: info.sequence_number,
+                        sender: log.address,
+                        data,
+                        timestamp: block.timestamp,
+                        block_number: log.block_number,
+                        transaction_hash: log.transaction_hash,
+                        event_type: L1EventType::BatchSubmitted(info),
+                    }
+                }
+                _ => continue,
+            };
+            block.events.push(event);
+        }
+
+        Ok(blocks)
+    }
+
+    /// Get the payload of a message sent with `sendL2MessageFromOrigin`
+    async fn get_message_from_origin(&self, transaction_hash: B256) -> Result<Vec<u8>> {
+        let transaction = self
+            .l1_client
+            .transaction_by_hash(transaction_hash)
+            .await?
+            .ok_or_else(|| eyre!("L1 transaction {transaction_hash} not found"))?;
+        decode_message_from_origin(&transaction.input)
     }
 
     /// Get inbox tracker statistics
     pub async fn get_stats(&self) -> InboxTrackerStats {
-        let last_processed = *self.last_processed_l1_block.read().await;
+        let last_processed = self.sync_status.read().await.l1_block_number;
         let pending_count = self.pending_messages.read().await.len();
         let latest_l1_block = self.get_latest_l1_block().await.unwrap_or(0);
 
@@ -303,10 +789,23 @@ impl InboxTracker {
         }
     }
 
-    /// Get the next message number to be processed
+    /// Get the next delayed message number expected from L1
     pub async fn get_next_message_number(&self) -> u64 {
-        // TODO: Track message numbers properly
-        0
+        self.sync_status
+            .read()
+            .await
+            .last_delayed_message
+            .map_or(0, |last| last + 1)
+    }
+
+    /// Take the next message of the sequencer batch stream, in batch order
+    pub async fn next_batch_message(&self) -> Option<BatchMessage> {
+        self.batch_messages.write().await.pop_front()
+    }
+
+    /// Get the current sync progress
+    pub async fn get_sync_status(&self) -> SyncStatus {
+        self.sync_status.read().await.clone()
     }
 
     /// Force process all pending messages
@@ -331,8 +830,10 @@ impl InboxTracker {
             config: self.config.clone(),
             storage: Arc::clone(&self.storage),
             is_running: Arc::clone(&self.is_running),
-            last_processed_l1_block: Arc::clone(&self.last_processed_l1_block),
+            sync_status: Arc::clone(&self.sync_status),
             pending_messages: Arc::clone(&self.pending_messages),
+            batch_messages: Arc::clone(&self.batch_messages),
+            l1_client: Arc::clone(&self.l1_client),
         }
     }
 }
@@ -361,8 +862,8 @@ pub struct L1Event {
 /// Types of L1 events we track
 #[derive(Debug, Clone, PartialEq)]
 pub enum L1EventType {
-    MessageSent,
-    BatchSubmitted,
+    MessageSent(DelayedMessageInfo),
+    BatchSubmitted(SequencerBatchInfo),
     StateUpdated,
     ChallengeCreated,
     ChallengeResolved,
@@ -378,3 +879,482 @@ pub struct InboxTrackerStats {
     pub pending_messages: usize,
     pub total_messages_processed: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+    use arbitrum_l1_client::MockL1Client;
+    use arbitrum_storage::L1MessageKind;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        blobs::encode_blobs,
+        events::{
+            encode_batch_from_blobs,
+            tests::{BATCH_FIXTURE, DEPOSIT_FIXTURE, ORIGIN_FIXTURE, load_fixture},
+        },
+    };
+
+    async fn open_tracker(config: &ArbitrumRethConfig, l1: &MockL1Client) -> InboxTracker {
+        let storage = ArbitrumStorage::new(config).await.unwrap();
+        storage.start().await.unwrap();
+
+        InboxTracker::with_l1_client(config, Arc::new(storage), Arc::new(l1.clone()))
+            .await
+            .unwrap()
+    }
+
+    /// Config tracking L1 without confirmation delay
+    fn create_test_config(temp_dir: &TempDir) -> ArbitrumRethConfig {
+        let mut config = ArbitrumRethConfig::default();
+        config.node.datadir = temp_dir.path().to_path_buf();
+        config.l1.confirmation_blocks = 0;
+        config
+    }
+
+    async fn create_test_tracker(l1: &MockL1Client) -> (InboxTracker, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let config = create_test_config(&temp_dir);
+
+        (open_tracker(&config, l1).await, temp_dir)
+    }
+
+    /// Replay a recorded fixture into the mock chain at `block_number`
+    async fn replay_fixture(l1: &MockL1Client, fixture: &str, block_number: u64) {
+        let (logs, transactions) = load_fixture(fixture);
+        for mut log in logs {
+            log.block_number = block_number;
+            l1.add_log(log).await.unwrap();
+        }
+        for transaction in transactions {
+            l1.add_transaction(transaction).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tracker_follows_l1_head() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(10).await;
+        let (tracker, _temp_dir) = create_test_tracker(&l1).await;
+
+        tracker.process_new_l1_blocks().await.unwrap();
+        let stats = tracker.get_stats().await;
+        assert_eq!(stats.latest_l1_block, 10);
+        assert_eq!(stats.last_processed_l1_block, 10);
+        assert_eq!(stats.blocks_behind, 0);
+
+        l1.advance_to(15).await;
+        assert_eq!(tracker.get_stats().await.blocks_behind, 5);
+        tracker.process_new_l1_blocks().await.unwrap();
+        assert_eq!(tracker.get_stats().await.last_processed_l1_block, 15);
+    }
+
+    #[tokio::test]
+    async fn test_tracker_catches_up_in_bounded_ranges() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(MAX_LOG_RANGE + 5).await;
+        let (tracker, _temp_dir) = create_test_tracker(&l1).await;
+
+        tracker.process_new_l1_blocks().await.unwrap();
+        assert_eq!(
+            tracker.get_stats().await.last_processed_l1_block,
+            MAX_LOG_RANGE
+        );
+        tracker.process_new_l1_blocks().await.unwrap();
+        assert_eq!(
+            tracker.get_stats().await.last_processed_l1_block,
+            MAX_LOG_RANGE + 5
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tracker_decodes_recorded_inbox_traffic() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(8).await;
+        replay_fixture(&l1, DEPOSIT_FIXTURE, 3).await;
+        replay_fixture(&l1, ORIGIN_FIXTURE, 4).await;
+        replay_fixture(&l1, BATCH_FIXTURE, 6).await;
+        let (tracker, _temp_dir) = create_test_tracker(&l1).await;
+
+        let blocks = tracker.get_l1_blocks(1, 8).await.unwrap();
+        assert_eq!(
+            blocks.iter().map(|b| b.number).collect::<Vec<_>>(),
+            vec![3, 4, 6]
+        );
+        assert_eq!(blocks[2].events.len(), 2);
+        assert!(matches!(
+            &blocks[2].events[1].event_type,
+            L1EventType::BatchSubmitted(batch) if batch.sequence_number == 563_218
+        ));
+
+        tracker.process_new_l1_blocks().await.unwrap();
+        let messages = tracker
+            .storage
+            .get_l1_messages(1_412_305, 1_412_307)
+            .await
+            .unwrap();
+        let kinds: Vec<L1MessageKind> = messages.iter().map(|m| m.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                L1MessageKind::EthDeposit,
+                L1MessageKind::L2Message,
+                L1MessageKind::BatchPostingReport
+            ]
+        );
+        assert_eq!(messages[0].block_number, 3);
+        assert_eq!(messages[0].data.len(), 52);
+        assert_eq!(messages[1].request_id, B256::from(U256::from(1_412_306)));
+        assert_eq!(messages[2].l1_base_fee, U256::from(23_417_925_113u64));
+
+        let batch = tracker.storage.get_batch(563_218).await.unwrap().unwrap();
+        assert_eq!(batch.l1_block_number, 6);
+        assert_eq!(batch.after_delayed_messages_read, 1_412_308);
+        // As declared by the poster, the previous batch being unknown
+        assert_eq!(batch.message_counts, (150_000_000, 150_000_003));
+
+        // Posting reports find the gas of the batch by its data hash
+        let (logs, transactions) = load_fixture(BATCH_FIXTURE);
+        let Some(InboxLog::BatchDelivered(info)) = decode_inbox_log(&logs[2]).unwrap() else {
+            panic!("expected SequencerBatchDelivered");
+        };
+        let mut sequencer_message = SequencerMessageHeader {
+            min_timestamp: info.min_timestamp,
+            max_timestamp: info.max_timestamp,
+            min_l1_block: info.min_block_number,
+            max_l1_block: info.max_block_number,
+            after_delayed_messages: info.after_delayed_messages_read,
+        }
+        .to_bytes()
+        .to_vec();
+        sequencer_message.extend(
+            decode_batch_from_origin(&transactions[0].input, info.sequence_number).unwrap(),
+        );
+        assert_eq!(batch.data_gas, batch_gas_cost(&sequencer_message));
+        assert_eq!(
+            tracker
+                .storage
+                .get_batch_gas(keccak256(&sequencer_message))
+                .await
+                .unwrap(),
+            Some(batch.data_gas)
+        );
+
+        let mut stream = Vec::new();
+        while let Some(message) = tracker.next_batch_message().await {
+            stream.push(message);
+        }
+        assert_eq!(stream.len(), 3);
+        assert!(stream.iter().all(|m| m.batch_number == 563_218));
+        assert_eq!(
+            stream.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert!(matches!(&stream[0].content, BatchMessageContent::L2Message(msg) if msg[0] == 4));
+        assert_eq!(stream[0].timestamp, 1_700_573_184);
+        assert_eq!(stream[0].l1_block_number, 18_650_100);
+        assert_eq!(
+            stream[1].content,
+            BatchMessageContent::Delayed {
+                message_index: 1_412_307
+            }
+        );
+        assert_eq!(stream[2].timestamp, 1_700_573_189);
+        assert_eq!(stream[2].delayed_messages_read, 1_412_308);
+    }
+
+    #[tokio::test]
+    async fn test_tracker_does_not_queue_own_batches() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(8).await;
+        replay_fixture(&l1, BATCH_FIXTURE, 6).await;
+        let (tracker, _temp_dir) = create_test_tracker(&l1).await;
+
+        // Posted by this node, with the blocks it already executed
+        let posted = ArbitrumBatch {
+            batch_number: 563_218,
+            block_range: (1, 3),
+            l1_block_number: 6,
+            timestamp: 1_700_573_189,
+            transactions: vec![],
+            l1_tx_hash: None,
+            after_delayed_messages_read: 1_412_308,
+            message_counts: (150_000_000, 150_000_003),
+            data_gas: 0,
+        };
+        tracker.storage.store_batch(&posted).await.unwrap();
+
+        tracker.process_new_l1_blocks().await.unwrap();
+        let batch = tracker.storage.get_batch(563_218).await.unwrap().unwrap();
+        assert_eq!(batch.block_range, (1, 3));
+        assert!(batch.data_gas > 0);
+        assert_eq!(tracker.next_batch_message().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_tracker_reads_blob_batches() {
+        let (mut logs, mut transactions) = load_fixture(BATCH_FIXTURE);
+        let Some(InboxLog::BatchDelivered(info)) = decode_inbox_log(&logs[2]).unwrap() else {
+            panic!("expected SequencerBatchDelivered");
+        };
+        let data = decode_batch_from_origin(&transactions[0].input, info.sequence_number).unwrap();
+
+        // Post the same batch in blobs instead of calldata
+        *logs[2].data.last_mut().unwrap() = 3;
+        let mut transaction = transactions.remove(0);
+        transaction.input = encode_batch_from_blobs(
+            info.sequence_number,
+            info.after_delayed_messages_read,
+            150_000_000,
+            150_000_003,
+        );
+        let blobs = encode_blobs(&data);
+        let hashes: Vec<B256> = (0..blobs.len())
+            .map(|i| B256::left_padding_from(&[1, i as u8]))
+            .collect();
+        transaction.blob_versioned_hashes = hashes.clone();
+
+        let l1 = MockL1Client::new();
+        l1.advance_to(8).await;
+        replay_fixture(&l1, DEPOSIT_FIXTURE, 3).await;
+        replay_fixture(&l1, ORIGIN_FIXTURE, 4).await;
+        for mut log in logs {
+            log.block_number = 6;
+            l1.add_log(log).await.unwrap();
+        }
+        l1.add_transaction(transaction.clone()).await;
+        let (tracker, _temp_dir) = create_test_tracker(&l1).await;
+
+        // Without its blobs the batch is retried rather than skipped
+        let err = tracker.process_new_l1_blocks().await.unwrap_err();
+        assert!(err.to_string().contains("not found"));
+        assert_eq!(tracker.get_stats().await.last_processed_l1_block, 0);
+        assert!(tracker.storage.get_batch(563_218).await.unwrap().is_none());
+
+        l1.add_blob_transaction(transaction, blobs).await;
+        tracker.process_new_l1_blocks().await.unwrap();
+        assert_eq!(tracker.get_stats().await.last_processed_l1_block, 8);
+        let batch = tracker.storage.get_batch(563_218).await.unwrap().unwrap();
+        let mut posted = SequencerMessageHeader {
+            min_timestamp: info.min_timestamp,
+            max_timestamp: info.max_timestamp,
+            min_l1_block: info.min_block_number,
+            max_l1_block: info.max_block_number,
+            after_delayed_messages: info.after_delayed_messages_read,
+        }
+        .to_bytes()
+        .to_vec();
+        posted.push(BLOB_HASHES_HEADER_FLAG);
+        for hash in &hashes {
+            posted.extend_from_slice(hash.as_slice());
+        }
+        assert_eq!(batch.data_gas, batch_gas_cost(&posted));
+        assert_eq!(
+            tracker
+                .storage
+                .get_batch_gas(keccak256(&posted))
+                .await
+                .unwrap(),
+            Some(batch.data_gas)
+        );
+
+        let mut stream = Vec::new();
+        while let Some(message) = tracker.next_batch_message().await {
+            stream.push(message);
+        }
+        assert_eq!(stream.len(), 3);
+        assert!(matches!(&stream[0].content, BatchMessageContent::L2Message(msg) if msg[0] == 4));
+        assert_eq!(
+            stream[1].content,
+            BatchMessageContent::Delayed {
+                message_index: 1_412_307
+            }
+        );
+        assert_eq!(stream[2].delayed_messages_read, 1_412_308);
+    }
+
+    #[tokio::test]
+    async fn test_tracker_fetches_data_from_untracked_inbox() {
+        let other_inbox = Address::repeat_byte(0x77);
+        let (mut logs, _) = load_fixture(DEPOSIT_FIXTURE);
+        logs[0].data[12..32].copy_from_slice(other_inbox.as_slice());
+        logs[1].address = other_inbox;
+
+        let l1 = MockL1Client::new();
+        l1.advance_to(4).await;
+        for mut log in logs {
+            log.block_number = 2;
+            l1.add_log(log).await.unwrap();
+        }
+        let (tracker, _temp_dir) = create_test_tracker(&l1).await;
+
+        let blocks = tracker.get_l1_blocks(1, 4).await.unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].events[0].data.len(), 52);
+    }
+
+    #[tokio::test]
+    async fn test_tracker_rejects_mismatched_message_data() {
+        let (mut logs, _) = load_fixture(DEPOSIT_FIXTURE);
+        let last = logs[1].data.len() - 33;
+        logs[1].data[last] ^= 1;
+
+        let l1 = MockL1Client::new();
+        l1.advance_to(4).await;
+        for mut log in logs {
+            log.block_number = 2;
+            l1.add_log(log).await.unwrap();
+        }
+        let (tracker, _temp_dir) = create_test_tracker(&l1).await;
+
+        let err = tracker.get_l1_blocks(1, 4).await.unwrap_err();
+        assert!(err.to_string().contains("Data hash mismatch"));
+    }
+
+    #[tokio::test]
+    async fn test_tracker_resumes_from_checkpoint() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(8).await;
+        replay_fixture(&l1, DEPOSIT_FIXTURE, 3).await;
+        replay_fixture(&l1, BATCH_FIXTURE, 6).await;
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = create_test_config(&temp_dir);
+
+        let checkpoint = {
+            let tracker = open_tracker(&config, &l1).await;
+            tracker.initialize_last_processed_block().await.unwrap();
+            tracker.process_new_l1_blocks().await.unwrap();
+            tracker.get_sync_status().await
+        };
+        assert_eq!(
+            checkpoint,
+            SyncStatus {
+                l1_block_number: 8,
+                last_delayed_message: Some(1_412_307),
+                last_batch: Some(563_218),
+                l1_block_hashes: vec![
+                    (3, l1.block_by_number(3).await.unwrap().unwrap().hash),
+                    (6, l1.block_by_number(6).await.unwrap().unwrap().hash),
+                    (8, l1.block_by_number(8).await.unwrap().unwrap().hash),
+                ],
+            }
+        );
+
+        // Restart against the same datadir after L1 moved on
+        l1.advance_to(12).await;
+        replay_fixture(&l1, ORIGIN_FIXTURE, 10).await;
+        let tracker = open_tracker(&config, &l1).await;
+        tracker.initialize_last_processed_block().await.unwrap();
+        assert_eq!(tracker.get_sync_status().await, checkpoint);
+        assert_eq!(tracker.get_next_message_number().await, 1_412_308);
+
+        tracker.process_new_l1_blocks().await.unwrap();
+        let status = tracker.get_sync_status().await;
+        assert_eq!(status.l1_block_number, 12);
+        // The origin fixture predates the checkpoint and is not ingested again
+        assert_eq!(status.last_delayed_message, Some(1_412_307));
+        assert_eq!(tracker.get_stats().await.pending_messages, 0);
+    }
+
+    #[tokio::test]
+    async fn test_tracker_waits_for_confirmations() {
+        let l1 = MockL1Client::new();
+        l1.advance_to(10).await;
+        replay_fixture(&l1, DEPOSIT_FIXTURE, 3).await;
+        replay_fixture(&l1, ORIGIN_FIXTURE, 5).await;
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = create_test_config(&temp_dir);
+        config.l1.confirmation_blocks = 6;
+        let tracker = open_tracker(&config, &l1).await;
+
+        tracker.process_new_l1_blocks().await.unwrap();
+        let status = tracker.get_sync_status().await;
+        assert_eq!(status.l1_block_number, 4);
+        assert_eq!(status.last_delayed_message, Some(1_412_305));
+
+        l1.advance_to(11).await;
+        tracker.process_new_l1_blocks().await.unwrap();
+        let status = tracker.get_sync_status().await;
+        assert_eq!(status.l1_block_number, 5);
+        assert_eq!(status.last_delayed_message, Some(1_412_306));
+    }
+
+    #[tokio::test]
+    async fn test_tracker_rolls_back_orphaned_l1_blocks() {
+        use arbitrum_storage::ArbitrumBlock;
+
+        let l1 = MockL1Client::new();
+        l1.advance_to(8).await;
+        replay_fixture(&l1, DEPOSIT_FIXTURE, 3).await;
+        replay_fixture(&l1, ORIGIN_FIXTURE, 4).await;
+        replay_fixture(&l1, BATCH_FIXTURE, 6).await;
+        let (tracker, _temp_dir) = create_test_tracker(&l1).await;
+
+        tracker.process_new_l1_blocks().await.unwrap();
+        assert_eq!(
+            tracker.get_sync_status().await.last_delayed_message,
+            Some(1_412_307)
+        );
+
+        // L2 blocks derived from L1 blocks 4 and 6
+        for (number, l1_block_number) in [(1u64, 4u64), (2, 6)] {
+            tracker
+                .storage
+                .store_block(&ArbitrumBlock {
+                    number,
+                    hash: B256::with_last_byte(number as u8),
+                    parent_hash: B256::with_last_byte(number as u8 - 1),
+                    timestamp: number,
+                    gas_used: 0,
+                    gas_limit: 30_000_000,
+                    transactions: vec![],
+                    l1_block_number,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+        let mut reorgs = tracker.storage.subscribe_reorgs();
+
+        // Replace L1 blocks 5.. with a competing fork without inbox traffic
+        l1.truncate(4).await;
+        for timestamp in 5..=10 {
+            l1.push_block_with_salt(timestamp, 1).await;
+        }
+
+        tracker.process_new_l1_blocks().await.unwrap();
+        let status = tracker.get_sync_status().await;
+        assert_eq!(status.l1_block_number, 4);
+        assert_eq!(status.last_delayed_message, Some(1_412_306));
+        assert_eq!(status.last_batch, None);
+        assert_eq!(
+            tracker.storage.get_sync_status().await.unwrap(),
+            Some(status)
+        );
+        assert!(
+            tracker
+                .storage
+                .get_l1_messages(1_412_307, 1_412_307)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(tracker.get_stats().await.pending_messages, 2);
+
+        assert_eq!(tracker.storage.get_current_block_number().await.unwrap(), 1);
+        assert_eq!(reorgs.recv().await.unwrap().new_head, 1);
+
+        // The fork is then followed from the common ancestor
+        tracker.process_new_l1_blocks().await.unwrap();
+        let status = tracker.get_sync_status().await;
+        assert_eq!(status.l1_block_number, 10);
+        assert_eq!(status.last_delayed_message, Some(1_412_306));
+        assert_eq!(
+            status.l1_block_hashes.last(),
+            Some(&(10, l1.block_by_number(10).await.unwrap().unwrap().hash))
+        );
+    }
+}
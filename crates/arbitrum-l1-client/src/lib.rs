@@ -0,0 +1,220 @@
+//! Arbitrum-Reth L1 Client
+//!
+//! Abstraction over the parent chain (L1) used by the inbox tracker and other
+//! components that need to observe L1, and by the batch submitter to post
+//! transactions to it. Two implementations are provided:
+//! a JSON-RPC client driven by [`L1Config`](arbitrum_config::L1Config) and an
+//! in-process mock whose chain can be scripted by tests.
+
+pub mod mock;
+pub mod rpc;
+
+use alloy_eips::eip4844::Blob;
+use alloy_primitives::{Address, B256};
+use async_trait::async_trait;
+use eyre::Result;
+pub use mock::MockL1Client;
+pub use rpc::JsonRpcL1Client;
+
+/// Header fields of an L1 block that the node cares about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L1BlockHeader {
+    pub number: u64,
+    pub hash: B256,
+    pub parent_hash: B256,
+    pub timestamp: u64,
+    pub base_fee_per_gas: Option<u64>,
+}
+
+/// A log emitted on L1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L1Log {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Vec<u8>,
+    pub block_number: u64,
+    pub block_hash: B256,
+    pub transaction_hash: B256,
+    pub transaction_index: u64,
+    pub log_index: u64,
+    pub removed: bool,
+}
+
+/// An L1 transaction as seen by the node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L1Transaction {
+    pub hash: B256,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub nonce: u64,
+    pub input: Vec<u8>,
+    /// Block the transaction was included in; `None` while pending
+    pub block_number: Option<u64>,
+    /// Versioned hashes of the blobs carried by an EIP-4844 transaction
+    pub blob_versioned_hashes: Vec<B256>,
+}
+
+/// Receipt of an L1 transaction included in a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L1Receipt {
+    pub transaction_hash: B256,
+    pub block_number: u64,
+    pub block_hash: B256,
+    pub gas_used: u64,
+    pub effective_gas_price: u128,
+    /// Whether the transaction executed without reverting
+    pub success: bool,
+}
+
+/// Call to estimate the gas of
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct L1CallRequest {
+    pub from: Address,
+    pub to: Address,
+    pub input: Vec<u8>,
+    /// Blobs made available to the call, as seen by the `BLOBHASH` opcode
+    pub blob_versioned_hashes: Vec<B256>,
+}
+
+/// Log query over an inclusive L1 block range
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    pub from_block: u64,
+    pub to_block: u64,
+    /// Emitting contracts (OR); empty matches any address
+    pub addresses: Vec<Address>,
+    /// Topics by position: AND across positions, OR within a position, `None` is a wildcard
+    pub topics: Vec<Option<Vec<B256>>>,
+}
+
+impl LogFilter {
+    /// Create a filter for the given block range
+    pub fn new(from_block: u64, to_block: u64) -> Self {
+        Self {
+            from_block,
+            to_block,
+            ..Default::default()
+        }
+    }
+
+    /// Restrict the filter to logs emitted by the given addresses
+    pub fn with_addresses(mut self, addresses: impl IntoIterator<Item = Address>) -> Self {
+        self.addresses = addresses.into_iter().collect();
+        self
+    }
+
+    /// Restrict the filter to logs whose first topic is one of `topics`
+    pub fn with_event_signatures(mut self, topics: impl IntoIterator<Item = B256>) -> Self {
+        let topics: Vec<B256> = topics.into_iter().collect();
+        if self.topics.is_empty() {
+            self.topics.push(Some(topics));
+        } else {
+            self.topics[0] = Some(topics);
+        }
+        self
+    }
+
+    /// Check whether a log satisfies this filter
+    pub fn matches(&self, log: &L1Log) -> bool {
+        if log.block_number < self.from_block || log.block_number > self.to_block {
+            return false;
+        }
+        if !self.addresses.is_empty() && !self.addresses.contains(&log.address) {
+            return false;
+        }
+        for (i, position) in self.topics.iter().enumerate() {
+            if let Some(or_list) = position {
+                match log.topics.get(i) {
+                    Some(topic) if or_list.contains(topic) => {}
+                    _ => return false,
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Access to the parent chain
+#[async_trait]
+pub trait L1Client: Send + Sync {
+    /// Get the chain id of L1
+    async fn chain_id(&self) -> Result<u64>;
+
+    /// Get the number of the latest L1 block
+    async fn block_number(&self) -> Result<u64>;
+
+    /// Get an L1 block header by number; `None` if the block does not exist yet
+    async fn block_by_number(&self, number: u64) -> Result<Option<L1BlockHeader>>;
+
+    /// Get all logs matching the filter, ordered by block and log index
+    async fn logs(&self, filter: &LogFilter) -> Result<Vec<L1Log>>;
+
+    /// Get a transaction by hash; `None` if it is unknown to the node
+    async fn transaction_by_hash(&self, hash: B256) -> Result<Option<L1Transaction>>;
+
+    /// Get the receipt of a transaction; `None` until it is included
+    async fn transaction_receipt(&self, hash: B256) -> Result<Option<L1Receipt>>;
+
+    /// Get the nonce of an account, counting its pending transactions if `pending`
+    async fn transaction_count(&self, address: Address, pending: bool) -> Result<u64>;
+
+    /// Estimate the gas used by a call
+    async fn estimate_gas(&self, request: &L1CallRequest) -> Result<u64>;
+
+    /// Get the suggested EIP-1559 priority fee
+    async fn max_priority_fee_per_gas(&self) -> Result<u128>;
+
+    /// Get the EIP-4844 blob base fee of the next block
+    async fn blob_base_fee(&self) -> Result<u128>;
+
+    /// Broadcast a signed EIP-2718 encoded transaction and return its hash
+    ///
+    /// Blob transactions are sent in their network form, with the sidecar.
+    async fn send_raw_transaction(&self, raw: &[u8]) -> Result<B256>;
+
+    /// Get the blobs with the given versioned hashes, carried by the L1 block
+    /// with timestamp `block_timestamp`, in the order of the hashes
+    ///
+    /// Fails unless every blob is available.
+    async fn blobs(&self, block_timestamp: u64, versioned_hashes: &[B256]) -> Result<Vec<Blob>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::address;
+
+    use super::*;
+
+    fn log_at(block_number: u64, address: Address, topics: Vec<B256>) -> L1Log {
+        L1Log {
+            address,
+            topics,
+            data: vec![],
+            block_number,
+            block_hash: B256::ZERO,
+            transaction_hash: B256::ZERO,
+            transaction_index: 0,
+            log_index: 0,
+            removed: false,
+        }
+    }
+
+    #[test]
+    fn test_log_filter_matches() {
+        let bridge = address!("0x8315177aB297bA92A06054cE80a67Ed4DBd7ed3a");
+        let other = address!("0x1111111111111111111111111111111111111111");
+        let sig = B256::from([1u8; 32]);
+
+        let filter = LogFilter::new(10, 20)
+            .with_addresses([bridge])
+            .with_event_signatures([sig]);
+
+        assert!(filter.matches(&log_at(10, bridge, vec![sig])));
+        assert!(filter.matches(&log_at(20, bridge, vec![sig, B256::ZERO])));
+        assert!(!filter.matches(&log_at(9, bridge, vec![sig])));
+        assert!(!filter.matches(&log_at(21, bridge, vec![sig])));
+        assert!(!filter.matches(&log_at(15, other, vec![sig])));
+        assert!(!filter.matches(&log_at(15, bridge, vec![B256::ZERO])));
+        assert!(!filter.matches(&log_at(15, bridge, vec![])));
+    }
+}
@@ -0,0 +1,638 @@
+//! In-process scriptable L1 chain for tests
+
+use std::{collections::HashMap, sync::Arc};
+
+use alloy_consensus::{Transaction, TxEip4844Variant, TxEnvelope, transaction::SignerRecoverable};
+use alloy_eips::{
+    eip2718::Decodable2718,
+    eip4844::{BLOB_TX_MIN_BLOB_GASPRICE, Blob},
+};
+use alloy_primitives::{Address, B256, keccak256};
+use async_trait::async_trait;
+use eyre::{Result, eyre};
+use tokio::sync::RwLock;
+
+use crate::{L1BlockHeader, L1CallRequest, L1Client, L1Log, L1Receipt, L1Transaction, LogFilter};
+
+/// Chain id of the mock chain, that of Ethereum mainnet like the default config
+pub const MOCK_CHAIN_ID: u64 = 1;
+
+/// Base fee of every mock block
+const BASE_FEE_PER_GAS: u64 = 1_000_000_000;
+
+/// Priority fee suggested by the mock
+const PRIORITY_FEE_PER_GAS: u128 = 1_000_000_000;
+
+/// Gas charged for executing a call on top of its intrinsic gas
+const EXECUTION_GAS: u64 = 50_000;
+
+/// A broadcast transaction waiting to be included
+#[derive(Debug, Clone)]
+struct PendingTransaction {
+    transaction: L1Transaction,
+    gas_limit: u64,
+    max_fee_per_gas: u128,
+    max_priority_fee_per_gas: u128,
+    max_fee_per_blob_gas: Option<u128>,
+    /// Blobs of the sidecar, in the order of the transaction's versioned hashes
+    blobs: Vec<Blob>,
+}
+
+#[derive(Debug, Default)]
+struct MockChain {
+    blocks: Vec<L1BlockHeader>,
+    logs: Vec<L1Log>,
+    transactions: HashMap<B256, L1Transaction>,
+    receipts: HashMap<B256, L1Receipt>,
+    pending: Vec<PendingTransaction>,
+    nonces: HashMap<Address, u64>, // Nonces of accounts with included mock transactions
+    blobs: HashMap<B256, Box<Blob>>, // Blobs of included transactions by versioned hash
+    blob_base_fee: u128,
+    base_fee: Option<u64>, // Base fee of new blocks, `BASE_FEE_PER_GAS` unless set
+}
+
+impl MockChain {
+    fn nonce(&self, address: Address) -> u64 {
+        self.nonces.get(&address).copied().unwrap_or_default()
+    }
+
+    fn blob_base_fee(&self) -> u128 {
+        self.blob_base_fee.max(BLOB_TX_MIN_BLOB_GASPRICE)
+    }
+
+    /// Move the includable pending transactions into the block
+    fn include_pending(&mut self, header: &L1BlockHeader) {
+        let base_fee = u128::from(header.base_fee_per_gas.unwrap_or_default());
+        let blob_base_fee = self.blob_base_fee();
+        while let Some(index) = self.pending.iter().position(|pending| {
+            pending.max_fee_per_gas >= base_fee
+                && pending
+                    .max_fee_per_blob_gas
+                    .is_none_or(|fee| fee >= blob_base_fee)
+                && pending.transaction.nonce == self.nonce(pending.transaction.from)
+        }) {
+            let mut pending = self.pending.remove(index);
+            let transaction = &mut pending.transaction;
+            transaction.block_number = Some(header.number);
+            *self.nonces.entry(transaction.from).or_default() += 1;
+
+            let gas = intrinsic_gas(&transaction.input) + EXECUTION_GAS;
+            let tip = pending
+                .max_priority_fee_per_gas
+                .min(pending.max_fee_per_gas - base_fee);
+            self.receipts.insert(
+                transaction.hash,
+                L1Receipt {
+                    transaction_hash: transaction.hash,
+                    block_number: header.number,
+                    block_hash: header.hash,
+                    gas_used: gas.min(pending.gas_limit),
+                    effective_gas_price: base_fee + tip,
+                    success: pending.gas_limit >= gas,
+                },
+            );
+            for (hash, blob) in transaction.blob_versioned_hashes.iter().zip(pending.blobs) {
+                self.blobs.insert(*hash, Box::new(blob));
+            }
+            self.transactions
+                .insert(transaction.hash, pending.transaction);
+        }
+    }
+}
+
+/// Gas charged for the calldata of a transaction
+fn intrinsic_gas(input: &[u8]) -> u64 {
+    let zeros = input.iter().filter(|b| **b == 0).count() as u64;
+    21_000 + zeros * 4 + (input.len() as u64 - zeros) * 16
+}
+
+/// Scriptable L1 client backed by an in-memory chain
+///
+/// Block hashes are derived deterministically from the block number, parent
+/// hash and a caller-chosen salt so that tests can build competing forks.
+/// Broadcast transactions are included in the next block once their nonce
+/// is due, like a single-node devnet would.
+#[derive(Debug, Clone, Default)]
+pub struct MockL1Client {
+    chain: Arc<RwLock<MockChain>>,
+}
+
+impl MockL1Client {
+    /// Create an empty mock chain
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a block with the given timestamp and return its header
+    pub async fn push_block(&self, timestamp: u64) -> L1BlockHeader {
+        self.push_block_with_salt(timestamp, 0).await
+    }
+
+    /// Append a block whose hash also commits to `salt`
+    pub async fn push_block_with_salt(&self, timestamp: u64, salt: u64) -> L1BlockHeader {
+        let mut chain = self.chain.write().await;
+        let (number, parent_hash) = match chain.blocks.last() {
+            Some(parent) => (parent.number + 1, parent.hash),
+            None => (0, B256::ZERO),
+        };
+
+        let mut preimage = Vec::with_capacity(56);
+        preimage.extend_from_slice(&number.to_be_bytes());
+        preimage.extend_from_slice(parent_hash.as_slice());
+        preimage.extend_from_slice(&salt.to_be_bytes());
+
+        let header = L1BlockHeader {
+            number,
+            hash: keccak256(&preimage),
+            parent_hash,
+            timestamp,
+            base_fee_per_gas: Some(chain.base_fee.unwrap_or(BASE_FEE_PER_GAS)),
+        };
+        chain.blocks.push(header.clone());
+        chain.include_pending(&header);
+        header
+    }
+
+    /// Append empty blocks until the chain head reaches `number`
+    pub async fn advance_to(&self, number: u64) {
+        loop {
+            let timestamp = {
+                let chain = self.chain.read().await;
+                match chain.blocks.last() {
+                    Some(head) if head.number >= number => break,
+                    Some(head) => head.timestamp + 12,
+                    None => 0,
+                }
+            };
+            self.push_block(timestamp).await;
+        }
+    }
+
+    /// Add a log to an existing block
+    ///
+    /// Block hash, transaction index and log index are filled in from the
+    /// chain; the caller provides address, topics, data and transaction hash.
+    pub async fn add_log(&self, mut log: L1Log) -> Result<()> {
+        let mut chain = self.chain.write().await;
+        let block = chain
+            .blocks
+            .get(log.block_number as usize)
+            .ok_or_else(|| eyre::eyre!("Block {} does not exist", log.block_number))?;
+        log.block_hash = block.hash;
+
+        let in_block = chain
+            .logs
+            .iter()
+            .filter(|l| l.block_number == log.block_number)
+            .count() as u64;
+        log.log_index = in_block;
+        log.transaction_index = in_block;
+        chain.logs.push(log);
+        Ok(())
+    }
+
+    /// Make a transaction retrievable by hash
+    pub async fn add_transaction(&self, transaction: L1Transaction) {
+        let mut chain = self.chain.write().await;
+        chain.transactions.insert(transaction.hash, transaction);
+    }
+
+    /// Make a blob transaction retrievable by hash and its blobs by the
+    /// transaction's versioned hashes, in order
+    pub async fn add_blob_transaction(&self, transaction: L1Transaction, blobs: Vec<Blob>) {
+        let mut chain = self.chain.write().await;
+        for (hash, blob) in transaction.blob_versioned_hashes.iter().zip(blobs) {
+            chain.blobs.insert(*hash, Box::new(blob));
+        }
+        chain.transactions.insert(transaction.hash, transaction);
+    }
+
+    /// Set the base fee of blocks appended from now on
+    pub async fn set_base_fee(&self, fee: u64) {
+        self.chain.write().await.base_fee = Some(fee);
+    }
+
+    /// Set the blob base fee; blob transactions paying less stay pending
+    pub async fn set_blob_base_fee(&self, fee: u128) {
+        self.chain.write().await.blob_base_fee = fee;
+    }
+
+    /// Get a blob of an included transaction, as a beacon node would serve it
+    pub async fn blob(&self, versioned_hash: B256) -> Option<Box<Blob>> {
+        let chain = self.chain.read().await;
+        chain.blobs.get(&versioned_hash).cloned()
+    }
+
+    /// Drop every block above `number` together with its logs and transactions
+    pub async fn truncate(&self, number: u64) {
+        let mut chain = self.chain.write().await;
+        chain.blocks.retain(|b| b.number <= number);
+        chain.logs.retain(|l| l.block_number <= number);
+
+        let dropped: Vec<L1Transaction> = chain
+            .transactions
+            .values()
+            .filter(|tx| tx.block_number.is_some_and(|n| n > number))
+            .cloned()
+            .collect();
+        for tx in dropped {
+            chain.transactions.remove(&tx.hash);
+            if chain.receipts.remove(&tx.hash).is_some()
+                && let Some(nonce) = chain.nonces.get_mut(&tx.from)
+            {
+                *nonce = nonce.saturating_sub(1);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl L1Client for MockL1Client {
+    async fn chain_id(&self) -> Result<u64> {
+        Ok(MOCK_CHAIN_ID)
+    }
+
+    async fn block_number(&self) -> Result<u64> {
+        let chain = self.chain.read().await;
+        Ok(chain.blocks.last().map(|b| b.number).unwrap_or_default())
+    }
+
+    async fn block_by_number(&self, number: u64) -> Result<Option<L1BlockHeader>> {
+        let chain = self.chain.read().await;
+        Ok(chain.blocks.get(number as usize).cloned())
+    }
+
+    async fn logs(&self, filter: &LogFilter) -> Result<Vec<L1Log>> {
+        let chain = self.chain.read().await;
+        let mut logs: Vec<L1Log> = chain
+            .logs
+            .iter()
+            .filter(|log| filter.matches(log))
+            .cloned()
+            .collect();
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+        Ok(logs)
+    }
+
+    async fn transaction_by_hash(&self, hash: B256) -> Result<Option<L1Transaction>> {
+        let chain = self.chain.read().await;
+        Ok(chain.transactions.get(&hash).cloned())
+    }
+
+    async fn transaction_receipt(&self, hash: B256) -> Result<Option<L1Receipt>> {
+        let chain = self.chain.read().await;
+        Ok(chain.receipts.get(&hash).cloned())
+    }
+
+    async fn transaction_count(&self, address: Address, pending: bool) -> Result<u64> {
+        let chain = self.chain.read().await;
+        let mut nonce = chain.nonce(address);
+        while pending
+            && chain
+                .pending
+                .iter()
+                .any(|p| p.transaction.from == address && p.transaction.nonce == nonce)
+        {
+            nonce += 1;
+        }
+        Ok(nonce)
+    }
+
+    async fn estimate_gas(&self, request: &L1CallRequest) -> Result<u64> {
+        Ok(intrinsic_gas(&request.input) + EXECUTION_GAS)
+    }
+
+    async fn max_priority_fee_per_gas(&self) -> Result<u128> {
+        Ok(PRIORITY_FEE_PER_GAS)
+    }
+
+    async fn blob_base_fee(&self) -> Result<u128> {
+        Ok(self.chain.read().await.blob_base_fee())
+    }
+
+    async fn blobs(&self, _block_timestamp: u64, versioned_hashes: &[B256]) -> Result<Vec<Blob>> {
+        let chain = self.chain.read().await;
+        let mut blobs = Vec::with_capacity(versioned_hashes.len());
+        for hash in versioned_hashes {
+            let blob = chain
+                .blobs
+                .get(hash)
+                .ok_or_else(|| eyre!("blob {hash} not found"))?;
+            // Blobs are too large to copy through the stack
+            blobs.extend_from_slice(std::slice::from_ref(blob.as_ref()));
+        }
+        Ok(blobs)
+    }
+
+    async fn send_raw_transaction(&self, raw: &[u8]) -> Result<B256> {
+        let envelope = TxEnvelope::decode_2718(&mut &raw[..])
+            .map_err(|e| eyre!("invalid transaction: {e}"))?;
+        let from = envelope
+            .recover_signer()
+            .map_err(|e| eyre!("invalid sender: {e}"))?;
+        if envelope.chain_id().is_some_and(|id| id != MOCK_CHAIN_ID) {
+            return Err(eyre!("invalid chain id"));
+        }
+        let blobs = match &envelope {
+            TxEnvelope::Eip4844(signed) => match signed.tx() {
+                TxEip4844Variant::TxEip4844WithSidecar(tx) => {
+                    if !tx.sidecar.versioned_hashes().eq(tx
+                        .tx
+                        .blob_versioned_hashes
+                        .iter()
+                        .copied())
+                    {
+                        return Err(eyre!("blob versioned hashes do not match the sidecar"));
+                    }
+                    tx.sidecar.blobs.clone()
+                }
+                TxEip4844Variant::TxEip4844(_) => {
+                    return Err(eyre!("blob transaction is missing its sidecar"));
+                }
+            },
+            _ => Vec::new(),
+        };
+
+        let mut chain = self.chain.write().await;
+        let nonce = envelope.nonce();
+        if nonce < chain.nonce(from) {
+            return Err(eyre!("nonce too low"));
+        }
+
+        let max_fee_per_gas = envelope.max_fee_per_gas();
+        let max_priority_fee_per_gas = envelope
+            .max_priority_fee_per_gas()
+            .unwrap_or(max_fee_per_gas);
+        if let Some(index) = chain
+            .pending
+            .iter()
+            .position(|p| p.transaction.from == from && p.transaction.nonce == nonce)
+        {
+            // Replacements must raise both fee caps by 10%, as in geth
+            let replaced = &chain.pending[index];
+            if max_fee_per_gas < replaced.max_fee_per_gas * 11 / 10
+                || max_priority_fee_per_gas < replaced.max_priority_fee_per_gas * 11 / 10
+            {
+                return Err(eyre!("replacement transaction underpriced"));
+            }
+            let replaced = chain.pending.remove(index);
+            chain.transactions.remove(&replaced.transaction.hash);
+        }
+
+        let transaction = L1Transaction {
+            hash: *envelope.tx_hash(),
+            from,
+            to: envelope.to(),
+            nonce,
+            input: envelope.input().to_vec(),
+            block_number: None,
+            blob_versioned_hashes: envelope
+                .blob_versioned_hashes()
+                .map(<[B256]>::to_vec)
+                .unwrap_or_default(),
+        };
+        chain
+            .transactions
+            .insert(transaction.hash, transaction.clone());
+        chain.pending.push(PendingTransaction {
+            transaction: transaction.clone(),
+            gas_limit: envelope.gas_limit(),
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            max_fee_per_blob_gas: envelope.max_fee_per_blob_gas(),
+            blobs,
+        });
+        Ok(transaction.hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_consensus::{SignableTransaction, TxEip1559, TxEip4844, TxEip4844WithSidecar};
+    use alloy_eips::{
+        eip2718::Encodable2718,
+        eip4844::{BlobTransactionSidecar, Bytes48, kzg_to_versioned_hash},
+    };
+    use alloy_primitives::{TxKind, U256};
+    use alloy_signer::SignerSync;
+    use alloy_signer_local::PrivateKeySigner;
+
+    use super::*;
+
+    fn signed_tx(signer: &PrivateKeySigner, nonce: u64, max_fee_per_gas: u128) -> Vec<u8> {
+        let tx = TxEip1559 {
+            chain_id: MOCK_CHAIN_ID,
+            nonce,
+            gas_limit: 100_000,
+            max_fee_per_gas,
+            max_priority_fee_per_gas: max_fee_per_gas / 2,
+            to: TxKind::Call(Address::repeat_byte(0x11)),
+            value: U256::ZERO,
+            access_list: Default::default(),
+            input: vec![1, 0, 2].into(),
+        };
+        let signature = signer.sign_hash_sync(&tx.signature_hash()).unwrap();
+        TxEnvelope::from(tx.into_signed(signature)).encoded_2718()
+    }
+
+    /// Blob transaction carrying a zero blob; the mock does not check KZG proofs
+    fn blob_tx(
+        signer: &PrivateKeySigner,
+        max_fee_per_gas: u128,
+        max_fee_per_blob_gas: u128,
+        with_sidecar: bool,
+    ) -> (Vec<u8>, B256) {
+        let commitment = Bytes48::repeat_byte(0xc0);
+        let versioned_hash = kzg_to_versioned_hash(commitment.as_slice());
+        let tx = TxEip4844 {
+            chain_id: MOCK_CHAIN_ID,
+            nonce: 0,
+            gas_limit: 100_000,
+            max_fee_per_gas,
+            max_priority_fee_per_gas: max_fee_per_gas / 2,
+            to: Address::repeat_byte(0x11),
+            value: U256::ZERO,
+            access_list: Default::default(),
+            blob_versioned_hashes: vec![versioned_hash],
+            max_fee_per_blob_gas,
+            input: Default::default(),
+        };
+        let tx: TxEip4844Variant = if with_sidecar {
+            let sidecar = BlobTransactionSidecar::new(
+                vec![Blob::ZERO],
+                vec![commitment],
+                vec![Bytes48::ZERO],
+            );
+            TxEip4844WithSidecar::from_tx_and_sidecar(tx, sidecar).into()
+        } else {
+            tx.into()
+        };
+        let signature = signer.sign_hash_sync(&tx.signature_hash()).unwrap();
+        let envelope = TxEnvelope::from(tx.into_signed(signature));
+        (envelope.encoded_2718(), versioned_hash)
+    }
+
+    fn log(block_number: u64) -> L1Log {
+        L1Log {
+            address: Address::repeat_byte(0x11),
+            topics: vec![B256::repeat_byte(0x22)],
+            data: vec![1, 2, 3],
+            block_number,
+            block_hash: B256::ZERO,
+            transaction_hash: B256::repeat_byte(0x33),
+            transaction_index: 0,
+            log_index: 0,
+            removed: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_chain_links_blocks() {
+        let client = MockL1Client::new();
+        client.advance_to(5).await;
+
+        assert_eq!(client.block_number().await.unwrap(), 5);
+        for n in 1..=5 {
+            let block = client.block_by_number(n).await.unwrap().unwrap();
+            let parent = client.block_by_number(n - 1).await.unwrap().unwrap();
+            assert_eq!(block.parent_hash, parent.hash);
+        }
+        assert!(client.block_by_number(6).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mock_logs_are_filtered_and_indexed() {
+        let client = MockL1Client::new();
+        client.advance_to(3).await;
+        client.add_log(log(2)).await.unwrap();
+        client.add_log(log(2)).await.unwrap();
+        client.add_log(log(3)).await.unwrap();
+        assert!(client.add_log(log(9)).await.is_err());
+
+        let logs = client.logs(&LogFilter::new(2, 2)).await.unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[1].log_index, 1);
+        assert_eq!(
+            logs[0].block_hash,
+            client.block_by_number(2).await.unwrap().unwrap().hash
+        );
+
+        let other = LogFilter::new(0, 3).with_addresses([Address::ZERO]);
+        assert!(client.logs(&other).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mock_includes_sent_transactions() {
+        let client = MockL1Client::new();
+        client.advance_to(1).await;
+        let signer = PrivateKeySigner::random();
+        let from = signer.address();
+
+        let hash = client
+            .send_raw_transaction(&signed_tx(&signer, 0, 4_000_000_000))
+            .await
+            .unwrap();
+        assert_eq!(client.transaction_count(from, true).await.unwrap(), 1);
+        assert_eq!(client.transaction_count(from, false).await.unwrap(), 0);
+        assert!(client.transaction_receipt(hash).await.unwrap().is_none());
+
+        let block = client.push_block(100).await;
+        let receipt = client.transaction_receipt(hash).await.unwrap().unwrap();
+        assert_eq!(receipt.block_hash, block.hash);
+        assert_eq!(receipt.gas_used, 21_000 + 4 + 32 + EXECUTION_GAS);
+        assert_eq!(receipt.effective_gas_price, 3_000_000_000);
+        assert!(receipt.success);
+        let tx = client.transaction_by_hash(hash).await.unwrap().unwrap();
+        assert_eq!((tx.from, tx.block_number), (from, Some(2)));
+        assert_eq!(client.transaction_count(from, false).await.unwrap(), 1);
+
+        let err = client
+            .send_raw_transaction(&signed_tx(&signer, 0, 4_000_000_000))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("nonce too low"));
+
+        // Below the base fee the transaction waits for a replacement
+        let stuck = client
+            .send_raw_transaction(&signed_tx(&signer, 1, 500_000_000))
+            .await
+            .unwrap();
+        client.push_block(112).await;
+        assert!(client.transaction_receipt(stuck).await.unwrap().is_none());
+        let err = client
+            .send_raw_transaction(&signed_tx(&signer, 1, 520_000_000))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("replacement transaction underpriced")
+        );
+        let replacement = client
+            .send_raw_transaction(&signed_tx(&signer, 1, 2_000_000_000))
+            .await
+            .unwrap();
+        client.push_block(124).await;
+        assert!(client.transaction_by_hash(stuck).await.unwrap().is_none());
+        assert!(
+            client
+                .transaction_receipt(replacement)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        client.truncate(3).await;
+        assert!(
+            client
+                .transaction_receipt(replacement)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(client.transaction_count(from, false).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_includes_blob_transactions() {
+        let client = MockL1Client::new();
+        client.advance_to(1).await;
+        client.set_blob_base_fee(10).await;
+        let signer = PrivateKeySigner::random();
+
+        let (raw, _) = blob_tx(&signer, 4_000_000_000, 20, false);
+        let err = client.send_raw_transaction(&raw).await.unwrap_err();
+        assert!(err.to_string().contains("missing its sidecar"));
+
+        let (raw, versioned_hash) = blob_tx(&signer, 4_000_000_000, 5, true);
+        let stuck = client.send_raw_transaction(&raw).await.unwrap();
+        client.push_block(100).await;
+        assert!(client.transaction_receipt(stuck).await.unwrap().is_none());
+        assert!(client.blob(versioned_hash).await.is_none());
+
+        let (raw, _) = blob_tx(&signer, 8_000_000_000, 20, true);
+        let hash = client.send_raw_transaction(&raw).await.unwrap();
+        client.push_block(112).await;
+        assert!(client.transaction_receipt(hash).await.unwrap().is_some());
+        let tx = client.transaction_by_hash(hash).await.unwrap().unwrap();
+        assert_eq!(tx.blob_versioned_hashes, vec![versioned_hash]);
+        let blob = client.blob(versioned_hash).await.unwrap();
+        assert!(blob.iter().all(|byte| *byte == 0));
+        let blobs = client.blobs(112, &[versioned_hash]).await.unwrap();
+        assert_eq!(blobs.len(), 1);
+        assert_eq!(blobs[0], *blob);
+        assert!(client.blobs(112, &[B256::ZERO]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mock_fork_changes_hashes() {
+        let client = MockL1Client::new();
+        client.advance_to(3).await;
+        let original = client.block_by_number(3).await.unwrap().unwrap();
+
+        client.truncate(2).await;
+        let forked = client.push_block_with_salt(original.timestamp, 1).await;
+        assert_eq!(forked.number, 3);
+        assert_eq!(forked.parent_hash, original.parent_hash);
+        assert_ne!(forked.hash, original.hash);
+    }
+}
@@ -0,0 +1,771 @@
+//! JSON-RPC backed L1 client
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use alloy_eips::eip4844::{Blob, kzg_to_versioned_hash};
+use alloy_primitives::{Address, B256, Bytes, U64, U128};
+use arbitrum_config::L1Config;
+use async_trait::async_trait;
+use eyre::{Result, eyre};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use tokio::{
+    net::TcpStream,
+    sync::{Mutex, OnceCell},
+};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message};
+use tracing::debug;
+
+use crate::{L1BlockHeader, L1CallRequest, L1Client, L1Log, L1Receipt, L1Transaction, LogFilter};
+
+/// Upper bound on a single HTTP request to the L1 endpoint
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+enum Transport {
+    Http {
+        client: reqwest::Client,
+        url: String,
+    },
+    /// Lazily connected WebSocket; requests are serialized over the connection
+    Ws {
+        url: String,
+        stream: Mutex<Option<Box<WsStream>>>,
+    },
+}
+
+/// Beacon node REST API, which serves the blobs L1 execution nodes do not
+struct BeaconApi {
+    client: reqwest::Client,
+    url: String,
+    /// Genesis time and seconds per slot of the beacon chain
+    timing: OnceCell<(u64, u64)>,
+}
+
+impl BeaconApi {
+    fn new(url: &str) -> Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
+            url: url.trim_end_matches('/').to_string(),
+            timing: OnceCell::new(),
+        })
+    }
+
+    /// GET `path` and decode the `data` of the response
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        debug!("Beacon API request: {path}");
+        let response: BeaconResponse<T> = self
+            .client
+            .get(format!("{}{path}", self.url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response.data)
+    }
+
+    /// Slot of the beacon block holding the L1 block with timestamp `timestamp`
+    async fn slot(&self, timestamp: u64) -> Result<u64> {
+        let (genesis_time, seconds_per_slot) = *self
+            .timing
+            .get_or_try_init(|| async {
+                let genesis: BeaconGenesis = self.get("/eth/v1/beacon/genesis").await?;
+                let spec: BeaconSpec = self.get("/eth/v1/config/spec").await?;
+                Ok::<_, eyre::Report>((
+                    genesis.genesis_time.parse()?,
+                    spec.seconds_per_slot.parse()?,
+                ))
+            })
+            .await?;
+        if timestamp < genesis_time || seconds_per_slot == 0 {
+            return Err(eyre!(
+                "L1 block timestamp {timestamp} is not in a beacon slot"
+            ));
+        }
+        Ok((timestamp - genesis_time) / seconds_per_slot)
+    }
+}
+
+/// L1 client speaking Ethereum JSON-RPC over HTTP or WebSocket
+///
+/// The WebSocket endpoint (`ws_url`) is preferred when configured, otherwise
+/// `rpc_url` is used; `ws://` and `wss://` URLs select the WebSocket transport.
+/// Blobs are read from the beacon node at `beacon_url`.
+pub struct JsonRpcL1Client {
+    transport: Transport,
+    next_id: AtomicU64,
+    beacon: Option<BeaconApi>,
+}
+
+impl JsonRpcL1Client {
+    /// Create a client from the L1 configuration
+    pub fn new(config: &L1Config) -> Result<Self> {
+        let url = config.ws_url.as_deref().unwrap_or(&config.rpc_url);
+        let client = Self::from_url(url)?;
+        match &config.beacon_url {
+            Some(beacon_url) => client.with_beacon_url(beacon_url),
+            None => Ok(client),
+        }
+    }
+
+    /// Read blobs from the beacon node API at `url`
+    pub fn with_beacon_url(mut self, url: &str) -> Result<Self> {
+        self.beacon = Some(BeaconApi::new(url)?);
+        Ok(self)
+    }
+
+    /// Create a client for a single endpoint URL
+    pub fn from_url(url: &str) -> Result<Self> {
+        let transport = if url.starts_with("ws://") || url.starts_with("wss://") {
+            Transport::Ws {
+                url: url.to_string(),
+                stream: Mutex::new(None),
+            }
+        } else if url.starts_with("http://") || url.starts_with("https://") {
+            Transport::Http {
+                client: reqwest::Client::builder()
+                    .timeout(REQUEST_TIMEOUT)
+                    .build()?,
+                url: url.to_string(),
+            }
+        } else {
+            return Err(eyre!("Unsupported L1 RPC URL scheme: {url}"));
+        };
+
+        Ok(Self {
+            transport,
+            next_id: AtomicU64::new(1),
+            beacon: None,
+        })
+    }
+
+    /// Send a JSON-RPC request and decode its result
+    pub async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        debug!("L1 RPC request {id}: {method}");
+
+        let response = match &self.transport {
+            Transport::Http { client, url } => {
+                client
+                    .post(url)
+                    .json(&body)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<Value>()
+                    .await?
+            }
+            Transport::Ws { url, stream } => {
+                let mut guard = stream.lock().await;
+                let result = Self::ws_roundtrip(url, &mut guard, &body, id).await;
+                if result.is_err() {
+                    // Drop the connection so the next request reconnects
+                    *guard = None;
+                }
+                result?
+            }
+        };
+
+        if let Some(error) = response.get("error") {
+            let code = error
+                .get("code")
+                .and_then(Value::as_i64)
+                .unwrap_or_default();
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error");
+            return Err(eyre!("L1 RPC {method} failed ({code}): {message}"));
+        }
+
+        let result = response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| eyre!("L1 RPC {method} returned no result"))?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    async fn ws_roundtrip(
+        url: &str,
+        stream: &mut Option<Box<WsStream>>,
+        body: &Value,
+        id: u64,
+    ) -> Result<Value> {
+        if stream.is_none() {
+            let (ws, _) = connect_async(url).await?;
+            *stream = Some(Box::new(ws));
+        }
+        let ws = stream
+            .as_mut()
+            .ok_or_else(|| eyre!("WebSocket connection unavailable"))?;
+
+        ws.send(Message::text(body.to_string())).await?;
+
+        while let Some(message) = ws.next().await {
+            let text = match message? {
+                Message::Text(text) => text.to_string(),
+                Message::Binary(bytes) => String::from_utf8(bytes.to_vec())?,
+                Message::Ping(payload) => {
+                    ws.send(Message::Pong(payload)).await?;
+                    continue;
+                }
+                Message::Close(_) => break,
+                _ => continue,
+            };
+
+            let response: Value = serde_json::from_str(&text)?;
+            // Skip subscription notifications and stale responses
+            if response.get("id").and_then(Value::as_u64) == Some(id) {
+                return Ok(response);
+            }
+        }
+
+        Err(eyre!("WebSocket connection closed"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcBlock {
+    number: U64,
+    hash: B256,
+    parent_hash: B256,
+    timestamp: U64,
+    base_fee_per_gas: Option<U64>,
+}
+
+impl From<RpcBlock> for L1BlockHeader {
+    fn from(block: RpcBlock) -> Self {
+        Self {
+            number: block.number.to(),
+            hash: block.hash,
+            parent_hash: block.parent_hash,
+            timestamp: block.timestamp.to(),
+            base_fee_per_gas: block.base_fee_per_gas.map(|fee| fee.to()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcLog {
+    address: Address,
+    topics: Vec<B256>,
+    data: Bytes,
+    block_number: U64,
+    block_hash: B256,
+    transaction_hash: B256,
+    transaction_index: U64,
+    log_index: U64,
+    #[serde(default)]
+    removed: bool,
+}
+
+impl From<RpcLog> for L1Log {
+    fn from(log: RpcLog) -> Self {
+        Self {
+            address: log.address,
+            topics: log.topics,
+            data: log.data.to_vec(),
+            block_number: log.block_number.to(),
+            block_hash: log.block_hash,
+            transaction_hash: log.transaction_hash,
+            transaction_index: log.transaction_index.to(),
+            log_index: log.log_index.to(),
+            removed: log.removed,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTransaction {
+    hash: B256,
+    from: Address,
+    to: Option<Address>,
+    nonce: U64,
+    input: Bytes,
+    block_number: Option<U64>,
+    #[serde(default)]
+    blob_versioned_hashes: Vec<B256>,
+}
+
+impl From<RpcTransaction> for L1Transaction {
+    fn from(tx: RpcTransaction) -> Self {
+        Self {
+            hash: tx.hash,
+            from: tx.from,
+            to: tx.to,
+            nonce: tx.nonce.to(),
+            input: tx.input.to_vec(),
+            block_number: tx.block_number.map(|n| n.to()),
+            blob_versioned_hashes: tx.blob_versioned_hashes,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcReceipt {
+    transaction_hash: B256,
+    block_number: U64,
+    block_hash: B256,
+    gas_used: U64,
+    effective_gas_price: U128,
+    /// Absent on pre-Byzantium receipts, which are treated as successful
+    status: Option<U64>,
+}
+
+impl From<RpcReceipt> for L1Receipt {
+    fn from(receipt: RpcReceipt) -> Self {
+        Self {
+            transaction_hash: receipt.transaction_hash,
+            block_number: receipt.block_number.to(),
+            block_hash: receipt.block_hash,
+            gas_used: receipt.gas_used.to(),
+            effective_gas_price: receipt.effective_gas_price.to(),
+            success: receipt.status.is_none_or(|status| status == U64::from(1)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BeaconResponse<T> {
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct BeaconGenesis {
+    genesis_time: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BeaconSpec {
+    #[serde(rename = "SECONDS_PER_SLOT")]
+    seconds_per_slot: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlobSidecar {
+    blob: Bytes,
+    kzg_commitment: Bytes,
+    kzg_proof: Bytes,
+}
+
+impl BlobSidecar {
+    /// Versioned hash of the blob's commitment
+    fn versioned_hash(&self) -> Result<B256> {
+        if self.kzg_commitment.len() != 48 {
+            return Err(eyre!("Blob sidecar has an invalid KZG commitment"));
+        }
+        Ok(kzg_to_versioned_hash(&self.kzg_commitment))
+    }
+
+    /// Check the KZG proof of the blob against its commitment
+    fn verify(&self) -> Result<()> {
+        let settings = c_kzg::ethereum_kzg_settings(0);
+        let blob = c_kzg::Blob::from_bytes(&self.blob)?;
+        let commitment = c_kzg::Bytes48::from_bytes(&self.kzg_commitment)?;
+        let proof = c_kzg::Bytes48::from_bytes(&self.kzg_proof)?;
+        if !settings.verify_blob_kzg_proof(&blob, &commitment, &proof)? {
+            return Err(eyre!("Blob does not match its KZG commitment"));
+        }
+        Ok(())
+    }
+}
+
+fn filter_params(filter: &LogFilter) -> Value {
+    let mut params = json!({
+        "fromBlock": format!("{:#x}", filter.from_block),
+        "toBlock": format!("{:#x}", filter.to_block),
+    });
+    if !filter.addresses.is_empty() {
+        params["address"] = json!(filter.addresses);
+    }
+    if !filter.topics.is_empty() {
+        params["topics"] = json!(filter.topics);
+    }
+    params
+}
+
+#[async_trait]
+impl L1Client for JsonRpcL1Client {
+    async fn chain_id(&self) -> Result<u64> {
+        let chain_id: U64 = self.request("eth_chainId", json!([])).await?;
+        Ok(chain_id.to())
+    }
+
+    async fn block_number(&self) -> Result<u64> {
+        let number: U64 = self.request("eth_blockNumber", json!([])).await?;
+        Ok(number.to())
+    }
+
+    async fn block_by_number(&self, number: u64) -> Result<Option<L1BlockHeader>> {
+        let block: Option<RpcBlock> = self
+            .request(
+                "eth_getBlockByNumber",
+                json!([format!("{number:#x}"), false]),
+            )
+            .await?;
+        Ok(block.map(Into::into))
+    }
+
+    async fn logs(&self, filter: &LogFilter) -> Result<Vec<L1Log>> {
+        let logs: Vec<RpcLog> = self
+            .request("eth_getLogs", json!([filter_params(filter)]))
+            .await?;
+        let mut logs: Vec<L1Log> = logs.into_iter().map(Into::into).collect();
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+        Ok(logs)
+    }
+
+    async fn transaction_by_hash(&self, hash: B256) -> Result<Option<L1Transaction>> {
+        let tx: Option<RpcTransaction> = self
+            .request("eth_getTransactionByHash", json!([hash]))
+            .await?;
+        Ok(tx.map(Into::into))
+    }
+
+    async fn transaction_receipt(&self, hash: B256) -> Result<Option<L1Receipt>> {
+        let receipt: Option<RpcReceipt> = self
+            .request("eth_getTransactionReceipt", json!([hash]))
+            .await?;
+        Ok(receipt.map(Into::into))
+    }
+
+    async fn transaction_count(&self, address: Address, pending: bool) -> Result<u64> {
+        let tag = if pending { "pending" } else { "latest" };
+        let count: U64 = self
+            .request("eth_getTransactionCount", json!([address, tag]))
+            .await?;
+        Ok(count.to())
+    }
+
+    async fn estimate_gas(&self, request: &L1CallRequest) -> Result<u64> {
+        let mut call = json!({
+            "from": request.from,
+            "to": request.to,
+            "input": Bytes::copy_from_slice(&request.input),
+        });
+        if !request.blob_versioned_hashes.is_empty() {
+            call["blobVersionedHashes"] = json!(request.blob_versioned_hashes);
+        }
+        let gas: U64 = self.request("eth_estimateGas", json!([call])).await?;
+        Ok(gas.to())
+    }
+
+    async fn max_priority_fee_per_gas(&self) -> Result<u128> {
+        let fee: U128 = self.request("eth_maxPriorityFeePerGas", json!([])).await?;
+        Ok(fee.to())
+    }
+
+    async fn blob_base_fee(&self) -> Result<u128> {
+        let fee: U128 = self.request("eth_blobBaseFee", json!([])).await?;
+        Ok(fee.to())
+    }
+
+    async fn send_raw_transaction(&self, raw: &[u8]) -> Result<B256> {
+        self.request(
+            "eth_sendRawTransaction",
+            json!([Bytes::copy_from_slice(raw)]),
+        )
+        .await
+    }
+
+    async fn blobs(&self, block_timestamp: u64, versioned_hashes: &[B256]) -> Result<Vec<Blob>> {
+        if versioned_hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let beacon = self
+            .beacon
+            .as_ref()
+            .ok_or_else(|| eyre!("No beacon node is configured to read blobs from"))?;
+        let slot = beacon.slot(block_timestamp).await?;
+        let sidecars: Vec<BlobSidecar> = beacon
+            .get(&format!("/eth/v1/beacon/blob_sidecars/{slot}"))
+            .await?;
+
+        let mut found = HashMap::new();
+        for sidecar in sidecars {
+            let hash = sidecar.versioned_hash()?;
+            if versioned_hashes.contains(&hash) {
+                sidecar.verify()?;
+                found.insert(hash, sidecar.blob);
+            }
+        }
+        let mut blobs = Vec::with_capacity(versioned_hashes.len());
+        for hash in versioned_hashes {
+            let blob = found
+                .get(hash)
+                .ok_or_else(|| eyre!("Blob {hash} is not in beacon slot {slot}"))?;
+            // Blobs are too large to copy through the stack
+            let blob = <&Blob>::try_from(blob.as_ref())?;
+            blobs.extend_from_slice(std::slice::from_ref(blob));
+        }
+        Ok(blobs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{hex, keccak256};
+    use axum::{
+        Json, Router,
+        extract::Path,
+        routing::{get, post},
+    };
+
+    use super::*;
+
+    async fn handler(Json(request): Json<Value>) -> Json<Value> {
+        let id = request["id"].clone();
+        let result = match request["method"].as_str().unwrap_or_default() {
+            "eth_chainId" => json!("0x1"),
+            "eth_blockNumber" => json!("0x2a"),
+            "eth_getBlockByNumber" if request["params"][0] == "0x2a" => json!({
+                "number": "0x2a",
+                "hash": format!("{}", B256::repeat_byte(0x2a)),
+                "parentHash": format!("{}", B256::repeat_byte(0x29)),
+                "timestamp": "0x64",
+                "baseFeePerGas": "0x3b9aca00",
+                "transactions": [],
+            }),
+            "eth_getBlockByNumber" => Value::Null,
+            "eth_getTransactionByHash" => json!({
+                "hash": request["params"][0],
+                "from": format!("{}", Address::repeat_byte(0x44)),
+                "to": format!("{}", Address::repeat_byte(0x11)),
+                "nonce": "0x7",
+                "input": "0xdeadbeef",
+                "blockNumber": "0x2a",
+                "blobVersionedHashes": [format!("{}", B256::repeat_byte(0x01))],
+            }),
+            "eth_getTransactionReceipt" => json!({
+                "transactionHash": request["params"][0],
+                "blockNumber": "0x2a",
+                "blockHash": format!("{}", B256::repeat_byte(0x2a)),
+                "gasUsed": "0x5208",
+                "effectiveGasPrice": "0x3b9aca01",
+                "status": "0x0",
+            }),
+            "eth_getTransactionCount" if request["params"][1] == "pending" => json!("0x8"),
+            "eth_getTransactionCount" => json!("0x7"),
+            "eth_estimateGas" => {
+                assert_eq!(request["params"][0]["input"], "0xdeadbeef");
+                assert!(request["params"][0].get("blobVersionedHashes").is_none());
+                json!("0x1d4c0")
+            }
+            "eth_maxPriorityFeePerGas" => json!("0x77359400"),
+            "eth_blobBaseFee" => json!("0x3"),
+            "eth_sendRawTransaction" => json!(keccak256(
+                hex::decode(request["params"][0].as_str().unwrap()).unwrap()
+            )),
+            "eth_getLogs" => {
+                assert_eq!(request["params"][0]["fromBlock"], "0x1");
+                assert_eq!(request["params"][0]["toBlock"], "0x2a");
+                json!([{
+                    "address": format!("{}", Address::repeat_byte(0x11)),
+                    "topics": [format!("{}", B256::repeat_byte(0x22))],
+                    "data": "0x0102",
+                    "blockNumber": "0x2a",
+                    "blockHash": format!("{}", B256::repeat_byte(0x2a)),
+                    "transactionHash": format!("{}", B256::repeat_byte(0x33)),
+                    "transactionIndex": "0x0",
+                    "logIndex": "0x3",
+                    "removed": false,
+                }])
+            }
+            _ => {
+                return Json(json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": -32601, "message": "method not found" },
+                }));
+            }
+        };
+        Json(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    }
+
+    async fn spawn_server() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().route("/", post(handler)))
+                .await
+                .unwrap();
+        });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_http_client_against_local_server() {
+        let client = JsonRpcL1Client::from_url(&spawn_server().await).unwrap();
+
+        assert_eq!(client.block_number().await.unwrap(), 42);
+
+        let block = client.block_by_number(42).await.unwrap().unwrap();
+        assert_eq!(block.hash, B256::repeat_byte(0x2a));
+        assert_eq!(block.parent_hash, B256::repeat_byte(0x29));
+        assert_eq!(block.timestamp, 100);
+        assert_eq!(block.base_fee_per_gas, Some(1_000_000_000));
+        assert!(client.block_by_number(43).await.unwrap().is_none());
+
+        let logs = client.logs(&LogFilter::new(1, 42)).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].data, vec![1, 2]);
+        assert_eq!(logs[0].log_index, 3);
+
+        let tx = client
+            .transaction_by_hash(B256::repeat_byte(0x33))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.hash, B256::repeat_byte(0x33));
+        assert_eq!(tx.nonce, 7);
+        assert_eq!(tx.input, vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(tx.block_number, Some(42));
+        assert_eq!(tx.blob_versioned_hashes, vec![B256::repeat_byte(0x01)]);
+
+        assert_eq!(client.chain_id().await.unwrap(), 1);
+        let receipt = client
+            .transaction_receipt(B256::repeat_byte(0x33))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(receipt.block_number, 42);
+        assert_eq!(receipt.gas_used, 21_000);
+        assert_eq!(receipt.effective_gas_price, 1_000_000_001);
+        assert!(!receipt.success);
+
+        let sender = Address::repeat_byte(0x44);
+        assert_eq!(client.transaction_count(sender, false).await.unwrap(), 7);
+        assert_eq!(client.transaction_count(sender, true).await.unwrap(), 8);
+        let call = L1CallRequest {
+            from: sender,
+            to: Address::repeat_byte(0x11),
+            input: vec![0xde, 0xad, 0xbe, 0xef],
+            ..Default::default()
+        };
+        assert_eq!(client.estimate_gas(&call).await.unwrap(), 120_000);
+        assert_eq!(
+            client.max_priority_fee_per_gas().await.unwrap(),
+            2_000_000_000
+        );
+        assert_eq!(client.blob_base_fee().await.unwrap(), 3);
+        assert_eq!(
+            client.send_raw_transaction(&[1, 2, 3]).await.unwrap(),
+            keccak256([1, 2, 3])
+        );
+
+        let err = client
+            .request::<Value>("eth_unknown", json!([]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("method not found"));
+    }
+
+    /// Blob with `byte` in its first field element, its commitment and proof
+    fn blob_sidecar(byte: u8) -> (Box<Blob>, Value) {
+        let mut blob = Box::new(Blob::ZERO);
+        blob[1] = byte;
+        let settings = c_kzg::ethereum_kzg_settings(0);
+        let kzg_blob = c_kzg::Blob::from_bytes(blob.as_slice()).unwrap();
+        let commitment = settings
+            .blob_to_kzg_commitment(&kzg_blob)
+            .unwrap()
+            .to_bytes();
+        let proof = settings
+            .compute_blob_kzg_proof(&kzg_blob, &commitment)
+            .unwrap()
+            .to_bytes();
+        let sidecar = json!({
+            "index": "0",
+            "blob": Bytes::copy_from_slice(blob.as_slice()),
+            "kzg_commitment": Bytes::copy_from_slice(commitment.as_slice()),
+            "kzg_proof": Bytes::copy_from_slice(proof.as_slice()),
+        });
+        (blob, sidecar)
+    }
+
+    #[tokio::test]
+    async fn test_blobs_are_read_from_the_beacon_node() {
+        let (blob, sidecar) = blob_sidecar(1);
+        let (_, other) = blob_sidecar(2);
+        let mut tampered = sidecar.clone();
+        tampered["kzg_proof"] = other["kzg_proof"].clone();
+        let hash = kzg_to_versioned_hash(
+            &hex::decode(sidecar["kzg_commitment"].as_str().unwrap()).unwrap(),
+        );
+
+        let app = Router::new()
+            .route(
+                "/eth/v1/beacon/genesis",
+                get(|| async { Json(json!({ "data": { "genesis_time": "1000" } })) }),
+            )
+            .route(
+                "/eth/v1/config/spec",
+                get(|| async { Json(json!({ "data": { "SECONDS_PER_SLOT": "12" } })) }),
+            )
+            .route(
+                "/eth/v1/beacon/blob_sidecars/:slot",
+                get(move |Path(slot): Path<u64>| {
+                    let sidecars = match slot {
+                        5 => json!([other, sidecar]),
+                        6 => json!([tampered]),
+                        _ => json!([]),
+                    };
+                    async move { Json(json!({ "data": sidecars })) }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = JsonRpcL1Client::from_url("http://localhost:8545").unwrap();
+        assert!(client.blobs(1060, &[hash]).await.is_err());
+        let client = client.with_beacon_url(&format!("http://{addr}/")).unwrap();
+
+        // The block at 1060 is in slot 5, whose sidecars hold the blob
+        let blobs = client.blobs(1060, &[hash]).await.unwrap();
+        assert_eq!(blobs.len(), 1);
+        assert_eq!(blobs[0], *blob);
+        // Slot 6 has a blob whose proof fails, slot 7 none
+        assert!(client.blobs(1072, &[hash]).await.is_err());
+        let err = client.blobs(1084, &[hash]).await.unwrap_err();
+        assert!(err.to_string().contains("slot 7"));
+        assert!(client.blobs(1060, &[B256::ZERO]).await.is_err());
+        assert!(client.blobs(1060, &[]).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_url_scheme_selects_transport() {
+        assert!(matches!(
+            JsonRpcL1Client::from_url("ws://localhost:8546")
+                .unwrap()
+                .transport,
+            Transport::Ws { .. }
+        ));
+        assert!(matches!(
+            JsonRpcL1Client::from_url("https://localhost:8545")
+                .unwrap()
+                .transport,
+            Transport::Http { .. }
+        ));
+        assert!(JsonRpcL1Client::from_url("ftp://localhost").is_err());
+    }
+}
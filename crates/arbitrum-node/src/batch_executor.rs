@@ -0,0 +1,177 @@
+//! Execution of the sequencer batch stream read from L1
+//!
+//! The inbox tracker decodes the batches posted to the SequencerInbox into
+//! their ordered messages. Each message is executed as the next L2 block,
+//! which is then indexed under the batch that carried it, so that the block
+//! can be traced back to its batch and L1 block.
+
+use std::{sync::Arc, time::Duration};
+
+use alloy_primitives::B256;
+use arbitrum_config::ArbitrumRethConfig;
+use arbitrum_consensus::{ArbitrumConsensus, ExecutionResult};
+use arbitrum_inbox_tracker::{BatchMessage, BatchMessageContent, InboxTracker};
+use arbitrum_storage::{ArbitrumBlock, ArbitrumStorage};
+use eyre::{Result, eyre};
+use tokio::{sync::RwLock, time::interval};
+use tracing::{debug, error, info};
+
+/// Executes the messages of batches read from L1 as L2 blocks
+pub struct BatchExecutor {
+    config: ArbitrumRethConfig,
+    consensus: Arc<ArbitrumConsensus>,
+    storage: Arc<ArbitrumStorage>,
+    tracker: Arc<InboxTracker>,
+    is_running: Arc<RwLock<bool>>,
+}
+
+impl BatchExecutor {
+    /// Create an executor of the batch messages `tracker` reads
+    pub fn new(
+        config: &ArbitrumRethConfig,
+        consensus: Arc<ArbitrumConsensus>,
+        storage: Arc<ArbitrumStorage>,
+        tracker: Arc<InboxTracker>,
+    ) -> Self {
+        Self {
+            config: config.clone(),
+            consensus,
+            storage,
+            tracker,
+            is_running: Arc::new(RwLock::new(false)),
+        }
+    }
+
+    /// Start the batch executor
+    pub async fn start(&self) -> Result<()> {
+        let mut running = self.is_running.write().await;
+        if *running {
+            return Ok(());
+        }
+
+        info!("Starting batch executor");
+
+        let self_clone = self.clone_for_task();
+        tokio::spawn(async move {
+            self_clone.execution_loop().await;
+        });
+
+        *running = true;
+        info!("Batch executor started");
+
+        Ok(())
+    }
+
+    /// Stop the batch executor
+    pub async fn stop(&self) -> Result<()> {
+        let mut running = self.is_running.write().await;
+        if !*running {
+            return Ok(());
+        }
+
+        info!("Stopping batch executor");
+
+        *running = false;
+        info!("Batch executor stopped");
+
+        Ok(())
+    }
+
+    /// Execution loop, draining the messages queued by the tracker
+    ///
+    /// A message that fails to execute is kept and retried on the next tick,
+    /// so that no message of the stream is skipped.
+    async fn execution_loop(&self) {
+        let mut interval = interval(Duration::from_millis(100));
+        let mut next = None;
+
+        loop {
+            interval.tick().await;
+
+            // Check if we should stop
+            if !*self.is_running.read().await {
+                break;
+            }
+
+            loop {
+                if next.is_none() {
+                    next = self.tracker.next_batch_message().await;
+                }
+                let Some(message) = &next else {
+                    break;
+                };
+                match self.execute_message(message).await {
+                    Ok(result) => {
+                        debug!(
+                            "Message {} of batch {} executed as block {}",
+                            message.index, message.batch_number, result.block_number
+                        );
+                        next = None;
+                    }
+                    Err(e) => {
+                        error!(
+                            "Failed to execute message {} of batch {}: {}",
+                            message.index, message.batch_number, e
+                        );
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Execute a batch message as the block after the current head
+    ///
+    /// Sequenced messages carry their transactions, delayed ones are read
+    /// from storage where the tracker recorded them. The first block executed
+    /// is the genesis block. The block is then assigned to its batch.
+    pub async fn execute_message(&self, message: &BatchMessage) -> Result<ExecutionResult> {
+        let transactions = match &message.content {
+            BatchMessageContent::L2Message(data) => self.consensus.process_sequencer_message(data),
+            BatchMessageContent::Delayed { message_index } => {
+                let delayed = self
+                    .storage
+                    .get_l1_messages(*message_index, *message_index)
+                    .await?
+                    .pop()
+                    .ok_or_else(|| {
+                        eyre!("Delayed message {message_index} has not been read from L1")
+                    })?;
+                self.consensus.process_l1_message(&delayed).await?
+            }
+        };
+        for tx in &transactions {
+            self.storage.store_transaction(tx).await?;
+        }
+
+        let head = self.consensus.current_block_number().await;
+        let parent = self.storage.get_block_by_number(head).await?;
+        let block = ArbitrumBlock {
+            number: parent.as_ref().map_or(0, |parent| parent.number + 1),
+            parent_hash: parent.map_or(B256::ZERO, |parent| parent.hash),
+            timestamp: message.timestamp,
+            gas_limit: self.config.l2.gas_limit,
+            transactions: transactions.iter().map(|tx| tx.hash).collect(),
+            l1_block_number: message.l1_block_number,
+            delayed_messages_read: message.delayed_messages_read,
+            ..Default::default()
+        };
+        let result = self.consensus.execute_block(&block).await?;
+        self.storage
+            .assign_block_to_batch(message.batch_number, result.block_number)
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Helper method to clone for async tasks
+    fn clone_for_task(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            consensus: Arc::clone(&self.consensus),
+            storage: Arc::clone(&self.storage),
+            tracker: Arc::clone(&self.tracker),
+            is_running: Arc::clone(&self.is_running),
+        }
+    }
+}
@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+pub mod batch_executor;
+pub mod node_interface;
 pub mod reth_integration;
 
 use std::sync::Arc;
@@ -11,6 +13,7 @@ use arbitrum_inbox_tracker::InboxTracker;
 use arbitrum_pool::ArbitrumTransactionPool;
 use arbitrum_storage::ArbitrumStorage;
 use arbitrum_validator::Validator;
+use batch_executor::BatchExecutor;
 use eyre::Result;
 use reth_chainspec::MAINNET;
 use reth_integration::RethNodeHandle;
@@ -28,6 +31,7 @@ pub struct ArbitrumRethNode {
     storage: Arc<ArbitrumStorage>,
     batch_submitter: Option<Arc<BatchSubmitter>>,
     inbox_tracker: Option<Arc<InboxTracker>>,
+    batch_executor: Option<Arc<BatchExecutor>>,
     validator: Option<Arc<Validator>>,
     is_running: Arc<RwLock<bool>>,
     // Reth node handle (placeholder until full integration)
@@ -55,7 +59,7 @@ impl ArbitrumRethNode {
         info!("Arbitrum consensus engine initialized");
 
         // Initialize transaction pool
-        let tx_pool = Arc::new(ArbitrumTransactionPool::new(&config).await?);
+        let tx_pool = Arc::new(ArbitrumTransactionPool::new(&config, Arc::clone(&storage)).await?);
         info!("Arbitrum transaction pool initialized");
 
         // Initialize batch submitter if sequencer mode is enabled
@@ -74,6 +78,16 @@ impl ArbitrumRethNode {
             Some(tracker)
         };
 
+        // Initialize the executor of the batches the tracker reads
+        let batch_executor = inbox_tracker.as_ref().map(|tracker| {
+            Arc::new(BatchExecutor::new(
+                &config,
+                Arc::clone(&consensus),
+                Arc::clone(&storage),
+                Arc::clone(tracker),
+            ))
+        });
+
         // Initialize validator if validator mode is enabled
         let validator = if config.validator.enable {
             let val = Arc::new(Validator::new(&config, Arc::clone(&storage)).await?);
@@ -90,6 +104,7 @@ impl ArbitrumRethNode {
             storage,
             batch_submitter,
             inbox_tracker,
+            batch_executor,
             validator,
             is_running: Arc::new(RwLock::new(false)),
             reth_handle: None,
@@ -147,6 +162,12 @@ impl ArbitrumRethNode {
             info!("Arbitrum inbox tracker started");
         }
 
+        // Start executing the batches read from L1
+        if let Some(ref batch_executor) = self.batch_executor {
+            batch_executor.start().await?;
+            info!("Arbitrum batch executor started");
+        }
+
         // Start batch submitter if enabled
         if let Some(ref batch_submitter) = self.batch_submitter {
             batch_submitter.start().await?;
@@ -235,6 +256,12 @@ impl ArbitrumRethNode {
             info!("Arbitrum batch submitter stopped");
         }
 
+        // Stop batch executor if running
+        if let Some(ref batch_executor) = self.batch_executor {
+            batch_executor.stop().await?;
+            info!("Arbitrum batch executor stopped");
+        }
+
         // Stop inbox tracker if running
         if let Some(ref inbox_tracker) = self.inbox_tracker {
             inbox_tracker.stop().await?;
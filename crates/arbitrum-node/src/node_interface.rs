@@ -0,0 +1,394 @@
+//! NodeInterface virtual contract at 0xc8
+//!
+//! As in Nitro, NodeInterface has no code on chain: the RPC server answers
+//! `eth_call`s to its address itself, with what only a node knows, such as
+//! gas estimates, the batches blocks were posted in and proofs of L2-to-L1
+//! sends. `estimateRetryableTicket` is meant for `eth_estimateGas`, which
+//! then estimates the retry of the ticket it describes instead of the call.
+
+use std::sync::Arc;
+
+use alloy_primitives::{Address, B256, U256, address, keccak256};
+use alloy_sol_types::{SolCall, SolInterface, sol};
+use arbitrum_config::ArbitrumRethConfig;
+use arbitrum_consensus::{arbsys, execution::ArbOsState, l1_pricing, outbox, retryables};
+use arbitrum_storage::{ArbitrumStorage, ArbitrumTransaction, ArbitrumTxKind, L2ToL1Send};
+use eyre::{Result, eyre};
+
+use crate::reth_integration::{estimate_transaction, pending_block};
+
+/// Address the RPC server answers NodeInterface calls at
+pub const NODE_INTERFACE_ADDRESS: Address = address!("0x00000000000000000000000000000000000000c8");
+
+sol! {
+    interface NodeInterface {
+        function estimateRetryableTicket(
+            address sender,
+            uint256 deposit,
+            address to,
+            uint256 l2CallValue,
+            address excessFeeRefundAddress,
+            address callValueRefundAddress,
+            bytes data
+        ) external;
+        function constructOutboxProof(uint64 size, uint64 leaf)
+            external view returns (bytes32 send, bytes32 root, bytes32[] proof);
+        function findBatchContainingBlock(uint64 blockNum) external view returns (uint64 batch);
+        function getL1Confirmations(bytes32 blockHash) external view returns (uint64 confirmations);
+        function gasEstimateComponents(address to, bool contractCreation, bytes data)
+            external payable
+            returns (uint64 gasEstimate, uint64 gasEstimateForL1, uint256 baseFee, uint256 l1BaseFeeEstimate);
+        function gasEstimateL1Component(address to, bool contractCreation, bytes data)
+            external payable
+            returns (uint64 gasEstimateForL1, uint256 baseFee, uint256 l1BaseFeeEstimate);
+        function nitroGenesisBlock() external pure returns (uint256 number);
+        function blockL1Num(uint64 l2BlockNum) external view returns (uint64 l1BlockNum);
+    }
+}
+
+use NodeInterface::NodeInterfaceCalls;
+
+/// Answer a call to NodeInterface, returning its ABI-encoded result
+pub async fn call(
+    config: &ArbitrumRethConfig,
+    storage: &Arc<ArbitrumStorage>,
+    tx: &ArbitrumTransaction,
+) -> Result<Vec<u8>> {
+    let method = NodeInterfaceCalls::abi_decode(&tx.data)
+        .map_err(|e| eyre!("Invalid NodeInterface call: {e}"))?;
+    let output = match method {
+        NodeInterfaceCalls::estimateRetryableTicket(args) => {
+            let (retry, deposit) = retry_transaction(config, storage, args).await?;
+            estimate_transaction(config, storage, &retry, deposit).await?;
+            vec![]
+        }
+        NodeInterfaceCalls::constructOutboxProof(args) => {
+            let proof = outbox_proof(storage, args.size, args.leaf).await?;
+            NodeInterface::constructOutboxProofCall::abi_encode_returns(
+                &NodeInterface::constructOutboxProofReturn {
+                    send: proof.send.hash,
+                    root: proof.root,
+                    proof: proof.proof,
+                },
+            )
+        }
+        NodeInterfaceCalls::findBatchContainingBlock(args) => {
+            let batch = storage
+                .get_batch_of_block(args.blockNum)
+                .await?
+                .ok_or_else(|| eyre!("Block {} is not in any batch yet", args.blockNum))?;
+            NodeInterface::findBatchContainingBlockCall::abi_encode_returns(&batch.batch_number)
+        }
+        NodeInterfaceCalls::getL1Confirmations(args) => {
+            let block = storage
+                .get_block(&args.blockHash)
+                .await?
+                .ok_or_else(|| eyre!("Unknown block {}", args.blockHash))?;
+            // Blocks count as confirmed on L1 once their batch is
+            let confirmations = match storage.get_batch_of_block(block.number).await? {
+                Some(batch) => {
+                    let l1_block_number = storage
+                        .get_sync_status()
+                        .await?
+                        .map_or(0, |status| status.l1_block_number);
+                    l1_block_number.saturating_sub(batch.l1_block_number)
+                }
+                None => 0,
+            };
+            NodeInterface::getL1ConfirmationsCall::abi_encode_returns(&confirmations)
+        }
+        NodeInterfaceCalls::gasEstimateComponents(args) => {
+            let inner = inner_transaction(tx, args.to, args.contractCreation, args.data.to_vec())?;
+            let estimate = estimate_transaction(config, storage, &inner, U256::ZERO).await?;
+            NodeInterface::gasEstimateComponentsCall::abi_encode_returns(
+                &NodeInterface::gasEstimateComponentsReturn {
+                    gasEstimate: estimate.gas,
+                    gasEstimateForL1: estimate.gas_for_l1,
+                    baseFee: U256::from(estimate.base_fee),
+                    l1BaseFeeEstimate: estimate.l1_base_fee,
+                },
+            )
+        }
+        NodeInterfaceCalls::gasEstimateL1Component(args) => {
+            let inner = inner_transaction(tx, args.to, args.contractCreation, args.data.to_vec())?;
+            let arbos = ArbOsState::load(storage, &config.gas).await?;
+            let base_fee = pending_block(config, storage, &arbos).await?.base_fee;
+            let l1_base_fee = arbos.l1_pricing.price_per_unit;
+            NodeInterface::gasEstimateL1ComponentCall::abi_encode_returns(
+                &NodeInterface::gasEstimateL1ComponentReturn {
+                    gasEstimateForL1: l1_pricing::estimated_poster_gas(
+                        &inner,
+                        l1_base_fee,
+                        base_fee,
+                    ),
+                    baseFee: U256::from(base_fee),
+                    l1BaseFeeEstimate: l1_base_fee,
+                },
+            )
+        }
+        NodeInterfaceCalls::nitroGenesisBlock(_) => {
+            // The chain runs Nitro rules from its first block
+            NodeInterface::nitroGenesisBlockCall::abi_encode_returns(&U256::ZERO)
+        }
+        NodeInterfaceCalls::blockL1Num(args) => {
+            let block = storage
+                .get_block_by_number(args.l2BlockNum)
+                .await?
+                .ok_or_else(|| eyre!("Unknown block {}", args.l2BlockNum))?;
+            NodeInterface::blockL1NumCall::abi_encode_returns(&block.l1_block_number)
+        }
+    };
+    Ok(output)
+}
+
+/// Gas a call to NodeInterface needs
+///
+/// For `estimateRetryableTicket` this is the gas of the ticket's retry, as
+/// Nitro swaps the call for the retry. Other methods are estimated as the
+/// plain transaction they are, once they are checked to succeed.
+pub async fn estimate_gas(
+    config: &ArbitrumRethConfig,
+    storage: &Arc<ArbitrumStorage>,
+    tx: &ArbitrumTransaction,
+) -> Result<u64> {
+    if let Ok(NodeInterfaceCalls::estimateRetryableTicket(args)) =
+        NodeInterfaceCalls::abi_decode(&tx.data)
+    {
+        let (retry, deposit) = retry_transaction(config, storage, args).await?;
+        return Ok(estimate_transaction(config, storage, &retry, deposit)
+            .await?
+            .gas);
+    }
+    call(config, storage, tx).await?;
+    Ok(estimate_transaction(config, storage, tx, U256::ZERO)
+        .await?
+        .gas)
+}
+
+/// Transaction a gas estimate method is asked about, sent by the caller
+fn inner_transaction(
+    tx: &ArbitrumTransaction,
+    to: Address,
+    contract_creation: bool,
+    data: Vec<u8>,
+) -> Result<ArbitrumTransaction> {
+    if !contract_creation && to == NODE_INTERFACE_ADDRESS {
+        return Err(eyre!("Cannot estimate a call to NodeInterface"));
+    }
+    Ok(ArbitrumTransaction {
+        to: (!contract_creation).then_some(to),
+        data,
+        ..tx.clone()
+    })
+}
+
+/// Retry of the ticket `estimateRetryableTicket` describes, and the deposit
+/// its sender gets from L1 first
+///
+/// The ticket comes from the aliased L1 sender, with the submission fee
+/// ArbOS charges at the current L1 price.
+async fn retry_transaction(
+    config: &ArbitrumRethConfig,
+    storage: &Arc<ArbitrumStorage>,
+    args: NodeInterface::estimateRetryableTicketCall,
+) -> Result<(ArbitrumTransaction, U256)> {
+    let arbos = ArbOsState::load(storage, &config.gas).await?;
+    let l1_base_fee = arbos.l1_pricing.price_per_unit;
+    let retry = ArbitrumTransaction {
+        hash: B256::ZERO,
+        from: arbsys::remap_l1_address(args.sender),
+        to: (args.to != Address::ZERO).then_some(args.to),
+        value: args.l2CallValue,
+        gas: 0,
+        gas_price: U256::ZERO,
+        nonce: 0,
+        data: args.data.to_vec(),
+        l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::SubmitRetryable {
+            request_id: B256::ZERO,
+            l1_base_fee,
+            deposit_value: args.deposit,
+            max_submission_fee: retryables::submission_fee(args.data.len(), l1_base_fee),
+            fee_refund_address: args.excessFeeRefundAddress,
+            beneficiary: args.callValueRefundAddress,
+        },
+    };
+    Ok((retry, args.deposit))
+}
+
+/// Send to L1 with its proof against a send root
+pub struct OutboxProof {
+    pub send: L2ToL1Send,
+    pub root: B256,
+    pub proof: Vec<B256>,
+}
+
+/// Proof of the send at `leaf` against the root of the tree of the first
+/// `size` sends
+///
+/// As in Nitro, the proof is assembled from the nodes of the send tree that
+/// ArbSys logged, a sibling per level, and folds to the root.
+pub async fn outbox_proof(storage: &ArbitrumStorage, size: u64, leaf: u64) -> Result<OutboxProof> {
+    let send_count = latest_send_count(storage).await?;
+    if size > send_count {
+        return Err(eyre!(
+            "No send root of size {size} yet, there are {send_count} sends"
+        ));
+    }
+    if leaf >= size {
+        return Err(eyre!("Leaf {leaf} is not in the tree of size {size}"));
+    }
+    let send = storage
+        .get_send(leaf)
+        .await?
+        .ok_or_else(|| eyre!("Send {leaf} not found"))?;
+    let mut proof = vec![];
+    for level in 0..outbox::height(size) {
+        proof.push(send_tree_node(storage, size, level, (leaf >> level) ^ 1).await?);
+    }
+    Ok(OutboxProof {
+        root: outbox::fold(send.hash, leaf, &proof),
+        send,
+        proof,
+    })
+}
+
+/// Node `index` of `level` of the tree of the first `size` sends
+///
+/// A node over sends that were all made is read as stored. One that the
+/// last send falls within is hashed from its children, descending towards
+/// that send: children before it are stored, those after it are zero.
+async fn send_tree_node(
+    storage: &ArbitrumStorage,
+    size: u64,
+    mut level: u64,
+    mut index: u64,
+) -> Result<B256> {
+    // Siblings of the descent, with whether they are left of it
+    let mut siblings = vec![];
+    let mut hash = loop {
+        let first = index << level;
+        let last = first + ((1 << level) - 1);
+        if first >= size {
+            break B256::ZERO;
+        }
+        if last < size {
+            break complete_send_tree_node(storage, level, last).await?;
+        }
+        level -= 1;
+        let right = (2 * index + 1) << level;
+        if right < size {
+            siblings.push((
+                complete_send_tree_node(storage, level, right - 1).await?,
+                true,
+            ));
+            index = 2 * index + 1;
+        } else {
+            siblings.push((B256::ZERO, false));
+            index *= 2;
+        }
+    };
+    for (sibling, is_left) in siblings.into_iter().rev() {
+        hash = if is_left {
+            keccak256([sibling.as_slice(), hash.as_slice()].concat())
+        } else {
+            keccak256([hash.as_slice(), sibling.as_slice()].concat())
+        };
+    }
+    Ok(hash)
+}
+
+/// Node of `level` completed by the send at `leaf`
+async fn complete_send_tree_node(storage: &ArbitrumStorage, level: u64, leaf: u64) -> Result<B256> {
+    if level == 0 {
+        let send = storage
+            .get_send(leaf)
+            .await?
+            .ok_or_else(|| eyre!("Send {leaf} not found"))?;
+        return Ok(keccak256(send.hash));
+    }
+    storage
+        .get_send_merkle_node(level, leaf)
+        .await?
+        .ok_or_else(|| eyre!("Send tree node of level {level} at leaf {leaf} not found"))
+}
+
+/// Sends to L1 up to and including the latest block
+async fn latest_send_count(storage: &ArbitrumStorage) -> Result<u64> {
+    let latest = storage.get_current_block_number().await?;
+    Ok(storage
+        .get_block_by_number(latest)
+        .await?
+        .map_or(0, |block| block.send_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrum_storage::{ArbitrumBlock, SendMerkleState};
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_outbox_proofs_from_stored_nodes() {
+        let temp = TempDir::new().unwrap();
+        let mut cfg = ArbitrumRethConfig::default();
+        cfg.node.datadir = temp.path().to_path_buf();
+        let storage = ArbitrumStorage::new(&cfg).await.unwrap();
+        storage.start().await.unwrap();
+
+        // Store the sends and the nodes they complete, as execution does
+        let hashes: Vec<B256> = (1..=11).map(B256::with_last_byte).collect();
+        let mut merkle = SendMerkleState::default();
+        let mut roots = vec![];
+        for (position, hash) in hashes.iter().enumerate() {
+            storage
+                .store_send(&L2ToL1Send {
+                    position: position as u64,
+                    hash: *hash,
+                    caller: Address::ZERO,
+                    destination: Address::ZERO,
+                    arb_block_number: 1,
+                    eth_block_number: 0,
+                    timestamp: 0,
+                    callvalue: U256::ZERO,
+                    data: vec![],
+                    transaction_hash: B256::ZERO,
+                })
+                .await
+                .unwrap();
+            for update in outbox::append(&mut merkle, *hash) {
+                storage
+                    .store_send_merkle_node(update.level, update.leaf, update.hash)
+                    .await
+                    .unwrap();
+            }
+            roots.push(outbox::root(&merkle));
+        }
+        storage
+            .store_block(&ArbitrumBlock {
+                number: 1,
+                send_count: hashes.len() as u64,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // Every proof matches the one built from the whole tree
+        for size in 1..=hashes.len() as u64 {
+            for leaf in 0..size {
+                let proof = outbox_proof(&storage, size, leaf).await.unwrap();
+                assert_eq!(proof.send.hash, hashes[leaf as usize]);
+                assert_eq!(
+                    proof.proof,
+                    outbox::proof(&hashes[..size as usize], leaf),
+                    "leaf {leaf} of {size}"
+                );
+                assert_eq!(proof.root, roots[size as usize - 1]);
+            }
+        }
+        assert!(outbox_proof(&storage, 12, 0).await.is_err());
+        assert!(outbox_proof(&storage, 3, 3).await.is_err());
+    }
+}
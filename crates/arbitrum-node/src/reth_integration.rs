@@ -2,24 +2,33 @@ use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 
 use alloy_primitives::{Address, B256, U256};
 use arbitrum_config::ArbitrumRethConfig;
-use arbitrum_storage::ArbitrumStorage;
+use arbitrum_consensus::{
+    execution::{self, ArbOsState},
+    l1_pricing,
+};
+use arbitrum_storage::{
+    ArbitrumBlock, ArbitrumStorage, ArbitrumTransaction, ArbitrumTxKind, L2Reorg,
+};
 use axum::{
     Json, Router, extract::State, response::IntoResponse, routing::get, serve as axum_serve,
 };
 use eyre::Result;
 use serde::{Deserialize, Serialize};
 use tokio::{
-    sync::{Mutex, oneshot},
+    sync::{Mutex, broadcast, oneshot},
     task::JoinHandle,
 };
 use tracing::{debug, info};
 
+use crate::node_interface::{self, NODE_INTERFACE_ADDRESS};
+
 /// Minimal scaffold for integrating with Reth SDK. This will be replaced by real NodeBuilder wiring.
 pub struct RethNodeHandle {
     server_shutdown_tx: Mutex<Option<oneshot::Sender<()>>>,
     server_task: Mutex<Option<JoinHandle<()>>>,
     reth_task: Mutex<Option<JoinHandle<()>>>,
     prune_task: Mutex<Option<JoinHandle<()>>>,
+    reorg_task: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl RethNodeHandle {
@@ -36,6 +45,10 @@ impl RethNodeHandle {
         if let Some(task) = self.prune_task.lock().await.take() {
             task.abort();
         }
+        // Stop forwarding reorgs to filters
+        if let Some(task) = self.reorg_task.lock().await.take() {
+            task.abort();
+        }
         Ok(())
     }
 
@@ -49,6 +62,9 @@ impl RethNodeHandle {
         if let Some(task) = self.prune_task.lock().await.take() {
             let _ = task.await;
         }
+        if let Some(task) = self.reorg_task.lock().await.take() {
+            let _ = task.await;
+        }
         Ok(())
     }
 }
@@ -151,6 +167,28 @@ pub async fn launch_reth_node(
         None
     };
 
+    // Rewind filters and queue removed logs when L2 blocks are unwound
+    let reorg_task = state.storage.as_ref().map(|storage_arc| {
+        let filters_arc = Arc::clone(&state.filters);
+        let storage_clone = Arc::clone(storage_arc);
+        let mut reorgs = storage_arc.subscribe_reorgs();
+        tokio::spawn(async move {
+            loop {
+                match reorgs.recv().await {
+                    Ok(reorg) => {
+                        let mut mgr = filters_arc.lock().await;
+                        mgr.apply_reorg(&reorg, &storage_clone).await;
+                        debug!(new_head = reorg.new_head, "Applied L2 reorg to filters");
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "Missed L2 reorg notifications");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    });
+
     // Optionally start experimental Reth NodeBuilder
     #[cfg(feature = "experimental-reth")]
     let reth_task = Some(spawn_reth_nodebuilder());
@@ -162,6 +200,7 @@ pub async fn launch_reth_node(
         server_task: Mutex::new(Some(server_task)),
         reth_task: Mutex::new(reth_task),
         prune_task: Mutex::new(prune_task),
+        reorg_task: Mutex::new(reorg_task),
     })
 }
 
@@ -224,20 +263,69 @@ fn address_to_hex(a: &Address) -> String {
 }
 
 fn block_object(block: &arbitrum_storage::ArbitrumBlock) -> serde_json::Value {
+    let header = block.header();
     serde_json::json!({
         "number": u64_to_hex(block.number),
         "hash": b256_to_hex(&block.hash),
         "parentHash": b256_to_hex(&block.parent_hash),
+        "sha3Uncles": b256_to_hex(&header.ommers_hash),
+        "miner": address_to_hex(&header.beneficiary),
+        "stateRoot": b256_to_hex(&block.state_root),
+        "transactionsRoot": b256_to_hex(&block.transactions_root),
+        "receiptsRoot": b256_to_hex(&block.receipts_root),
+        "logsBloom": format!("0x{}", hex::encode(block.logs_bloom.as_slice())),
+        "difficulty": u256_to_hex(&header.difficulty),
         "timestamp": u64_to_hex(block.timestamp),
         "gasUsed": u64_to_hex(block.gas_used),
         "gasLimit": u64_to_hex(block.gas_limit),
-    "transactions": block.transactions.iter().map(b256_to_hex).collect::<Vec<_>>(),
-        // Minimal shape; add fields as needed for parity tests
+        "baseFeePerGas": u64_to_hex(block.base_fee),
+        "extraData": format!("0x{}", hex::encode(&header.extra_data)),
+        "mixHash": b256_to_hex(&header.mix_hash),
+        "nonce": format!("0x{}", hex::encode(header.nonce.as_slice())),
+        "uncles": [],
+        "transactions": block.transactions.iter().map(b256_to_hex).collect::<Vec<_>>(),
+        // Nitro fields decoded from the header
+        "l1BlockNumber": u64_to_hex(block.l1_block_number),
+        "sendCount": u64_to_hex(block.send_count),
+        "sendRoot": b256_to_hex(&block.send_root),
     })
 }
 
-fn tx_object(tx: &arbitrum_storage::ArbitrumTransaction) -> serde_json::Value {
+fn log_object(log: &arbitrum_storage::Log) -> serde_json::Value {
     serde_json::json!({
+        "address": address_to_hex(&log.address),
+        "topics": log.topics.iter().map(b256_to_hex).collect::<Vec<_>>(),
+        "data": format!("0x{}", hex::encode(&log.data)),
+        "blockHash": log.block_hash.as_ref().map(b256_to_hex),
+        "blockNumber": log.block_number.map(u64_to_hex),
+        "transactionHash": log.transaction_hash.as_ref().map(b256_to_hex),
+        "transactionIndex": log.transaction_index.map(u64_to_hex),
+        "logIndex": log.log_index.map(u64_to_hex),
+        "removed": log.removed,
+    })
+}
+
+/// Send to L1 with the fields the outbox executes it with
+fn send_object(send: &arbitrum_storage::L2ToL1Send) -> serde_json::Value {
+    serde_json::json!({
+        "index": u64_to_hex(send.position),
+        "hash": b256_to_hex(&send.hash),
+        "l2Sender": address_to_hex(&send.caller),
+        "to": address_to_hex(&send.destination),
+        "l2Block": u64_to_hex(send.arb_block_number),
+        "l1Block": u64_to_hex(send.eth_block_number),
+        "l2Timestamp": u64_to_hex(send.timestamp),
+        "value": u256_to_hex(&send.callvalue),
+        "data": format!("0x{}", hex::encode(&send.data)),
+        "transactionHash": b256_to_hex(&send.transaction_hash),
+    })
+}
+
+/// Transaction as Nitro's RPC shows it, with the fields of its Arbitrum type
+fn tx_object(tx: &arbitrum_storage::ArbitrumTransaction, chain_id: u64) -> serde_json::Value {
+    let mut object = serde_json::json!({
+        "type": u64_to_hex(u64::from(tx.tx_type())),
+        "chainId": u64_to_hex(chain_id),
         "hash": b256_to_hex(&tx.hash),
         "from": address_to_hex(&tx.from),
         "to": tx.to.as_ref().map(address_to_hex),
@@ -245,8 +333,55 @@ fn tx_object(tx: &arbitrum_storage::ArbitrumTransaction) -> serde_json::Value {
         "nonce": u64_to_hex(tx.nonce),
         "gas": u64_to_hex(tx.gas),
         "gasPrice": u256_to_hex(&tx.gas_price),
-        // Minimal set
-    })
+        "input": format!("0x{}", hex::encode(&tx.data)),
+    });
+    let extra = match &tx.kind {
+        ArbitrumTxKind::Deposit { l1_request_id } => serde_json::json!({
+            "requestId": b256_to_hex(l1_request_id),
+        }),
+        ArbitrumTxKind::Contract { request_id } => serde_json::json!({
+            "requestId": b256_to_hex(request_id),
+        }),
+        ArbitrumTxKind::Retry {
+            ticket_id,
+            refund_to,
+            max_refund,
+            submission_fee_refund,
+        } => serde_json::json!({
+            "ticketId": b256_to_hex(ticket_id),
+            "refundTo": address_to_hex(refund_to),
+            "maxRefund": u256_to_hex(max_refund),
+            "submissionFeeRefund": u256_to_hex(submission_fee_refund),
+        }),
+        // The retry is described by the common fields, which Nitro also
+        // shows under its own names
+        ArbitrumTxKind::SubmitRetryable {
+            request_id,
+            l1_base_fee,
+            deposit_value,
+            max_submission_fee,
+            fee_refund_address,
+            beneficiary,
+        } => serde_json::json!({
+            "requestId": b256_to_hex(request_id),
+            "l1BaseFee": u256_to_hex(l1_base_fee),
+            "depositValue": u256_to_hex(deposit_value),
+            "retryTo": tx.to.as_ref().map(address_to_hex),
+            "retryValue": u256_to_hex(&tx.value),
+            "retryData": format!("0x{}", hex::encode(&tx.data)),
+            "beneficiary": address_to_hex(beneficiary),
+            "maxSubmissionFee": u256_to_hex(max_submission_fee),
+            "refundTo": address_to_hex(fee_refund_address),
+        }),
+        ArbitrumTxKind::Signed
+        | ArbitrumTxKind::Unsigned
+        | ArbitrumTxKind::BatchPostingReport { .. }
+        | ArbitrumTxKind::Initialize { .. } => serde_json::json!({}),
+    };
+    if let (Some(object), serde_json::Value::Object(extra)) = (object.as_object_mut(), extra) {
+        object.extend(extra);
+    }
+    object
 }
 
 #[allow(clippy::collapsible_if)]
@@ -270,7 +405,62 @@ async fn json_rpc(
                 serde_json::json!("0x0")
             }
         }
-        "eth_gasPrice" => serde_json::json!("0x174876e800"),
+        "eth_gasPrice" => {
+            // Arbitrum pays no tips, so the base fee is the whole price
+            let base_fee = match &state.storage {
+                Some(storage) => next_base_fee(&state.config, storage).await,
+                None => state.config.gas.l2_gas_price,
+            };
+            serde_json::json!(u64_to_hex(base_fee))
+        }
+        "eth_feeHistory" => {
+            // params: [blockCount, newestBlock, rewardPercentiles]
+            let mut out = serde_json::Value::Null;
+            if let (Some(params), Some(storage)) = (
+                req.params.as_ref().and_then(|v| v.as_array()),
+                &state.storage,
+            ) {
+                match fee_history(&state.config, storage, params).await {
+                    Ok(history) => out = history,
+                    Err(e) => debug!("Fee history failed: {}", e),
+                }
+            }
+            out
+        }
+        "eth_estimateGas" => {
+            // params: [{from, to, gas, gasPrice, value, data}, "latest"]
+            let mut out = serde_json::Value::Null;
+            if let (Some(call), Some(storage)) = (
+                req.params
+                    .as_ref()
+                    .and_then(|v| v.get(0))
+                    .and_then(|v| v.as_object()),
+                &state.storage,
+            ) {
+                match estimate_gas(&state.config, storage, call).await {
+                    Ok(gas) => out = serde_json::json!(u64_to_hex(gas)),
+                    Err(e) => debug!("Gas estimation failed: {}", e),
+                }
+            }
+            out
+        }
+        "eth_call" => {
+            // params: [{from, to, gas, gasPrice, value, data}, "latest"]
+            let mut out = serde_json::Value::Null;
+            if let (Some(params), Some(storage)) = (
+                req.params
+                    .as_ref()
+                    .and_then(|v| v.get(0))
+                    .and_then(|v| v.as_object()),
+                &state.storage,
+            ) {
+                match call(&state.config, storage, params).await {
+                    Ok(output) => out = serde_json::json!(format!("0x{}", hex::encode(output))),
+                    Err(e) => debug!("Call failed: {}", e),
+                }
+            }
+            out
+        }
         "eth_getBlockByNumber" => {
             // params: ["0xN"|"latest", includeTxs]
             let mut out = serde_json::Value::Null;
@@ -311,7 +501,7 @@ async fn json_rpc(
                             let expanded: Vec<serde_json::Value> = txs
                                 .into_iter()
                                 .filter_map(|res| res.ok().flatten())
-                                .map(|tx| tx_object(&tx))
+                                .map(|tx| tx_object(&tx, state.config.l2.chain_id))
                                 .collect();
                             *arr = expanded;
                         }
@@ -353,7 +543,7 @@ async fn json_rpc(
                                 let expanded: Vec<serde_json::Value> = txs
                                     .into_iter()
                                     .filter_map(|res| res.ok().flatten())
-                                    .map(|tx| tx_object(&tx))
+                                    .map(|tx| tx_object(&tx, state.config.l2.chain_id))
                                     .collect();
                                 *arr = expanded;
                             }
@@ -471,7 +661,7 @@ async fn json_rpc(
                     .and_then(parse_b256_hex)
                 {
                     if let Ok(Some(tx)) = storage.get_transaction(&h).await {
-                        out = tx_object(&tx);
+                        out = tx_object(&tx, state.config.l2.chain_id);
                     }
                 }
             }
@@ -496,18 +686,9 @@ async fn json_rpc(
                             "blockNumber": u64_to_hex(rcpt.block_number),
                             "cumulativeGasUsed": u64_to_hex(rcpt.cumulative_gas_used),
                             "gasUsed": u64_to_hex(rcpt.gas_used),
+                            "gasUsedForL1": u64_to_hex(rcpt.gas_used_for_l1),
                             "contractAddress": rcpt.contract_address.as_ref().map(address_to_hex),
-                            "logs": rcpt.logs.iter().map(|l| serde_json::json!({
-                                "address": address_to_hex(&l.address),
-                                "topics": l.topics.iter().map(b256_to_hex).collect::<Vec<_>>(),
-                                "data": format!("0x{}", hex::encode(&l.data)),
-                                "blockHash": l.block_hash.as_ref().map(b256_to_hex),
-                                "blockNumber": l.block_number.map(u64_to_hex),
-                                "transactionHash": l.transaction_hash.as_ref().map(b256_to_hex),
-                                "transactionIndex": l.transaction_index.map(u64_to_hex),
-                                "logIndex": l.log_index.map(u64_to_hex),
-                                "removed": l.removed,
-                            })).collect::<Vec<_>>(),
+                            "logs": rcpt.logs.iter().map(log_object).collect::<Vec<_>>(),
                             "status": u64_to_hex(rcpt.status),
                             "effectiveGasPrice": u256_to_hex(&rcpt.effective_gas_price),
                         });
@@ -550,7 +731,7 @@ async fn json_rpc(
                     && let Some(h) = block.transactions.get(i)
                     && let Ok(Some(tx)) = storage.get_transaction(h).await
                 {
-                    out = tx_object(&tx);
+                    out = tx_object(&tx, state.config.l2.chain_id);
                 }
             }
             out
@@ -581,7 +762,7 @@ async fn json_rpc(
                     && let Some(txh) = block.transactions.get(i)
                     && let Ok(Some(tx)) = storage.get_transaction(txh).await
                 {
-                    out = tx_object(&tx);
+                    out = tx_object(&tx, state.config.l2.chain_id);
                 }
             }
             out
@@ -642,17 +823,22 @@ async fn json_rpc(
                     .and_then(|hex| u64::from_str_radix(hex, 16).ok())
                 {
                     let mut mgr = state.filters.lock().await;
+                    // Logs of unwound blocks are reported first, flagged as removed
+                    let mut logs: Vec<serde_json::Value> =
+                        mgr.take_removed_logs(id).iter().map(log_object).collect();
                     if let Some((from, to, def)) = mgr.next_poll_range(id, storage).await {
-                        let logs = collect_logs_in_range(
-                            storage,
-                            from,
-                            to,
-                            def.addresses.as_ref(),
-                            def.topics.as_ref(),
-                        )
-                        .await;
-                        out = serde_json::Value::Array(logs);
+                        logs.extend(
+                            collect_logs_in_range(
+                                storage,
+                                from,
+                                to,
+                                def.addresses.as_ref(),
+                                def.topics.as_ref(),
+                            )
+                            .await,
+                        );
                     }
+                    out = serde_json::Value::Array(logs);
                 }
             }
             out
@@ -672,6 +858,36 @@ async fn json_rpc(
             }
             out
         }
+        "arb_getL2ToL1Proof" => {
+            // params: [sendIndex, blockNumber|"latest"]
+            let mut out = serde_json::Value::Null;
+            if let (Some(params), Some(storage)) = (
+                req.params.as_ref().and_then(|v| v.as_array()),
+                &state.storage,
+            ) {
+                match l2_to_l1_proof(storage, params).await {
+                    Ok(proof) => out = proof,
+                    Err(e) => debug!("L2-to-L1 proof failed: {}", e),
+                }
+            }
+            out
+        }
+        "arb_findL2ToL1Messages" => {
+            // params: [{fromBlock, toBlock, l2Sender, to}]
+            let mut out = serde_json::Value::Array(vec![]);
+            if let (Some(params), Some(storage)) = (
+                req.params.as_ref().and_then(|v| v.as_array()),
+                &state.storage,
+            ) {
+                if let Some(f) = params.first().and_then(|v| v.as_object()) {
+                    match find_l2_to_l1_messages(storage, f).await {
+                        Ok(sends) => out = serde_json::Value::Array(sends),
+                        Err(e) => debug!("Finding L2-to-L1 messages failed: {}", e),
+                    }
+                }
+            }
+            out
+        }
         _ => serde_json::Value::Null,
     };
 
@@ -752,6 +968,295 @@ async fn parse_filter_fields(
     (from_block, to_block, addrs, topics)
 }
 
+/// Transaction of a call object as `eth_call` and `eth_estimateGas` take it
+fn call_transaction(
+    call: &serde_json::Map<String, serde_json::Value>,
+) -> Result<ArbitrumTransaction> {
+    let field = |name: &str| call.get(name).and_then(|v| v.as_str());
+    let data = field("data")
+        .or_else(|| field("input"))
+        .map(|s| hex::decode(s.strip_prefix("0x").unwrap_or(s)))
+        .transpose()?
+        .unwrap_or_default();
+    Ok(ArbitrumTransaction {
+        hash: B256::ZERO,
+        from: field("from")
+            .and_then(parse_address_hex)
+            .unwrap_or_default(),
+        to: field("to").and_then(parse_address_hex),
+        value: field("value").and_then(parse_u256_hex).unwrap_or_default(),
+        gas: field("gas")
+            .and_then(parse_u256_hex)
+            .map_or(0, |gas| gas.saturating_to()),
+        gas_price: field("gasPrice")
+            .and_then(parse_u256_hex)
+            .unwrap_or_default(),
+        nonce: 0,
+        data,
+        l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
+    })
+}
+
+/// Block a call runs in: the latest, at the base fee of the next one that
+/// would include it
+pub(crate) async fn pending_block(
+    config: &ArbitrumRethConfig,
+    storage: &Arc<ArbitrumStorage>,
+    arbos: &ArbOsState,
+) -> Result<ArbitrumBlock> {
+    let latest = storage.get_current_block_number().await?;
+    let block = storage
+        .get_block_by_number(latest)
+        .await?
+        .unwrap_or(ArbitrumBlock {
+            gas_limit: config.l2.gas_limit,
+            ..Default::default()
+        });
+    Ok(ArbitrumBlock {
+        base_fee: arbos.l2_pricing.base_fee,
+        ..block
+    })
+}
+
+/// Output of a call on top of the latest block
+async fn call(
+    config: &ArbitrumRethConfig,
+    storage: &Arc<ArbitrumStorage>,
+    call: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Vec<u8>> {
+    let tx = call_transaction(call)?;
+    if tx.to == Some(NODE_INTERFACE_ADDRESS) {
+        return node_interface::call(config, storage, &tx).await;
+    }
+    let arbos = ArbOsState::load(storage, &config.gas).await?;
+    let block = pending_block(config, storage, &arbos).await?;
+    let result = execution::call(
+        Arc::clone(storage),
+        config.l2.chain_id,
+        &block,
+        &tx,
+        &arbos,
+        U256::ZERO,
+    )
+    .await?;
+    if !result.is_success() {
+        return Err(eyre::eyre!("Call did not succeed: {result:?}"));
+    }
+    Ok(result
+        .output()
+        .map(|output| output.to_vec())
+        .unwrap_or_default())
+}
+
+/// Gas a call needs on top of the latest block, including its L1 data fee
+async fn estimate_gas(
+    config: &ArbitrumRethConfig,
+    storage: &Arc<ArbitrumStorage>,
+    call: &serde_json::Map<String, serde_json::Value>,
+) -> Result<u64> {
+    let tx = call_transaction(call)?;
+    if tx.to == Some(NODE_INTERFACE_ADDRESS) {
+        return node_interface::estimate_gas(config, storage, &tx).await;
+    }
+    Ok(estimate_transaction(config, storage, &tx, U256::ZERO)
+        .await?
+        .gas)
+}
+
+/// Gas estimate of a transaction, in the parts NodeInterface reports
+pub(crate) struct GasEstimate {
+    pub gas: u64,          // Gas limit the transaction needs, its L1 data fee included
+    pub gas_for_l1: u64,   // Gas paying for its L1 data, padded
+    pub base_fee: u64,     // Base fee of the block it is estimated in
+    pub l1_base_fee: U256, // L1 price per unit of calldata
+}
+
+/// Estimate the gas `tx` needs on top of the latest block, with `deposit`
+/// credited to its sender
+pub(crate) async fn estimate_transaction(
+    config: &ArbitrumRethConfig,
+    storage: &Arc<ArbitrumStorage>,
+    tx: &ArbitrumTransaction,
+    deposit: U256,
+) -> Result<GasEstimate> {
+    let arbos = ArbOsState::load(storage, &config.gas).await?;
+    let block = pending_block(config, storage, &arbos).await?;
+    let l2_gas = execution::estimate_gas(
+        Arc::clone(storage),
+        config.l2.chain_id,
+        &block,
+        tx,
+        &arbos,
+        deposit,
+    )
+    .await?;
+    let l1_base_fee = arbos.l1_pricing.price_per_unit;
+    let gas_for_l1 = l1_pricing::estimated_poster_gas(tx, l1_base_fee, block.base_fee);
+    Ok(GasEstimate {
+        gas: l2_gas + gas_for_l1,
+        gas_for_l1,
+        base_fee: block.base_fee,
+        l1_base_fee,
+    })
+}
+
+/// Base fee of the next block, set by the L2 pricing as of the latest one
+async fn next_base_fee(config: &ArbitrumRethConfig, storage: &Arc<ArbitrumStorage>) -> u64 {
+    match storage.get_l2_pricing().await {
+        Ok(Some(pricing)) => pricing.base_fee,
+        _ => config.gas.l2_gas_price,
+    }
+}
+
+/// Most blocks a fee history covers
+const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
+/// Base fees and gas used of up to `blockCount` blocks ending at `newestBlock`
+///
+/// The base fees run one past the newest block. Rewards are zero at every
+/// percentile, as Arbitrum pays no tips.
+async fn fee_history(
+    config: &ArbitrumRethConfig,
+    storage: &Arc<ArbitrumStorage>,
+    params: &[serde_json::Value],
+) -> Result<serde_json::Value> {
+    let count = parse_number(params.first())
+        .ok_or_else(|| eyre::eyre!("Invalid block count"))?
+        .min(MAX_FEE_HISTORY_BLOCKS);
+    let latest = storage.get_current_block_number().await?;
+    let newest = match params.get(1).and_then(|v| v.as_str()) {
+        Some("latest" | "pending") | None => latest,
+        _ => parse_number(params.get(1))
+            .ok_or_else(|| eyre::eyre!("Invalid newest block"))?
+            .min(latest),
+    };
+    let percentiles = params
+        .get(2)
+        .and_then(|v| v.as_array())
+        .map_or(0, |percentiles| percentiles.len());
+
+    let oldest = (newest + 1).saturating_sub(count);
+    let mut base_fees = vec![];
+    let mut gas_used_ratios = vec![];
+    for number in oldest..=newest {
+        let block = storage
+            .get_block_by_number(number)
+            .await?
+            .ok_or_else(|| eyre::eyre!("Block {number} not found"))?;
+        base_fees.push(u64_to_hex(block.base_fee));
+        gas_used_ratios.push(if block.gas_limit == 0 {
+            0.0
+        } else {
+            block.gas_used as f64 / block.gas_limit as f64
+        });
+    }
+    let next = if newest == latest {
+        next_base_fee(config, storage).await
+    } else {
+        storage
+            .get_block_by_number(newest + 1)
+            .await?
+            .map_or(config.gas.l2_gas_price, |block| block.base_fee)
+    };
+    base_fees.push(u64_to_hex(next));
+
+    let blocks = gas_used_ratios.len();
+    let mut history = serde_json::json!({
+        "oldestBlock": u64_to_hex(oldest),
+        "baseFeePerGas": base_fees,
+        "gasUsedRatio": gas_used_ratios,
+    });
+    if percentiles > 0 {
+        history["reward"] = serde_json::json!(vec![vec!["0x0"; percentiles]; blocks]);
+    }
+    Ok(history)
+}
+
+/// Proof of a send to L1 against the send root of a block, the latest by
+/// default, with the send
+async fn l2_to_l1_proof(
+    storage: &Arc<ArbitrumStorage>,
+    params: &[serde_json::Value],
+) -> Result<serde_json::Value> {
+    let index = parse_number(params.first()).ok_or_else(|| eyre::eyre!("Invalid send index"))?;
+    let latest = storage.get_current_block_number().await?;
+    let number = match params.get(1).and_then(|v| v.as_str()) {
+        Some("latest") | None => latest,
+        _ => parse_number(params.get(1)).ok_or_else(|| eyre::eyre!("Invalid block"))?,
+    };
+    let block = storage
+        .get_block_by_number(number)
+        .await?
+        .ok_or_else(|| eyre::eyre!("Block {number} not found"))?;
+    let proof = node_interface::outbox_proof(storage, block.send_count, index).await?;
+
+    let mut out = send_object(&proof.send);
+    out["root"] = serde_json::json!(b256_to_hex(&proof.root));
+    out["sendCount"] = serde_json::json!(u64_to_hex(block.send_count));
+    out["blockNumber"] = serde_json::json!(u64_to_hex(block.number));
+    out["proof"] = serde_json::json!(proof.proof.iter().map(b256_to_hex).collect::<Vec<_>>());
+    Ok(out)
+}
+
+/// Sends to L1 of the blocks in a filter's range, of the L2 sender and to
+/// the L1 destination it names if any
+async fn find_l2_to_l1_messages(
+    storage: &Arc<ArbitrumStorage>,
+    f: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Vec<serde_json::Value>> {
+    let (from_block, to_block, _, _) = parse_filter_fields(f, storage).await;
+    let address = |name: &str| {
+        f.get(name)
+            .and_then(|v| v.as_str())
+            .and_then(parse_address_hex)
+    };
+    let (sender, destination) = (address("l2Sender"), address("to"));
+
+    // The sends of a block follow those of its parent
+    let send_count = |number: u64| async move {
+        Ok::<_, eyre::Report>(
+            storage
+                .get_block_by_number(number)
+                .await?
+                .map_or(0, |block| block.send_count),
+        )
+    };
+    let first = match from_block.checked_sub(1) {
+        Some(parent) => send_count(parent).await?,
+        None => 0,
+    };
+    let end = send_count(to_block).await?;
+    if end <= first {
+        return Ok(vec![]);
+    }
+    Ok(storage
+        .get_sends(first, end - 1)
+        .await?
+        .iter()
+        .filter(|send| sender.is_none_or(|sender| send.caller == sender))
+        .filter(|send| destination.is_none_or(|destination| send.destination == destination))
+        .map(send_object)
+        .collect())
+}
+
+/// Quantity given as a hex or decimal string, or as a number
+fn parse_number(value: Option<&serde_json::Value>) -> Option<u64> {
+    match value {
+        Some(serde_json::Value::Number(n)) => n.as_u64(),
+        Some(serde_json::Value::String(s)) => match s.strip_prefix("0x") {
+            Some(stripped) => u64::from_str_radix(stripped, 16).ok(),
+            None => s.parse::<u64>().ok(),
+        },
+        _ => None,
+    }
+}
+
+fn parse_u256_hex(s: &str) -> Option<U256> {
+    U256::from_str_radix(s.strip_prefix("0x").unwrap_or(s), 16).ok()
+}
+
 fn parse_address_hex(s: &str) -> Option<Address> {
     let hex = s.strip_prefix("0x").unwrap_or(s);
     let bytes = hex::decode(hex).ok()?;
@@ -894,6 +1399,8 @@ struct FilterDef {
 struct FilterInstance {
     def: FilterDef,
     last_block: u64,
+    // Matching logs of unwound blocks not yet delivered
+    removed_logs: Vec<arbitrum_storage::Log>,
 }
 
 #[derive(Default)]
@@ -910,11 +1417,50 @@ impl FiltersManager {
     fn install_filter(&mut self, def: FilterDef) -> u64 {
         self.next_id = self.next_id.saturating_add(1);
         let id = self.next_id;
-        self.installed
-            .insert(id, FilterInstance { def, last_block: 0 });
+        self.installed.insert(
+            id,
+            FilterInstance {
+                def,
+                last_block: 0,
+                removed_logs: Vec::new(),
+            },
+        );
         id
     }
 
+    /// Rewind filters past the new head and queue the removed logs they had already delivered
+    async fn apply_reorg(&mut self, reorg: &L2Reorg, storage: &Arc<ArbitrumStorage>) {
+        for (id, inst) in self.installed.iter_mut() {
+            if inst.last_block <= reorg.new_head {
+                continue;
+            }
+            let delivered_up_to = inst.last_block;
+            inst.removed_logs.extend(
+                reorg
+                    .removed_logs
+                    .iter()
+                    .filter(|log| {
+                        log.block_number.is_some_and(|n| n <= delivered_up_to)
+                            && log_matches(
+                                log,
+                                inst.def.addresses.as_ref(),
+                                inst.def.topics.as_ref(),
+                            )
+                    })
+                    .cloned(),
+            );
+            inst.last_block = reorg.new_head;
+            let _ = storage.set_filter_cursor(*id, inst.last_block).await;
+        }
+    }
+
+    fn take_removed_logs(&mut self, id: u64) -> Vec<arbitrum_storage::Log> {
+        self.installed
+            .get_mut(&id)
+            .map(|inst| std::mem::take(&mut inst.removed_logs))
+            .unwrap_or_default()
+    }
+
     async fn next_poll_range(
         &mut self,
         id: u64,
@@ -1021,6 +1567,7 @@ mod tests {
                 gas_limit: 30_000_000,
                 transactions: vec![],
                 l1_block_number: 0,
+                ..Default::default()
             };
             storage.store_block(&blk).await.unwrap();
         }
@@ -1061,10 +1608,116 @@ mod tests {
         // Next poll should prune and return None
         let r2 = mgr.next_poll_range(id, &storage).await;
         assert!(r2.is_none());
-        assert_eq!(mgr.installed.contains_key(&id), false);
+        assert!(!mgr.installed.contains_key(&id));
         assert!(mgr.pruned_total >= 1);
     }
 
+    #[tokio::test]
+    async fn test_filters_manager_reorg_delivers_removed_logs() {
+        let (storage, _tmp, _cfg) = make_storage().await;
+        let addr = address!("0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa");
+
+        // Blocks 1..=4 with one matching log each
+        for n in 1..=4u64 {
+            let tx_hash = B256::from([0x10 + n as u8; 32]);
+            let blk = arbitrum_storage::ArbitrumBlock {
+                number: n,
+                hash: B256::from([n as u8; 32]),
+                parent_hash: B256::from([(n - 1) as u8; 32]),
+                timestamp: 1_700_000_000 + n,
+                gas_used: 21_000,
+                gas_limit: 30_000_000,
+                transactions: vec![tx_hash],
+                l1_block_number: 100 + n,
+                ..Default::default()
+            };
+            storage.store_block(&blk).await.unwrap();
+            storage
+                .store_receipt(&arbitrum_storage::ArbitrumReceipt {
+                    transaction_hash: tx_hash,
+                    transaction_index: 0,
+                    block_hash: blk.hash,
+                    block_number: n,
+                    cumulative_gas_used: 21_000,
+                    gas_used: 21_000,
+                    gas_used_for_l1: 0,
+                    contract_address: None,
+                    logs: vec![arbitrum_storage::Log {
+                        address: addr,
+                        topics: vec![],
+                        data: vec![n as u8],
+                        block_hash: None,
+                        block_number: None,
+                        transaction_hash: None,
+                        transaction_index: None,
+                        log_index: None,
+                        removed: false,
+                    }],
+                    status: 1,
+                    effective_gas_price: U256::ZERO,
+                })
+                .await
+                .unwrap();
+        }
+
+        let mut mgr = FiltersManager {
+            ttl_millis: FiltersManager::DEFAULT_TTL_MILLIS,
+            ..Default::default()
+        };
+        let id = mgr.install_filter(FilterDef {
+            from_block: None,
+            to_block: None,
+            addresses: Some(vec![addr]),
+            topics: None,
+        });
+        let other = mgr.install_filter(FilterDef {
+            from_block: None,
+            to_block: None,
+            addresses: Some(vec![Address::ZERO]),
+            topics: None,
+        });
+        assert_eq!(mgr.next_poll_range(id, &storage).await.unwrap().1, 4);
+        assert_eq!(mgr.next_poll_range(other, &storage).await.unwrap().1, 4);
+
+        // L1 block 102 is orphaned, unwinding L2 blocks 3 and 4
+        let removed = storage.unwind_blocks_after_l1_block(102).await.unwrap();
+        mgr.apply_reorg(
+            &L2Reorg {
+                new_head: 2,
+                removed_logs: removed,
+            },
+            &storage,
+        )
+        .await;
+
+        let logs = mgr.take_removed_logs(id);
+        assert_eq!(logs.len(), 2);
+        assert!(logs.iter().all(|l| l.removed));
+        assert_eq!(log_object(&logs[0])["blockNumber"], "0x3");
+        assert_eq!(log_object(&logs[1])["removed"], true);
+        assert!(mgr.take_removed_logs(id).is_empty());
+        assert!(mgr.take_removed_logs(other).is_empty());
+        assert_eq!(storage.get_filter_cursor(id).await.unwrap(), 2);
+
+        // Replacement blocks are delivered again from the new head
+        storage
+            .store_block(&arbitrum_storage::ArbitrumBlock {
+                number: 3,
+                hash: B256::from([0x33; 32]),
+                parent_hash: B256::from([2u8; 32]),
+                timestamp: 1_700_000_003,
+                gas_used: 0,
+                gas_limit: 30_000_000,
+                transactions: vec![],
+                l1_block_number: 103,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let (start, end, _) = mgr.next_poll_range(id, &storage).await.unwrap();
+        assert_eq!((start, end), (3, 3));
+    }
+
     #[tokio::test]
     async fn test_collect_logs_index_basic() {
         let (storage, _tmp, _cfg) = make_storage().await;
@@ -1081,6 +1734,7 @@ mod tests {
             gas_limit: 30_000_000,
             transactions: vec![txh1, txh2],
             l1_block_number: 0,
+            ..Default::default()
         };
         storage.store_block(&blk).await.unwrap();
 
@@ -1095,6 +1749,8 @@ mod tests {
             nonce: 0,
             data: vec![],
             l1_sequence_number: None,
+            raw: vec![],
+            kind: arbitrum_storage::ArbitrumTxKind::Signed,
         };
         let tx2 = arbitrum_storage::ArbitrumTransaction {
             hash: blk.transactions[1],
@@ -1124,6 +1780,7 @@ mod tests {
             block_number: 1,
             cumulative_gas_used: 0,
             gas_used: 0,
+            gas_used_for_l1: 0,
             contract_address: None,
             logs: vec![log1.clone()],
             status: 1,
@@ -1175,6 +1832,7 @@ mod tests {
                 gas_limit: 30_000_000,
                 transactions: vec![],
                 l1_block_number: 0,
+                ..Default::default()
             };
             storage.store_block(&blk).await.unwrap();
         }
@@ -2,7 +2,8 @@ use alloy_primitives::{B256, U256, address};
 use arbitrum_config::ArbitrumRethConfig;
 use arbitrum_node::reth_integration::launch_reth_node;
 use arbitrum_storage::{
-    ArbitrumAccount, ArbitrumBlock, ArbitrumReceipt, ArbitrumStorage, ArbitrumTransaction, Log,
+    ArbitrumAccount, ArbitrumBlock, ArbitrumReceipt, ArbitrumStorage, ArbitrumTransaction,
+    ArbitrumTxKind, L2PricingState, Log,
 };
 use tempfile::TempDir;
 
@@ -29,6 +30,7 @@ async fn json_rpc_eth_block_number_reflects_storage() {
         gas_limit: 30_000_000,
         transactions: vec![],
         l1_block_number: 0,
+        ..Default::default()
     };
     storage.store_block(&block).await.expect("store block");
 
@@ -77,6 +79,7 @@ async fn json_rpc_eth_get_block_by_number() {
         gas_limit: 30_000_000,
         transactions: vec![],
         l1_block_number: 0,
+        ..Default::default()
     };
     storage.store_block(&block).await.expect("store block");
 
@@ -162,17 +165,21 @@ async fn json_rpc_eth_get_block_by_hash() {
 
     let storage = ArbitrumStorage::new(&cfg).await.expect("storage new");
     storage.start().await.expect("storage start");
-    let hash = B256::from([9u8; 32]);
     let block = ArbitrumBlock {
         number: 9,
-        hash,
         parent_hash: B256::from([8u8; 32]),
         timestamp: 9,
         gas_used: 9000,
         gas_limit: 30_000_000,
         transactions: vec![],
-        l1_block_number: 0,
-    };
+        l1_block_number: 0x20,
+        send_count: 2,
+        send_root: B256::from([7u8; 32]),
+        delayed_messages_read: 3,
+        ..Default::default()
+    }
+    .seal();
+    let hash = block.hash;
     storage.store_block(&block).await.expect("store block");
 
     let handle = launch_reth_node(&cfg, Some(storage.into()))
@@ -199,6 +206,16 @@ async fn json_rpc_eth_get_block_by_hash() {
         body["result"]["hash"],
         format!("0x{}", hex::encode(hash.as_slice()))
     );
+    assert_eq!(
+        body["result"]["mixHash"],
+        "0x0000000000000002000000000000002000000000000000000000000000000000"
+    );
+    assert_eq!(
+        body["result"]["extraData"],
+        format!("0x{}", hex::encode([7u8; 32]))
+    );
+    assert_eq!(body["result"]["nonce"], "0x0000000000000003");
+    assert_eq!(body["result"]["difficulty"], "0x1");
 
     handle.stop().await.expect("stop");
 }
@@ -280,6 +297,8 @@ async fn json_rpc_eth_transaction_count_and_block_tx_counts() {
         nonce: 7,
         data: vec![],
         l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
     };
     let tx2 = ArbitrumTransaction {
         hash: B256::from([0x22u8; 32]),
@@ -291,6 +310,8 @@ async fn json_rpc_eth_transaction_count_and_block_tx_counts() {
         nonce: 8,
         data: vec![],
         l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
     };
     storage.store_transaction(&tx1).await.expect("store tx1");
     storage.store_transaction(&tx2).await.expect("store tx2");
@@ -304,6 +325,7 @@ async fn json_rpc_eth_transaction_count_and_block_tx_counts() {
         gas_limit: 30_000_000,
         transactions: vec![tx1.hash, tx2.hash],
         l1_block_number: 0,
+        ..Default::default()
     };
     storage.store_block(&block).await.expect("store block");
     // Also store account with nonce to check eth_getTransactionCount
@@ -416,6 +438,8 @@ async fn json_rpc_block_with_full_transactions() {
         nonce: 9,
         data: vec![],
         l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
     };
     storage.store_transaction(&tx).await.expect("store tx");
     let bh = B256::from([0x98u8; 32]);
@@ -428,6 +452,7 @@ async fn json_rpc_block_with_full_transactions() {
         gas_limit: 30_000_000,
         transactions: vec![tx.hash],
         l1_block_number: 0,
+        ..Default::default()
     };
     storage.store_block(&block).await.expect("store block");
 
@@ -497,6 +522,8 @@ async fn json_rpc_tx_by_block_ref_and_index() {
         nonce: 1,
         data: vec![],
         l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
     };
     let tx2 = ArbitrumTransaction {
         hash: B256::from([0x55u8; 32]),
@@ -508,6 +535,8 @@ async fn json_rpc_tx_by_block_ref_and_index() {
         nonce: 2,
         data: vec![],
         l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
     };
     storage.store_transaction(&tx1).await.expect("store tx1");
     storage.store_transaction(&tx2).await.expect("store tx2");
@@ -521,6 +550,7 @@ async fn json_rpc_tx_by_block_ref_and_index() {
         gas_limit: 30_000_000,
         transactions: vec![tx1.hash, tx2.hash],
         l1_block_number: 0,
+        ..Default::default()
     };
     storage.store_block(&block).await.expect("store block");
 
@@ -595,6 +625,8 @@ async fn json_rpc_eth_get_transaction_receipt() {
         nonce: 0,
         data: vec![],
         l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
     };
     storage.store_transaction(&tx).await.expect("store tx");
     let block = ArbitrumBlock {
@@ -606,6 +638,7 @@ async fn json_rpc_eth_get_transaction_receipt() {
         gas_limit: 30_000_000,
         transactions: vec![txh],
         l1_block_number: 0,
+        ..Default::default()
     };
     storage.store_block(&block).await.expect("store block");
     let receipt = ArbitrumReceipt {
@@ -615,6 +648,7 @@ async fn json_rpc_eth_get_transaction_receipt() {
         block_number: 7,
         cumulative_gas_used: 21_000,
         gas_used: 21_000,
+        gas_used_for_l1: 0,
         contract_address: None,
         logs: vec![],
         status: 1,
@@ -649,6 +683,7 @@ async fn json_rpc_eth_get_transaction_receipt() {
     );
     assert_eq!(body["result"]["blockNumber"], "0x7");
     assert_eq!(body["result"]["status"], "0x1");
+    assert_eq!(body["result"]["gasUsedForL1"], "0x0");
 
     handle.stop().await.expect("stop");
 }
@@ -677,6 +712,8 @@ async fn json_rpc_eth_get_logs_basic() {
         nonce: 0,
         data: vec![],
         l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
     };
     storage.store_transaction(&tx).await.expect("store tx");
     let block = ArbitrumBlock {
@@ -688,6 +725,7 @@ async fn json_rpc_eth_get_logs_basic() {
         gas_limit: 30_000_000,
         transactions: vec![txh],
         l1_block_number: 0,
+        ..Default::default()
     };
     storage.store_block(&block).await.expect("store block");
     // Add a receipt with a single log
@@ -700,6 +738,7 @@ async fn json_rpc_eth_get_logs_basic() {
         block_number: 9,
         cumulative_gas_used: 21_000,
         gas_used: 21_000,
+        gas_used_for_l1: 0,
         contract_address: None,
         logs: vec![Log {
             address: log_addr,
@@ -789,6 +828,8 @@ async fn json_rpc_eth_filters_roundtrip() {
         nonce: 0,
         data: vec![],
         l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
     };
     storage.store_transaction(&tx).await.expect("store tx");
     let block = ArbitrumBlock {
@@ -800,6 +841,7 @@ async fn json_rpc_eth_filters_roundtrip() {
         gas_limit: 30_000_000,
         transactions: vec![txh],
         l1_block_number: 0,
+        ..Default::default()
     };
     storage.store_block(&block).await.expect("store block");
     let log_addr = address!("0xcccccccccccccccccccccccccccccccccccccccc");
@@ -811,6 +853,7 @@ async fn json_rpc_eth_filters_roundtrip() {
         block_number: 10,
         cumulative_gas_used: 21_000,
         gas_used: 21_000,
+        gas_used_for_l1: 0,
         contract_address: None,
         logs: vec![Log {
             address: log_addr,
@@ -937,6 +980,8 @@ async fn json_rpc_eth_get_logs_multi_address_and_topic_or() {
         nonce: 0,
         data: vec![],
         l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
     };
     let tx2 = ArbitrumTransaction {
         hash: txh2,
@@ -948,6 +993,8 @@ async fn json_rpc_eth_get_logs_multi_address_and_topic_or() {
         nonce: 1,
         data: vec![],
         l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
     };
     storage.store_transaction(&tx1).await.expect("store tx1");
     storage.store_transaction(&tx2).await.expect("store tx2");
@@ -960,6 +1007,7 @@ async fn json_rpc_eth_get_logs_multi_address_and_topic_or() {
         gas_limit: 30_000_000,
         transactions: vec![txh1, txh2],
         l1_block_number: 0,
+        ..Default::default()
     };
     storage.store_block(&block).await.expect("store block");
 
@@ -974,6 +1022,7 @@ async fn json_rpc_eth_get_logs_multi_address_and_topic_or() {
         block_number: 11,
         cumulative_gas_used: 21_000,
         gas_used: 21_000,
+        gas_used_for_l1: 0,
         contract_address: None,
         logs: vec![Log {
             address: addr1,
@@ -996,6 +1045,7 @@ async fn json_rpc_eth_get_logs_multi_address_and_topic_or() {
         block_number: 11,
         cumulative_gas_used: 42_000,
         gas_used: 21_000,
+        gas_used_for_l1: 0,
         contract_address: None,
         logs: vec![Log {
             address: addr2,
@@ -1087,6 +1137,8 @@ async fn json_rpc_eth_get_logs_multi_position_topics_and() {
         nonce: 0,
         data: vec![],
         l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
     };
     let tx2 = ArbitrumTransaction {
         hash: txh2,
@@ -1098,6 +1150,8 @@ async fn json_rpc_eth_get_logs_multi_position_topics_and() {
         nonce: 1,
         data: vec![],
         l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
     };
     storage.store_transaction(&tx1).await.expect("store tx1");
     storage.store_transaction(&tx2).await.expect("store tx2");
@@ -1110,6 +1164,7 @@ async fn json_rpc_eth_get_logs_multi_position_topics_and() {
         gas_limit: 30_000_000,
         transactions: vec![txh1, txh2],
         l1_block_number: 0,
+        ..Default::default()
     };
     storage.store_block(&block).await.expect("store block");
 
@@ -1126,6 +1181,7 @@ async fn json_rpc_eth_get_logs_multi_position_topics_and() {
         block_number: 12,
         cumulative_gas_used: 21_000,
         gas_used: 21_000,
+        gas_used_for_l1: 0,
         contract_address: None,
         logs: vec![Log {
             address: addr,
@@ -1148,6 +1204,7 @@ async fn json_rpc_eth_get_logs_multi_position_topics_and() {
         block_number: 12,
         cumulative_gas_used: 42_000,
         gas_used: 21_000,
+        gas_used_for_l1: 0,
         contract_address: None,
         logs: vec![Log {
             address: addr,
@@ -1212,3 +1269,912 @@ async fn json_rpc_eth_get_logs_multi_position_topics_and() {
 
     handle.stop().await.expect("stop");
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn json_rpc_eth_estimate_gas_includes_l1_fee() {
+    use arbitrum_consensus::l1_pricing;
+
+    let temp = TempDir::new().expect("tempdir");
+
+    let mut cfg = ArbitrumRethConfig::default();
+    cfg.rpc.port = 18562;
+    cfg.node.datadir = temp.path().to_path_buf();
+
+    let storage = ArbitrumStorage::new(&cfg).await.expect("storage new");
+    storage.start().await.expect("storage start");
+    let base_fee = 100_000_000;
+    let block = ArbitrumBlock {
+        number: 1,
+        gas_limit: 30_000_000,
+        base_fee,
+        ..Default::default()
+    }
+    .seal();
+    storage.store_block(&block).await.expect("store block");
+    let price_per_unit = U256::from(30_000_000_000u64);
+    storage
+        .store_l1_pricing(&l1_pricing::initial_state(price_per_unit))
+        .await
+        .expect("store L1 pricing");
+
+    let handle = launch_reth_node(&cfg, Some(storage.into()))
+        .await
+        .expect("launch");
+    let url = format!("http://127.0.0.1:{}", cfg.rpc.port);
+    let client = reqwest::Client::new();
+    let from = address!("0x3333333333333333333333333333333333333333");
+    let to = address!("0x4444444444444444444444444444444444444444");
+    let data = vec![0x01u8; 10];
+    let resp = client
+        .post(&url)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_estimateGas",
+            "params": [{
+                "from": format!("0x{}", hex::encode(from.as_slice())),
+                "to": format!("0x{}", hex::encode(to.as_slice())),
+                "data": format!("0x{}", hex::encode(&data)),
+            }, "latest"]
+        }))
+        .send()
+        .await
+        .expect("post");
+    assert!(resp.status().is_success());
+    let body: serde_json::Value = resp.json().await.expect("json body");
+
+    // Intrinsic gas of a transfer with ten nonzero data bytes, plus the
+    // padded L1 fee of the same transaction
+    let tx = ArbitrumTransaction {
+        hash: B256::ZERO,
+        from,
+        to: Some(to),
+        value: U256::ZERO,
+        gas: 0,
+        gas_price: U256::ZERO,
+        nonce: 0,
+        data,
+        l1_sequence_number: None,
+        raw: vec![],
+        kind: ArbitrumTxKind::Signed,
+    };
+    let l1_gas = l1_pricing::estimated_poster_gas(&tx, price_per_unit, base_fee);
+    assert!(l1_gas > 0);
+    assert_eq!(body["result"], format!("0x{:x}", 21_000 + 10 * 16 + l1_gas));
+
+    handle.stop().await.expect("stop");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn json_rpc_gas_price_and_fee_history_follow_l2_pricing() {
+    let temp = TempDir::new().expect("tempdir");
+
+    let mut cfg = ArbitrumRethConfig::default();
+    cfg.rpc.port = 18563;
+    cfg.node.datadir = temp.path().to_path_buf();
+
+    let storage = ArbitrumStorage::new(&cfg).await.expect("storage new");
+    storage.start().await.expect("storage start");
+    for (number, base_fee, gas_used) in [(0, 100_000_000, 0), (1, 150_000_000, 15_000_000)] {
+        let block = ArbitrumBlock {
+            number,
+            gas_limit: 30_000_000,
+            gas_used,
+            base_fee,
+            ..Default::default()
+        }
+        .seal();
+        storage.store_block(&block).await.expect("store block");
+    }
+    storage
+        .store_l2_pricing(&L2PricingState {
+            gas_backlog: 200_000_000,
+            base_fee: 200_000_000,
+        })
+        .await
+        .expect("store pricing");
+
+    let handle = launch_reth_node(&cfg, Some(storage.into()))
+        .await
+        .expect("launch");
+    let url = format!("http://127.0.0.1:{}", cfg.rpc.port);
+    let client = reqwest::Client::new();
+    let call = |method: &'static str, params: serde_json::Value| {
+        let client = client.clone();
+        let url = url.clone();
+        async move {
+            let resp = client
+                .post(&url)
+                .json(&serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": method,
+                    "params": params,
+                }))
+                .send()
+                .await
+                .expect("post");
+            assert!(resp.status().is_success());
+            resp.json::<serde_json::Value>().await.expect("json body")
+        }
+    };
+
+    // The gas price is the base fee of the next block
+    let body = call("eth_gasPrice", serde_json::json!([])).await;
+    assert_eq!(body["result"], "0xbebc200");
+
+    let body = call(
+        "eth_feeHistory",
+        serde_json::json!(["0x5", "latest", [25, 75]]),
+    )
+    .await;
+    let history = &body["result"];
+    assert_eq!(history["oldestBlock"], "0x0");
+    assert_eq!(
+        history["baseFeePerGas"],
+        serde_json::json!(["0x5f5e100", "0x8f0d180", "0xbebc200"])
+    );
+    assert_eq!(history["gasUsedRatio"], serde_json::json!([0.0, 0.5]));
+    assert_eq!(
+        history["reward"],
+        serde_json::json!([["0x0", "0x0"], ["0x0", "0x0"]])
+    );
+
+    // An older newest block ends with the base fee of its successor
+    let body = call("eth_feeHistory", serde_json::json!([1, "0x0"])).await;
+    let history = &body["result"];
+    assert_eq!(
+        history["baseFeePerGas"],
+        serde_json::json!(["0x5f5e100", "0x8f0d180"])
+    );
+    assert!(history.get("reward").is_none());
+
+    handle.stop().await.expect("stop");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn json_rpc_eth_call_reads_arbgasinfo() {
+    use alloy_primitives::keccak256;
+    use arbitrum_consensus::{l1_pricing, precompiles::ARBGASINFO_ADDRESS};
+
+    let temp = TempDir::new().expect("tempdir");
+
+    let mut cfg = ArbitrumRethConfig::default();
+    cfg.rpc.port = 18564;
+    cfg.node.datadir = temp.path().to_path_buf();
+
+    let storage = ArbitrumStorage::new(&cfg).await.expect("storage new");
+    storage.start().await.expect("storage start");
+    let block = ArbitrumBlock {
+        number: 1,
+        gas_limit: 30_000_000,
+        base_fee: 100_000_000,
+        ..Default::default()
+    }
+    .seal();
+    storage.store_block(&block).await.expect("store block");
+    let price_per_unit = U256::from(30_000_000_000u64);
+    storage
+        .store_l1_pricing(&l1_pricing::initial_state(price_per_unit))
+        .await
+        .expect("store L1 pricing");
+    let base_fee = 200_000_000u64;
+    storage
+        .store_l2_pricing(&L2PricingState {
+            gas_backlog: 200_000_000,
+            base_fee,
+        })
+        .await
+        .expect("store pricing");
+
+    let handle = launch_reth_node(&cfg, Some(storage.into()))
+        .await
+        .expect("launch");
+    let url = format!("http://127.0.0.1:{}", cfg.rpc.port);
+    let client = reqwest::Client::new();
+    let eth_call = |signature: &'static str| {
+        let client = client.clone();
+        let url = url.clone();
+        async move {
+            let selector = &keccak256(signature)[..4];
+            let resp = client
+                .post(&url)
+                .json(&serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "eth_call",
+                    "params": [{
+                        "to": format!("0x{}", hex::encode(ARBGASINFO_ADDRESS.as_slice())),
+                        "data": format!("0x{}", hex::encode(selector)),
+                    }, "latest"]
+                }))
+                .send()
+                .await
+                .expect("post");
+            assert!(resp.status().is_success());
+            let body: serde_json::Value = resp.json().await.expect("json body");
+            let output = body["result"].as_str().expect("call output");
+            hex::decode(output.trim_start_matches("0x"))
+                .expect("hex output")
+                .chunks(32)
+                .map(U256::from_be_slice)
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // Values come from the stored pricing, not the configuration
+    assert_eq!(
+        eth_call("getL1BaseFeeEstimate()").await,
+        vec![price_per_unit]
+    );
+    assert_eq!(
+        eth_call("getMinimumGasPrice()").await,
+        vec![U256::from(cfg.gas.l2_gas_price)]
+    );
+    assert_eq!(
+        eth_call("getGasBacklog()").await,
+        vec![U256::from(200_000_000)]
+    );
+
+    // The call pays no gas, yet prices are at the pending block's base fee
+    let per_byte = price_per_unit * U256::from(16);
+    let min_base_fee = U256::from(cfg.gas.l2_gas_price);
+    assert_eq!(
+        eth_call("getPricesInWei()").await,
+        vec![
+            per_byte * U256::from(140),
+            per_byte,
+            U256::from(base_fee * 20_000),
+            min_base_fee,
+            U256::from(base_fee) - min_base_fee,
+            U256::from(base_fee),
+        ]
+    );
+
+    handle.stop().await.expect("stop");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn json_rpc_node_interface_answers_calls() {
+    use alloy_primitives::keccak256;
+    use alloy_sol_types::SolCall;
+    use arbitrum_consensus::{l1_pricing, outbox};
+    use arbitrum_node::node_interface::{NODE_INTERFACE_ADDRESS, NodeInterface};
+    use arbitrum_storage::{ArbitrumBatch, L2ToL1Send, SendMerkleState, SyncStatus};
+
+    let temp = TempDir::new().expect("tempdir");
+
+    let mut cfg = ArbitrumRethConfig::default();
+    cfg.rpc.port = 18565;
+    cfg.node.datadir = temp.path().to_path_buf();
+
+    let storage = ArbitrumStorage::new(&cfg).await.expect("storage new");
+    storage.start().await.expect("storage start");
+    let price_per_unit = U256::from(30_000_000_000u64);
+    storage
+        .store_l1_pricing(&l1_pricing::initial_state(price_per_unit))
+        .await
+        .expect("store L1 pricing");
+
+    // Block 1 makes three sends to L1, block 2 none
+    let sends: Vec<B256> = (1..=3).map(B256::with_last_byte).collect();
+    let tx_hash = B256::with_last_byte(0x10);
+    for (position, hash) in sends.iter().enumerate() {
+        storage
+            .store_send(&L2ToL1Send {
+                position: position as u64,
+                hash: *hash,
+                caller: address!("0x3333333333333333333333333333333333333333"),
+                destination: address!("0x4444444444444444444444444444444444444444"),
+                arb_block_number: 1,
+                eth_block_number: 6,
+                timestamp: 0,
+                callvalue: U256::ZERO,
+                data: vec![],
+                transaction_hash: tx_hash,
+            })
+            .await
+            .expect("store send");
+    }
+    let mut merkle = SendMerkleState::default();
+    for send in &sends {
+        outbox::append(&mut merkle, *send);
+    }
+    let mut hashes = vec![];
+    for (number, transactions) in [(0, vec![]), (1, vec![tx_hash]), (2, vec![])] {
+        let block = ArbitrumBlock {
+            number,
+            gas_limit: 30_000_000,
+            base_fee: 100_000_000,
+            transactions,
+            l1_block_number: 5 + number,
+            send_count: if number == 0 { 0 } else { 3 },
+            ..Default::default()
+        }
+        .seal();
+        storage.store_block(&block).await.expect("store block");
+        hashes.push(block.hash);
+    }
+
+    // Blocks 1 and 2 were posted in batch 1 at L1 block 10, now 14 deep
+    storage
+        .store_batch(&ArbitrumBatch {
+            batch_number: 1,
+            block_range: (1, 2),
+            l1_block_number: 10,
+            timestamp: 0,
+            transactions: vec![tx_hash],
+            l1_tx_hash: None,
+            after_delayed_messages_read: 0,
+            message_counts: (0, 2),
+            data_gas: 0,
+        })
+        .await
+        .expect("store batch");
+    storage
+        .store_sync_status(&SyncStatus {
+            l1_block_number: 24,
+            last_delayed_message: None,
+            last_batch: Some(1),
+            l1_block_hashes: vec![],
+        })
+        .await
+        .expect("store sync status");
+
+    let handle = launch_reth_node(&cfg, Some(storage.into()))
+        .await
+        .expect("launch");
+    let url = format!("http://127.0.0.1:{}", cfg.rpc.port);
+    let client = reqwest::Client::new();
+    let rpc = |method: &'static str, data: Vec<u8>| {
+        let client = client.clone();
+        let url = url.clone();
+        async move {
+            let resp = client
+                .post(&url)
+                .json(&serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": method,
+                    "params": [{
+                        "from": "0x3333333333333333333333333333333333333333",
+                        "to": format!("0x{}", hex::encode(NODE_INTERFACE_ADDRESS.as_slice())),
+                        "data": format!("0x{}", hex::encode(data)),
+                    }, "latest"]
+                }))
+                .send()
+                .await
+                .expect("post");
+            assert!(resp.status().is_success());
+            let body: serde_json::Value = resp.json().await.expect("json body");
+            let result = body["result"].as_str().map(str::to_owned);
+            result.map(|hex_result| hex::decode(hex_result.trim_start_matches("0x")).expect("hex"))
+        }
+    };
+
+    let output = rpc(
+        "eth_call",
+        NodeInterface::findBatchContainingBlockCall { blockNum: 2 }.abi_encode(),
+    )
+    .await
+    .expect("batch of block 2");
+    assert_eq!(
+        NodeInterface::findBatchContainingBlockCall::abi_decode_returns(&output).unwrap(),
+        1
+    );
+    // Block 0 was never posted
+    let call = NodeInterface::findBatchContainingBlockCall { blockNum: 0 };
+    assert!(rpc("eth_call", call.abi_encode()).await.is_none());
+
+    let call = NodeInterface::getL1ConfirmationsCall {
+        blockHash: hashes[2],
+    };
+    let output = rpc("eth_call", call.abi_encode())
+        .await
+        .expect("confirmations");
+    assert_eq!(
+        NodeInterface::getL1ConfirmationsCall::abi_decode_returns(&output).unwrap(),
+        14
+    );
+
+    // The proof of the second send folds to the root of all three
+    let call = NodeInterface::constructOutboxProofCall { size: 3, leaf: 1 };
+    let output = rpc("eth_call", call.abi_encode()).await.expect("proof");
+    let proof = NodeInterface::constructOutboxProofCall::abi_decode_returns(&output).unwrap();
+    assert_eq!(proof.send, sends[1]);
+    assert_eq!(proof.root, outbox::root(&merkle));
+    let pair = |left: B256, right: B256| keccak256([left.as_slice(), right.as_slice()].concat());
+    let leaf = keccak256(sends[1]);
+    assert_eq!(
+        proof.proof,
+        vec![keccak256(sends[0]), pair(keccak256(sends[2]), B256::ZERO)]
+    );
+    assert_eq!(pair(pair(proof.proof[0], leaf), proof.proof[1]), proof.root);
+    let call = NodeInterface::constructOutboxProofCall { size: 4, leaf: 1 };
+    assert!(rpc("eth_call", call.abi_encode()).await.is_none());
+
+    // The components of a transfer add up to its estimate
+    let to = address!("0x4444444444444444444444444444444444444444");
+    let call = NodeInterface::gasEstimateComponentsCall {
+        to,
+        contractCreation: false,
+        data: vec![0x01; 10].into(),
+    };
+    let output = rpc("eth_call", call.abi_encode())
+        .await
+        .expect("components");
+    let components = NodeInterface::gasEstimateComponentsCall::abi_decode_returns(&output).unwrap();
+    assert!(components.gasEstimateForL1 > 0);
+    assert_eq!(
+        components.gasEstimate,
+        21_000 + 10 * 16 + components.gasEstimateForL1
+    );
+    assert_eq!(components.baseFee, U256::from(cfg.gas.l2_gas_price));
+    assert_eq!(components.l1BaseFeeEstimate, price_per_unit);
+    let call = NodeInterface::gasEstimateL1ComponentCall {
+        to,
+        contractCreation: false,
+        data: vec![0x01; 10].into(),
+    };
+    let output = rpc("eth_call", call.abi_encode())
+        .await
+        .expect("L1 component");
+    let l1_component =
+        NodeInterface::gasEstimateL1ComponentCall::abi_decode_returns(&output).unwrap();
+    assert_eq!(l1_component.gasEstimateForL1, components.gasEstimateForL1);
+
+    // Estimating a ticket estimates its retry, paid for by the deposit
+    let call = NodeInterface::estimateRetryableTicketCall {
+        sender: address!("0x5555555555555555555555555555555555555555"),
+        deposit: U256::from(10u64.pow(18)),
+        to,
+        l2CallValue: U256::from(10u64.pow(17)),
+        excessFeeRefundAddress: to,
+        callValueRefundAddress: to,
+        data: Default::default(),
+    };
+    let output = rpc("eth_estimateGas", call.abi_encode())
+        .await
+        .expect("retry estimate");
+    assert_eq!(U256::from_be_slice(&output), U256::from(21_000));
+
+    handle.stop().await.expect("stop");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn json_rpc_node_interface_locates_batches_read_from_l1() {
+    use std::{sync::Arc, time::Duration};
+
+    use alloy_sol_types::{SolCall, SolEvent};
+    use arbitrum_batch_submitter::encoder::{BatchEncoder, BlockContents};
+    use arbitrum_consensus::ArbitrumConsensus;
+    use arbitrum_inbox_tracker::{
+        InboxTracker,
+        events::{SequencerBatchDelivered, TimeBounds, encode_batch_from_origin},
+    };
+    use arbitrum_l1_client::{L1Log, L1Transaction, MockL1Client};
+    use arbitrum_node::{
+        batch_executor::BatchExecutor,
+        node_interface::{NODE_INTERFACE_ADDRESS, NodeInterface},
+    };
+
+    let temp = TempDir::new().expect("tempdir");
+
+    let mut cfg = ArbitrumRethConfig::default();
+    cfg.rpc.port = 18568;
+    cfg.node.datadir = temp.path().to_path_buf();
+    cfg.l1.confirmation_blocks = 0;
+
+    let storage = Arc::new(ArbitrumStorage::new(&cfg).await.expect("storage new"));
+    storage.start().await.expect("storage start");
+
+    // Another poster sends batch 1, carrying two blocks, to L1 block 10
+    let mut encoder = BatchEncoder::new(11, 100_000, 0);
+    for timestamp in [1_700_000_001, 1_700_000_002] {
+        encoder
+            .add_block(&BlockContents {
+                timestamp,
+                l1_block_number: 5,
+                delayed_messages: 0,
+                transactions: vec![vec![0x02, 0xc0]],
+            })
+            .expect("add block");
+    }
+    let encoded = encoder.finish().expect("encode batch");
+    let l1 = MockL1Client::new();
+    l1.advance_to(24).await;
+    let l1_tx_hash = B256::repeat_byte(0xb1);
+    l1.add_transaction(L1Transaction {
+        hash: l1_tx_hash,
+        from: address!("0x5555555555555555555555555555555555555555"),
+        to: Some(cfg.l1.sequencer_inbox_address),
+        nonce: 0,
+        input: encode_batch_from_origin(1, &encoded.data, 0, 0, encoded.message_count),
+        block_number: Some(10),
+        blob_versioned_hashes: vec![],
+    })
+    .await;
+    let log = SequencerBatchDelivered {
+        batchSequenceNumber: U256::from(1),
+        beforeAcc: B256::ZERO,
+        afterAcc: B256::repeat_byte(1),
+        delayedAcc: B256::ZERO,
+        afterDelayedMessagesRead: U256::ZERO,
+        timeBounds: TimeBounds {
+            minTimestamp: 0,
+            maxTimestamp: u64::MAX,
+            minBlockNumber: 0,
+            maxBlockNumber: u64::MAX,
+        },
+        dataLocation: 0,
+    }
+    .encode_log_data();
+    l1.add_log(L1Log {
+        address: cfg.l1.sequencer_inbox_address,
+        topics: log.topics().to_vec(),
+        data: log.data.to_vec(),
+        block_number: 10,
+        block_hash: B256::ZERO,
+        transaction_hash: l1_tx_hash,
+        transaction_index: 0,
+        log_index: 0,
+        removed: false,
+    })
+    .await
+    .expect("add log");
+
+    // The chain starts from its genesis block
+    let consensus = Arc::new(
+        ArbitrumConsensus::new(&cfg, Arc::clone(&storage))
+            .await
+            .expect("consensus new"),
+    );
+    consensus.start().await.expect("consensus start");
+    consensus
+        .execute_block(&ArbitrumBlock {
+            number: 0,
+            gas_limit: 30_000_000,
+            timestamp: 1_700_000_000,
+            ..Default::default()
+        })
+        .await
+        .expect("genesis");
+
+    // Each message of the batch is executed as the next block
+    let tracker = Arc::new(
+        InboxTracker::with_l1_client(&cfg, Arc::clone(&storage), Arc::new(l1))
+            .await
+            .expect("tracker"),
+    );
+    let executor = BatchExecutor::new(
+        &cfg,
+        Arc::clone(&consensus),
+        Arc::clone(&storage),
+        Arc::clone(&tracker),
+    );
+    tracker.start().await.expect("tracker start");
+    executor.start().await.expect("executor start");
+    let last = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(block) = storage.get_block_by_number(2).await.expect("block 2") {
+                break block;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("batch executed");
+    executor.stop().await.expect("executor stop");
+    tracker.stop().await.expect("tracker stop");
+    assert_eq!(last.timestamp, 1_700_000_002);
+    assert_eq!(last.l1_block_number, 5);
+
+    let handle = launch_reth_node(&cfg, Some(storage)).await.expect("launch");
+    let url = format!("http://127.0.0.1:{}", cfg.rpc.port);
+    let client = reqwest::Client::new();
+    let call = |data: Vec<u8>| {
+        let client = client.clone();
+        let url = url.clone();
+        async move {
+            let resp = client
+                .post(&url)
+                .json(&serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "eth_call",
+                    "params": [{
+                        "to": format!("0x{}", hex::encode(NODE_INTERFACE_ADDRESS.as_slice())),
+                        "data": format!("0x{}", hex::encode(data)),
+                    }, "latest"]
+                }))
+                .send()
+                .await
+                .expect("post");
+            assert!(resp.status().is_success());
+            let body: serde_json::Value = resp.json().await.expect("json body");
+            let result = body["result"].as_str().map(str::to_owned);
+            result.map(|hex_result| hex::decode(hex_result.trim_start_matches("0x")).expect("hex"))
+        }
+    };
+
+    for number in [1, 2] {
+        let output =
+            call(NodeInterface::findBatchContainingBlockCall { blockNum: number }.abi_encode())
+                .await
+                .expect("batch of block");
+        assert_eq!(
+            NodeInterface::findBatchContainingBlockCall::abi_decode_returns(&output).unwrap(),
+            1
+        );
+    }
+    let call_data = NodeInterface::findBatchContainingBlockCall { blockNum: 3 }.abi_encode();
+    assert!(call(call_data).await.is_none());
+
+    // The tracker synced L1 up to block 24, 14 blocks past the batch
+    let call_data = NodeInterface::getL1ConfirmationsCall {
+        blockHash: last.hash,
+    }
+    .abi_encode();
+    let output = call(call_data).await.expect("confirmations");
+    assert_eq!(
+        NodeInterface::getL1ConfirmationsCall::abi_decode_returns(&output).unwrap(),
+        14
+    );
+
+    handle.stop().await.expect("stop");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn json_rpc_arb_serves_l2_to_l1_messages_and_proofs() {
+    use alloy_primitives::keccak256;
+    use arbitrum_consensus::outbox;
+    use arbitrum_storage::{L2ToL1Send, SendMerkleState};
+
+    let temp = TempDir::new().expect("tempdir");
+
+    let mut cfg = ArbitrumRethConfig::default();
+    cfg.rpc.port = 18566;
+    cfg.node.datadir = temp.path().to_path_buf();
+
+    let storage = ArbitrumStorage::new(&cfg).await.expect("storage new");
+    storage.start().await.expect("storage start");
+
+    // Block 1 sends twice to L1 and block 2 once, to another destination
+    let destinations = [
+        address!("0x4444444444444444444444444444444444444444"),
+        address!("0x4444444444444444444444444444444444444444"),
+        address!("0x5555555555555555555555555555555555555555"),
+    ];
+    let mut sends = vec![];
+    let mut merkle = SendMerkleState::default();
+    let mut roots = vec![B256::ZERO];
+    for (number, count) in [(1u64, 2usize), (2, 1)] {
+        for _ in 0..count {
+            let position = sends.len() as u64;
+            let send = L2ToL1Send {
+                position,
+                hash: B256::with_last_byte(0x20 + position as u8),
+                caller: address!("0x3333333333333333333333333333333333333333"),
+                destination: destinations[position as usize],
+                arb_block_number: number,
+                eth_block_number: 100 + number,
+                timestamp: 1_700_000_000 + number,
+                callvalue: U256::from(position),
+                data: vec![position as u8],
+                transaction_hash: B256::with_last_byte(0x10 + number as u8),
+            };
+            storage.store_send(&send).await.expect("store send");
+            outbox::append(&mut merkle, send.hash);
+            sends.push(send);
+        }
+        roots.push(outbox::root(&merkle));
+    }
+    for number in 0..=2u64 {
+        let block = ArbitrumBlock {
+            number,
+            gas_limit: 30_000_000,
+            send_count: [0, 2, 3][number as usize],
+            send_root: roots[number as usize],
+            ..Default::default()
+        }
+        .seal();
+        storage.store_block(&block).await.expect("store block");
+    }
+
+    let handle = launch_reth_node(&cfg, Some(storage.into()))
+        .await
+        .expect("launch");
+    let url = format!("http://127.0.0.1:{}", cfg.rpc.port);
+    let client = reqwest::Client::new();
+    let rpc = |method: &'static str, params: serde_json::Value| {
+        let client = client.clone();
+        let url = url.clone();
+        async move {
+            let resp = client
+                .post(&url)
+                .json(&serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": method,
+                    "params": params,
+                }))
+                .send()
+                .await
+                .expect("post");
+            assert!(resp.status().is_success());
+            let body: serde_json::Value = resp.json().await.expect("json body");
+            body["result"].clone()
+        }
+    };
+
+    // Block 2 holds the third send
+    let found = rpc(
+        "arb_findL2ToL1Messages",
+        serde_json::json!([{ "fromBlock": "0x2", "toBlock": "latest" }]),
+    )
+    .await;
+    let found = found.as_array().expect("sends");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0]["index"], "0x2");
+    assert_eq!(found[0]["l2Block"], "0x2");
+    assert_eq!(found[0]["l1Block"], "0x66");
+    assert_eq!(found[0]["value"], "0x2");
+    assert_eq!(found[0]["data"], "0x02");
+    let found = rpc(
+        "arb_findL2ToL1Messages",
+        serde_json::json!([{
+            "fromBlock": "0x0",
+            "to": "0x4444444444444444444444444444444444444444",
+        }]),
+    )
+    .await;
+    let indexes: Vec<&str> = found
+        .as_array()
+        .expect("sends")
+        .iter()
+        .map(|send| send["index"].as_str().expect("index"))
+        .collect();
+    assert_eq!(indexes, vec!["0x0", "0x1"]);
+
+    // A send proves against the root of its own block and of later ones
+    let hex_of = |hash: &B256| format!("0x{}", hex::encode(hash));
+    let pair = |left: B256, right: B256| keccak256([left.as_slice(), right.as_slice()].concat());
+    let proof = rpc("arb_getL2ToL1Proof", serde_json::json!(["0x1", "0x1"])).await;
+    assert_eq!(proof["root"], hex_of(&roots[1]));
+    assert_eq!(proof["sendCount"], "0x2");
+    assert_eq!(proof["hash"], hex_of(&sends[1].hash));
+    assert_eq!(
+        proof["proof"],
+        serde_json::json!([hex_of(&keccak256(sends[0].hash))])
+    );
+    let proof = rpc("arb_getL2ToL1Proof", serde_json::json!([1])).await;
+    assert_eq!(proof["root"], hex_of(&roots[2]));
+    assert_eq!(proof["blockNumber"], "0x2");
+    assert_eq!(
+        proof["proof"],
+        serde_json::json!([
+            hex_of(&keccak256(sends[0].hash)),
+            hex_of(&pair(keccak256(sends[2].hash), B256::ZERO)),
+        ])
+    );
+
+    // The third send is not under the root of block 1
+    let proof = rpc("arb_getL2ToL1Proof", serde_json::json!(["0x2", "0x1"])).await;
+    assert!(proof.is_null());
+
+    handle.stop().await.expect("stop");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn json_rpc_transactions_show_their_arbitrum_type() {
+    let temp = TempDir::new().expect("tempdir");
+
+    let mut cfg = ArbitrumRethConfig::default();
+    cfg.rpc.port = 18567;
+    cfg.node.datadir = temp.path().to_path_buf();
+    cfg.l2.chain_id = 42161;
+
+    let storage = ArbitrumStorage::new(&cfg).await.expect("storage new");
+    storage.start().await.expect("storage start");
+
+    let retry_to = address!("0x2222222222222222222222222222222222222222");
+    let submission = ArbitrumTransaction {
+        hash: B256::ZERO,
+        from: address!("0x1111111111111111111111111111111111111111"),
+        to: Some(retry_to),
+        value: U256::from(5),
+        gas: 100_000,
+        gas_price: U256::from(1_000),
+        nonce: 0,
+        data: vec![0xca, 0xfe],
+        l1_sequence_number: Some(3),
+        raw: vec![],
+        kind: ArbitrumTxKind::SubmitRetryable {
+            request_id: B256::with_last_byte(3),
+            l1_base_fee: U256::from(30),
+            deposit_value: U256::from(10_000),
+            max_submission_fee: U256::from(700),
+            fee_refund_address: address!("0x3333333333333333333333333333333333333333"),
+            beneficiary: address!("0x4444444444444444444444444444444444444444"),
+        },
+    }
+    .seal(cfg.l2.chain_id);
+    let retry = ArbitrumTransaction {
+        kind: ArbitrumTxKind::Retry {
+            ticket_id: submission.hash,
+            refund_to: address!("0x3333333333333333333333333333333333333333"),
+            max_refund: U256::from(9_000),
+            submission_fee_refund: U256::from(600),
+        },
+        l1_sequence_number: None,
+        ..submission.clone()
+    }
+    .seal(cfg.l2.chain_id);
+    storage
+        .store_transaction(&submission)
+        .await
+        .expect("store submission");
+    storage
+        .store_transaction(&retry)
+        .await
+        .expect("store retry");
+
+    let handle = launch_reth_node(&cfg, Some(storage.into()))
+        .await
+        .expect("launch");
+    let url = format!("http://127.0.0.1:{}", cfg.rpc.port);
+    let client = reqwest::Client::new();
+    let get = |hash: B256| {
+        let client = client.clone();
+        let url = url.clone();
+        async move {
+            let resp = client
+                .post(&url)
+                .json(&serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "eth_getTransactionByHash",
+                    "params": [format!("0x{}", hex::encode(hash))],
+                }))
+                .send()
+                .await
+                .expect("post");
+            assert!(resp.status().is_success());
+            let body: serde_json::Value = resp.json().await.expect("json body");
+            body["result"].clone()
+        }
+    };
+
+    let tx = get(submission.hash).await;
+    assert_eq!(tx["type"], "0x69");
+    assert_eq!(tx["chainId"], "0xa4b1");
+    assert_eq!(
+        tx["requestId"],
+        format!("0x{}", hex::encode(B256::with_last_byte(3)))
+    );
+    assert_eq!(tx["l1BaseFee"], "0x1e");
+    assert_eq!(tx["depositValue"], "0x2710");
+    assert_eq!(tx["maxSubmissionFee"], "0x2bc");
+    assert_eq!(tx["retryTo"], "0x2222222222222222222222222222222222222222");
+    assert_eq!(tx["retryValue"], "0x5");
+    assert_eq!(tx["retryData"], "0xcafe");
+    assert_eq!(tx["refundTo"], "0x3333333333333333333333333333333333333333");
+    assert_eq!(
+        tx["beneficiary"],
+        "0x4444444444444444444444444444444444444444"
+    );
+
+    let tx = get(retry.hash).await;
+    assert_eq!(tx["type"], "0x68");
+    assert_eq!(tx["input"], "0xcafe");
+    assert_eq!(
+        tx["ticketId"],
+        format!("0x{}", hex::encode(submission.hash))
+    );
+    assert_eq!(tx["refundTo"], "0x3333333333333333333333333333333333333333");
+    assert_eq!(tx["maxRefund"], "0x2328");
+    assert_eq!(tx["submissionFeeRefund"], "0x258");
+    assert!(tx.get("requestId").is_none());
+
+    handle.stop().await.expect("stop");
+}
@@ -3,20 +3,23 @@
 use std::{
     collections::{HashMap, VecDeque},
     sync::Arc,
+    time::Duration,
 };
 
 use alloy_primitives::{Address, B256, U256};
 use arbitrum_config::ArbitrumRethConfig;
-use arbitrum_storage::{ArbitrumTransaction, L1Message};
+use arbitrum_storage::{ArbitrumStorage, ArbitrumTransaction, L1Message};
 use eyre::Result;
-use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tokio::{sync::RwLock, time::interval};
+use tracing::{debug, error, info};
 
 /// Arbitrum transaction pool that handles L2 transactions and L1 messages
 #[allow(dead_code)]
 pub struct ArbitrumTransactionPool {
     config: ArbitrumRethConfig,
+    storage: Arc<ArbitrumStorage>,
     is_running: Arc<RwLock<bool>>,
+    base_fee: Arc<RwLock<u64>>, // L2 base fee of the next block
     pending_transactions: Arc<RwLock<HashMap<B256, ArbitrumTransaction>>>,
     queued_transactions: Arc<RwLock<HashMap<Address, VecDeque<ArbitrumTransaction>>>>,
     l1_messages: Arc<RwLock<VecDeque<L1Message>>>,
@@ -25,12 +28,14 @@ pub struct ArbitrumTransactionPool {
 
 impl ArbitrumTransactionPool {
     /// Create a new Arbitrum transaction pool
-    pub async fn new(config: &ArbitrumRethConfig) -> Result<Self> {
+    pub async fn new(config: &ArbitrumRethConfig, storage: Arc<ArbitrumStorage>) -> Result<Self> {
         info!("Initializing Arbitrum transaction pool");
 
         Ok(Self {
             config: config.clone(),
+            storage,
             is_running: Arc::new(RwLock::new(false)),
+            base_fee: Arc::new(RwLock::new(config.gas.l2_gas_price)),
             pending_transactions: Arc::new(RwLock::new(HashMap::new())),
             queued_transactions: Arc::new(RwLock::new(HashMap::new())),
             l1_messages: Arc::new(RwLock::new(VecDeque::new())),
@@ -49,10 +54,14 @@ impl ArbitrumTransactionPool {
 
         // TODO: Start background tasks for:
         // - Transaction validation
-        // - Gas price updates
         // - Transaction eviction
         // - L1 message processing
 
+        let self_clone = self.clone_for_task();
+        tokio::spawn(async move {
+            self_clone.gas_price_loop().await;
+        });
+
         *running = true;
         info!("Arbitrum transaction pool started");
 
@@ -132,7 +141,7 @@ impl ArbitrumTransactionPool {
         let mut transactions: Vec<ArbitrumTransaction> = pending.values().cloned().collect();
 
         // Sort by gas price (highest first)
-        transactions.sort_by(|a, b| b.gas_price.cmp(&a.gas_price));
+        transactions.sort_by_key(|tx| std::cmp::Reverse(tx.gas_price));
 
         // Take only the requested number
         transactions.truncate(limit);
@@ -189,6 +198,15 @@ impl ArbitrumTransactionPool {
             return Err(eyre::eyre!("Transaction gas price cannot be zero"));
         }
 
+        let base_fee = *self.base_fee.read().await;
+        if tx.gas_price < U256::from(base_fee) {
+            return Err(eyre::eyre!(
+                "Transaction gas price {} is below the base fee {}",
+                tx.gas_price,
+                base_fee
+            ));
+        }
+
         // TODO: More comprehensive validation:
         // - Signature validation
         // - Nonce validation
@@ -220,17 +238,87 @@ impl ArbitrumTransactionPool {
         pending.contains_key(hash)
     }
 
-    /// Update gas prices based on network conditions
+    /// L2 base fee the pool admits transactions at
+    pub async fn base_fee(&self) -> u64 {
+        *self.base_fee.read().await
+    }
+
+    /// Follow the base fee of the L2 pricing
+    ///
+    /// Pending transactions whose gas price no longer covers the base fee of
+    /// the next block are queued until it falls back, and queued ones that
+    /// cover it again become pending.
     pub async fn update_gas_prices(&self) -> Result<()> {
         debug!("Updating gas prices");
 
-        // TODO: Implement dynamic gas price updates based on:
-        // - Network congestion
-        // - L1 gas prices
-        // - Transaction priority
+        let Some(pricing) = self.storage.get_l2_pricing().await? else {
+            return Ok(());
+        };
+        let base_fee = U256::from(pricing.base_fee);
+        *self.base_fee.write().await = pricing.base_fee;
+
+        let mut pending = self.pending_transactions.write().await;
+        let mut queued = self.queued_transactions.write().await;
+        let underpriced: Vec<B256> = pending
+            .values()
+            .filter(|tx| tx.gas_price < base_fee)
+            .map(|tx| tx.hash)
+            .collect();
+        for hash in underpriced {
+            if let Some(tx) = pending.remove(&hash) {
+                queued.entry(tx.from).or_default().push_back(tx);
+            }
+        }
+        for txs in queued.values_mut() {
+            let (affordable, underpriced) = txs.drain(..).partition(|tx| tx.gas_price >= base_fee);
+            *txs = underpriced;
+            for tx in affordable {
+                pending.insert(tx.hash, tx);
+            }
+        }
+        queued.retain(|_, txs| !txs.is_empty());
 
+        debug!(
+            "Base fee is {}, {} transactions pending, {} senders queued",
+            base_fee,
+            pending.len(),
+            queued.len()
+        );
         Ok(())
     }
+
+    /// Refresh the gas prices every `price_update_interval` seconds
+    async fn gas_price_loop(&self) {
+        let period = Duration::from_secs(self.config.gas.price_update_interval.max(1));
+        let mut interval = interval(period);
+
+        loop {
+            interval.tick().await;
+
+            // Check if we should stop
+            if !*self.is_running.read().await {
+                break;
+            }
+
+            if let Err(e) = self.update_gas_prices().await {
+                error!("Failed to update gas prices: {}", e);
+            }
+        }
+    }
+
+    /// Helper method to clone for async tasks
+    fn clone_for_task(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            storage: Arc::clone(&self.storage),
+            is_running: Arc::clone(&self.is_running),
+            base_fee: Arc::clone(&self.base_fee),
+            pending_transactions: Arc::clone(&self.pending_transactions),
+            queued_transactions: Arc::clone(&self.queued_transactions),
+            l1_messages: Arc::clone(&self.l1_messages),
+            transaction_count: Arc::clone(&self.transaction_count),
+        }
+    }
 }
 
 /// Transaction pool statistics
@@ -241,3 +329,66 @@ pub struct PoolStats {
     pub l1_messages: usize,
     pub total_transactions: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use arbitrum_storage::{ArbitrumTxKind, L2PricingState};
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn tx(byte: u8, gas_price: u64) -> ArbitrumTransaction {
+        ArbitrumTransaction {
+            hash: B256::with_last_byte(byte),
+            from: Address::with_last_byte(byte),
+            to: None,
+            value: U256::ZERO,
+            gas: 21_000,
+            gas_price: U256::from(gas_price),
+            nonce: 0,
+            data: vec![],
+            l1_sequence_number: None,
+            raw: vec![],
+            kind: ArbitrumTxKind::Signed,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pool_follows_base_fee() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = ArbitrumRethConfig::default();
+        config.node.datadir = temp_dir.path().to_path_buf();
+        let storage = Arc::new(ArbitrumStorage::new(&config).await.unwrap());
+        storage.start().await.unwrap();
+        let pool = ArbitrumTransactionPool::new(&config, Arc::clone(&storage))
+            .await
+            .unwrap();
+
+        // Until a block is executed the base fee is the minimum
+        pool.update_gas_prices().await.unwrap();
+        let min_base_fee = config.gas.l2_gas_price;
+        assert_eq!(pool.base_fee().await, min_base_fee);
+        assert!(pool.add_transaction(tx(1, min_base_fee - 1)).await.is_err());
+        pool.add_transaction(tx(1, min_base_fee)).await.unwrap();
+        pool.add_transaction(tx(2, 3 * min_base_fee)).await.unwrap();
+
+        // A rising base fee queues what no longer pays it
+        let mut pricing = L2PricingState {
+            gas_backlog: 1_000_000_000,
+            base_fee: 2 * min_base_fee,
+        };
+        storage.store_l2_pricing(&pricing).await.unwrap();
+        pool.update_gas_prices().await.unwrap();
+        let best = pool.get_best_transactions(10).await;
+        assert_eq!(best.len(), 1);
+        assert_eq!(best[0].hash, B256::with_last_byte(2));
+        assert_eq!(pool.get_stats().await.queued_transactions, 1);
+
+        // and a falling one brings it back
+        pricing.base_fee = min_base_fee;
+        storage.store_l2_pricing(&pricing).await.unwrap();
+        pool.update_gas_prices().await.unwrap();
+        assert_eq!(pool.get_best_transactions(10).await.len(), 2);
+        assert_eq!(pool.get_stats().await.queued_transactions, 0);
+    }
+}
@@ -4,7 +4,10 @@
 //! for storage in the LMDB database. It supports multiple encoding formats
 //! optimized for different types of data.
 
-use alloy_primitives::{Address, B256, U256};
+use std::collections::{BTreeMap, BTreeSet};
+
+use alloy_consensus::{EMPTY_OMMER_ROOT_HASH, Header};
+use alloy_primitives::{Address, B64, B256, Bloom, Bytes, I256, U256};
 use bincode;
 use eyre::{Context, Result};
 use rlp::{Decodable, Encodable};
@@ -73,6 +76,12 @@ impl DatabaseKey for keys::StorageKey {
     }
 }
 
+impl DatabaseKey for keys::CodeHash {
+    fn encode(&self) -> Result<Vec<u8>> {
+        Ok(self.0.as_slice().to_vec())
+    }
+}
+
 impl DatabaseKey for keys::BatchNumber {
     fn encode(&self) -> Result<Vec<u8>> {
         Ok(self.0.to_be_bytes().to_vec())
@@ -97,6 +106,42 @@ impl DatabaseKey for keys::FilterId {
     }
 }
 
+impl DatabaseKey for keys::TicketId {
+    fn encode(&self) -> Result<Vec<u8>> {
+        Ok(self.0.as_slice().to_vec())
+    }
+}
+
+impl DatabaseKey for keys::RetryableTimeout {
+    fn encode(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(40); // 8 bytes timeout + 32 bytes ticket id
+        bytes.extend_from_slice(&self.timeout.to_be_bytes());
+        bytes.extend_from_slice(self.ticket_id.as_slice());
+        Ok(bytes)
+    }
+}
+
+impl DatabaseKey for keys::SendPosition {
+    fn encode(&self) -> Result<Vec<u8>> {
+        Ok(self.0.to_be_bytes().to_vec())
+    }
+}
+
+impl DatabaseKey for keys::DataHash {
+    fn encode(&self) -> Result<Vec<u8>> {
+        Ok(self.0.as_slice().to_vec())
+    }
+}
+
+impl DatabaseKey for keys::MerkleNode {
+    fn encode(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(16); // 8 bytes level + 8 bytes leaf
+        bytes.extend_from_slice(&self.level.to_be_bytes());
+        bytes.extend_from_slice(&self.leaf.to_be_bytes());
+        Ok(bytes)
+    }
+}
+
 // Implement DatabaseValue for primitive types
 
 impl DatabaseValue for u64 {
@@ -319,16 +364,65 @@ mod tests {
         assert_eq!(value, decoded);
     }
 
+    #[test]
+    fn test_l1_message_kind_from_byte() {
+        assert_eq!(L1MessageKind::from(3), L1MessageKind::L2Message);
+        assert_eq!(L1MessageKind::from(12), L1MessageKind::EthDeposit);
+        assert_eq!(L1MessageKind::from(13), L1MessageKind::BatchPostingReport);
+        assert_eq!(L1MessageKind::from(4), L1MessageKind::Invalid);
+        assert_eq!(L1MessageKind::SubmitRetryable as u8, 9);
+    }
+
     #[test]
     fn test_metadata_key() {
         let key = keys::MetadataKey("latest_block".to_string());
         let encoded = DatabaseKey::encode(&key).unwrap();
         assert_eq!(encoded, "latest_block".as_bytes().to_vec());
     }
+
+    #[test]
+    fn test_block_header_carries_arbitrum_fields() {
+        let block = ArbitrumBlock {
+            number: 7,
+            parent_hash: B256::repeat_byte(0x11),
+            timestamp: 1_700_000_000,
+            gas_limit: 1 << 50,
+            l1_block_number: 0x1234,
+            base_fee: 100_000_000,
+            send_count: 3,
+            send_root: B256::repeat_byte(0x22),
+            delayed_messages_read: 5,
+            arbos_version: 20,
+            ..Default::default()
+        }
+        .seal();
+
+        let header = block.header();
+        assert_eq!(
+            header.mix_hash,
+            B256::from(alloy_primitives::hex!(
+                "0000000000000003000000000000123400000000000000140000000000000000"
+            ))
+        );
+        assert_eq!(header.extra_data.as_ref(), block.send_root.as_slice());
+        assert_eq!(header.nonce, B64::from(5u64));
+        assert_eq!(header.difficulty, U256::from(1));
+        assert_eq!(header.base_fee_per_gas, Some(100_000_000));
+
+        assert_eq!(block.hash, header.hash_slow());
+        let resent = ArbitrumBlock {
+            send_count: 4,
+            ..block.clone()
+        };
+        assert_ne!(resent.compute_hash(), block.hash);
+    }
 }
 
 /// Arbitrum-specific data types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// An L2 block with the header fields Nitro produces. `hash` is the hash of
+/// the RLP of [`ArbitrumBlock::header`], set by [`ArbitrumBlock::seal`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ArbitrumBlock {
     pub number: u64,
     pub hash: B256,
@@ -338,6 +432,62 @@ pub struct ArbitrumBlock {
     pub gas_limit: u64,
     pub transactions: Vec<B256>,
     pub l1_block_number: u64,
+    pub state_root: B256,
+    pub transactions_root: B256,
+    pub receipts_root: B256,
+    pub logs_bloom: Bloom,
+    pub base_fee: u64,
+    pub send_count: u64, // L2-to-L1 messages sent up to and including this block
+    pub send_root: B256, // Root of the outbox accumulator after this block
+    pub delayed_messages_read: u64, // Delayed inbox messages read up to and including this block
+    pub arbos_version: u64, // ArbOS format version the block was executed with
+}
+
+/// Difficulty of every Arbitrum block
+pub const ARBITRUM_DIFFICULTY: u64 = 1;
+
+impl ArbitrumBlock {
+    /// The Ethereum header of the block
+    ///
+    /// Nitro carries its own fields in the header: the mix hash holds the send
+    /// count, the L1 block number and the ArbOS version, the extra data the
+    /// send root, and the nonce the number of delayed messages read.
+    pub fn header(&self) -> Header {
+        let mut mix_hash = B256::ZERO;
+        mix_hash[..8].copy_from_slice(&self.send_count.to_be_bytes());
+        mix_hash[8..16].copy_from_slice(&self.l1_block_number.to_be_bytes());
+        mix_hash[16..24].copy_from_slice(&self.arbos_version.to_be_bytes());
+        Header {
+            parent_hash: self.parent_hash,
+            ommers_hash: EMPTY_OMMER_ROOT_HASH,
+            beneficiary: Address::ZERO,
+            state_root: self.state_root,
+            transactions_root: self.transactions_root,
+            receipts_root: self.receipts_root,
+            logs_bloom: self.logs_bloom,
+            difficulty: U256::from(ARBITRUM_DIFFICULTY),
+            number: self.number,
+            gas_limit: self.gas_limit,
+            gas_used: self.gas_used,
+            timestamp: self.timestamp,
+            extra_data: Bytes::copy_from_slice(self.send_root.as_slice()),
+            mix_hash,
+            nonce: B64::from(self.delayed_messages_read),
+            base_fee_per_gas: Some(self.base_fee),
+            ..Default::default()
+        }
+    }
+
+    /// Hash of the RLP encoding of the header
+    pub fn compute_hash(&self) -> B256 {
+        self.header().hash_slow()
+    }
+
+    /// Set the block hash from the header
+    pub fn seal(mut self) -> Self {
+        self.hash = self.compute_hash();
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -351,6 +501,57 @@ pub struct ArbitrumTransaction {
     pub nonce: u64,
     pub data: Vec<u8>,
     pub l1_sequence_number: Option<u64>,
+    pub raw: Vec<u8>, // EIP-2718 encoding of a signed transaction, empty if derived from L1
+    pub kind: ArbitrumTxKind,
+}
+
+/// Origin of an L2 transaction and the fields specific to it
+///
+/// The common fields live on [`ArbitrumTransaction`]: `gas_price` holds the
+/// fee cap, and for retryables `to`, `value` and `data` describe the retry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArbitrumTxKind {
+    /// Signed Ethereum transaction
+    #[default]
+    Signed,
+    /// ETH deposited from L1
+    Deposit { l1_request_id: B256 },
+    /// Unsigned transaction from L1, ordered by the sender's nonce
+    Unsigned,
+    /// Unsigned transaction from an L1 contract, identified by its request id
+    Contract { request_id: B256 },
+    /// Retryable ticket submission
+    SubmitRetryable {
+        request_id: B256,
+        l1_base_fee: U256,
+        deposit_value: U256,
+        max_submission_fee: U256,
+        fee_refund_address: Address,
+        beneficiary: Address, // Receives the call value if the ticket is cancelled or expires
+    },
+    /// Batch poster spending report for the L1 pricer
+    BatchPostingReport {
+        batch_timestamp: U256,
+        batch_poster: Address,
+        data_hash: B256,
+        batch_number: u64,
+        l1_base_fee_estimate: U256,
+        batch_data_gas: u64, // Gas of the batch's data plus the extra gas reported
+    },
+    /// Chain initialization parameters
+    Initialize {
+        chain_id: U256,
+        initial_l1_base_fee: U256,
+        chain_config: Vec<u8>, // Serialized chain config JSON, empty if absent
+    },
+    /// Redeem attempt of a retryable ticket, whose gas was paid for by the
+    /// transaction that scheduled it
+    Retry {
+        ticket_id: B256,
+        refund_to: Address, // Receives the refunds, up to `max_refund`, the rest going to `from`
+        max_refund: U256,
+        submission_fee_refund: U256, // Refunded if the attempt succeeds
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -370,15 +571,200 @@ pub struct ArbitrumBatch {
     pub timestamp: u64,
     pub transactions: Vec<B256>,
     pub l1_tx_hash: Option<B256>, // Hash of the L1 transaction that submitted this batch
+    pub after_delayed_messages_read: u64, /* Delayed inbox messages consumed up to the end of this batch */
+    pub message_counts: (u64, u64),       // Messages in the L2 stream before and after this batch
+    pub data_gas: u64, // L1 gas of the sequencer message, 0 until read back from L1
+}
+
+/// Kind byte of a delayed inbox message, as emitted by the L1 Bridge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum L1MessageKind {
+    L2Message = 3,
+    EndOfBlock = 6,
+    L2FundedByL1 = 7,
+    RollupEvent = 8,
+    SubmitRetryable = 9,
+    BatchForGasEstimation = 10,
+    Initialize = 11,
+    EthDeposit = 12,
+    BatchPostingReport = 13,
+    Invalid = 0xff,
+}
+
+impl From<u8> for L1MessageKind {
+    fn from(kind: u8) -> Self {
+        match kind {
+            3 => Self::L2Message,
+            6 => Self::EndOfBlock,
+            7 => Self::L2FundedByL1,
+            8 => Self::RollupEvent,
+            9 => Self::SubmitRetryable,
+            10 => Self::BatchForGasEstimation,
+            11 => Self::Initialize,
+            12 => Self::EthDeposit,
+            13 => Self::BatchPostingReport,
+            _ => Self::Invalid,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct L1Message {
     pub message_number: u64,
-    pub sender: Address,
+    pub kind: L1MessageKind,
+    pub sender: Address, // Sender as the Bridge recorded it, aliased on L1 for contracts
     pub data: Vec<u8>,
     pub timestamp: u64,
     pub block_number: u64,
+    pub request_id: B256, // Delayed message index as bytes32
+    pub l1_base_fee: U256,
+    pub batch_gas_cost: Option<u64>, // L1 gas of the batch a posting report names, once read
+}
+
+/// Inbox tracker progress, checkpointed under the `sync_status` metadata key
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub l1_block_number: u64,              // Last fully processed L1 block
+    pub last_delayed_message: Option<u64>, // Highest delayed message index ingested
+    pub last_batch: Option<u64>,           // Highest sequencer batch number ingested
+    pub l1_block_hashes: Vec<(u64, B256)>, // Recently processed L1 blocks, oldest first
+}
+
+/// Batch-posting transaction sent to L1 but not yet confirmed, checkpointed
+/// under the `in_flight_batch` metadata key so a restart resumes tracking it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InFlightBatch {
+    pub batch: ArbitrumBatch,
+    pub data: Vec<u8>, // Compressed batch data, to rebuild replacements from
+    pub blobs: bool,   // Whether the data is posted in blobs rather than calldata
+    pub nonce: u64,
+    pub gas_limit: u64,
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_blob_gas: u128,
+    pub tx_hashes: Vec<B256>, // Every broadcast version of the transaction, latest last
+    pub sent_at: u64,         // Unix time in milliseconds of the latest broadcast
+}
+
+/// State of the L2 gas pricing, kept under the `l2_pricing` metadata key and
+/// updated with every block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct L2PricingState {
+    pub gas_backlog: u64, // Gas used beyond what the speed limit has drained
+    pub base_fee: u64,    // Base fee in wei of the next block
+}
+
+/// Merkle accumulator of the L2-to-L1 sends, kept under the `send_merkle`
+/// metadata key
+///
+/// Holds what ArbOS keeps of its send accumulator: the number of sends and,
+/// per level, the root of the complete subtree not yet merged into a higher
+/// one, or zero.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SendMerkleState {
+    pub size: u64,
+    pub partials: Vec<B256>,
+}
+
+/// Send to L1 logged by ArbSys as `L2ToL1Tx`, a leaf of the send tree kept
+/// in the `sends` table
+///
+/// Holds what the outbox on L1 needs to execute the send besides its proof.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct L2ToL1Send {
+    pub position: u64, // Leaf of the send in the send tree
+    pub hash: B256,
+    pub caller: Address,
+    pub destination: Address,
+    pub arb_block_number: u64,
+    pub eth_block_number: u64, // L1 block number of the L2 block
+    pub timestamp: u64,
+    pub callvalue: U256,
+    pub data: Vec<u8>,
+    pub transaction_hash: B256,
+}
+
+/// State of the L1 pricer, kept under the `l1_pricing` metadata key
+///
+/// Mirrors ArbOS's L1 pricing: the L1 fees of sequenced transactions collect
+/// in the pricer's pool, which refunds batch posters what they report
+/// spending and pays a reward per unit of data. The price per unit follows
+/// the pool's surplus.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct L1PricingState {
+    pub price_per_unit: U256, // Wei per unit of L1 calldata
+    pub units_since_update: u64,
+    pub last_update_time: u64, // Timestamp of the batch of the last report
+    #[serde(with = "i256_bytes")]
+    pub last_surplus: I256, // Pool funds less what is due, at the last report
+    pub funds_due_for_rewards: U256,
+    pub l1_fees_available: U256, // Funds in the pool
+    pub batch_posters: BTreeMap<Address, BatchPosterState>,
+    pub pay_rewards_to: Address,
+    pub per_unit_reward: u64,
+    pub equilibration_units: u64,
+    pub inertia: u64,
+    pub per_batch_gas_cost: u64,
+    pub amortized_cost_cap_bips: u64, // Cap on reported costs per unit, 0 for none
+}
+
+/// Funds the L1 pricer owes a batch poster
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchPosterState {
+    pub funds_due: U256,
+    pub pay_to: Address,
+}
+
+/// Retryable ticket, kept in the `retryables` table until it is redeemed,
+/// cancelled or expires
+///
+/// The ticket's call value sits in its escrow account meanwhile. The
+/// `retryable_timeouts` table indexes tickets by `timeout` so the expired ones
+/// are found first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retryable {
+    pub id: B256, // Hash of the submission that created the ticket
+    pub num_tries: u64,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub call_value: U256,
+    pub beneficiary: Address, // Receives the call value if the ticket is cancelled or expires
+    pub data: Vec<u8>,
+    pub timeout: u64, // Timestamp after which the ticket can no longer be redeemed
+}
+
+/// Values a block overwrote, kept in the `changesets` table under its number
+/// so the block can be unwound
+///
+/// Holds the state root, L1 and L2 pricing and send accumulator the block
+/// started from, and the values it replaced of the accounts, storage slots
+/// and retryable tickets it wrote, none where there were none. `code` lists
+/// the bytecode it added.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockChangeset {
+    pub state_root: B256,
+    pub l2_pricing: Option<L2PricingState>,
+    pub l1_pricing: Option<L1PricingState>,
+    pub send_merkle: Option<SendMerkleState>,
+    pub accounts: BTreeMap<Address, Option<ArbitrumAccount>>,
+    pub storage: BTreeMap<(Address, B256), U256>,
+    pub code: BTreeSet<B256>,
+    pub retryables: BTreeMap<B256, Option<Retryable>>,
+}
+
+// bincode cannot deserialize the string form of signed integers
+mod i256_bytes {
+    use alloy_primitives::I256;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &I256, serializer: S) -> Result<S::Ok, S::Error> {
+        value.to_be_bytes::<32>().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<I256, D::Error> {
+        <[u8; 32]>::deserialize(deserializer).map(I256::from_be_bytes)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -402,6 +788,7 @@ pub struct ArbitrumReceipt {
     pub block_number: u64,
     pub cumulative_gas_used: u64,
     pub gas_used: u64,
+    pub gas_used_for_l1: u64, // Part of `gas_used` paying for the L1 data
     pub contract_address: Option<Address>,
     pub logs: Vec<Log>,
     pub status: u64,
@@ -465,6 +852,94 @@ impl DatabaseValue for L1Message {
     }
 }
 
+impl DatabaseValue for SyncStatus {
+    fn encode(&self) -> Result<Vec<u8>> {
+        let encoded = bincode::serialize(self).context("Failed to serialize SyncStatus")?;
+        Ok(encoded)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data).context("Failed to deserialize SyncStatus")
+    }
+}
+
+impl DatabaseValue for InFlightBatch {
+    fn encode(&self) -> Result<Vec<u8>> {
+        let encoded = bincode::serialize(self).context("Failed to serialize InFlightBatch")?;
+        Ok(encoded)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data).context("Failed to deserialize InFlightBatch")
+    }
+}
+
+impl DatabaseValue for L2PricingState {
+    fn encode(&self) -> Result<Vec<u8>> {
+        let encoded = bincode::serialize(self).context("Failed to serialize L2PricingState")?;
+        Ok(encoded)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data).context("Failed to deserialize L2PricingState")
+    }
+}
+
+impl DatabaseValue for L1PricingState {
+    fn encode(&self) -> Result<Vec<u8>> {
+        let encoded = bincode::serialize(self).context("Failed to serialize L1PricingState")?;
+        Ok(encoded)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data).context("Failed to deserialize L1PricingState")
+    }
+}
+
+impl DatabaseValue for SendMerkleState {
+    fn encode(&self) -> Result<Vec<u8>> {
+        let encoded = bincode::serialize(self).context("Failed to serialize SendMerkleState")?;
+        Ok(encoded)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data).context("Failed to deserialize SendMerkleState")
+    }
+}
+
+impl DatabaseValue for L2ToL1Send {
+    fn encode(&self) -> Result<Vec<u8>> {
+        let encoded = bincode::serialize(self).context("Failed to serialize L2ToL1Send")?;
+        Ok(encoded)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data).context("Failed to deserialize L2ToL1Send")
+    }
+}
+
+impl DatabaseValue for Retryable {
+    fn encode(&self) -> Result<Vec<u8>> {
+        let encoded = bincode::serialize(self).context("Failed to serialize Retryable")?;
+        Ok(encoded)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data).context("Failed to deserialize Retryable")
+    }
+}
+
+impl DatabaseValue for BlockChangeset {
+    fn encode(&self) -> Result<Vec<u8>> {
+        let encoded = bincode::serialize(self).context("Failed to serialize BlockChangeset")?;
+        Ok(encoded)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data).context("Failed to deserialize BlockChangeset")
+    }
+}
+
 impl DatabaseValue for Log {
     fn encode(&self) -> Result<Vec<u8>> {
         let encoded = bincode::serialize(self).context("Failed to serialize Log")?;
@@ -42,6 +42,8 @@ pub struct DatabaseTables {
     accounts: Database<Bytes, Bytes>,
     /// Contract storage indexed by (address, key)
     storage: Database<Bytes, Bytes>,
+    /// Contract bytecode indexed by code hash
+    code: Database<Bytes, Bytes>,
     /// Transaction receipts indexed by hash
     receipts: Database<Bytes, Bytes>,
     /// State trie nodes indexed by hash
@@ -58,6 +60,20 @@ pub struct DatabaseTables {
     logs_by_block: Database<Bytes, Bytes>,
     /// Filter last-seen timestamps (epoch millis)
     filter_last_seen: Database<Bytes, Bytes>,
+    /// Retryable tickets indexed by ticket id
+    retryables: Database<Bytes, Bytes>,
+    /// Retryable ticket ids indexed by timeout
+    retryable_timeouts: Database<Bytes, Bytes>,
+    /// L2-to-L1 sends indexed by leaf
+    sends: Database<Bytes, Bytes>,
+    /// Values overwritten by each block, indexed by block number
+    changesets: Database<Bytes, Bytes>,
+    /// L1 gas of sequencer messages indexed by hash
+    batch_gas: Database<Bytes, Bytes>,
+    /// Batch numbers indexed by L2 block number
+    block_batches: Database<Bytes, Bytes>,
+    /// Send tree nodes indexed by (level, leaf)
+    send_merkle_nodes: Database<Bytes, Bytes>,
 }
 
 impl ArbitrumDatabase {
@@ -87,7 +103,7 @@ impl ArbitrumDatabase {
         let env = unsafe {
             EnvOpenOptions::new()
                 .map_size(max_size)
-                .max_dbs(16) // Allow up to 16 databases
+                .max_dbs(32) // Allow up to 32 databases
                 .max_readers(1024) // Support many concurrent readers
                 .open(db_path)
                 .context("Failed to open LMDB environment")?
@@ -127,6 +143,9 @@ impl ArbitrumDatabase {
             storage: env
                 .create_database(&mut wtxn, Some("storage"))
                 .context("Failed to create storage table")?,
+            code: env
+                .create_database(&mut wtxn, Some("code"))
+                .context("Failed to create code table")?,
             receipts: env
                 .create_database(&mut wtxn, Some("receipts"))
                 .context("Failed to create receipts table")?,
@@ -151,6 +170,27 @@ impl ArbitrumDatabase {
             filter_last_seen: env
                 .create_database(&mut wtxn, Some("filter_last_seen"))
                 .context("Failed to create filter_last_seen table")?,
+            retryables: env
+                .create_database(&mut wtxn, Some("retryables"))
+                .context("Failed to create retryables table")?,
+            retryable_timeouts: env
+                .create_database(&mut wtxn, Some("retryable_timeouts"))
+                .context("Failed to create retryable_timeouts table")?,
+            sends: env
+                .create_database(&mut wtxn, Some("sends"))
+                .context("Failed to create sends table")?,
+            changesets: env
+                .create_database(&mut wtxn, Some("changesets"))
+                .context("Failed to create changesets table")?,
+            batch_gas: env
+                .create_database(&mut wtxn, Some("batch_gas"))
+                .context("Failed to create batch_gas table")?,
+            block_batches: env
+                .create_database(&mut wtxn, Some("block_batches"))
+                .context("Failed to create block_batches table")?,
+            send_merkle_nodes: env
+                .create_database(&mut wtxn, Some("send_merkle_nodes"))
+                .context("Failed to create send_merkle_nodes table")?,
         };
 
         wtxn.commit().context("Failed to commit table creation")?;
@@ -181,6 +221,7 @@ impl ArbitrumDatabase {
                 transactions: tables_guard.transactions,
                 accounts: tables_guard.accounts,
                 storage: tables_guard.storage,
+                code: tables_guard.code,
                 receipts: tables_guard.receipts,
                 state_trie: tables_guard.state_trie,
                 batches: tables_guard.batches,
@@ -189,6 +230,13 @@ impl ArbitrumDatabase {
                 filter_cursors: tables_guard.filter_cursors,
                 logs_by_block: tables_guard.logs_by_block,
                 filter_last_seen: tables_guard.filter_last_seen,
+                retryables: tables_guard.retryables,
+                retryable_timeouts: tables_guard.retryable_timeouts,
+                sends: tables_guard.sends,
+                changesets: tables_guard.changesets,
+                batch_gas: tables_guard.batch_gas,
+                block_batches: tables_guard.block_batches,
+                send_merkle_nodes: tables_guard.send_merkle_nodes,
             }
         };
 
@@ -224,6 +272,7 @@ impl ArbitrumDatabase {
                 transactions: tables_guard.transactions,
                 accounts: tables_guard.accounts,
                 storage: tables_guard.storage,
+                code: tables_guard.code,
                 receipts: tables_guard.receipts,
                 state_trie: tables_guard.state_trie,
                 batches: tables_guard.batches,
@@ -232,6 +281,13 @@ impl ArbitrumDatabase {
                 filter_cursors: tables_guard.filter_cursors,
                 logs_by_block: tables_guard.logs_by_block,
                 filter_last_seen: tables_guard.filter_last_seen,
+                retryables: tables_guard.retryables,
+                retryable_timeouts: tables_guard.retryable_timeouts,
+                sends: tables_guard.sends,
+                changesets: tables_guard.changesets,
+                batch_gas: tables_guard.batch_gas,
+                block_batches: tables_guard.block_batches,
+                send_merkle_nodes: tables_guard.send_merkle_nodes,
             }
         };
 
@@ -307,6 +363,23 @@ impl ArbitrumDatabase {
         .await
     }
 
+    /// Get the value under the lowest key of a table, as keys sort bytewise
+    pub async fn first<V>(&self, table: TableType) -> Result<Option<V>>
+    where
+        V: DatabaseValue + Send + Sync + 'static,
+    {
+        self.read(move |txn, tables| {
+            let db = Self::get_table(tables, table);
+
+            match db.first(txn) {
+                Ok(Some((_, value_bytes))) => Ok(Some(V::decode(value_bytes)?)),
+                Ok(None) => Ok(None),
+                Err(err) => Err(eyre::eyre!("Database first error: {}", err)),
+            }
+        })
+        .await
+    }
+
     /// Get database statistics
     pub async fn stats(&self) -> Result<DatabaseStats> {
         self.read(|txn, tables| {
@@ -325,12 +398,13 @@ impl ArbitrumDatabase {
     }
 
     /// Helper to get the correct database for a table type
-    fn get_table(tables: &DatabaseTables, table: TableType) -> &Database<Bytes, Bytes> {
+    pub(crate) fn get_table(tables: &DatabaseTables, table: TableType) -> &Database<Bytes, Bytes> {
         match table {
             TableType::Blocks => &tables.blocks,
             TableType::Transactions => &tables.transactions,
             TableType::Accounts => &tables.accounts,
             TableType::Storage => &tables.storage,
+            TableType::Code => &tables.code,
             TableType::Receipts => &tables.receipts,
             TableType::StateTrie => &tables.state_trie,
             TableType::Batches => &tables.batches,
@@ -339,6 +413,13 @@ impl ArbitrumDatabase {
             TableType::FilterCursors => &tables.filter_cursors,
             TableType::LogsByBlock => &tables.logs_by_block,
             TableType::FilterLastSeen => &tables.filter_last_seen,
+            TableType::Retryables => &tables.retryables,
+            TableType::RetryableTimeouts => &tables.retryable_timeouts,
+            TableType::Sends => &tables.sends,
+            TableType::Changesets => &tables.changesets,
+            TableType::BatchGas => &tables.batch_gas,
+            TableType::BlockBatches => &tables.block_batches,
+            TableType::SendMerkleNodes => &tables.send_merkle_nodes,
         }
     }
 
@@ -9,30 +9,49 @@
 pub mod codec;
 pub mod database;
 pub mod schema;
+pub mod transaction;
+pub mod trie;
 
 // Re-export data types for other crates
 use std::sync::Arc;
 
-use alloy_primitives::{Address, B256};
+use alloy_primitives::{Address, B256, U256};
 use arbitrum_config::ArbitrumRethConfig;
 pub use codec::{
-    ArbitrumAccount, ArbitrumBatch, ArbitrumBlock, ArbitrumReceipt, ArbitrumTransaction, L1Message,
-    Log,
+    ArbitrumAccount, ArbitrumBatch, ArbitrumBlock, ArbitrumReceipt, ArbitrumTransaction,
+    ArbitrumTxKind, BatchPosterState, BlockChangeset, InFlightBatch, L1Message, L1MessageKind,
+    L1PricingState, L2PricingState, L2ToL1Send, Log, Retryable, SendMerkleState, SyncStatus,
 };
 use eyre::Result;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock, broadcast};
 use tracing::{debug, info};
 
 use crate::{
+    codec::{DatabaseKey, DatabaseValue},
     database::ArbitrumDatabase,
     schema::{TableType, keys, metadata_keys},
+    trie::{DirtyState, EMPTY_ROOT_HASH},
 };
 
+/// Notification that L2 blocks were unwound after an L1 reorg
+#[derive(Debug, Clone)]
+pub struct L2Reorg {
+    /// Highest L2 block that survived the unwind
+    pub new_head: u64,
+    /// Logs of the unwound blocks, flagged as `removed`
+    pub removed_logs: Vec<codec::Log>,
+}
+
 /// Arbitrum storage layer that handles L2 state and Arbitrum-specific data
 pub struct ArbitrumStorage {
     config: ArbitrumRethConfig,
     is_running: Arc<RwLock<bool>>,
     db: Arc<ArbitrumDatabase>,
+    reorgs: broadcast::Sender<L2Reorg>,
+    /// State changed since the tries were last updated
+    dirty: Arc<Mutex<DirtyState>>,
+    /// Values overwritten by the block being executed, if one was begun
+    changes: Arc<Mutex<Option<BlockChangeset>>>,
 }
 
 impl ArbitrumStorage {
@@ -46,6 +65,9 @@ impl ArbitrumStorage {
             config: config.clone(),
             is_running: Arc::new(RwLock::new(false)),
             db: Arc::new(db),
+            reorgs: broadcast::channel(16).0,
+            dirty: Arc::new(Mutex::new(DirtyState::default())),
+            changes: Arc::new(Mutex::new(None)),
         })
     }
 
@@ -166,7 +188,28 @@ impl ArbitrumStorage {
         Ok(())
     }
 
-    /// Store a block in the database
+    /// Start recording the values overwritten until the next block is stored
+    ///
+    /// State written before is committed to the trie first, so the root the
+    /// block starts from covers it. A block begun but never stored keeps
+    /// recording into the next one.
+    pub async fn begin_block(&self) -> Result<()> {
+        let state_root = self.commit_state_root().await?;
+        let mut changes = self.changes.lock().await;
+        if changes.is_none() {
+            *changes = Some(BlockChangeset {
+                state_root,
+                l2_pricing: self.get_l2_pricing().await?,
+                l1_pricing: self.get_l1_pricing().await?,
+                send_merkle: self.get_send_merkle().await?,
+                ..Default::default()
+            });
+        }
+        Ok(())
+    }
+
+    /// Store a block in the database, with the values it overwrote since
+    /// [`Self::begin_block`]
     pub async fn store_block(&self, block: &codec::ArbitrumBlock) -> Result<()> {
         // Store by block number
         let key = keys::BlockNumber(block.number);
@@ -186,6 +229,11 @@ impl ArbitrumStorage {
                 &block.number,
             )
             .await?;
+        if let Some(changeset) = self.changes.lock().await.take() {
+            self.db
+                .put::<keys::BlockNumber, BlockChangeset>(TableType::Changesets, &key, &changeset)
+                .await?;
+        }
         Ok(())
     }
 
@@ -280,10 +328,13 @@ impl ArbitrumStorage {
         address: Address,
         account: &codec::ArbitrumAccount,
     ) -> Result<()> {
+        self.record_account(address).await?;
         let key = keys::AccountAddress(address);
         self.db
             .put::<keys::AccountAddress, codec::ArbitrumAccount>(TableType::Accounts, &key, account)
-            .await
+            .await?;
+        self.dirty.lock().await.accounts.insert(address);
+        Ok(())
     }
 
     /// Get an account by address
@@ -294,6 +345,127 @@ impl ArbitrumStorage {
             .await
     }
 
+    /// Delete an account, as when it self-destructs or is cleared while empty
+    pub async fn delete_account(&self, address: &Address) -> Result<()> {
+        self.record_account(*address).await?;
+        let key = keys::AccountAddress(*address);
+        self.db
+            .delete::<keys::AccountAddress>(TableType::Accounts, &key)
+            .await?;
+        self.dirty.lock().await.accounts.insert(*address);
+        Ok(())
+    }
+
+    /// Keep the account at `address` as it was before the block being
+    /// executed first wrote it
+    async fn record_account(&self, address: Address) -> Result<()> {
+        if let Some(changes) = self.changes.lock().await.as_mut()
+            && !changes.accounts.contains_key(&address)
+        {
+            let account = self.get_account(&address).await?;
+            changes.accounts.insert(address, account);
+        }
+        Ok(())
+    }
+
+    /// Store a contract storage slot, deleting it when set to zero
+    pub async fn store_storage(&self, address: Address, slot: B256, value: U256) -> Result<()> {
+        if let Some(changes) = self.changes.lock().await.as_mut()
+            && !changes.storage.contains_key(&(address, slot))
+        {
+            let value = self.get_storage(&address, &slot).await?;
+            changes.storage.insert((address, slot), value);
+        }
+        // Committing the slot rewrites the storage root of the account
+        self.record_account(address).await?;
+        let key = keys::StorageKey { address, slot };
+        if value.is_zero() {
+            self.db
+                .delete::<keys::StorageKey>(TableType::Storage, &key)
+                .await?;
+        } else {
+            self.db
+                .put::<keys::StorageKey, U256>(TableType::Storage, &key, &value)
+                .await?;
+        }
+        let mut dirty = self.dirty.lock().await;
+        dirty.slots.entry(address).or_default().insert(slot);
+        Ok(())
+    }
+
+    /// Get a contract storage slot, zero if never set
+    pub async fn get_storage(&self, address: &Address, slot: &B256) -> Result<U256> {
+        let key = keys::StorageKey {
+            address: *address,
+            slot: *slot,
+        };
+        Ok(self
+            .db
+            .get::<keys::StorageKey, U256>(TableType::Storage, &key)
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// Store contract bytecode under its hash
+    pub async fn store_code(&self, code_hash: B256, code: &[u8]) -> Result<()> {
+        if let Some(changes) = self.changes.lock().await.as_mut()
+            && self.get_code(&code_hash).await?.is_none()
+        {
+            changes.code.insert(code_hash);
+        }
+        let key = keys::CodeHash(code_hash);
+        self.db
+            .put::<keys::CodeHash, Vec<u8>>(TableType::Code, &key, &code.to_vec())
+            .await
+    }
+
+    /// Get contract bytecode by its hash
+    pub async fn get_code(&self, code_hash: &B256) -> Result<Option<Vec<u8>>> {
+        let key = keys::CodeHash(*code_hash);
+        self.db
+            .get::<keys::CodeHash, Vec<u8>>(TableType::Code, &key)
+            .await
+    }
+
+    /// Bring the state trie up to date with the accounts and storage changed
+    /// since the last commit and return its new root
+    pub async fn commit_state_root(&self) -> Result<B256> {
+        // Writers wait for the commit, so no change slips between the tries
+        // being updated and the dirty set being cleared
+        let mut dirty = self.dirty.lock().await;
+        if dirty.is_empty() {
+            return self.get_state_root().await;
+        }
+        let changes = dirty.clone();
+        let root = self
+            .db
+            .write(move |txn, tables| {
+                let metadata = *ArbitrumDatabase::get_table(tables, TableType::Metadata);
+                let key = keys::MetadataKey::from(metadata_keys::STATE_ROOT).encode()?;
+                let root = metadata
+                    .get(txn, &key)?
+                    .map(B256::decode)
+                    .transpose()?
+                    .unwrap_or(EMPTY_ROOT_HASH);
+                let root = trie::update_tries(txn, tables, root, changes)?;
+                metadata.put(txn, &key, &root.encode()?)?;
+                Ok(root)
+            })
+            .await?;
+        *dirty = DirtyState::default();
+        debug!("Committed state root {}", root);
+        Ok(root)
+    }
+
+    /// Get the root of the state trie as of the last commit
+    pub async fn get_state_root(&self) -> Result<B256> {
+        Ok(self
+            .db
+            .get::<keys::MetadataKey, B256>(TableType::Metadata, &metadata_keys::STATE_ROOT.into())
+            .await?
+            .unwrap_or(EMPTY_ROOT_HASH))
+    }
+
     /// Store an L1 message in the database
     pub async fn store_l1_message(&self, message: &codec::L1Message) -> Result<()> {
         let key = keys::L1MessageNumber(message.message_number);
@@ -330,12 +502,47 @@ impl ArbitrumStorage {
         Ok(out)
     }
 
-    /// Store an Arbitrum batch in the database
+    /// Checkpoint the inbox tracker progress
+    pub async fn store_sync_status(&self, status: &codec::SyncStatus) -> Result<()> {
+        self.db
+            .put::<keys::MetadataKey, codec::SyncStatus>(
+                TableType::Metadata,
+                &metadata_keys::SYNC_STATUS.into(),
+                status,
+            )
+            .await
+    }
+
+    /// Load the last inbox tracker checkpoint, if any
+    pub async fn get_sync_status(&self) -> Result<Option<codec::SyncStatus>> {
+        self.db
+            .get::<keys::MetadataKey, codec::SyncStatus>(
+                TableType::Metadata,
+                &metadata_keys::SYNC_STATUS.into(),
+            )
+            .await
+    }
+
+    /// Store an Arbitrum batch in the database and index the L2 blocks of
+    /// its `block_range`
     pub async fn store_batch(&self, batch: &codec::ArbitrumBatch) -> Result<()> {
         let key = keys::BatchNumber(batch.batch_number);
         self.db
             .put::<keys::BatchNumber, codec::ArbitrumBatch>(TableType::Batches, &key, batch)
             .await?;
+        // (0, 0) is the range of a batch none of whose messages were executed
+        if batch.block_range != (0, 0) {
+            let (first, last) = batch.block_range;
+            for number in first..=last {
+                self.db
+                    .put::<keys::BlockNumber, u64>(
+                        TableType::BlockBatches,
+                        &keys::BlockNumber(number),
+                        &batch.batch_number,
+                    )
+                    .await?;
+            }
+        }
         // Update latest batch number
         self.db
             .put::<keys::MetadataKey, u64>(
@@ -370,6 +577,605 @@ impl ArbitrumStorage {
             .await
     }
 
+    /// Record that executing a message of `batch_number` produced L2 block
+    /// `block_number`, widening the `block_range` of the batch to include it
+    pub async fn assign_block_to_batch(&self, batch_number: u64, block_number: u64) -> Result<()> {
+        let mut batch = self
+            .get_batch(batch_number)
+            .await?
+            .ok_or_else(|| eyre::eyre!("Batch {} not found", batch_number))?;
+        batch.block_range = match batch.block_range {
+            (0, 0) => (block_number, block_number),
+            (first, last) => (first.min(block_number), last.max(block_number)),
+        };
+        self.store_batch(&batch).await
+    }
+
+    /// Get the batch carrying L2 block `number`
+    pub async fn get_batch_of_block(&self, number: u64) -> Result<Option<codec::ArbitrumBatch>> {
+        let batch_number = self
+            .db
+            .get::<keys::BlockNumber, u64>(TableType::BlockBatches, &keys::BlockNumber(number))
+            .await?;
+        match batch_number {
+            Some(batch_number) => self.get_batch(batch_number).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Record the L1 gas of the sequencer message hashing to `data_hash`
+    pub async fn store_batch_gas(&self, data_hash: B256, gas: u64) -> Result<()> {
+        self.db
+            .put::<keys::DataHash, u64>(TableType::BatchGas, &keys::DataHash(data_hash), &gas)
+            .await
+    }
+
+    /// Get the L1 gas of the sequencer message hashing to `data_hash`, known
+    /// once the batch was read from L1
+    pub async fn get_batch_gas(&self, data_hash: B256) -> Result<Option<u64>> {
+        self.db
+            .get::<keys::DataHash, u64>(TableType::BatchGas, &keys::DataHash(data_hash))
+            .await
+    }
+
+    /// Checkpoint the batch-posting transaction awaiting confirmation
+    pub async fn store_in_flight_batch(&self, in_flight: &codec::InFlightBatch) -> Result<()> {
+        self.db
+            .put::<keys::MetadataKey, codec::InFlightBatch>(
+                TableType::Metadata,
+                &metadata_keys::IN_FLIGHT_BATCH.into(),
+                in_flight,
+            )
+            .await
+    }
+
+    /// Load the batch-posting transaction awaiting confirmation, if any
+    pub async fn get_in_flight_batch(&self) -> Result<Option<codec::InFlightBatch>> {
+        self.db
+            .get::<keys::MetadataKey, codec::InFlightBatch>(
+                TableType::Metadata,
+                &metadata_keys::IN_FLIGHT_BATCH.into(),
+            )
+            .await
+    }
+
+    /// Forget the batch-posting transaction once it is confirmed or abandoned
+    pub async fn delete_in_flight_batch(&self) -> Result<()> {
+        self.db
+            .delete::<keys::MetadataKey>(
+                TableType::Metadata,
+                &metadata_keys::IN_FLIGHT_BATCH.into(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Persist the L2 pricing state after a block
+    pub async fn store_l2_pricing(&self, pricing: &codec::L2PricingState) -> Result<()> {
+        self.db
+            .put::<keys::MetadataKey, codec::L2PricingState>(
+                TableType::Metadata,
+                &metadata_keys::L2_PRICING.into(),
+                pricing,
+            )
+            .await
+    }
+
+    /// Load the L2 pricing state, none before the first block is executed
+    pub async fn get_l2_pricing(&self) -> Result<Option<codec::L2PricingState>> {
+        self.db
+            .get::<keys::MetadataKey, codec::L2PricingState>(
+                TableType::Metadata,
+                &metadata_keys::L2_PRICING.into(),
+            )
+            .await
+    }
+
+    /// Persist the L1 pricing state after a block
+    pub async fn store_l1_pricing(&self, pricing: &codec::L1PricingState) -> Result<()> {
+        self.db
+            .put::<keys::MetadataKey, codec::L1PricingState>(
+                TableType::Metadata,
+                &metadata_keys::L1_PRICING.into(),
+                pricing,
+            )
+            .await
+    }
+
+    /// Load the L1 pricing state, none before the first block is executed
+    pub async fn get_l1_pricing(&self) -> Result<Option<codec::L1PricingState>> {
+        self.db
+            .get::<keys::MetadataKey, codec::L1PricingState>(
+                TableType::Metadata,
+                &metadata_keys::L1_PRICING.into(),
+            )
+            .await
+    }
+
+    /// Persist the send accumulator after a block
+    pub async fn store_send_merkle(&self, merkle: &codec::SendMerkleState) -> Result<()> {
+        self.db
+            .put::<keys::MetadataKey, codec::SendMerkleState>(
+                TableType::Metadata,
+                &metadata_keys::SEND_MERKLE.into(),
+                merkle,
+            )
+            .await
+    }
+
+    /// Load the send accumulator, none before the first block is executed
+    pub async fn get_send_merkle(&self) -> Result<Option<codec::SendMerkleState>> {
+        self.db
+            .get::<keys::MetadataKey, codec::SendMerkleState>(
+                TableType::Metadata,
+                &metadata_keys::SEND_MERKLE.into(),
+            )
+            .await
+    }
+
+    /// Store a send to L1 under its leaf in the send tree
+    pub async fn store_send(&self, send: &codec::L2ToL1Send) -> Result<()> {
+        self.db
+            .put::<keys::SendPosition, codec::L2ToL1Send>(
+                TableType::Sends,
+                &keys::SendPosition(send.position),
+                send,
+            )
+            .await
+    }
+
+    /// Get the send at leaf `position` of the send tree
+    pub async fn get_send(&self, position: u64) -> Result<Option<codec::L2ToL1Send>> {
+        self.db
+            .get::<keys::SendPosition, codec::L2ToL1Send>(
+                TableType::Sends,
+                &keys::SendPosition(position),
+            )
+            .await
+    }
+
+    /// Store the node of the send tree that the append of `leaf` completed
+    /// at `level`, as ArbSys logs it in `SendMerkleUpdate`
+    pub async fn store_send_merkle_node(&self, level: u64, leaf: u64, hash: B256) -> Result<()> {
+        self.db
+            .put::<keys::MerkleNode, B256>(
+                TableType::SendMerkleNodes,
+                &keys::MerkleNode { level, leaf },
+                &hash,
+            )
+            .await
+    }
+
+    /// Get the node of the send tree at `level` whose last leaf is `leaf`
+    pub async fn get_send_merkle_node(&self, level: u64, leaf: u64) -> Result<Option<B256>> {
+        self.db
+            .get::<keys::MerkleNode, B256>(
+                TableType::SendMerkleNodes,
+                &keys::MerkleNode { level, leaf },
+            )
+            .await
+    }
+
+    /// Get the sends at leaves `start` to `end` inclusive
+    pub async fn get_sends(&self, start: u64, end: u64) -> Result<Vec<codec::L2ToL1Send>> {
+        let mut out = Vec::new();
+        for position in start..=end {
+            if let Some(send) = self.get_send(position).await? {
+                out.push(send);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Store a retryable ticket, moving it in the timeout index if its
+    /// timeout changed
+    pub async fn store_retryable(&self, retryable: &codec::Retryable) -> Result<()> {
+        let key = keys::TicketId(retryable.id);
+        let existing = self.get_retryable(&retryable.id).await?;
+        if let Some(changes) = self.changes.lock().await.as_mut() {
+            changes
+                .retryables
+                .entry(retryable.id)
+                .or_insert_with(|| existing.clone());
+        }
+        if let Some(existing) = existing
+            && existing.timeout != retryable.timeout
+        {
+            self.db
+                .delete::<keys::RetryableTimeout>(
+                    TableType::RetryableTimeouts,
+                    &(existing.timeout, existing.id).into(),
+                )
+                .await?;
+        }
+        self.db
+            .put::<keys::TicketId, codec::Retryable>(TableType::Retryables, &key, retryable)
+            .await?;
+        self.db
+            .put::<keys::RetryableTimeout, B256>(
+                TableType::RetryableTimeouts,
+                &(retryable.timeout, retryable.id).into(),
+                &retryable.id,
+            )
+            .await
+    }
+
+    /// Get a retryable ticket by id, expired or not
+    pub async fn get_retryable(&self, id: &B256) -> Result<Option<codec::Retryable>> {
+        self.db
+            .get::<keys::TicketId, codec::Retryable>(TableType::Retryables, &keys::TicketId(*id))
+            .await
+    }
+
+    /// Delete a retryable ticket and its timeout, returning it if it existed
+    pub async fn delete_retryable(&self, id: &B256) -> Result<Option<codec::Retryable>> {
+        let Some(retryable) = self.get_retryable(id).await? else {
+            return Ok(None);
+        };
+        if let Some(changes) = self.changes.lock().await.as_mut() {
+            changes
+                .retryables
+                .entry(*id)
+                .or_insert_with(|| Some(retryable.clone()));
+        }
+        self.db
+            .delete::<keys::TicketId>(TableType::Retryables, &keys::TicketId(*id))
+            .await?;
+        self.db
+            .delete::<keys::RetryableTimeout>(
+                TableType::RetryableTimeouts,
+                &(retryable.timeout, retryable.id).into(),
+            )
+            .await?;
+        Ok(Some(retryable))
+    }
+
+    /// Get the retryable ticket with the earliest timeout, if any
+    pub async fn get_next_retryable_timeout(&self) -> Result<Option<codec::Retryable>> {
+        match self.db.first::<B256>(TableType::RetryableTimeouts).await? {
+            Some(id) => self.get_retryable(&id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Delete L1 messages received in L1 blocks above `l1_block_number`
+    ///
+    /// Returns the number of the latest remaining message, if any.
+    pub async fn rollback_l1_messages_after(&self, l1_block_number: u64) -> Result<Option<u64>> {
+        let mut latest = self
+            .db
+            .get::<keys::MetadataKey, u64>(
+                TableType::Metadata,
+                &metadata_keys::LATEST_L1_MESSAGE_NUMBER.into(),
+            )
+            .await?
+            .unwrap_or(0);
+
+        let remaining = loop {
+            let key = keys::L1MessageNumber(latest);
+            match self
+                .db
+                .get::<keys::L1MessageNumber, codec::L1Message>(TableType::L1Messages, &key)
+                .await?
+            {
+                Some(message) if message.block_number > l1_block_number => {
+                    self.db
+                        .delete::<keys::L1MessageNumber>(TableType::L1Messages, &key)
+                        .await?;
+                    debug!("Rolled back L1 message {}", latest);
+                    if latest == 0 {
+                        break None;
+                    }
+                    latest -= 1;
+                }
+                Some(_) => break Some(latest),
+                None => break None,
+            }
+        };
+
+        self.db
+            .put::<keys::MetadataKey, u64>(
+                TableType::Metadata,
+                &metadata_keys::LATEST_L1_MESSAGE_NUMBER.into(),
+                &remaining.unwrap_or(0),
+            )
+            .await?;
+        Ok(remaining)
+    }
+
+    /// Delete batches posted in L1 blocks above `l1_block_number`
+    ///
+    /// Returns the number of the latest remaining batch, if any.
+    pub async fn rollback_batches_after(&self, l1_block_number: u64) -> Result<Option<u64>> {
+        let mut latest = self
+            .db
+            .get::<keys::MetadataKey, u64>(
+                TableType::Metadata,
+                &metadata_keys::LATEST_BATCH_NUMBER.into(),
+            )
+            .await?
+            .unwrap_or(0);
+
+        let remaining = loop {
+            match self.get_batch(latest).await? {
+                Some(batch) if batch.l1_block_number > l1_block_number => {
+                    if batch.block_range != (0, 0) {
+                        let (first, last) = batch.block_range;
+                        for number in first..=last {
+                            self.db
+                                .delete::<keys::BlockNumber>(
+                                    TableType::BlockBatches,
+                                    &keys::BlockNumber(number),
+                                )
+                                .await?;
+                        }
+                    }
+                    self.db
+                        .delete::<keys::BatchNumber>(TableType::Batches, &keys::BatchNumber(latest))
+                        .await?;
+                    debug!("Rolled back batch {}", latest);
+                    if latest == 0 {
+                        break None;
+                    }
+                    latest -= 1;
+                }
+                Some(_) => break Some(latest),
+                None => break None,
+            }
+        };
+
+        self.db
+            .put::<keys::MetadataKey, u64>(
+                TableType::Metadata,
+                &metadata_keys::LATEST_BATCH_NUMBER.into(),
+                &remaining.unwrap_or(0),
+            )
+            .await?;
+        Ok(remaining)
+    }
+
+    /// Subscribe to L2 unwind notifications
+    pub fn subscribe_reorgs(&self) -> broadcast::Receiver<L2Reorg> {
+        self.reorgs.subscribe()
+    }
+
+    /// Unwind every L2 block derived from L1 blocks above `l1_block_number`
+    ///
+    /// Returns the logs of the unwound blocks, flagged as `removed`.
+    pub async fn unwind_blocks_after_l1_block(
+        &self,
+        l1_block_number: u64,
+    ) -> Result<Vec<codec::Log>> {
+        let mut new_head = self.get_current_block_number().await?;
+        while new_head > 0 {
+            match self.get_block_by_number(new_head).await? {
+                Some(block) if block.l1_block_number > l1_block_number => new_head -= 1,
+                _ => break,
+            }
+        }
+        self.unwind_blocks_above(new_head).await
+    }
+
+    /// Remove all L2 blocks above `number` together with their transactions,
+    /// receipts, indexed logs, sends to L1 and the send tree nodes these
+    /// completed, revert the state they wrote and notify reorg subscribers
+    ///
+    /// Accounts, storage, code, retryables, pricing, the send accumulator and
+    /// the state root go back to the values the changesets of the blocks
+    /// recorded. Trie nodes are kept by hash, so the root of `number` resolves
+    /// again. Returns the logs of the unwound blocks, flagged as `removed`.
+    pub async fn unwind_blocks_above(&self, number: u64) -> Result<Vec<codec::Log>> {
+        let latest = self.get_current_block_number().await?;
+        if latest <= number {
+            return Ok(Vec::new());
+        }
+
+        // The sends of the unwound blocks follow those of the new head
+        let send_count = |block: Option<codec::ArbitrumBlock>| block.map_or(0, |b| b.send_count);
+        let sends_kept = send_count(self.get_block_by_number(number).await?);
+        let sends_made = send_count(self.get_block_by_number(latest).await?);
+
+        let mut removed_logs = Vec::new();
+        for n in ((number + 1)..=latest).rev() {
+            let Some(block) = self.get_block_by_number(n).await? else {
+                continue;
+            };
+
+            let logs_key = keys::BlockNumber(n);
+            if let Some(logs) = self
+                .db
+                .get::<keys::BlockNumber, Vec<codec::Log>>(TableType::LogsByBlock, &logs_key)
+                .await?
+            {
+                removed_logs.extend(logs.into_iter().rev().map(|mut log| {
+                    log.removed = true;
+                    log
+                }));
+                self.db
+                    .delete::<keys::BlockNumber>(TableType::LogsByBlock, &logs_key)
+                    .await?;
+            }
+
+            for tx_hash in &block.transactions {
+                let key = keys::TransactionHash(*tx_hash);
+                self.db
+                    .delete::<keys::TransactionHash>(TableType::Receipts, &key)
+                    .await?;
+                self.db
+                    .delete::<keys::TransactionHash>(TableType::Transactions, &key)
+                    .await?;
+            }
+
+            self.db
+                .delete::<keys::BlockHash>(TableType::Blocks, &keys::BlockHash(block.hash))
+                .await?;
+            self.db
+                .delete::<keys::BlockNumber>(TableType::Blocks, &logs_key)
+                .await?;
+            self.db
+                .delete::<keys::BlockNumber>(TableType::BlockBatches, &logs_key)
+                .await?;
+            if let Some(changeset) = self
+                .db
+                .get::<keys::BlockNumber, BlockChangeset>(TableType::Changesets, &logs_key)
+                .await?
+            {
+                self.revert_changeset(changeset).await?;
+                self.db
+                    .delete::<keys::BlockNumber>(TableType::Changesets, &logs_key)
+                    .await?;
+            }
+            debug!("Unwound L2 block {}", n);
+        }
+        for position in sends_kept..sends_made {
+            self.db
+                .delete::<keys::SendPosition>(TableType::Sends, &keys::SendPosition(position))
+                .await?;
+            // The append of a leaf completes a node per trailing one bit
+            for level in 1..=u64::from(position.trailing_ones()) {
+                self.db
+                    .delete::<keys::MerkleNode>(
+                        TableType::SendMerkleNodes,
+                        &keys::MerkleNode {
+                            level,
+                            leaf: position,
+                        },
+                    )
+                    .await?;
+            }
+        }
+        // Report removed logs in chain order
+        removed_logs.reverse();
+
+        self.db
+            .put::<keys::MetadataKey, u64>(
+                TableType::Metadata,
+                &metadata_keys::LATEST_BLOCK_NUMBER.into(),
+                &number,
+            )
+            .await?;
+        info!("Unwound L2 blocks {}-{}", number + 1, latest);
+
+        // No subscribers is not an error
+        let _ = self.reorgs.send(L2Reorg {
+            new_head: number,
+            removed_logs: removed_logs.clone(),
+        });
+        Ok(removed_logs)
+    }
+
+    /// Write back the values a block overwrote, bypassing the changeset of
+    /// any block being executed
+    async fn revert_changeset(&self, changeset: BlockChangeset) -> Result<()> {
+        for (address, account) in changeset.accounts {
+            let key = keys::AccountAddress(address);
+            match account {
+                Some(account) => {
+                    self.db
+                        .put::<keys::AccountAddress, codec::ArbitrumAccount>(
+                            TableType::Accounts,
+                            &key,
+                            &account,
+                        )
+                        .await?
+                }
+                None => {
+                    self.db
+                        .delete::<keys::AccountAddress>(TableType::Accounts, &key)
+                        .await?;
+                }
+            }
+        }
+        for ((address, slot), value) in changeset.storage {
+            let key = keys::StorageKey { address, slot };
+            if value.is_zero() {
+                self.db
+                    .delete::<keys::StorageKey>(TableType::Storage, &key)
+                    .await?;
+            } else {
+                self.db
+                    .put::<keys::StorageKey, U256>(TableType::Storage, &key, &value)
+                    .await?;
+            }
+        }
+        for code_hash in changeset.code {
+            self.db
+                .delete::<keys::CodeHash>(TableType::Code, &keys::CodeHash(code_hash))
+                .await?;
+        }
+        for (id, retryable) in changeset.retryables {
+            // The ticket as the block left it goes, with its timeout
+            if let Some(current) = self.get_retryable(&id).await? {
+                self.db
+                    .delete::<keys::RetryableTimeout>(
+                        TableType::RetryableTimeouts,
+                        &(current.timeout, id).into(),
+                    )
+                    .await?;
+                self.db
+                    .delete::<keys::TicketId>(TableType::Retryables, &keys::TicketId(id))
+                    .await?;
+            }
+            if let Some(retryable) = retryable {
+                self.db
+                    .put::<keys::TicketId, codec::Retryable>(
+                        TableType::Retryables,
+                        &keys::TicketId(id),
+                        &retryable,
+                    )
+                    .await?;
+                self.db
+                    .put::<keys::RetryableTimeout, B256>(
+                        TableType::RetryableTimeouts,
+                        &(retryable.timeout, id).into(),
+                        &id,
+                    )
+                    .await?;
+            }
+        }
+
+        match changeset.l2_pricing {
+            Some(pricing) => self.store_l2_pricing(&pricing).await?,
+            None => {
+                self.db
+                    .delete::<keys::MetadataKey>(
+                        TableType::Metadata,
+                        &metadata_keys::L2_PRICING.into(),
+                    )
+                    .await?;
+            }
+        }
+        match changeset.l1_pricing {
+            Some(pricing) => self.store_l1_pricing(&pricing).await?,
+            None => {
+                self.db
+                    .delete::<keys::MetadataKey>(
+                        TableType::Metadata,
+                        &metadata_keys::L1_PRICING.into(),
+                    )
+                    .await?;
+            }
+        }
+        match changeset.send_merkle {
+            Some(merkle) => self.store_send_merkle(&merkle).await?,
+            None => {
+                self.db
+                    .delete::<keys::MetadataKey>(
+                        TableType::Metadata,
+                        &metadata_keys::SEND_MERKLE.into(),
+                    )
+                    .await?;
+            }
+        }
+        self.db
+            .put::<keys::MetadataKey, B256>(
+                TableType::Metadata,
+                &metadata_keys::STATE_ROOT.into(),
+                &changeset.state_root,
+            )
+            .await
+    }
+
     /// Get the current block number
     pub async fn get_current_block_number(&self) -> Result<u64> {
         let n = self
@@ -518,6 +1324,7 @@ pub struct StorageStats {
 mod tests {
     use std::path::PathBuf;
 
+    use alloy_primitives::U256;
     use arbitrum_config::*;
     use tempfile::TempDir;
 
@@ -578,6 +1385,7 @@ mod tests {
             gas_limit: 30_000_000,
             transactions: vec![],
             l1_block_number: 0,
+            ..Default::default()
         };
         storage.store_block(&blk).await.unwrap();
         assert_eq!(storage.get_current_block_number().await.unwrap(), 1);
@@ -595,6 +1403,8 @@ mod tests {
             nonce: 0,
             data: vec![],
             l1_sequence_number: None,
+            raw: vec![],
+            kind: ArbitrumTxKind::Signed,
         };
         storage.store_transaction(&tx).await.unwrap();
         assert!(storage.get_transaction(&tx.hash).await.unwrap().is_some());
@@ -612,13 +1422,153 @@ mod tests {
         let fetched = storage.get_account(&addr).await.unwrap();
         assert!(fetched.is_some());
         assert_eq!(fetched.unwrap().balance, U256::from(100u64));
+        storage.delete_account(&addr).await.unwrap();
+        assert!(storage.get_account(&addr).await.unwrap().is_none());
+
+        // Contract storage and code
+        let slot = B256::with_last_byte(1);
+        assert_eq!(storage.get_storage(&addr, &slot).await.unwrap(), U256::ZERO);
+        storage
+            .store_storage(addr, slot, U256::from(7u64))
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get_storage(&addr, &slot).await.unwrap(),
+            U256::from(7u64)
+        );
+        storage.store_storage(addr, slot, U256::ZERO).await.unwrap();
+        assert_eq!(storage.get_storage(&addr, &slot).await.unwrap(), U256::ZERO);
+        let code_hash = B256::repeat_byte(0xc0);
+        storage.store_code(code_hash, &[0x60, 0x00]).await.unwrap();
+        assert_eq!(
+            storage.get_code(&code_hash).await.unwrap(),
+            Some(vec![0x60, 0x00])
+        );
+    }
+
+    /// State root of `accounts` with their slots, rebuilt from scratch
+    fn full_state_root(accounts: &[(ArbitrumAccount, Vec<(B256, U256)>)]) -> B256 {
+        use alloy_primitives::keccak256;
+        use alloy_trie::{HashBuilder, Nibbles};
+
+        fn root(mut leaves: Vec<(B256, Vec<u8>)>) -> B256 {
+            leaves.sort();
+            let mut builder = HashBuilder::default();
+            for (key, value) in leaves {
+                builder.add_leaf(Nibbles::unpack(key), &value);
+            }
+            builder.root()
+        }
+
+        root(
+            accounts
+                .iter()
+                .map(|(account, slots)| {
+                    let storage_root = root(
+                        slots
+                            .iter()
+                            .map(|(slot, value)| (keccak256(slot), trie::slot_rlp(*value)))
+                            .collect(),
+                    );
+                    let account = ArbitrumAccount {
+                        storage_root,
+                        ..account.clone()
+                    };
+                    (keccak256(account.address), trie::account_rlp(&account))
+                })
+                .collect(),
+        )
     }
 
     #[tokio::test]
-    async fn test_batches_and_l1_messages() {
+    async fn test_state_root_is_updated_incrementally() {
         use alloy_primitives::address;
         let (storage, _tmp) = create_test_storage().await;
         storage.start().await.unwrap();
+        assert_eq!(storage.commit_state_root().await.unwrap(), EMPTY_ROOT_HASH);
+
+        let account = |address, balance: u64, nonce| ArbitrumAccount {
+            address,
+            balance: U256::from(balance),
+            nonce,
+            code_hash: B256::ZERO,
+            storage_root: B256::ZERO,
+        };
+        let alice = account(
+            address!("0x1111111111111111111111111111111111111111"),
+            100,
+            1,
+        );
+        let contract = ArbitrumAccount {
+            code_hash: B256::repeat_byte(0xc0),
+            ..account(address!("0x2222222222222222222222222222222222222222"), 0, 1)
+        };
+        let slot = B256::with_last_byte;
+
+        storage.store_account(alice.address, &alice).await.unwrap();
+        storage
+            .store_account(contract.address, &contract)
+            .await
+            .unwrap();
+        for (i, value) in [(1, 5u64), (2, 7), (3, 9)] {
+            storage
+                .store_storage(contract.address, slot(i), U256::from(value))
+                .await
+                .unwrap();
+        }
+        let root = storage.commit_state_root().await.unwrap();
+        let slots = vec![
+            (slot(1), U256::from(5)),
+            (slot(2), U256::from(7)),
+            (slot(3), U256::from(9)),
+        ];
+        assert_eq!(
+            root,
+            full_state_root(&[(alice.clone(), vec![]), (contract.clone(), slots)])
+        );
+        // The storage root is written back to the account
+        let stored = storage
+            .get_account(&contract.address)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_ne!(stored.storage_root, B256::ZERO);
+
+        // Nothing changed, nothing to commit
+        assert_eq!(storage.commit_state_root().await.unwrap(), root);
+        assert_eq!(storage.get_state_root().await.unwrap(), root);
+
+        // Clear and change slots, delete one account and create another
+        let bob = account(
+            address!("0x3333333333333333333333333333333333333333"),
+            42,
+            0,
+        );
+        storage.store_account(bob.address, &bob).await.unwrap();
+        storage.delete_account(&alice.address).await.unwrap();
+        storage
+            .store_storage(contract.address, slot(1), U256::ZERO)
+            .await
+            .unwrap();
+        storage
+            .store_storage(contract.address, slot(3), U256::from(10))
+            .await
+            .unwrap();
+        let contract = ArbitrumAccount { nonce: 2, ..stored };
+        storage
+            .store_account(contract.address, &contract)
+            .await
+            .unwrap();
+        let root = storage.commit_state_root().await.unwrap();
+        let slots = vec![(slot(2), U256::from(7)), (slot(3), U256::from(10))];
+        assert_eq!(root, full_state_root(&[(bob, vec![]), (contract, slots)]));
+    }
+
+    #[tokio::test]
+    async fn test_batches_and_l1_messages() {
+        use alloy_primitives::{I256, address};
+        let (storage, _tmp) = create_test_storage().await;
+        storage.start().await.unwrap();
 
         // Batch
         let batch = ArbitrumBatch {
@@ -628,6 +1578,9 @@ mod tests {
             timestamp: 1_700_000_100,
             transactions: vec![],
             l1_tx_hash: Some(B256::from([3u8; 32])),
+            after_delayed_messages_read: 2,
+            message_counts: (4, 9),
+            data_gas: 0,
         };
         storage.store_batch(&batch).await.unwrap();
         assert!(storage.get_batch(10).await.unwrap().is_some());
@@ -640,25 +1593,347 @@ mod tests {
                 .batch_number,
             10
         );
+        assert_eq!(
+            storage
+                .get_batch_of_block(3)
+                .await
+                .unwrap()
+                .unwrap()
+                .batch_number,
+            10
+        );
+        assert!(storage.get_batch_of_block(6).await.unwrap().is_none());
+
+        // A batch read from L1 carries its blocks once they are executed
+        let read = ArbitrumBatch {
+            batch_number: 11,
+            block_range: (0, 0),
+            l1_block_number: 1001,
+            ..batch.clone()
+        };
+        storage.store_batch(&read).await.unwrap();
+        assert!(storage.get_batch_of_block(0).await.unwrap().is_none());
+        storage.assign_block_to_batch(11, 6).await.unwrap();
+        storage.assign_block_to_batch(11, 7).await.unwrap();
+        assert_eq!(
+            storage.get_batch(11).await.unwrap().unwrap().block_range,
+            (6, 7)
+        );
+        assert_eq!(
+            storage
+                .get_batch_of_block(7)
+                .await
+                .unwrap()
+                .unwrap()
+                .batch_number,
+            11
+        );
+        assert!(storage.assign_block_to_batch(12, 8).await.is_err());
+
+        let data_hash = B256::repeat_byte(0xda);
+        assert_eq!(storage.get_batch_gas(data_hash).await.unwrap(), None);
+        storage.store_batch_gas(data_hash, 41_234).await.unwrap();
+        assert_eq!(
+            storage.get_batch_gas(data_hash).await.unwrap(),
+            Some(41_234)
+        );
 
         // L1 messages
         let m1 = L1Message {
             message_number: 1,
+            kind: L1MessageKind::L2Message,
             sender: address!("0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"),
             data: vec![1],
             timestamp: 1,
             block_number: 100,
+            request_id: B256::with_last_byte(1),
+            l1_base_fee: U256::ZERO,
+            batch_gas_cost: None,
         };
         let m2 = L1Message {
             message_number: 2,
+            kind: L1MessageKind::L2Message,
             sender: address!("0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"),
             data: vec![2],
             timestamp: 2,
             block_number: 101,
+            request_id: B256::with_last_byte(2),
+            l1_base_fee: U256::ZERO,
+            batch_gas_cost: None,
         };
         storage.store_l1_message(&m1).await.unwrap();
         storage.store_l1_message(&m2).await.unwrap();
         let msgs = storage.get_l1_messages(1, 2).await.unwrap();
         assert_eq!(msgs.len(), 2);
+
+        // Sync status checkpoint
+        assert!(storage.get_sync_status().await.unwrap().is_none());
+        let status = SyncStatus {
+            l1_block_number: 101,
+            last_delayed_message: Some(2),
+            last_batch: None,
+            l1_block_hashes: vec![(101, B256::with_last_byte(101))],
+        };
+        storage.store_sync_status(&status).await.unwrap();
+        assert_eq!(storage.get_sync_status().await.unwrap(), Some(status));
+
+        // In-flight batch checkpoint
+        assert!(storage.get_in_flight_batch().await.unwrap().is_none());
+        let in_flight = InFlightBatch {
+            batch: batch.clone(),
+            data: vec![0, 1, 2],
+            blobs: false,
+            nonce: 7,
+            gas_limit: 100_000,
+            max_fee_per_gas: 2_000_000_000,
+            max_priority_fee_per_gas: 1_000_000_000,
+            max_fee_per_blob_gas: 0,
+            tx_hashes: vec![B256::with_last_byte(7)],
+            sent_at: 1_700_000_000_000,
+        };
+        storage.store_in_flight_batch(&in_flight).await.unwrap();
+        let loaded = storage.get_in_flight_batch().await.unwrap().unwrap();
+        assert_eq!(loaded.batch.batch_number, 10);
+        assert_eq!((loaded.nonce, loaded.tx_hashes), (7, in_flight.tx_hashes));
+        storage.delete_in_flight_batch().await.unwrap();
+        assert!(storage.get_in_flight_batch().await.unwrap().is_none());
+
+        // L2 pricing state
+        assert!(storage.get_l2_pricing().await.unwrap().is_none());
+        let pricing = L2PricingState {
+            gas_backlog: 80_000_000,
+            base_fee: 120_000_000,
+        };
+        storage.store_l2_pricing(&pricing).await.unwrap();
+        assert_eq!(storage.get_l2_pricing().await.unwrap(), Some(pricing));
+
+        // L1 pricing state, with a negative surplus
+        assert!(storage.get_l1_pricing().await.unwrap().is_none());
+        let poster = Address::with_last_byte(0x99);
+        let pricing = L1PricingState {
+            price_per_unit: U256::from(50_000_000_000u64),
+            units_since_update: 1_600,
+            last_update_time: 1_700_000_000,
+            last_surplus: I256::try_from(-12_345).unwrap(),
+            funds_due_for_rewards: U256::from(16_000),
+            l1_fees_available: U256::from(80_000_000_000_000u64),
+            batch_posters: [(
+                poster,
+                BatchPosterState {
+                    funds_due: U256::from(1_000),
+                    pay_to: poster,
+                },
+            )]
+            .into(),
+            pay_rewards_to: poster,
+            per_unit_reward: 10,
+            equilibration_units: 160_000_000,
+            inertia: 10,
+            per_batch_gas_cost: 210_000,
+            amortized_cost_cap_bips: 0,
+        };
+        storage.store_l1_pricing(&pricing).await.unwrap();
+        assert_eq!(storage.get_l1_pricing().await.unwrap(), Some(pricing));
+
+        // Send accumulator
+        assert!(storage.get_send_merkle().await.unwrap().is_none());
+        let merkle = SendMerkleState {
+            size: 3,
+            partials: vec![B256::repeat_byte(1), B256::repeat_byte(2)],
+        };
+        storage.store_send_merkle(&merkle).await.unwrap();
+        assert_eq!(storage.get_send_merkle().await.unwrap(), Some(merkle));
+
+        // Roll back everything received after L1 block 100
+        assert_eq!(
+            storage.rollback_l1_messages_after(100).await.unwrap(),
+            Some(1)
+        );
+        assert!(storage.get_l1_messages(2, 2).await.unwrap().is_empty());
+        assert_eq!(storage.rollback_batches_after(999).await.unwrap(), None);
+        assert!(storage.get_latest_batch().await.unwrap().is_none());
+        assert!(storage.get_batch_of_block(3).await.unwrap().is_none());
+        assert!(storage.get_batch_of_block(7).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retryables_by_timeout() {
+        use alloy_primitives::address;
+        let (storage, _tmp) = create_test_storage().await;
+
+        let ticket = |id: u8, timeout: u64| Retryable {
+            id: B256::with_last_byte(id),
+            num_tries: 0,
+            from: address!("0x1111111111111111111111111111111111111111"),
+            to: None,
+            call_value: U256::from(id),
+            beneficiary: address!("0x2222222222222222222222222222222222222222"),
+            data: vec![id],
+            timeout,
+        };
+        assert!(
+            storage
+                .get_next_retryable_timeout()
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Timeouts order tickets, whatever their ids
+        storage.store_retryable(&ticket(1, 300)).await.unwrap();
+        storage.store_retryable(&ticket(2, 200)).await.unwrap();
+        let next = storage.get_next_retryable_timeout().await.unwrap().unwrap();
+        assert_eq!(next, ticket(2, 200));
+
+        // Extending a ticket moves it behind the other one
+        storage.store_retryable(&ticket(2, 400)).await.unwrap();
+        let next = storage.get_next_retryable_timeout().await.unwrap().unwrap();
+        assert_eq!(next.id, B256::with_last_byte(1));
+        assert_eq!(
+            storage
+                .get_retryable(&B256::with_last_byte(2))
+                .await
+                .unwrap()
+                .unwrap()
+                .timeout,
+            400
+        );
+
+        let deleted = storage
+            .delete_retryable(&B256::with_last_byte(1))
+            .await
+            .unwrap();
+        assert_eq!(deleted, Some(ticket(1, 300)));
+        assert!(
+            storage
+                .delete_retryable(&B256::with_last_byte(1))
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let next = storage.get_next_retryable_timeout().await.unwrap().unwrap();
+        assert_eq!(next, ticket(2, 400));
+
+        storage
+            .delete_retryable(&B256::with_last_byte(2))
+            .await
+            .unwrap();
+        assert!(
+            storage
+                .get_next_retryable_timeout()
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unwind_blocks_after_l1_block() {
+        use alloy_primitives::U256;
+        let (storage, _tmp) = create_test_storage().await;
+        storage.start().await.unwrap();
+        let mut reorgs = storage.subscribe_reorgs();
+
+        for n in 1..=3u64 {
+            let tx_hash = B256::with_last_byte(0x10 + n as u8);
+            let block = ArbitrumBlock {
+                number: n,
+                hash: B256::with_last_byte(n as u8),
+                parent_hash: B256::with_last_byte(n as u8 - 1),
+                timestamp: 1_700_000_000 + n,
+                gas_used: 21_000,
+                gas_limit: 30_000_000,
+                transactions: vec![tx_hash],
+                l1_block_number: 100 + n,
+                send_count: n,
+                ..Default::default()
+            };
+            storage.store_block(&block).await.unwrap();
+            // One send to L1 per block
+            storage
+                .store_send(&L2ToL1Send {
+                    position: n - 1,
+                    hash: B256::with_last_byte(0x20 + n as u8),
+                    caller: Address::repeat_byte(0x42),
+                    destination: Address::repeat_byte(0x43),
+                    arb_block_number: n,
+                    eth_block_number: 100 + n,
+                    timestamp: block.timestamp,
+                    callvalue: U256::ZERO,
+                    data: vec![],
+                    transaction_hash: tx_hash,
+                })
+                .await
+                .unwrap();
+            storage
+                .store_receipt(&ArbitrumReceipt {
+                    transaction_hash: tx_hash,
+                    transaction_index: 0,
+                    block_hash: block.hash,
+                    block_number: n,
+                    cumulative_gas_used: 21_000,
+                    gas_used: 21_000,
+                    gas_used_for_l1: 0,
+                    contract_address: None,
+                    logs: vec![Log {
+                        address: Address::repeat_byte(0x42),
+                        topics: vec![],
+                        data: vec![n as u8],
+                        block_hash: None,
+                        block_number: None,
+                        transaction_hash: None,
+                        transaction_index: None,
+                        log_index: None,
+                        removed: false,
+                    }],
+                    status: 1,
+                    effective_gas_price: U256::ZERO,
+                })
+                .await
+                .unwrap();
+        }
+        // The second send completes the pair of the first two
+        let pair = B256::repeat_byte(0x12);
+        storage.store_send_merkle_node(1, 1, pair).await.unwrap();
+        assert_eq!(
+            storage.get_send_merkle_node(1, 1).await.unwrap(),
+            Some(pair)
+        );
+
+        let removed = storage.unwind_blocks_after_l1_block(101).await.unwrap();
+        assert_eq!(storage.get_current_block_number().await.unwrap(), 1);
+        assert!(storage.get_block_by_number(2).await.unwrap().is_none());
+        assert!(
+            storage
+                .get_block(&B256::with_last_byte(3))
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            storage
+                .get_receipt(&B256::with_last_byte(0x12))
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(storage.get_block_by_number(1).await.unwrap().is_some());
+        let sends = storage.get_sends(0, 2).await.unwrap();
+        assert_eq!(sends.len(), 1);
+        assert_eq!(sends[0].arb_block_number, 1);
+        assert!(storage.get_send(1).await.unwrap().is_none());
+        assert!(storage.get_send_merkle_node(1, 1).await.unwrap().is_none());
+
+        assert_eq!(removed.len(), 2);
+        assert!(removed.iter().all(|log| log.removed));
+        assert_eq!(removed[0].block_number, Some(2));
+        assert_eq!(removed[1].block_number, Some(3));
+
+        let reorg = reorgs.recv().await.unwrap();
+        assert_eq!(reorg.new_head, 1);
+        assert_eq!(reorg.removed_logs.len(), 2);
+
+        // Nothing left to unwind
+        assert!(storage.unwind_blocks_above(1).await.unwrap().is_empty());
     }
 }
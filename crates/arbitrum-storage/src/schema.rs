@@ -14,6 +14,8 @@ pub enum TableType {
     Accounts,
     /// Contract storage indexed by (address, key)
     Storage,
+    /// Contract bytecode indexed by code hash
+    Code,
     /// Transaction receipts indexed by hash
     Receipts,
     /// State trie nodes indexed by hash
@@ -30,6 +32,20 @@ pub enum TableType {
     LogsByBlock,
     /// Filter last-seen timestamps for TTL handling
     FilterLastSeen,
+    /// Retryable tickets indexed by ticket id
+    Retryables,
+    /// Retryable ticket ids indexed by (timeout, ticket id)
+    RetryableTimeouts,
+    /// L2-to-L1 sends indexed by their leaf in the send tree
+    Sends,
+    /// Values overwritten by each block, indexed by block number
+    Changesets,
+    /// L1 gas of sequencer messages indexed by their hash
+    BatchGas,
+    /// Batch numbers indexed by the L2 blocks they carry
+    BlockBatches,
+    /// Nodes of the send tree indexed by (level, last leaf)
+    SendMerkleNodes,
 }
 
 impl TableType {
@@ -40,6 +56,7 @@ impl TableType {
             TableType::Transactions,
             TableType::Accounts,
             TableType::Storage,
+            TableType::Code,
             TableType::Receipts,
             TableType::StateTrie,
             TableType::Batches,
@@ -48,6 +65,13 @@ impl TableType {
             TableType::FilterCursors,
             TableType::LogsByBlock,
             TableType::FilterLastSeen,
+            TableType::Retryables,
+            TableType::RetryableTimeouts,
+            TableType::Sends,
+            TableType::Changesets,
+            TableType::BatchGas,
+            TableType::BlockBatches,
+            TableType::SendMerkleNodes,
         ]
     }
 
@@ -58,6 +82,7 @@ impl TableType {
             TableType::Transactions => "transactions",
             TableType::Accounts => "accounts",
             TableType::Storage => "storage",
+            TableType::Code => "code",
             TableType::Receipts => "receipts",
             TableType::StateTrie => "state_trie",
             TableType::Batches => "batches",
@@ -66,6 +91,13 @@ impl TableType {
             TableType::FilterCursors => "filter_cursors",
             TableType::LogsByBlock => "logs_by_block",
             TableType::FilterLastSeen => "filter_last_seen",
+            TableType::Retryables => "retryables",
+            TableType::RetryableTimeouts => "retryable_timeouts",
+            TableType::Sends => "sends",
+            TableType::Changesets => "changesets",
+            TableType::BatchGas => "batch_gas",
+            TableType::BlockBatches => "block_batches",
+            TableType::SendMerkleNodes => "send_merkle_nodes",
         }
     }
 }
@@ -98,6 +130,10 @@ pub mod keys {
         pub slot: B256,
     }
 
+    /// Contract code hash key (32 bytes)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct CodeHash(pub B256);
+
     /// State trie node hash key (32 bytes)
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
     pub struct TrieNodeHash(pub B256);
@@ -118,6 +154,33 @@ pub mod keys {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
     pub struct FilterId(pub u64);
 
+    /// Retryable ticket id key (32 bytes)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct TicketId(pub B256);
+
+    /// Timeout key ordering tickets by when they expire (8 bytes, big-endian,
+    /// then the 32-byte ticket id)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+    pub struct RetryableTimeout {
+        pub timeout: u64,
+        pub ticket_id: B256,
+    }
+
+    /// Send tree leaf key (8 bytes, big-endian)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+    pub struct SendPosition(pub u64);
+
+    /// Sequencer message hash key (32 bytes)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct DataHash(pub B256);
+
+    /// Send tree node key (8 bytes level, then 8 bytes leaf, big-endian)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+    pub struct MerkleNode {
+        pub level: u64,
+        pub leaf: u64,
+    }
+
     // Implement From traits for easier usage
     impl From<u64> for BlockNumber {
         fn from(n: u64) -> Self {
@@ -184,6 +247,30 @@ pub mod keys {
             Self(n)
         }
     }
+
+    impl From<B256> for TicketId {
+        fn from(id: B256) -> Self {
+            Self(id)
+        }
+    }
+
+    impl From<(u64, B256)> for RetryableTimeout {
+        fn from((timeout, ticket_id): (u64, B256)) -> Self {
+            Self { timeout, ticket_id }
+        }
+    }
+
+    impl From<u64> for SendPosition {
+        fn from(n: u64) -> Self {
+            Self(n)
+        }
+    }
+
+    impl From<B256> for DataHash {
+        fn from(hash: B256) -> Self {
+            Self(hash)
+        }
+    }
 }
 
 /// Common metadata keys used in the database
@@ -200,6 +287,16 @@ pub mod metadata_keys {
     pub const SCHEMA_VERSION: &str = "schema_version";
     /// Node sync status
     pub const SYNC_STATUS: &str = "sync_status";
+    /// Batch-posting transaction awaiting L1 confirmation
+    pub const IN_FLIGHT_BATCH: &str = "in_flight_batch";
+    /// Root of the state trie as of the latest committed block
+    pub const STATE_ROOT: &str = "state_root";
+    /// Gas backlog and base fee of the L2 pricing
+    pub const L2_PRICING: &str = "l2_pricing";
+    /// Price per unit, funds and batch poster debts of the L1 pricing
+    pub const L1_PRICING: &str = "l1_pricing";
+    /// Size and partials of the L2-to-L1 send accumulator
+    pub const SEND_MERKLE: &str = "send_merkle";
 }
 
 #[cfg(test)]
@@ -211,11 +308,18 @@ mod tests {
     #[test]
     fn test_table_types() {
         let all_tables = TableType::all();
-        assert_eq!(all_tables.len(), 12);
+        assert_eq!(all_tables.len(), 20);
 
         assert_eq!(TableType::Blocks.name(), "blocks");
         assert_eq!(TableType::Transactions.name(), "transactions");
         assert_eq!(TableType::Accounts.name(), "accounts");
+        assert_eq!(TableType::Code.name(), "code");
+        assert_eq!(TableType::RetryableTimeouts.name(), "retryable_timeouts");
+        assert_eq!(TableType::Sends.name(), "sends");
+        assert_eq!(TableType::Changesets.name(), "changesets");
+        assert_eq!(TableType::BatchGas.name(), "batch_gas");
+        assert_eq!(TableType::BlockBatches.name(), "block_batches");
+        assert_eq!(TableType::SendMerkleNodes.name(), "send_merkle_nodes");
     }
 
     #[test]
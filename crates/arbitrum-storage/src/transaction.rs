@@ -0,0 +1,361 @@
+//! EIP-2718 encoding of Arbitrum transactions
+//!
+//! Transactions derived from L1 have their own types in Nitro, from 0x64 to
+//! 0x6a, each the RLP list of its fields after the type byte. Their hash is
+//! the keccak of that encoding, as for any typed transaction, so the chain id
+//! and every field of a transaction go into its hash. Signed transactions keep
+//! the encoding they were submitted with.
+//!
+//! Internal transactions are sent by ArbOS to itself, their data the ABI
+//! encoded call ArbOS makes.
+
+use alloy_primitives::{Address, B256, U256, keccak256};
+use alloy_rlp::{EMPTY_STRING_CODE, Encodable, Header};
+
+use crate::codec::{ArbitrumTransaction, ArbitrumTxKind};
+
+/// ETH deposited from L1
+pub const DEPOSIT_TX_TYPE: u8 = 0x64;
+/// Unsigned transaction from L1
+pub const UNSIGNED_TX_TYPE: u8 = 0x65;
+/// Unsigned transaction from an L1 contract
+pub const CONTRACT_TX_TYPE: u8 = 0x66;
+/// Redeem attempt of a retryable ticket
+pub const RETRY_TX_TYPE: u8 = 0x68;
+/// Retryable ticket submission
+pub const SUBMIT_RETRYABLE_TX_TYPE: u8 = 0x69;
+/// Transaction issued by ArbOS itself
+pub const INTERNAL_TX_TYPE: u8 = 0x6a;
+
+/// Type byte of a legacy transaction, which has none in its encoding
+const LEGACY_TX_TYPE: u8 = 0;
+
+impl ArbitrumTxKind {
+    /// Nitro transaction type of the kind, none for signed transactions,
+    /// whose type is in their encoding
+    pub fn arbitrum_tx_type(&self) -> Option<u8> {
+        match self {
+            Self::Signed => None,
+            Self::Deposit { .. } => Some(DEPOSIT_TX_TYPE),
+            Self::Unsigned => Some(UNSIGNED_TX_TYPE),
+            Self::Contract { .. } => Some(CONTRACT_TX_TYPE),
+            Self::Retry { .. } => Some(RETRY_TX_TYPE),
+            Self::SubmitRetryable { .. } => Some(SUBMIT_RETRYABLE_TX_TYPE),
+            Self::BatchPostingReport { .. } | Self::Initialize { .. } => Some(INTERNAL_TX_TYPE),
+        }
+    }
+}
+
+impl ArbitrumTransaction {
+    /// EIP-2718 type of the transaction, 0 for legacy transactions
+    pub fn tx_type(&self) -> u8 {
+        self.kind.arbitrum_tx_type().unwrap_or_else(|| {
+            match self.raw.first() {
+                // Legacy transactions start with their RLP list header
+                Some(&byte) if byte < 0x7f => byte,
+                _ => LEGACY_TX_TYPE,
+            }
+        })
+    }
+
+    /// EIP-2718 encoding of the transaction on chain `chain_id`
+    pub fn encoded_2718(&self, chain_id: u64) -> Vec<u8> {
+        let Some(tx_type) = self.kind.arbitrum_tx_type() else {
+            return self.raw.clone();
+        };
+        let payload = self.rlp_fields(U256::from(chain_id));
+        let mut out = Vec::with_capacity(1 + payload.len() + 9);
+        out.push(tx_type);
+        Header {
+            list: true,
+            payload_length: payload.len(),
+        }
+        .encode(&mut out);
+        out.extend_from_slice(&payload);
+        out
+    }
+
+    /// Hash of the EIP-2718 encoding of the transaction on chain `chain_id`
+    pub fn compute_hash(&self, chain_id: u64) -> B256 {
+        keccak256(self.encoded_2718(chain_id))
+    }
+
+    /// Set the hash of a transaction derived from L1 from its fields
+    pub fn seal(mut self, chain_id: u64) -> Self {
+        self.hash = self.compute_hash(chain_id);
+        self
+    }
+
+    /// RLP of the fields of an Arbitrum transaction, in Nitro's order and
+    /// without the list header
+    fn rlp_fields(&self, chain_id: U256) -> Vec<u8> {
+        let mut out = Vec::new();
+        chain_id.encode(&mut out);
+        match &self.kind {
+            ArbitrumTxKind::Signed => {}
+            ArbitrumTxKind::Deposit { l1_request_id } => {
+                l1_request_id.encode(&mut out);
+                self.from.encode(&mut out);
+                self.to.unwrap_or_default().encode(&mut out);
+                self.value.encode(&mut out);
+            }
+            ArbitrumTxKind::Unsigned => {
+                self.from.encode(&mut out);
+                self.nonce.encode(&mut out);
+                self.gas_price.encode(&mut out);
+                self.gas.encode(&mut out);
+                encode_to(self.to, &mut out);
+                self.value.encode(&mut out);
+                self.data.as_slice().encode(&mut out);
+            }
+            ArbitrumTxKind::Contract { request_id } => {
+                request_id.encode(&mut out);
+                self.from.encode(&mut out);
+                self.gas_price.encode(&mut out);
+                self.gas.encode(&mut out);
+                encode_to(self.to, &mut out);
+                self.value.encode(&mut out);
+                self.data.as_slice().encode(&mut out);
+            }
+            ArbitrumTxKind::Retry {
+                ticket_id,
+                refund_to,
+                max_refund,
+                submission_fee_refund,
+            } => {
+                self.nonce.encode(&mut out);
+                self.from.encode(&mut out);
+                self.gas_price.encode(&mut out);
+                self.gas.encode(&mut out);
+                encode_to(self.to, &mut out);
+                self.value.encode(&mut out);
+                self.data.as_slice().encode(&mut out);
+                ticket_id.encode(&mut out);
+                refund_to.encode(&mut out);
+                max_refund.encode(&mut out);
+                submission_fee_refund.encode(&mut out);
+            }
+            ArbitrumTxKind::SubmitRetryable {
+                request_id,
+                l1_base_fee,
+                deposit_value,
+                max_submission_fee,
+                fee_refund_address,
+                beneficiary,
+            } => {
+                request_id.encode(&mut out);
+                self.from.encode(&mut out);
+                l1_base_fee.encode(&mut out);
+                deposit_value.encode(&mut out);
+                self.gas_price.encode(&mut out);
+                self.gas.encode(&mut out);
+                encode_to(self.to, &mut out);
+                self.value.encode(&mut out);
+                beneficiary.encode(&mut out);
+                max_submission_fee.encode(&mut out);
+                fee_refund_address.encode(&mut out);
+                self.data.as_slice().encode(&mut out);
+            }
+            ArbitrumTxKind::BatchPostingReport { .. } | ArbitrumTxKind::Initialize { .. } => {
+                self.data.as_slice().encode(&mut out);
+            }
+        }
+        out
+    }
+}
+
+/// Encode a recipient, the empty string for contract creations
+fn encode_to(to: Option<Address>, out: &mut Vec<u8>) {
+    match to {
+        Some(to) => to.encode(out),
+        None => out.push(EMPTY_STRING_CODE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, hex};
+    use alloy_rlp::Decodable;
+
+    use super::*;
+
+    fn transaction(kind: ArbitrumTxKind) -> ArbitrumTransaction {
+        ArbitrumTransaction {
+            hash: B256::ZERO,
+            from: address!("0x1111111111111111111111111111111111111111"),
+            to: Some(address!("0x2222222222222222222222222222222222222222")),
+            value: U256::from(7),
+            gas: 100_000,
+            gas_price: U256::from(1_000),
+            nonce: 3,
+            data: vec![0xab, 0xcd],
+            l1_sequence_number: Some(1),
+            raw: vec![],
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_arbitrum_transactions_encode_their_fields() {
+        let tx = transaction(ArbitrumTxKind::Unsigned);
+        let encoded = tx.encoded_2718(42_161);
+        assert_eq!(encoded[0], UNSIGNED_TX_TYPE);
+        let mut payload = &encoded[1..];
+        let header = Header::decode(&mut payload).expect("header");
+        assert!(header.list);
+        assert_eq!(header.payload_length, payload.len());
+        assert_eq!(u64::decode(&mut payload).expect("chain id"), 42_161);
+        assert_eq!(Address::decode(&mut payload).expect("from"), tx.from);
+        assert_eq!(u64::decode(&mut payload).expect("nonce"), 3);
+        assert_eq!(U256::decode(&mut payload).expect("fee cap"), tx.gas_price);
+        assert_eq!(u64::decode(&mut payload).expect("gas"), tx.gas);
+        assert_eq!(
+            Address::decode(&mut payload).expect("to"),
+            tx.to.expect("to")
+        );
+        assert_eq!(U256::decode(&mut payload).expect("value"), tx.value);
+        assert_eq!(
+            alloy_primitives::Bytes::decode(&mut payload).expect("data"),
+            tx.data
+        );
+        assert!(payload.is_empty());
+
+        // The hash commits to the chain and to every field
+        assert_eq!(tx.compute_hash(42_161), keccak256(&encoded));
+        assert_ne!(tx.compute_hash(42_161), tx.compute_hash(1));
+        let creation = ArbitrumTransaction {
+            to: None,
+            ..tx.clone()
+        };
+        assert_ne!(creation.compute_hash(42_161), tx.compute_hash(42_161));
+        assert_eq!(tx.clone().seal(42_161).hash, tx.compute_hash(42_161));
+
+        let internal = transaction(ArbitrumTxKind::Initialize {
+            chain_id: U256::from(42_161),
+            initial_l1_base_fee: U256::ZERO,
+            chain_config: vec![],
+        });
+        assert_eq!(
+            internal.encoded_2718(42_161),
+            hex!("6ac682a4b182abcd").to_vec()
+        );
+
+        // Nitro's encodings of the other types, fields in their order
+        let request_id = B256::with_last_byte(9);
+        let fixtures = [
+            (
+                ArbitrumTxKind::Deposit {
+                    l1_request_id: request_id,
+                },
+                hex!(
+                    "64f84f82a4b1a0000000000000000000000000000000000000000000000000000000"
+                    "0000000009941111111111111111111111111111111111111111942222222222222222"
+                    "22222222222222222222222207"
+                )
+                .to_vec(),
+            ),
+            (
+                ArbitrumTxKind::Contract { request_id },
+                hex!(
+                    "66f85982a4b1a0000000000000000000000000000000000000000000000000000000"
+                    "00000000099411111111111111111111111111111111111111118203e8830186a094"
+                    "22222222222222222222222222222222222222220782abcd"
+                )
+                .to_vec(),
+            ),
+            (
+                ArbitrumTxKind::Retry {
+                    ticket_id: request_id,
+                    refund_to: address!("0x3333333333333333333333333333333333333333"),
+                    max_refund: U256::from(500),
+                    submission_fee_refund: U256::from(60),
+                },
+                hex!(
+                    "68f87382a4b1039411111111111111111111111111111111111111118203e8830186a0"
+                    "9422222222222222222222222222222222222222220782abcda00000000000000000"
+                    "0000000000000000000000000000000000000000000000099433333333333333333333"
+                    "333333333333333333338201f43c"
+                )
+                .to_vec(),
+            ),
+            (
+                ArbitrumTxKind::SubmitRetryable {
+                    request_id,
+                    l1_base_fee: U256::from(30),
+                    deposit_value: U256::from(10_000),
+                    max_submission_fee: U256::from(700),
+                    fee_refund_address: address!("0x5555555555555555555555555555555555555555"),
+                    beneficiary: address!("0x4444444444444444444444444444444444444444"),
+                },
+                hex!(
+                    "69f88a82a4b1a0000000000000000000000000000000000000000000000000000000"
+                    "00000000099411111111111111111111111111111111111111111e8227108203e883"
+                    "0186a094222222222222222222222222222222222222222207944444444444444444"
+                    "4444444444444444444444448202bc94555555555555555555555555555555555555"
+                    "555582abcd"
+                )
+                .to_vec(),
+            ),
+        ];
+        for (kind, expected) in fixtures {
+            assert_eq!(transaction(kind).encoded_2718(42_161), expected);
+        }
+    }
+
+    #[test]
+    fn test_transaction_types() {
+        let kinds = [
+            (
+                ArbitrumTxKind::Deposit {
+                    l1_request_id: B256::ZERO,
+                },
+                0x64,
+            ),
+            (ArbitrumTxKind::Unsigned, 0x65),
+            (
+                ArbitrumTxKind::Contract {
+                    request_id: B256::ZERO,
+                },
+                0x66,
+            ),
+            (
+                ArbitrumTxKind::Retry {
+                    ticket_id: B256::ZERO,
+                    refund_to: Address::ZERO,
+                    max_refund: U256::ZERO,
+                    submission_fee_refund: U256::ZERO,
+                },
+                0x68,
+            ),
+            (
+                ArbitrumTxKind::SubmitRetryable {
+                    request_id: B256::ZERO,
+                    l1_base_fee: U256::ZERO,
+                    deposit_value: U256::ZERO,
+                    max_submission_fee: U256::ZERO,
+                    fee_refund_address: Address::ZERO,
+                    beneficiary: Address::ZERO,
+                },
+                0x69,
+            ),
+        ];
+        for (kind, tx_type) in kinds {
+            let tx = transaction(kind);
+            assert_eq!(tx.tx_type(), tx_type);
+            assert_eq!(tx.encoded_2718(1)[0], tx_type);
+        }
+
+        // Signed transactions keep their own encoding
+        let signed = ArbitrumTransaction {
+            raw: vec![0x02, 0xc0],
+            ..transaction(ArbitrumTxKind::Signed)
+        };
+        assert_eq!(signed.tx_type(), 2);
+        assert_eq!(signed.encoded_2718(1), signed.raw);
+        let legacy = ArbitrumTransaction {
+            raw: vec![0xc0],
+            ..signed
+        };
+        assert_eq!(legacy.tx_type(), 0);
+    }
+}
@@ -0,0 +1,663 @@
+//! Merkle Patricia tries over the state
+//!
+//! The state trie maps `keccak(address)` to the RLP of each account, whose
+//! storage root commits to a storage trie mapping `keccak(slot)` to the RLP of
+//! the slot value, as in Ethereum's secure tries. Nodes are stored in the
+//! `state_trie` table under the hash of their RLP, except those shorter than
+//! 32 bytes, which are embedded in their parent. Nodes are never pruned, so
+//! the tries of earlier roots stay readable.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use alloy_primitives::{Address, B256, U256, b256, keccak256};
+use alloy_rlp::{Encodable, Header};
+use eyre::{Result, eyre};
+use heed::{Database, RwTxn, types::Bytes};
+
+use crate::{
+    codec::{ArbitrumAccount, DatabaseKey, DatabaseValue},
+    database::{ArbitrumDatabase, DatabaseTables},
+    schema::{TableType, keys},
+};
+
+/// Root of the empty trie, `keccak(rlp(""))`
+pub const EMPTY_ROOT_HASH: B256 =
+    b256!("0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421");
+
+/// Hash of empty code, `keccak("")`
+pub const KECCAK_EMPTY: B256 =
+    b256!("0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+
+/// Storage for trie nodes, keyed by the hash of their RLP
+pub trait NodeStore {
+    fn node(&self, hash: &B256) -> Result<Option<Vec<u8>>>;
+    fn put_node(&mut self, hash: B256, rlp: Vec<u8>) -> Result<()>;
+}
+
+/// Reference to a child node: its hash, or its RLP if shorter than 32 bytes
+type NodeRef = Vec<u8>;
+
+#[derive(Debug, Clone)]
+enum Node {
+    Empty,
+    Leaf(Vec<u8>, Vec<u8>),
+    Extension(Vec<u8>, NodeRef),
+    Branch(Box<[Option<NodeRef>; 16]>, Option<Vec<u8>>),
+}
+
+/// A Merkle Patricia trie, identified by its root
+///
+/// Updates write the new nodes to the store and move the root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatriciaTrie {
+    root: B256,
+}
+
+impl PatriciaTrie {
+    /// Open the trie with the given root
+    pub fn new(root: B256) -> Self {
+        Self { root }
+    }
+
+    pub fn root(&self) -> B256 {
+        self.root
+    }
+
+    /// Get the value under `key`
+    pub fn get(&self, store: &impl NodeStore, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let mut node = self.root_node(store)?;
+        let mut path = &nibbles(key)[..];
+        loop {
+            node = match node {
+                Node::Empty => return Ok(None),
+                Node::Leaf(rest, value) => return Ok((rest == path).then_some(value)),
+                Node::Extension(rest, child) => match path.strip_prefix(&rest[..]) {
+                    Some(remaining) => {
+                        path = remaining;
+                        load(store, &child)?
+                    }
+                    None => return Ok(None),
+                },
+                Node::Branch(children, value) => match path.split_first() {
+                    None => return Ok(value),
+                    Some((&index, remaining)) => {
+                        path = remaining;
+                        match &children[index as usize] {
+                            Some(child) => load(store, child)?,
+                            None => return Ok(None),
+                        }
+                    }
+                },
+            };
+        }
+    }
+
+    /// Set the value under `key`
+    pub fn insert(&mut self, store: &mut impl NodeStore, key: &[u8], value: Vec<u8>) -> Result<()> {
+        let root = self.root_node(store)?;
+        let root = insert_at(store, root, &nibbles(key), value)?;
+        self.set_root(store, root)
+    }
+
+    /// Remove the value under `key`, if any
+    pub fn remove(&mut self, store: &mut impl NodeStore, key: &[u8]) -> Result<()> {
+        let root = self.root_node(store)?;
+        let root = remove_at(store, root, &nibbles(key))?;
+        self.set_root(store, root)
+    }
+
+    fn root_node(&self, store: &impl NodeStore) -> Result<Node> {
+        if self.root == EMPTY_ROOT_HASH {
+            return Ok(Node::Empty);
+        }
+        load(store, self.root.as_slice())
+    }
+
+    fn set_root(&mut self, store: &mut impl NodeStore, root: Node) -> Result<()> {
+        // The root is stored by hash even when short
+        let rlp = encode(&root);
+        self.root = keccak256(&rlp);
+        if !matches!(root, Node::Empty) {
+            store.put_node(self.root, rlp)?;
+        }
+        Ok(())
+    }
+}
+
+/// RLP of an account as committed to by the state trie
+pub fn account_rlp(account: &ArbitrumAccount) -> Vec<u8> {
+    let mut payload = Vec::new();
+    Encodable::encode(&account.nonce, &mut payload);
+    account
+        .balance
+        .to_be_bytes_trimmed_vec()
+        .as_slice()
+        .encode(&mut payload);
+    storage_root(account.storage_root)
+        .as_slice()
+        .encode(&mut payload);
+    code_hash(account.code_hash).as_slice().encode(&mut payload);
+    list(payload)
+}
+
+/// RLP of a storage slot value as committed to by a storage trie
+pub fn slot_rlp(value: U256) -> Vec<u8> {
+    let mut out = Vec::new();
+    value.to_be_bytes_trimmed_vec().as_slice().encode(&mut out);
+    out
+}
+
+/// Storage root of an account, which may be zero for accounts without storage
+pub fn storage_root(root: B256) -> B256 {
+    if root == B256::ZERO {
+        EMPTY_ROOT_HASH
+    } else {
+        root
+    }
+}
+
+/// Code hash of an account, which may be zero for accounts without code
+pub fn code_hash(hash: B256) -> B256 {
+    if hash == B256::ZERO {
+        KECCAK_EMPTY
+    } else {
+        hash
+    }
+}
+
+/// Accounts and storage slots changed since the tries were last updated
+#[derive(Debug, Clone, Default)]
+pub struct DirtyState {
+    pub accounts: BTreeSet<Address>,
+    pub slots: BTreeMap<Address, BTreeSet<B256>>,
+}
+
+impl DirtyState {
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.slots.is_empty()
+    }
+}
+
+/// Trie nodes in the `state_trie` table, within a write transaction
+struct TableNodes<'a, 'env> {
+    txn: &'a mut RwTxn<'env>,
+    table: Database<Bytes, Bytes>,
+}
+
+impl NodeStore for TableNodes<'_, '_> {
+    fn node(&self, hash: &B256) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .table
+            .get(&*self.txn, hash.as_slice())?
+            .map(<[u8]>::to_vec))
+    }
+
+    fn put_node(&mut self, hash: B256, rlp: Vec<u8>) -> Result<()> {
+        self.table.put(self.txn, hash.as_slice(), &rlp)?;
+        Ok(())
+    }
+}
+
+/// Bring the tries with root `state_root` up to date with the dirty accounts
+/// and slots, as they are now in the flat tables
+///
+/// Storage roots of accounts with changed slots are written back to their
+/// account records. Returns the new state root.
+pub(crate) fn update_tries(
+    txn: &mut RwTxn,
+    tables: &DatabaseTables,
+    state_root: B256,
+    dirty: DirtyState,
+) -> Result<B256> {
+    let accounts = *ArbitrumDatabase::get_table(tables, TableType::Accounts);
+    let storage = *ArbitrumDatabase::get_table(tables, TableType::Storage);
+    let table = *ArbitrumDatabase::get_table(tables, TableType::StateTrie);
+
+    let mut state = PatriciaTrie::new(state_root);
+    let addresses: BTreeSet<Address> = dirty
+        .accounts
+        .iter()
+        .chain(dirty.slots.keys())
+        .copied()
+        .collect();
+    for address in addresses {
+        let key = keccak256(address);
+        let Some(bytes) = accounts.get(txn, address.as_slice())? else {
+            state.remove(&mut TableNodes { txn, table }, key.as_slice())?;
+            continue;
+        };
+        let mut account = ArbitrumAccount::decode(bytes)?;
+
+        if let Some(slots) = dirty.slots.get(&address) {
+            let mut trie = PatriciaTrie::new(storage_root(account.storage_root));
+            for slot in slots {
+                let slot_key = keys::StorageKey {
+                    address,
+                    slot: *slot,
+                }
+                .encode()?;
+                let value = storage
+                    .get(txn, &slot_key)?
+                    .map(U256::decode)
+                    .transpose()?
+                    .unwrap_or_default();
+                let mut nodes = TableNodes { txn, table };
+                if value.is_zero() {
+                    trie.remove(&mut nodes, keccak256(slot).as_slice())?;
+                } else {
+                    trie.insert(&mut nodes, keccak256(slot).as_slice(), slot_rlp(value))?;
+                }
+            }
+            if trie.root() != account.storage_root {
+                account.storage_root = trie.root();
+                accounts.put(txn, address.as_slice(), &account.encode()?)?;
+            }
+        }
+
+        state.insert(
+            &mut TableNodes { txn, table },
+            key.as_slice(),
+            account_rlp(&account),
+        )?;
+    }
+    Ok(state.root())
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+fn insert_at(store: &mut impl NodeStore, node: Node, path: &[u8], value: Vec<u8>) -> Result<Node> {
+    Ok(match node {
+        Node::Empty => Node::Leaf(path.to_vec(), value),
+        Node::Leaf(rest, existing) => {
+            if rest == path {
+                return Ok(Node::Leaf(rest, value));
+            }
+            let common = common_prefix(&rest, path);
+            let mut branch = Node::Branch(Box::default(), None);
+            branch = insert_at(store, branch, &rest[common..], existing)?;
+            branch = insert_at(store, branch, &path[common..], value)?;
+            extend(store, &path[..common], branch)?
+        }
+        Node::Extension(rest, child) => {
+            let common = common_prefix(&rest, path);
+            if common == rest.len() {
+                let child = insert_at(store, load(store, &child)?, &path[common..], value)?;
+                return extend(store, &rest, child);
+            }
+            let mut children: Box<[Option<NodeRef>; 16]> = Box::default();
+            children[rest[common] as usize] = if common + 1 == rest.len() {
+                Some(child)
+            } else {
+                store_node(store, &Node::Extension(rest[common + 1..].to_vec(), child))?
+            };
+            let branch = insert_at(store, Node::Branch(children, None), &path[common..], value)?;
+            extend(store, &path[..common], branch)?
+        }
+        Node::Branch(mut children, existing) => match path.split_first() {
+            None => Node::Branch(children, Some(value)),
+            Some((&index, rest)) => {
+                let child = match &children[index as usize] {
+                    Some(child) => load(store, child)?,
+                    None => Node::Empty,
+                };
+                let child = insert_at(store, child, rest, value)?;
+                children[index as usize] = store_node(store, &child)?;
+                Node::Branch(children, existing)
+            }
+        },
+    })
+}
+
+fn remove_at(store: &mut impl NodeStore, node: Node, path: &[u8]) -> Result<Node> {
+    Ok(match node {
+        Node::Empty => Node::Empty,
+        Node::Leaf(rest, value) => {
+            if rest == path {
+                Node::Empty
+            } else {
+                Node::Leaf(rest, value)
+            }
+        }
+        Node::Extension(rest, child) => match path.strip_prefix(&rest[..]) {
+            Some(remaining) => {
+                let child = remove_at(store, load(store, &child)?, remaining)?;
+                extend(store, &rest, child)?
+            }
+            None => Node::Extension(rest, child),
+        },
+        Node::Branch(mut children, mut value) => {
+            match path.split_first() {
+                None => value = None,
+                Some((&index, rest)) => {
+                    if let Some(child) = &children[index as usize] {
+                        let child = remove_at(store, load(store, child)?, rest)?;
+                        children[index as usize] = store_node(store, &child)?;
+                    }
+                }
+            }
+            collapse(store, children, value)?
+        }
+    })
+}
+
+/// Prefix `node` with `path`, merging with its own path where it has one
+fn extend(store: &mut impl NodeStore, path: &[u8], node: Node) -> Result<Node> {
+    if path.is_empty() {
+        return Ok(node);
+    }
+    Ok(match node {
+        Node::Empty => Node::Empty,
+        Node::Leaf(rest, value) => Node::Leaf([path, &rest].concat(), value),
+        Node::Extension(rest, child) => Node::Extension([path, &rest].concat(), child),
+        branch @ Node::Branch(..) => {
+            let child =
+                store_node(store, &branch)?.ok_or_else(|| eyre!("Branch node has no reference"))?;
+            Node::Extension(path.to_vec(), child)
+        }
+    })
+}
+
+/// Replace a branch left with a single entry by the equivalent shorter node
+fn collapse(
+    store: &mut impl NodeStore,
+    children: Box<[Option<NodeRef>; 16]>,
+    value: Option<Vec<u8>>,
+) -> Result<Node> {
+    let mut occupied = children
+        .iter()
+        .enumerate()
+        .filter_map(|(index, child)| child.as_ref().map(|child| (index as u8, child)));
+    let only = occupied.next();
+    if occupied.next().is_some() {
+        return Ok(Node::Branch(children, value));
+    }
+    Ok(match (only, value) {
+        (None, None) => Node::Empty,
+        (None, Some(value)) => Node::Leaf(vec![], value),
+        (Some((index, child)), None) => {
+            let child = load(store, child)?;
+            extend(store, &[index], child)?
+        }
+        (Some(_), value) => Node::Branch(children, value),
+    })
+}
+
+fn load(store: &impl NodeStore, reference: &[u8]) -> Result<Node> {
+    if reference.len() < 32 {
+        return decode(reference);
+    }
+    let hash = B256::from_slice(reference);
+    let rlp = store
+        .node(&hash)?
+        .ok_or_else(|| eyre!("Trie node {hash} not found"))?;
+    decode(&rlp)
+}
+
+/// Store a node, returning how its parent refers to it
+fn store_node(store: &mut impl NodeStore, node: &Node) -> Result<Option<NodeRef>> {
+    if matches!(node, Node::Empty) {
+        return Ok(None);
+    }
+    let rlp = encode(node);
+    if rlp.len() < 32 {
+        return Ok(Some(rlp));
+    }
+    let hash = keccak256(&rlp);
+    store.put_node(hash, rlp)?;
+    Ok(Some(hash.to_vec()))
+}
+
+fn encode(node: &Node) -> Vec<u8> {
+    let mut payload = Vec::new();
+    match node {
+        Node::Empty => {
+            [].as_slice().encode(&mut payload);
+            return payload;
+        }
+        Node::Leaf(path, value) => {
+            hex_prefix(path, true).as_slice().encode(&mut payload);
+            value.as_slice().encode(&mut payload);
+        }
+        Node::Extension(path, child) => {
+            hex_prefix(path, false).as_slice().encode(&mut payload);
+            encode_ref(child, &mut payload);
+        }
+        Node::Branch(children, value) => {
+            for child in children.iter() {
+                match child {
+                    Some(child) => encode_ref(child, &mut payload),
+                    None => [].as_slice().encode(&mut payload),
+                }
+            }
+            value.as_deref().unwrap_or_default().encode(&mut payload);
+        }
+    }
+    list(payload)
+}
+
+fn encode_ref(reference: &[u8], out: &mut Vec<u8>) {
+    if reference.len() < 32 {
+        // Embedded nodes appear as themselves
+        out.extend_from_slice(reference);
+    } else {
+        reference.encode(out);
+    }
+}
+
+fn decode(rlp: &[u8]) -> Result<Node> {
+    let mut buf = rlp;
+    let header = Header::decode(&mut buf).map_err(|e| eyre!("Invalid trie node: {e}"))?;
+    if !header.list {
+        return Ok(Node::Empty);
+    }
+    let mut items = Vec::new();
+    while !buf.is_empty() {
+        let start = buf;
+        let header = Header::decode(&mut buf).map_err(|e| eyre!("Invalid trie node: {e}"))?;
+        if header.list {
+            // An embedded node, kept whole as its own reference
+            let len = start.len() - buf.len() + header.payload_length;
+            items.push(start[..len].to_vec());
+        } else {
+            items.push(buf[..header.payload_length].to_vec());
+        }
+        buf = &buf[header.payload_length..];
+    }
+
+    match items.len() {
+        2 => {
+            let (path, leaf) = decode_hex_prefix(&items[0])?;
+            let second = items.pop().unwrap_or_default();
+            Ok(if leaf {
+                Node::Leaf(path, second)
+            } else {
+                Node::Extension(path, second)
+            })
+        }
+        17 => {
+            let value = items.pop().filter(|value| !value.is_empty());
+            let mut children: Box<[Option<NodeRef>; 16]> = Box::default();
+            for (child, item) in children.iter_mut().zip(items) {
+                *child = (!item.is_empty()).then_some(item);
+            }
+            Ok(Node::Branch(children, value))
+        }
+        n => Err(eyre!("Trie node has {n} items")),
+    }
+}
+
+fn hex_prefix(path: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 };
+    let mut out = Vec::with_capacity(path.len() / 2 + 1);
+    let rest = if path.len() % 2 == 1 {
+        out.push(((flag + 1) << 4) | path[0]);
+        &path[1..]
+    } else {
+        out.push(flag << 4);
+        path
+    };
+    out.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+    out
+}
+
+fn decode_hex_prefix(encoded: &[u8]) -> Result<(Vec<u8>, bool)> {
+    let (&first, rest) = encoded
+        .split_first()
+        .ok_or_else(|| eyre!("Empty trie node path"))?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return Err(eyre!("Invalid trie node path flag {flag}"));
+    }
+    let mut path = Vec::with_capacity(rest.len() * 2 + 1);
+    if flag & 1 == 1 {
+        path.push(first & 0x0f);
+    }
+    path.extend(nibbles(rest));
+    Ok((path, flag & 2 == 2))
+}
+
+fn list(payload: Vec<u8>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 9);
+    Header {
+        list: true,
+        payload_length: payload.len(),
+    }
+    .encode(&mut out);
+    out.extend(payload);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use alloy_primitives::{address, hex};
+    use alloy_trie::{HashBuilder, Nibbles};
+
+    use super::*;
+
+    impl NodeStore for HashMap<B256, Vec<u8>> {
+        fn node(&self, hash: &B256) -> Result<Option<Vec<u8>>> {
+            Ok(self.get(hash).cloned())
+        }
+
+        fn put_node(&mut self, hash: B256, rlp: Vec<u8>) -> Result<()> {
+            self.insert(hash, rlp);
+            Ok(())
+        }
+    }
+
+    /// Root of the trie holding `entries`, from alloy-trie
+    fn reference_root(entries: &BTreeMap<Vec<u8>, Vec<u8>>) -> B256 {
+        let mut builder = HashBuilder::default();
+        for (key, value) in entries {
+            builder.add_leaf(Nibbles::unpack(key), value);
+        }
+        builder.root()
+    }
+
+    #[test]
+    fn test_trie_fixtures() {
+        // From the Ethereum trie tests
+        let fixtures: [(&[(&str, &str)], B256); 3] = [
+            (
+                &[
+                    ("do", "verb"),
+                    ("horse", "stallion"),
+                    ("doge", "coin"),
+                    ("dog", "puppy"),
+                ],
+                b256!("0x5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84"),
+            ),
+            (
+                &[
+                    ("doe", "reindeer"),
+                    ("dog", "puppy"),
+                    ("dogglesworth", "cat"),
+                ],
+                b256!("0x8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"),
+            ),
+            (
+                &[("foo", "bar"), ("food", "bass")],
+                b256!("0x17beaa1648bafa633cda809c90c04af50fc8aed3cb40d16efbddee6fdf63c4c3"),
+            ),
+        ];
+        for (entries, root) in fixtures {
+            let mut store = HashMap::new();
+            let mut trie = PatriciaTrie::new(EMPTY_ROOT_HASH);
+            for (key, value) in entries {
+                trie.insert(&mut store, key.as_bytes(), value.as_bytes().to_vec())
+                    .unwrap();
+            }
+            assert_eq!(trie.root(), root);
+            for (key, value) in entries {
+                assert_eq!(
+                    trie.get(&store, key.as_bytes()).unwrap(),
+                    Some(value.as_bytes().to_vec())
+                );
+            }
+
+            // Removing every entry in turn leads back to the empty trie
+            for (key, _) in entries {
+                trie.remove(&mut store, key.as_bytes()).unwrap();
+                assert_eq!(trie.get(&store, key.as_bytes()).unwrap(), None);
+            }
+            assert_eq!(trie.root(), EMPTY_ROOT_HASH);
+        }
+    }
+
+    #[test]
+    fn test_incremental_updates_match_full_rebuild() {
+        let mut store = HashMap::new();
+        let mut trie = PatriciaTrie::new(EMPTY_ROOT_HASH);
+        let mut entries = BTreeMap::new();
+        for i in 0..500u64 {
+            let key = keccak256(i.to_be_bytes()).to_vec();
+            // Short values give embedded nodes, long ones hashed nodes
+            let value = vec![i as u8; (i % 40) as usize + 1];
+            trie.insert(&mut store, &key, value.clone()).unwrap();
+            entries.insert(key, value);
+        }
+        assert_eq!(trie.root(), reference_root(&entries));
+
+        let earlier = trie;
+        for i in (0..500u64).step_by(3) {
+            let key = keccak256(i.to_be_bytes()).to_vec();
+            trie.remove(&mut store, &key).unwrap();
+            entries.remove(&key);
+        }
+        assert_eq!(trie.root(), reference_root(&entries));
+
+        // Nodes of earlier roots are kept
+        let key = keccak256(0u64.to_be_bytes());
+        assert_eq!(earlier.get(&store, key.as_slice()).unwrap(), Some(vec![0]));
+        assert_eq!(trie.get(&store, key.as_slice()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_account_encoding() {
+        let account = ArbitrumAccount {
+            address: address!("0x1111111111111111111111111111111111111111"),
+            balance: U256::from(10u64.pow(18)),
+            nonce: 1,
+            code_hash: B256::ZERO,
+            storage_root: B256::ZERO,
+        };
+        assert_eq!(
+            account_rlp(&account),
+            hex!(
+                "f84c0188 0de0b6b3a7640000
+                 a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421
+                 a0c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+            )
+        );
+        assert_eq!(slot_rlp(U256::from(0x2a)), vec![0x2a]);
+        assert_eq!(slot_rlp(U256::from(0x80)), vec![0x81, 0x80]);
+    }
+}